@@ -0,0 +1,117 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tracing::warn;
+
+use crate::registry;
+use crate::retry::with_retry;
+use crate::style;
+use crate::utils::{ensure_dir_exists, get_cache_path};
+use crate::version::parse_version;
+
+/// How serious a vulnerability is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Upper-case label used in reports
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+}
+
+/// A published security advisory for a registry package
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Advisory {
+    /// Advisory identifier (`SOP-<year>-<number>`)
+    pub id: String,
+    pub package: String,
+    pub title: String,
+    pub severity: Severity,
+    /// Version requirement matching every affected release (`>=1.0.0, <1.0.3`)
+    pub vulnerable: String,
+    /// Version requirement matching the fixed releases, if a fix was published
+    #[serde(default)]
+    pub patched: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl Advisory {
+    /// Check whether a version of the advised package is affected
+    pub fn affects(&self, version: &str) -> bool {
+        let (Ok(requirement), Some(version)) =
+            (VersionReq::parse(&self.vulnerable), parse_version(version))
+        else {
+            return false;
+        };
+        requirement.matches(&version)
+    }
+}
+
+/// The advisory database as published by the registry
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AdvisoryDb {
+    #[serde(default)]
+    pub advisories: Vec<Advisory>,
+}
+
+impl AdvisoryDb {
+    /// Get the advisories that affect a package version
+    pub fn matching<'a>(
+        &'a self,
+        package: &'a str,
+        version: &'a str,
+    ) -> impl Iterator<Item = &'a Advisory> {
+        self.advisories
+            .iter()
+            .filter(move |advisory| advisory.package == package && advisory.affects(version))
+    }
+}
+
+/// Get the path of the cached copy of the advisory database
+fn advisory_db_path() -> Result<PathBuf> {
+    Ok(get_cache_path()?.join("advisory-db.json"))
+}
+
+/// Download the latest advisory database, falling back to the cached copy when offline
+pub fn load_advisory_db() -> Result<AdvisoryDb> {
+    let cached = advisory_db_path()?;
+    let content = match with_retry(
+        "download the advisory database",
+        registry::download_advisory_db,
+    ) {
+        Ok(content) => {
+            if let Some(parent) = cached.parent() {
+                ensure_dir_exists(parent)?;
+            }
+            fs::write(&cached, &content)?;
+            content
+        }
+        Err(e) if cached.is_file() => {
+            warn!(
+                "{} Could not update the advisory database ({}), using the cached copy",
+                style::warn_mark(),
+                e
+            );
+            fs::read_to_string(&cached)?
+        }
+        Err(e) => return Err(anyhow!("Could not download the advisory database: {}", e)),
+    };
+
+    serde_json::from_str(&content).map_err(|e| anyhow!("The advisory database is invalid: {}", e))
+}
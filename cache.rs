@@ -0,0 +1,707 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, trace};
+
+use crate::checksum::hash_file;
+use crate::git;
+use crate::install::INSTALLED_MTIME;
+use crate::lockfile::read_sop_lock;
+use crate::registry;
+use crate::retry::with_retry;
+use crate::stats;
+use crate::utils::{
+    dir_exists, dir_size, ensure_dir_exists, get_cache_path, get_system_store_path,
+    get_vendor_path, write_atomic,
+};
+
+/// Directory of the content store, relative to the cache root
+const CONTENT_DIR: &str = "content";
+
+/// File listing the lockfiles that use the cache, relative to the cache root
+const LOCKFILES_FILE: &str = "lockfiles.toml";
+
+/// Get the directory holding a package's versions, relative to the cache root
+///
+/// Packages from the public registry live at `<name>`; packages from another registry live at
+/// `registries/<url>/<name>`, so the same name on two registries never collides.
+pub fn package_relative_path(package: &str, registry: Option<&str>) -> PathBuf {
+    match registry {
+        Some(url) => PathBuf::from("registries").join(url_key(url)).join(package),
+        None => PathBuf::from(package),
+    }
+}
+
+/// Get the cache directory for a specific package version
+pub fn package_cache_path(package: &str, version: &str, registry: Option<&str>) -> Result<PathBuf> {
+    Ok(get_cache_path()?
+        .join(package_relative_path(package, registry))
+        .join(version))
+}
+
+/// Get the read-only stores consulted before the cache, in order
+///
+/// These are the project's vendor directory, when `sop vendor` has set one up, and the
+/// system-wide store. Both use the same layout as the per-user cache and are never written to
+/// by installs.
+fn read_only_stores() -> Vec<PathBuf> {
+    get_vendor_path()
+        .into_iter()
+        .chain(std::iter::once(get_system_store_path()))
+        .collect()
+}
+
+/// Find a package version in one of the read-only stores
+fn stored_package(package: &str, version: &str, registry: Option<&str>) -> Option<PathBuf> {
+    read_only_stores()
+        .into_iter()
+        .map(|store| {
+            store
+                .join(package_relative_path(package, registry))
+                .join(version)
+        })
+        .find(|path| dir_exists(path))
+}
+
+/// Check whether a package version is already in a read-only store or the cache
+pub fn is_cached(package: &str, version: &str, registry: Option<&str>) -> Result<bool> {
+    Ok(stored_package(package, version, registry).is_some()
+        || dir_exists(&package_cache_path(package, version, registry)?))
+}
+
+/// Make sure a package version is in the cache, downloading it if needed
+///
+/// The vendor directory and the system store are consulted first, then the per-user cache.
+/// `registry` is the URL of the registry the package comes from, or `None` for the public
+/// registry. Returns the path of the cached package. With `offline` set, a cache miss is an
+/// error instead of a download.
+pub fn fetch_package(
+    package: &str,
+    version: &str,
+    registry: Option<&str>,
+    offline: bool,
+) -> Result<PathBuf> {
+    let registry_url = registry.map_or_else(registry::registry_url, str::to_string);
+    if let Some(store_dir) = stored_package(package, version, registry) {
+        trace!("{} v{} found in {}", package, version, store_dir.display());
+        stats::record_cache_hit(&registry_url);
+        return Ok(store_dir);
+    }
+
+    let cache_dir = package_cache_path(package, version, registry)?;
+    if dir_exists(&cache_dir) {
+        trace!("{} v{} found in {}", package, version, cache_dir.display());
+        stats::record_cache_hit(&registry_url);
+        mark_used(&cache_dir);
+        return Ok(cache_dir);
+    }
+    debug!("{} v{} is not cached", package, version);
+
+    if offline {
+        return Err(anyhow!(
+            "Package '{}' v{} is not in the cache and network access is disabled. Run 'sop fetch' first.",
+            package,
+            version
+        ));
+    }
+
+    // Download into a temporary directory first so an interrupted download never looks cached
+    let partial_dir = partial_path(&cache_dir, version);
+    if partial_dir.exists() {
+        fs::remove_dir_all(&partial_dir)?;
+    }
+    let downloaded_from = registry::download_package(package, version, registry, &partial_dir)?;
+    intern_files(&partial_dir)?;
+    seal_files(&partial_dir)?;
+    fs::rename(&partial_dir, &cache_dir)?;
+    stats::record_download(&downloaded_from, dir_size(&cache_dir)?);
+
+    Ok(cache_dir)
+}
+
+/// Get the directory a package version is downloaded into before it's moved into the cache
+///
+/// The whole version is kept in the name: `1.0.0` and `1.0.3` must not share `1.0.partial`.
+fn partial_path(cache_dir: &Path, version: &str) -> PathBuf {
+    cache_dir.with_file_name(format!("{}.partial", version))
+}
+
+/// Get the detached signature published for a package version, if it has one
+///
+/// Signatures are kept next to the cached package as `<version>.minisig`, in a read-only
+/// store or the cache. With `offline` set, only those are consulted.
+pub fn fetch_signature(
+    package: &str,
+    version: &str,
+    registry: Option<&str>,
+    offline: bool,
+) -> Result<Option<String>> {
+    let cached_path = signature_cache_path(package, version, registry)?;
+    let stored = read_only_stores().into_iter().map(|store| {
+        store
+            .join(package_relative_path(package, registry))
+            .join(format!("{}.minisig", version))
+    });
+    for path in stored.chain(std::iter::once(cached_path.clone())) {
+        if path.is_file() {
+            return Ok(Some(fs::read_to_string(path)?));
+        }
+    }
+
+    if offline {
+        return Ok(None);
+    }
+    let signature = with_retry(
+        &format!("download the signature of {} v{}", package, version),
+        || registry::download_signature(package, version),
+    )?;
+    if let Some(signature) = &signature {
+        if let Some(parent) = cached_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&cached_path, signature)?;
+    }
+    Ok(signature)
+}
+
+/// Get the path a package's signature is cached at
+pub fn signature_cache_path(
+    package: &str,
+    version: &str,
+    registry: Option<&str>,
+) -> Result<PathBuf> {
+    Ok(get_cache_path()?
+        .join(package_relative_path(package, registry))
+        .join(format!("{}.minisig", version)))
+}
+
+/// Turn a URL into a single directory name
+fn url_key(url: &str) -> String {
+    url.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Get the directory holding checkouts of a git repository, relative to the cache root
+pub fn git_relative_path(url: &str) -> PathBuf {
+    PathBuf::from("git").join(url_key(url))
+}
+
+/// Get the cache directory holding checkouts of a git repository
+fn git_cache_path(url: &str) -> Result<PathBuf> {
+    Ok(get_cache_path()?.join(git_relative_path(url)))
+}
+
+/// Find a checkout of a git repository in one of the read-only stores
+fn stored_git_checkout(url: &str, commit: &str) -> Option<PathBuf> {
+    read_only_stores()
+        .into_iter()
+        .map(|store| store.join(git_relative_path(url)).join(commit))
+        .find(|path| dir_exists(path))
+}
+
+/// Check whether a commit of a git repository is already in a read-only store or the cache
+pub fn is_git_cached(url: &str, commit: &str) -> Result<bool> {
+    Ok(
+        stored_git_checkout(url, commit).is_some()
+            || dir_exists(&git_cache_path(url)?.join(commit)),
+    )
+}
+
+/// Make sure a checkout of a git repository is in the cache, cloning it if needed
+///
+/// `reference` is a branch, tag, or commit; locked commits are served from a read-only store or
+/// the cache without touching the network. Returns the cached checkout and the commit it is at.
+pub fn fetch_git_package(
+    url: &str,
+    reference: Option<&str>,
+    offline: bool,
+) -> Result<(PathBuf, String)> {
+    let repo_cache = git_cache_path(url)?;
+    if let Some(reference) = reference {
+        let stored = stored_git_checkout(url, reference);
+        for cached in stored.into_iter().chain([repo_cache.join(reference)]) {
+            if dir_exists(&cached) {
+                stats::record_cache_hit(url);
+                mark_used(&cached);
+                return Ok((cached, reference.to_string()));
+            }
+        }
+    }
+
+    if offline {
+        return Err(anyhow!(
+            "Git dependency {} is not in the cache and network access is disabled. Run 'sop fetch' first.",
+            url
+        ));
+    }
+
+    let partial_dir = repo_cache.join(".partial");
+    if partial_dir.exists() {
+        fs::remove_dir_all(&partial_dir)?;
+    }
+    fs::create_dir_all(&repo_cache)?;
+    let commit = git::clone_repository(url, reference, &partial_dir)?;
+    intern_files(&partial_dir)?;
+    seal_files(&partial_dir)?;
+
+    let cache_dir = repo_cache.join(&commit);
+    if cache_dir.exists() {
+        fs::remove_dir_all(&cache_dir)?;
+    }
+    fs::rename(&partial_dir, &cache_dir)?;
+    stats::record_download(url, dir_size(&cache_dir)?);
+
+    Ok((cache_dir, commit))
+}
+
+/// Record that a cached package was just used, so `sop cache clean` removes it last
+///
+/// This is best effort: the modification time of the package's directory is moved to now, and
+/// a cache that can't be touched (such as a read-only store) is still used as it is.
+fn mark_used(dir: &Path) {
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.set_modified(SystemTime::now());
+    }
+}
+
+/// A package version or git checkout held in the cache
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    /// Package name, or the directory name of the repository for git checkouts
+    pub package: String,
+    /// Version, or commit for git checkouts
+    pub version: String,
+    pub path: PathBuf,
+    pub size: u64,
+    /// When the entry was downloaded or last used
+    pub last_used: SystemTime,
+    pub git: bool,
+}
+
+/// List everything in the per-user cache, least recently used first
+///
+/// Read-only stores are left out, since sop never cleans them. Downloads that were interrupted
+/// (`.partial` directories) are left out too.
+pub fn cached_entries() -> Result<Vec<CacheEntry>> {
+    let root = get_cache_path()?;
+    let mut entries = Vec::new();
+    for (name, dir) in subdirectories(&root)? {
+        match name.as_str() {
+            CONTENT_DIR => {}
+            "git" => {
+                for (repository, repository_dir) in subdirectories(&dir)? {
+                    for (commit, checkout) in subdirectories(&repository_dir)? {
+                        entries.push(cache_entry(&repository, &commit, checkout, true)?);
+                    }
+                }
+            }
+            "registries" => {
+                for (_, registry_dir) in subdirectories(&dir)? {
+                    collect_packages(&registry_dir, &mut entries)?;
+                }
+            }
+            _ => collect_package(&name, &dir, &mut entries)?,
+        }
+    }
+    entries.sort_by_key(|entry| entry.last_used);
+    Ok(entries)
+}
+
+/// Add every version of every package under a registry's directory
+fn collect_packages(dir: &Path, entries: &mut Vec<CacheEntry>) -> Result<()> {
+    for (name, package_dir) in subdirectories(dir)? {
+        collect_package(&name, &package_dir, entries)?;
+    }
+    Ok(())
+}
+
+/// Add every version of a package, or of each package in an `@scope` directory
+fn collect_package(name: &str, dir: &Path, entries: &mut Vec<CacheEntry>) -> Result<()> {
+    if name.starts_with('@') {
+        for (scoped, package_dir) in subdirectories(dir)? {
+            collect_versions(&format!("{}/{}", name, scoped), &package_dir, entries)?;
+        }
+        return Ok(());
+    }
+    collect_versions(name, dir, entries)
+}
+
+fn collect_versions(package: &str, dir: &Path, entries: &mut Vec<CacheEntry>) -> Result<()> {
+    for (version, version_dir) in subdirectories(dir)? {
+        entries.push(cache_entry(package, &version, version_dir, false)?);
+    }
+    Ok(())
+}
+
+fn cache_entry(package: &str, version: &str, path: PathBuf, git: bool) -> Result<CacheEntry> {
+    Ok(CacheEntry {
+        package: package.to_string(),
+        version: version.to_string(),
+        size: dir_size(&path)?,
+        last_used: fs::metadata(&path)?.modified()?,
+        path,
+        git,
+    })
+}
+
+/// List the directories in a directory by name, skipping interrupted downloads
+fn subdirectories(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    if !dir_exists(dir) {
+        return Ok(Vec::new());
+    }
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_dir() && !name.ends_with(".partial") && name != ".partial" {
+            dirs.push((name, entry.path()));
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Delete a cached package version or checkout, with its signature
+///
+/// Directories left empty are removed up to the cache root, so no empty package or scope
+/// directories linger.
+pub fn remove_entry(entry: &CacheEntry) -> Result<()> {
+    fs::remove_dir_all(&entry.path)?;
+    if let Some(version) = entry.path.file_name() {
+        let signature = entry
+            .path
+            .with_file_name(format!("{}.minisig", version.to_string_lossy()));
+        if signature.is_file() {
+            fs::remove_file(signature)?;
+        }
+    }
+
+    let root = get_cache_path()?;
+    for dir in entry.path.ancestors().skip(1) {
+        if dir == root || !dir.starts_with(&root) || fs::read_dir(dir)?.next().is_some() {
+            break;
+        }
+        fs::remove_dir(dir)?;
+    }
+    Ok(())
+}
+
+/// Move the files of a downloaded package into the content store
+///
+/// The cache keeps every file once, under `content/<xx>/<sha256>`, and package directories hold
+/// hard links to it, so files that are identical across versions, registries and git checkouts
+/// take up space only once. Looking packages up by name and version works as before. Files that
+/// can't be linked are left where they are.
+fn intern_files(dir: &Path) -> Result<()> {
+    let content_dir = get_cache_path()?.join(CONTENT_DIR);
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            intern_files(&path)?;
+        } else if file_type.is_file() {
+            let stored = content_path(&content_dir, &path)?;
+            if let Err(e) = intern_file(&path, &stored) {
+                debug!("Keeping {} out of the content store: {}", path.display(), e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Get where a file's contents are kept in the content store
+fn content_path(content_dir: &Path, file: &Path) -> Result<PathBuf> {
+    let checksum = hash_file(file)?;
+    let hex = checksum
+        .split_once(':')
+        .map_or(checksum.as_str(), |(_, hex)| hex);
+    Ok(content_dir.join(&hex[..2]).join(hex))
+}
+
+/// Replace a file with a link to the same contents in the content store, adding them if new
+fn intern_file(path: &Path, stored: &Path) -> Result<()> {
+    if stored.is_file() {
+        // Link next to the file first, so a failure never loses it
+        let linked = path.with_extension("sop-link");
+        fs::hard_link(stored, &linked)?;
+        fs::rename(&linked, path)?;
+    } else {
+        if let Some(parent) = stored.parent() {
+            ensure_dir_exists(parent)?;
+        }
+        fs::hard_link(path, stored)?;
+    }
+    Ok(())
+}
+
+/// Give the files of a package entering the cache the timestamp installed files have, and make
+/// them read-only
+///
+/// Cached files are shared with the content store, and with sop_modules when installing with
+/// the link strategy, so nothing may change them in place: their metadata is settled once,
+/// here, and installs leave them alone. Files already in the store are sealed already.
+fn seal_files(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            seal_files(&entry.path())?;
+        } else if file_type.is_file() {
+            let metadata = entry.metadata()?;
+            if !is_sealed(&metadata) {
+                seal_file(&entry.path(), &metadata)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn seal_file(path: &Path, metadata: &fs::Metadata) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let executable = metadata.permissions().mode() & 0o111 != 0;
+    // The timestamp can only be set through a handle opened for writing
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(UNIX_EPOCH + Duration::from_secs(INSTALLED_MTIME))?;
+    let mode = if executable { 0o555 } else { 0o444 };
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+/// Files stay writable elsewhere, where read-only files get in the way of deleting them, so
+/// they are never linked into sop_modules either
+#[cfg(not(unix))]
+fn seal_file(path: &Path, _metadata: &fs::Metadata) -> Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(UNIX_EPOCH + Duration::from_secs(INSTALLED_MTIME))?;
+    Ok(())
+}
+
+/// Check whether a cached file has been sealed, and so can be shared without anything
+/// changing it
+pub fn is_sealed(metadata: &fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok());
+        metadata.permissions().mode() & 0o222 == 0
+            && mtime == Some(Duration::from_secs(INSTALLED_MTIME))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// Delete files from the content store that no package in the cache links to any more
+///
+/// `kept` is everything that stays in the cache. With `dry_run`, nothing is deleted. Returns
+/// how many files were (or would be) deleted and their total size.
+pub fn sweep_content(kept: &[&CacheEntry], dry_run: bool) -> Result<(usize, u64)> {
+    let content_dir = get_cache_path()?.join(CONTENT_DIR);
+    if !dir_exists(&content_dir) {
+        return Ok((0, 0));
+    }
+
+    let mut used = HashSet::new();
+    for entry in kept {
+        collect_content_paths(&content_dir, &entry.path, &mut used)?;
+    }
+
+    let (mut count, mut size) = (0, 0);
+    for (_, prefix_dir) in subdirectories(&content_dir)? {
+        for file in fs::read_dir(&prefix_dir)? {
+            let file = file?.path();
+            if used.contains(&file) {
+                continue;
+            }
+            count += 1;
+            size += fs::metadata(&file)?.len();
+            if !dry_run {
+                fs::remove_file(&file)?;
+            }
+        }
+        if !dry_run && fs::read_dir(&prefix_dir)?.next().is_none() {
+            fs::remove_dir(&prefix_dir)?;
+        }
+    }
+    if !dry_run && fs::read_dir(&content_dir)?.next().is_none() {
+        fs::remove_dir(&content_dir)?;
+    }
+    Ok((count, size))
+}
+
+fn collect_content_paths(
+    content_dir: &Path,
+    dir: &Path,
+    used: &mut HashSet<PathBuf>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            collect_content_paths(content_dir, &entry.path(), used)?;
+        } else {
+            used.insert(content_path(content_dir, &entry.path())?);
+        }
+    }
+    Ok(())
+}
+
+/// The lockfiles of the projects that installed packages from the cache
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Lockfiles {
+    #[serde(default)]
+    lockfiles: BTreeSet<PathBuf>,
+}
+
+fn read_lockfiles(path: &Path) -> Result<Lockfiles> {
+    if !path.exists() {
+        return Ok(Lockfiles::default());
+    }
+    Ok(toml::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Remember a project's lockfile, so `sop cache gc` keeps the packages it uses
+pub fn register_lockfile(lock_path: &Path) -> Result<()> {
+    let lock_path = fs::canonicalize(lock_path)?;
+    let path = get_cache_path()?.join(LOCKFILES_FILE);
+    let mut registered = read_lockfiles(&path)?;
+    if registered.lockfiles.insert(lock_path) {
+        ensure_dir_exists(&get_cache_path()?)?;
+        write_atomic(&path, &toml::to_string_pretty(&registered)?)?;
+    }
+    Ok(())
+}
+
+/// The outcome of looking for cache entries that no project uses
+pub struct Garbage {
+    /// Cache entries no registered lockfile refers to
+    pub unused: Vec<CacheEntry>,
+    /// Cache entries some registered lockfile refers to
+    pub used: Vec<CacheEntry>,
+    /// Registered lockfiles that no longer exist
+    pub forgotten: Vec<PathBuf>,
+}
+
+/// Find the cache entries that none of the registered projects' lockfiles refers to
+///
+/// Lockfiles that no longer exist are dropped from the register, unless `dry_run` is set. A
+/// lockfile that can't be read is an error, since the packages it locks can't be told apart.
+pub fn find_garbage(dry_run: bool) -> Result<Garbage> {
+    let path = get_cache_path()?.join(LOCKFILES_FILE);
+    let mut registered = read_lockfiles(&path)?;
+
+    let mut referenced = HashSet::new();
+    let mut forgotten = Vec::new();
+    for lock_path in &registered.lockfiles {
+        if !lock_path.is_file() {
+            forgotten.push(lock_path.clone());
+            continue;
+        }
+        let lock = read_sop_lock(lock_path).map_err(|e| {
+            anyhow!(
+                "Can't tell which cached packages {} uses: {}. Fix or delete it first.",
+                lock_path.display(),
+                e
+            )
+        })?;
+        for package in &lock.packages {
+            if let Some((url, commit)) = package.git_source() {
+                referenced.insert(git_cache_path(url)?.join(commit));
+            } else if package.path_source().is_none() {
+                referenced.insert(package_cache_path(
+                    &package.name,
+                    &package.version,
+                    package.registry_source(),
+                )?);
+            }
+        }
+    }
+
+    if !dry_run && !forgotten.is_empty() {
+        for lock_path in &forgotten {
+            registered.lockfiles.remove(lock_path);
+        }
+        write_atomic(&path, &toml::to_string_pretty(&registered)?)?;
+    }
+
+    let (used, unused) = cached_entries()?
+        .into_iter()
+        .partition(|entry| referenced.contains(&entry.path));
+    Ok(Garbage {
+        unused,
+        used,
+        forgotten,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sop-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn partial_downloads_of_patch_versions_do_not_collide() {
+        let package_dir = Path::new("/cache/json");
+        let first = partial_path(&package_dir.join("1.0.0"), "1.0.0");
+        let second = partial_path(&package_dir.join("1.0.3"), "1.0.3");
+        assert_eq!(first, package_dir.join("1.0.0.partial"));
+        assert_eq!(second, package_dir.join("1.0.3.partial"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sealed_files_are_read_only_with_the_installed_time() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("seal");
+        fs::create_dir_all(dir.join("bin")).unwrap();
+        fs::write(dir.join("lib.so"), "export fn hello() {}").unwrap();
+        fs::write(dir.join("bin/tool"), "#!/bin/sh").unwrap();
+        fs::set_permissions(dir.join("bin/tool"), fs::Permissions::from_mode(0o775)).unwrap();
+
+        seal_files(&dir).unwrap();
+        let lib = fs::metadata(dir.join("lib.so")).unwrap();
+        let tool = fs::metadata(dir.join("bin/tool")).unwrap();
+        assert_eq!(lib.permissions().mode() & 0o777, 0o444);
+        assert_eq!(tool.permissions().mode() & 0o777, 0o555);
+        assert!(is_sealed(&lib) && is_sealed(&tool));
+        assert_eq!(
+            fs::read_to_string(dir.join("lib.so")).unwrap(),
+            "export fn hello() {}"
+        );
+
+        // Sealing again leaves sealed files as they are
+        seal_files(&dir).unwrap();
+        assert!(is_sealed(&fs::metadata(dir.join("lib.so")).unwrap()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn writable_files_are_not_sealed() {
+        let dir = temp_dir("unsealed");
+        fs::write(dir.join("lib.so"), "").unwrap();
+        assert!(!is_sealed(&fs::metadata(dir.join("lib.so")).unwrap()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Prefix identifying the algorithm of every checksum sop produces
+const ALGORITHM: &str = "sha256";
+
+/// Compute the checksum of a directory tree (`sha256:<hex>`)
+///
+/// Files are visited in sorted order and both their relative paths and contents are hashed,
+/// so the result only depends on what is in the directory, not where it lives.
+pub fn hash_dir(dir: &Path) -> Result<String> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for file in files {
+        let relative = file.strip_prefix(dir).unwrap_or(&file);
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        let contents = fs::read(&file)?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+
+    Ok(format!("{}:{:x}", ALGORITHM, hasher.finalize()))
+}
+
+/// Compute the checksum of an installed tree, such as sop_modules (`sha256:<hex>`)
+///
+/// Unlike `hash_dir`, every directory, even an empty one, and whether each file is executable
+/// count too, so two trees only have the same checksum when they are installed identically.
+/// Symbolic links, which point at machine-specific paths, and the top-level entries in
+/// `ignored` are left out.
+pub fn hash_tree(dir: &Path, ignored: &[&str]) -> Result<String> {
+    let mut entries = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let path = entry.path();
+            if current == dir && ignored.iter().any(|name| entry.file_name() == *name) {
+                continue;
+            }
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(path.clone());
+                entries.push((path, None));
+            } else if file_type.is_file() {
+                entries.push((path, Some(is_executable(&entry.metadata()?))));
+            }
+        }
+    }
+    entries.sort();
+
+    let mut hasher = Sha256::new();
+    for (path, executable) in entries {
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        match executable {
+            None => {
+                hasher.update(b"d");
+                hasher.update(relative.as_bytes());
+                hasher.update([0]);
+            }
+            Some(executable) => {
+                let contents = fs::read(&path)?;
+                hasher.update(if executable { b"x" } else { b"f" });
+                hasher.update(relative.as_bytes());
+                hasher.update([0]);
+                hasher.update((contents.len() as u64).to_le_bytes());
+                hasher.update(&contents);
+            }
+        }
+    }
+
+    Ok(format!("{}:{:x}", ALGORITHM, hasher.finalize()))
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Compute the checksum of a single file (`sha256:<hex>`)
+pub fn hash_file(path: &Path) -> Result<String> {
+    let contents = fs::read(path)?;
+    Ok(format!("{}:{:x}", ALGORITHM, Sha256::digest(&contents)))
+}
@@ -1,13 +1,80 @@
+use crate::advisory::Severity;
 use crate::commands;
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use crate::commands::browse::BrowseTarget;
+use crate::commands::doc::DocFormat;
+use crate::commands::foreach::ForeachOptions;
+use crate::commands::graph::GraphFormat;
+use crate::commands::sbom::SbomFormat;
+use crate::commands::tree::{TreeFormat, TreeOptions};
+use anyhow::{anyhow, Result};
+use clap::{ArgAction, Parser, Subcommand};
+use colored::Colorize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::exit_code::EXIT_CODES_HELP;
+use crate::i18n::{localized_enum, tr};
+use crate::install::{self, InstallStrategy};
+use crate::lockfile;
+use crate::logging;
+use crate::project_lock;
+use crate::registry;
+use crate::scripts;
+use crate::signature;
+use crate::stats;
+use crate::style::{self, ColorChoice};
+use crate::update_check;
+use crate::utils;
+use crate::version::SavePrefix;
 
 #[derive(Parser)]
 #[command(name = "sop")]
 #[command(author, version, about = "Official package manager for Soplang", long_about = None)]
+#[command(after_help = EXIT_CODES_HELP)]
 pub struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Print a timing and download report after the command finishes
+    #[arg(long, global = true)]
+    timings: bool,
+
+    /// Show more detail about network, cache and resolver activity (-vv for everything)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Only print warnings and errors, even with -v
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// When to color output (overrides $NO_COLOR and the `color` setting)
+    #[arg(long, global = true, value_parser = localized_enum::<ColorChoice>(), value_name = "WHEN")]
+    color: Option<ColorChoice>,
+
+    /// Print machine-readable JSON instead of text (list, info, outdated, tree, why, audit,
+    /// licenses, config, cache stats, env and check)
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// How to install packages from the cache (overrides the `install-strategy` setting)
+    #[arg(long, global = true, value_parser = localized_enum::<InstallStrategy>(), value_name = "STRATEGY")]
+    install_strategy: Option<InstallStrategy>,
+
+    /// Don't run package scripts, such as postinstall hooks, during installs
+    #[arg(long, global = true)]
+    ignore_scripts: bool,
+
+    /// Fail instead of waiting when another sop process is changing the project's packages
+    #[arg(long, global = true)]
+    no_wait: bool,
+
+    /// Work on the project containing DIR instead of the current directory
+    #[arg(short = 'C', global = true, value_name = "DIR")]
+    directory: Option<PathBuf>,
+
+    /// Work on the project with this sop.toml instead of the current directory's
+    #[arg(long, global = true, value_name = "PATH", conflicts_with = "directory")]
+    manifest_path: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -17,84 +84,985 @@ pub enum Commands {
         /// Skip interactive prompts and use default values
         #[arg(short = 'y', long)]
         yes: bool,
+
+        /// Write a sop.toml for the existing sources in the current directory
+        #[arg(long)]
+        adopt: bool,
     },
 
     /// Install dependencies from sop.toml
-    Setup,
+    Setup {
+        /// Install only from the local cache, without network access
+        #[arg(long)]
+        offline: bool,
+
+        /// How many packages to download at once (defaults to the `jobs` setting or CPU count)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Refuse registry packages without a valid signature from a trusted key
+        #[arg(long)]
+        require_signatures: bool,
+
+        /// Install only [dependencies], leaving out [dev-dependencies] (also SOP_ENV=production)
+        #[arg(long)]
+        production: bool,
+
+        /// Fail instead of changing sop.lock, e.g. when it is out of date with sop.toml
+        #[arg(long)]
+        locked: bool,
+
+        /// Like --locked, and also fail instead of using the network or changing sop.toml
+        #[arg(long)]
+        frozen: bool,
+    },
+
+    /// Download every package in sop.lock into the cache without installing
+    Fetch,
 
     /// Add a package to the project
     Add {
-        /// Package name to add
+        /// Package name to add, or a git repository URL (omit to pick one interactively)
+        package: Option<String>,
+
+        /// Specific version to install
+        #[arg(long)]
+        version: Option<String>,
+
+        /// Add to [dev-dependencies] instead of [dependencies]
+        #[arg(short = 'D', long)]
+        dev: bool,
+
+        /// Mark the dependency as optional
+        #[arg(long, conflicts_with = "dev")]
+        optional: bool,
+
+        /// Git branch to track when adding a git URL
+        #[arg(long, conflicts_with_all = ["tag", "rev", "version"])]
+        branch: Option<String>,
+
+        /// Git tag to check out when adding a git URL
+        #[arg(long, conflicts_with_all = ["rev", "version"])]
+        tag: Option<String>,
+
+        /// Git commit to check out when adding a git URL
+        #[arg(long, conflicts_with = "version")]
+        rev: Option<String>,
+
+        /// Add the package in a local directory, linking it into sop_modules
+        #[arg(long, conflicts_with_all = ["package", "version", "branch", "tag", "rev"])]
+        path: Option<String>,
+
+        /// Add the package from a registry configured under [registries] in ~/.sop/config.toml
+        #[arg(long, conflicts_with_all = ["path", "branch", "tag", "rev"])]
+        registry: Option<String>,
+
+        /// Save exactly the resolved version (=1.2.3) instead of the save-prefix setting
+        #[arg(long, conflicts_with_all = ["tilde", "path", "branch", "tag", "rev"])]
+        exact: bool,
+
+        /// Save a tilde requirement (~1.2.3), allowing patch updates only
+        #[arg(long, conflicts_with_all = ["path", "branch", "tag", "rev"])]
+        tilde: bool,
+
+        /// Consider pre-releases, such as 1.2.0-beta.1, when picking the newest version
+        #[arg(long, conflicts_with_all = ["path", "branch", "tag", "rev"])]
+        pre: bool,
+
+        /// Refuse registry packages without a valid signature from a trusted key
+        #[arg(long)]
+        require_signatures: bool,
+
+        /// Fail instead of changing sop.lock, e.g. when it is out of date with sop.toml
+        #[arg(long)]
+        locked: bool,
+
+        /// Like --locked, and also fail instead of using the network or changing sop.toml
+        #[arg(long)]
+        frozen: bool,
+    },
+
+    /// Install a package globally so its binaries can be used outside any project
+    Install {
+        /// Package name to install
         package: String,
 
         /// Specific version to install
-        #[arg(short, long)]
+        #[arg(long)]
         version: Option<String>,
+
+        /// Install into ~/.sop/packages and link binaries into ~/.sop/bin
+        #[arg(short, long)]
+        global: bool,
+    },
+
+    /// Uninstall a globally installed package
+    Uninstall {
+        /// Package name to uninstall
+        package: String,
+
+        /// Uninstall from ~/.sop/packages
+        #[arg(short, long)]
+        global: bool,
     },
 
     /// Remove a package from the project
     Remove {
-        /// Package name to remove
-        package: String,
+        /// Names of the packages to remove
+        #[arg(required = true)]
+        packages: Vec<String>,
     },
 
     /// Run a Soplang script
     Run {
         /// Path to the script (defaults to entry in sop.toml)
         script: Option<String>,
+
+        /// Run the named [[bin]] target from sop.toml
+        #[arg(long, conflicts_with = "script")]
+        bin: Option<String>,
+
+        /// Show how the interpreter is invoked
+        #[arg(short = 'x', long)]
+        show_command: bool,
+
+        /// Write a crash report file if the interpreter fails
+        #[arg(long)]
+        crash_report: bool,
+    },
+
+    /// Bundle the project and its installed packages into dist/
+    Build {
+        /// Leave out packages only needed by dev-dependencies
+        #[arg(long)]
+        release: bool,
+
+        /// Write a JSON manifest describing the bundle next to it
+        #[arg(long)]
+        manifest: bool,
+
+        /// Bundle the named [[bin]] target from sop.toml
+        #[arg(long)]
+        bin: Option<String>,
+    },
+
+    /// Run the project's tests (the .so files under tests/)
+    Test {
+        /// Only run test files whose path contains this text
+        filter: Option<String>,
+
+        /// Re-run affected tests whenever sources change
+        #[arg(short, long)]
+        watch: bool,
+    },
+
+    /// Run a binary provided by a dependency
+    #[command(visible_alias = "x")]
+    Exec {
+        /// Name of the binary to run
+        tool: String,
+
+        /// Arguments passed through to the binary
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+
+    /// Run a sop subcommand or shell command in every workspace member
+    Foreach {
+        /// Run each member after the workspace members it depends on
+        #[arg(long)]
+        topological: bool,
+
+        /// Run members at the same time (with --topological, one dependency level at a time)
+        #[arg(short, long)]
+        parallel: bool,
+
+        /// Run the command through the shell instead of as a sop subcommand
+        #[arg(long)]
+        shell: bool,
+
+        /// Stop starting members once one fails
+        #[arg(long)]
+        fail_fast: bool,
+
+        /// Only run in members whose name matches; may be a glob like `core-*` and repeated
+        #[arg(long = "member", value_name = "NAME")]
+        members: Vec<String>,
+
+        /// Command to run, e.g. `sop foreach test` or `sop foreach --shell -- ls src`
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
     },
 
     /// Update project dependencies
     Update {
         /// Specific package to update (updates all if not specified)
         package: Option<String>,
+
+        /// Show which packages would change without modifying anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// How many packages to download at once (defaults to the `jobs` setting or CPU count)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Update to pre-releases, such as 1.2.0-beta.1, too
+        #[arg(long)]
+        pre: bool,
+
+        /// Fail instead of changing sop.lock, e.g. when it is out of date with sop.toml
+        #[arg(long)]
+        locked: bool,
+
+        /// Like --locked, and also fail instead of using the network or changing sop.toml
+        #[arg(long)]
+        frozen: bool,
+    },
+
+    /// Show dependencies with newer versions available
+    Outdated {
+        /// Include the update command and changelog link for each dependency
+        #[arg(long)]
+        suggest: bool,
     },
 
     /// List installed packages
-    List,
+    List {
+        /// List globally installed packages instead
+        #[arg(short, long)]
+        global: bool,
+    },
+
+    /// Show the resolved dependency tree
+    Tree {
+        /// Output format
+        #[arg(long, value_parser = localized_enum::<TreeFormat>(), default_value = "text")]
+        format: TreeFormat,
+
+        /// Maximum depth of the tree
+        #[arg(long)]
+        depth: Option<usize>,
+
+        /// Show the packages that depend on this package instead
+        #[arg(short, long, value_name = "PACKAGE")]
+        invert: Option<String>,
+
+        /// Expand each package only the first time it appears; later ones are marked (*)
+        #[arg(long)]
+        dedupe: bool,
+    },
+
+    /// Export the resolved dependency graph for visualization tools
+    Graph {
+        /// Output format
+        #[arg(long, value_parser = localized_enum::<GraphFormat>(), default_value = "dot")]
+        format: GraphFormat,
+    },
+
+    /// Find unused dependencies, and imports that sop.toml doesn't declare
+    Udeps {
+        /// Remove the unused dependencies from sop.toml and sop_modules
+        #[arg(long)]
+        fix: bool,
+
+        /// Add the missing dependencies at their latest version
+        #[arg(long)]
+        add_missing: bool,
+    },
+
+    /// Show every dependency path that leads to a package
+    Why {
+        /// Name of the package to explain
+        package: String,
+    },
+
+    /// List packages resolved at more than one version across the workspace, with what
+    /// requires each version
+    Duplicates,
+
+    /// Search project and installed dependency sources for a pattern
+    Grep {
+        /// Regular expression to search for
+        pattern: String,
+
+        /// Match case-insensitively
+        #[arg(short, long)]
+        ignore_case: bool,
+
+        /// Treat the pattern as a literal string
+        #[arg(short = 'F', long)]
+        fixed_strings: bool,
+
+        /// Only search the project's own sources
+        #[arg(long)]
+        no_deps: bool,
+    },
 
     /// Show information about a package
     Info {
-        /// Package name
+        /// Package name, optionally scoped (`@org/package`)
+        package: String,
+    },
+
+    /// Log in to the package registry with an API token
+    Login {
+        /// API token (prompted for if not given)
+        #[arg(long, conflicts_with = "rotate")]
+        token: Option<String>,
+
+        /// Replace the stored token with a new one and revoke the old token
+        #[arg(long)]
+        rotate: bool,
+    },
+
+    /// Inspect and clean the global package cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+
+    /// Read and change settings in ~/.sop/config.toml
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Check locked packages against the security advisory database
+    Audit {
+        /// Lowest severity that makes the command fail
+        #[arg(long, value_enum, default_value_t = Severity::Low)]
+        fail_on: Severity,
+
+        /// Advisory IDs to leave out of the report (can be repeated)
+        #[arg(long, value_name = "ID")]
+        ignore: Vec<String>,
+    },
+
+    /// List the license of every dependency and check them against a policy
+    Licenses {
+        /// Policy file with `allow` and `deny` lists (defaults to license-policy.toml)
+        #[arg(long)]
+        policy: Option<String>,
+    },
+
+    /// Open a package's homepage, repository or documentation in the browser
+    Browse {
+        /// Name of the package
         package: String,
+
+        /// Open the source repository
+        #[arg(long, conflicts_with_all = ["home", "docs"])]
+        repo: bool,
+
+        /// Open the homepage
+        #[arg(long, conflicts_with = "docs")]
+        home: bool,
+
+        /// Open the documentation, or the registry's documentation pages when it lists none
+        #[arg(long)]
+        docs: bool,
+    },
+
+    /// List the files and sop.toml of a package archive or registry package without installing it
+    Inspect {
+        /// Path to a .tar.gz package archive, or name@version from the registry
+        target: String,
+    },
+
+    /// Resolve merge conflicts in sop.lock by merging both sides and re-resolving it
+    Lock {
+        /// Merge the sides of a conflicted sop.lock and lock it against the merged sop.toml
+        #[arg(long, required_unless_present = "merge_driver")]
+        resolve: bool,
+
+        /// Run as a git merge driver: merge.<name>.driver = "sop lock --merge-driver %O %A %B"
+        #[arg(
+            long,
+            num_args = 3,
+            value_names = ["BASE", "OURS", "THEIRS"],
+            conflicts_with = "resolve"
+        )]
+        merge_driver: Option<Vec<PathBuf>>,
+
+        /// Re-resolve only from the local cache, without network access
+        #[arg(long, requires = "resolve")]
+        offline: bool,
+    },
+
+    /// Print the project's resolved sop.toml, or with --json every setting with its default
+    Manifest {
+        /// Include the packages from sop.lock (with --json)
+        #[arg(long)]
+        lock: bool,
+    },
+
+    /// Show how much disk space each installed dependency takes up, largest first
+    Size,
+
+    /// Browse the dependency tree, outdated packages and sizes, and add, remove or update
+    /// packages, in an interactive terminal UI
+    Ui,
+
+    /// Serve a local web page showing the dependency graph, outdated packages and audit
+    /// findings, which refreshes when sop.toml or sop.lock changes
+    Dashboard {
+        /// Port to serve the page on, on 127.0.0.1
+        #[arg(long, default_value_t = 7070)]
+        port: u16,
     },
 
+    /// Render the doc comments in the project's and its dependencies' .so files into
+    /// target/doc
+    Doc {
+        /// Output format
+        #[arg(long, value_parser = localized_enum::<DocFormat>(), default_value = "html")]
+        format: DocFormat,
+
+        /// Open the generated index in a browser
+        #[arg(long)]
+        open: bool,
+
+        /// Only document the project, not its dependencies
+        #[arg(long)]
+        no_deps: bool,
+    },
+
+    /// Write a software bill of materials for the project and its dependencies
+    Sbom {
+        /// SBOM standard to emit
+        #[arg(long, value_parser = localized_enum::<SbomFormat>(), default_value = "spdx")]
+        format: SbomFormat,
+
+        /// Write the SBOM to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Include packages only dev-dependencies need
+        #[arg(long)]
+        dev: bool,
+    },
+
+    /// Copy every locked package into the project so setup works offline
+    Vendor {
+        /// Directory to vendor into (defaults to the one in sop.toml, or vendor/)
+        #[arg(long)]
+        path: Option<String>,
+    },
+
+    /// Serve resolve, list, add, remove and outdated to editors over JSON-RPC on stdin/stdout
+    Daemon,
+
+    /// Delete installed packages that no dependency in sop.toml needs any more
+    Prune {
+        /// List what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check that sop_modules holds exactly the packages, versions and checksums in sop.lock
+    Verify {
+        /// Also check timestamps, permissions and sop.lock's formatting, and print a checksum
+        /// of the whole installed tree to compare across machines
+        #[arg(long)]
+        reproducible: bool,
+    },
+
+    /// Show the project's version, or bump it in sop.toml
+    Version {
+        /// major, minor, patch, or an explicit version such as 1.4.0
+        bump: Option<String>,
+
+        /// Commit sop.toml as v<version>
+        #[arg(long, requires = "bump")]
+        commit: bool,
+
+        /// Commit sop.toml and tag the commit v<version>
+        #[arg(long, requires = "bump")]
+        tag: bool,
+    },
+
+    /// Add the features, fixes and breaking changes since the last tag to CHANGELOG.md
+    Changelog {
+        /// List the entries that would be added without changing CHANGELOG.md
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Publish the project to the registry
+    Publish {
+        /// Publish every workspace member, each after the members it depends on
+        #[arg(long)]
+        workspace: bool,
+
+        /// Show what would be published without uploading anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Verify, test, bump the version, update the changelog, build, commit and tag a release
+    Release {
+        /// major, minor, patch, or an explicit version such as 1.4.0
+        bump: String,
+
+        /// Run the checks and show what would change without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Diagnose problems with the interpreter, registries, cache, config and project
+    Doctor,
+
+    /// Show the directories, registry, interpreter and config files sop is using
+    Env,
+
     /// Clean project by removing sop_modules directory
     Clean,
 
-    /// Validate sop.toml file
-    Check,
+    /// Repair sop.toml problems that have a safe fix, showing a diff of the changes
+    Fix {
+        /// Show the changes without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Validate sop.toml, with rules turned on or off in its [check] table
+    Check {
+        /// Treat warnings, such as a missing license or 'latest' dependencies, as errors
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Any other command runs the `sop-<command>` executable on PATH, if there is one
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+/// Subcommands of `sop cache`
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Print where the cache is
+    Dir,
+
+    /// Show the cache's size and contents, and hit/miss counts and bytes downloaded per registry
+    Stats,
+
+    /// Delete cached packages, all of them unless limited by age or size
+    Clean {
+        /// Only delete packages that haven't been downloaded or used for this long, e.g. 30d
+        #[arg(long, value_name = "AGE", value_parser = utils::parse_duration)]
+        older_than: Option<Duration>,
+
+        /// Delete the least recently used packages until the cache fits in this size, e.g. 2GB
+        #[arg(long, value_name = "SIZE", value_parser = utils::parse_size)]
+        max_size: Option<u64>,
+
+        /// List what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Delete cached packages that no longer appear in the sop.lock of any project sop knows
+    Gc {
+        /// List what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Subcommands of `sop config`
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective value of a setting
+    Get {
+        /// Dotted key, e.g. `registry.url`
+        key: String,
+
+        /// Only look at the project's .sop/config.toml
+        #[arg(long)]
+        project: bool,
+    },
+    /// Change a setting
+    Set {
+        /// Dotted key, e.g. `init.license`
+        key: String,
+        /// New value; lists are comma-separated
+        value: String,
+
+        /// Change the project's .sop/config.toml instead of ~/.sop/config.toml
+        #[arg(long)]
+        project: bool,
+    },
+    /// Remove a setting so its default applies again
+    Unset {
+        /// Dotted key
+        key: String,
+
+        /// Change the project's .sop/config.toml instead of ~/.sop/config.toml
+        #[arg(long)]
+        project: bool,
+    },
+    /// List every effective setting
+    List {
+        /// Only list the project's .sop/config.toml
+        #[arg(long)]
+        project: bool,
+    },
+}
+
+impl Commands {
+    /// Check whether the command was told not to use the network
+    fn is_offline(&self) -> bool {
+        let (_, frozen) = self.lockfile_flags();
+        frozen
+            || matches!(
+                self,
+                Commands::Setup { offline: true, .. } | Commands::Lock { offline: true, .. }
+            )
+    }
+
+    /// The command's `--locked` and `--frozen` flags
+    fn lockfile_flags(&self) -> (bool, bool) {
+        match self {
+            Commands::Setup { locked, frozen, .. }
+            | Commands::Add { locked, frozen, .. }
+            | Commands::Update { locked, frozen, .. } => (*locked, *frozen),
+            _ => (false, false),
+        }
+    }
+
+    /// Check whether the command can print its output as JSON
+    fn supports_json(&self) -> bool {
+        matches!(
+            self,
+            Commands::List { .. }
+                | Commands::Info { .. }
+                | Commands::Outdated { .. }
+                | Commands::Tree { .. }
+                | Commands::Why { .. }
+                | Commands::Duplicates
+                | Commands::Audit { .. }
+                | Commands::Licenses { .. }
+                | Commands::Size
+                | Commands::Manifest { .. }
+                | Commands::Inspect { .. }
+                | Commands::Cache { .. }
+                | Commands::Env
+                | Commands::Check { .. }
+                | Commands::Config {
+                    command: ConfigCommand::Get { .. } | ConfigCommand::List { .. }
+                }
+        )
+    }
 }
 
 impl Cli {
     pub fn execute(&self) -> Result<()> {
+        let started = Instant::now();
+        // Settings can come from the project's config, so find the project first
+        self.locate_project()?;
+        style::set_color_choice(style::resolve_color_choice(self.color));
+        // The daemon's stdout carries its responses and exec's belongs to the tool, so
+        // everything they log goes to stderr
+        logging::init(
+            self.verbose,
+            self.quiet,
+            matches!(self.command, Some(Commands::Daemon | Commands::Exec { .. })),
+        );
+        let update_check = self
+            .wants_update_check()
+            .then(|| update_check::start(self.command.as_ref().is_some_and(Commands::is_offline)))
+            .flatten();
+        if self.no_wait {
+            project_lock::no_wait();
+        }
+        if self.ignore_scripts {
+            scripts::ignore_scripts();
+        }
+        if let Some(strategy) = self.install_strategy {
+            install::use_install_strategy(strategy);
+        }
+        let result = self.run_command();
+
+        // Keep the cumulative cache statistics up to date even when the command failed
+        if let Err(e) = stats::persist_session() {
+            eprintln!(
+                "{} Failed to save cache statistics: {}",
+                style::warn_mark(),
+                e
+            );
+        }
+
+        if self.timings {
+            print_timings(started);
+        }
+        if let Some(check) = update_check {
+            check.finish();
+        }
+
+        result
+    }
+
+    /// Check whether to look for a newer sop, which would only get in the way of scripts
+    /// reading the output
+    fn wants_update_check(&self) -> bool {
+        !self.quiet && !self.json && !matches!(self.command, None | Some(Commands::Daemon))
+    }
+
+    /// Point the project paths at the project chosen with `-C` or `--manifest-path`
+    fn locate_project(&self) -> Result<()> {
+        if (self.directory.is_some() || self.manifest_path.is_some())
+            && matches!(self.command, Some(Commands::Init { .. }))
+        {
+            return Err(anyhow!(tr(
+                "sop init creates the project in the current directory. Run it there instead of using -C or --manifest-path."
+            )));
+        }
+        if let Some(dir) = &self.directory {
+            utils::search_project_from(dir)?;
+        }
+        if let Some(manifest) = &self.manifest_path {
+            utils::use_manifest_path(manifest)?;
+        }
+        Ok(())
+    }
+
+    fn run_command(&self) -> Result<()> {
+        let json = self.json;
+        if json && !self.command.as_ref().is_some_and(Commands::supports_json) {
+            return Err(anyhow!(tr("--json is not supported by this command.")));
+        }
+        if let Some(command) = &self.command {
+            let (locked, frozen) = command.lockfile_flags();
+            restrict_lockfile(locked, frozen);
+        }
+
         match &self.command {
-            Some(Commands::Init { yes }) => commands::init::execute(*yes),
-            Some(Commands::Setup) => commands::setup::execute(),
-            Some(Commands::Add { package, version }) => commands::add::execute(package, version),
-            Some(Commands::Remove { package }) => commands::remove::execute(package),
-            Some(Commands::Run { script }) => commands::run::execute(script),
-            Some(Commands::Update { package }) => commands::update::execute(package),
-            Some(Commands::List) => {
-                println!("Command 'list' not yet implemented");
-                // Will call commands::list::execute() once implemented
-                Ok(())
+            Some(Commands::Init { yes, adopt }) => {
+                if *adopt {
+                    commands::init::adopt()
+                } else {
+                    commands::init::execute(*yes)
+                }
             }
-            Some(Commands::Info { package }) => {
-                println!("Command 'info' not yet implemented");
-                // Will call commands::info::execute(package) once implemented
-                Ok(())
+            Some(Commands::Setup {
+                offline,
+                jobs,
+                require_signatures,
+                production,
+                ..
+            }) => {
+                if *require_signatures {
+                    signature::require_signatures();
+                }
+                commands::setup::execute(*offline, *jobs, *production)
+            }
+            Some(Commands::Fetch) => commands::fetch::execute(),
+            Some(Commands::Add {
+                package,
+                version,
+                dev,
+                optional,
+                branch,
+                tag,
+                rev,
+                path,
+                registry,
+                exact,
+                tilde,
+                pre,
+                require_signatures,
+                ..
+            }) => {
+                if *require_signatures {
+                    signature::require_signatures();
+                }
+                lockfile::ensure_lock_may_change("Adding a dependency")?;
+                if *pre {
+                    registry::allow_prereleases();
+                }
+                let save_prefix = match (exact, tilde) {
+                    (true, _) => Some(SavePrefix::Exact),
+                    (_, true) => Some(SavePrefix::Tilde),
+                    _ => None,
+                };
+                match (path, package) {
+                    (Some(path), _) => commands::add::add_path(path, *dev, *optional),
+                    (None, Some(package)) => commands::add::execute(
+                        package,
+                        version,
+                        *dev,
+                        *optional,
+                        branch,
+                        tag,
+                        rev,
+                        registry,
+                        save_prefix,
+                    ),
+                    (None, None) => commands::add::pick(*dev, *optional, save_prefix),
+                }
+            }
+            Some(Commands::Install {
+                package,
+                version,
+                global,
+            }) => commands::install::execute(package, version, *global),
+            Some(Commands::Uninstall { package, global }) => {
+                commands::uninstall::execute(package, *global)
+            }
+            Some(Commands::Remove { packages }) => commands::remove::execute(packages),
+            Some(Commands::Run {
+                script,
+                bin,
+                show_command,
+                crash_report,
+            }) => commands::run::execute(script, bin, *show_command, *crash_report),
+            Some(Commands::Build {
+                release,
+                manifest,
+                bin,
+            }) => commands::build::execute(*release, *manifest, bin),
+            Some(Commands::Test { filter, watch }) => commands::test::execute(filter, *watch),
+            Some(Commands::Exec { tool, args }) => commands::exec::execute(tool, args),
+            Some(Commands::Foreach {
+                topological,
+                parallel,
+                shell,
+                fail_fast,
+                members,
+                command,
+            }) => commands::foreach::execute(
+                command,
+                members,
+                ForeachOptions {
+                    topological: *topological,
+                    parallel: *parallel,
+                    shell: *shell,
+                    fail_fast: *fail_fast,
+                },
+            ),
+            Some(Commands::Update {
+                package,
+                dry_run,
+                jobs,
+                pre,
+                ..
+            }) => {
+                if *pre {
+                    registry::allow_prereleases();
+                }
+                commands::update::execute(package, *dry_run, *jobs)
+            }
+            Some(Commands::Outdated { suggest }) => commands::outdated::execute(json, *suggest),
+            Some(Commands::List { global }) => commands::list::execute(*global, json),
+            Some(Commands::Tree {
+                format,
+                depth,
+                invert,
+                dedupe,
+            }) => commands::tree::execute(
+                *format,
+                json,
+                &TreeOptions {
+                    depth: *depth,
+                    invert: invert.clone(),
+                    dedupe: *dedupe,
+                },
+            ),
+            Some(Commands::Graph { format }) => commands::graph::execute(*format),
+            Some(Commands::Udeps { fix, add_missing }) => {
+                commands::udeps::execute(*fix, *add_missing)
+            }
+            Some(Commands::Why { package }) => commands::why::execute(package, json),
+            Some(Commands::Duplicates) => commands::duplicates::execute(json),
+            Some(Commands::Grep {
+                pattern,
+                ignore_case,
+                fixed_strings,
+                no_deps,
+            }) => commands::grep::execute(pattern, *ignore_case, *fixed_strings, *no_deps),
+            Some(Commands::Info { package }) => commands::info::execute(package, json),
+            Some(Commands::Login { token, rotate }) => commands::login::execute(token, *rotate),
+            Some(Commands::Cache { command }) => match command {
+                CacheCommand::Dir => commands::cache::dir(json),
+                CacheCommand::Stats => commands::cache::stats(json),
+                CacheCommand::Clean {
+                    older_than,
+                    max_size,
+                    dry_run,
+                } => commands::cache::clean(*older_than, *max_size, *dry_run, json),
+                CacheCommand::Gc { dry_run } => commands::cache::gc(*dry_run, json),
+            },
+            Some(Commands::Config { command }) => match command {
+                ConfigCommand::Get { key, project } => commands::config::get(key, *project, json),
+                ConfigCommand::Set {
+                    key,
+                    value,
+                    project,
+                } => commands::config::set(key, value, *project),
+                ConfigCommand::Unset { key, project } => commands::config::unset(key, *project),
+                ConfigCommand::List { project } => commands::config::list(*project, json),
+            },
+            Some(Commands::Audit { fail_on, ignore }) => {
+                commands::audit::execute(json, *fail_on, ignore)
+            }
+            Some(Commands::Licenses { policy }) => commands::licenses::execute(policy, json),
+            Some(Commands::Size) => commands::size::execute(json),
+            Some(Commands::Ui) => commands::ui::execute(),
+            Some(Commands::Dashboard { port }) => commands::dashboard::execute(*port),
+            Some(Commands::Lock {
+                merge_driver: Some(paths),
+                ..
+            }) => commands::lock::merge_driver(&paths[0], &paths[1], &paths[2]),
+            Some(Commands::Lock { offline, .. }) => commands::lock::resolve(*offline),
+            Some(Commands::Manifest { lock }) => commands::manifest::execute(*lock, json),
+            Some(Commands::Inspect { target }) => commands::inspect::execute(target, json),
+            Some(Commands::Browse {
+                package,
+                repo,
+                home,
+                docs,
+            }) => {
+                let target = match (repo, home, docs) {
+                    (true, _, _) => BrowseTarget::Repository,
+                    (_, true, _) => BrowseTarget::Homepage,
+                    (_, _, true) => BrowseTarget::Documentation,
+                    _ => BrowseTarget::Default,
+                };
+                commands::browse::execute(package, target)
+            }
+            Some(Commands::Doc {
+                format,
+                open,
+                no_deps,
+            }) => commands::doc::execute(*format, *open, *no_deps),
+            Some(Commands::Sbom {
+                format,
+                output,
+                dev,
+            }) => commands::sbom::execute(*format, output, *dev),
+            Some(Commands::Vendor { path }) => commands::vendor::execute(path),
+            Some(Commands::Daemon) => commands::daemon::execute(),
+            Some(Commands::Prune { dry_run }) => commands::prune::execute(*dry_run),
+            Some(Commands::Verify { reproducible }) => commands::verify::execute(*reproducible),
+            Some(Commands::Version { bump, commit, tag }) => {
+                commands::version::execute(bump, *commit, *tag)
             }
+            Some(Commands::Changelog { dry_run }) => commands::changelog::execute(*dry_run),
+            Some(Commands::Publish { workspace, dry_run }) => {
+                commands::publish::execute(*workspace, *dry_run)
+            }
+            Some(Commands::Release { bump, dry_run }) => commands::release::execute(bump, *dry_run),
+            Some(Commands::Doctor) => commands::doctor::execute(),
+            Some(Commands::Env) => commands::env::execute(json),
             Some(Commands::Clean) => {
                 println!("Command 'clean' not yet implemented");
                 // Will call commands::clean::execute() once implemented
                 Ok(())
             }
-            Some(Commands::Check) => {
-                println!("Command 'check' not yet implemented");
-                // Will call commands::check::execute() once implemented
-                Ok(())
-            }
+            Some(Commands::Check { strict }) => commands::check::execute(*strict, json),
+            Some(Commands::Fix { dry_run }) => commands::fix::execute(*dry_run),
+            Some(Commands::External(args)) => commands::external::execute(args),
             None => {
                 println!("No command specified. Run 'sop --help' for usage information.");
                 Ok(())
@@ -102,3 +1070,86 @@ impl Cli {
         }
     }
 }
+
+/// Print how long the command took and what it downloaded
+fn print_timings(started: Instant) {
+    println!(
+        "\n{} finished in {:.2}s",
+        style::info("Timings:").bold(),
+        started.elapsed().as_secs_f64()
+    );
+
+    let session = stats::session_stats();
+    if session.is_empty() {
+        println!("  No packages were fetched");
+    } else {
+        commands::cache::print_registry_stats(&session);
+    }
+}
+
+/// Apply `--locked` and `--frozen` for the rest of the command
+fn restrict_lockfile(locked: bool, frozen: bool) {
+    if frozen {
+        lockfile::freeze();
+    } else if locked {
+        lockfile::forbid_lock_changes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Language;
+    use clap::CommandFactory;
+
+    #[test]
+    fn every_help_text_has_a_somali_translation() {
+        fn collect(command: &clap::Command, texts: &mut Vec<String>) {
+            texts.extend(command.get_about().map(ToString::to_string));
+            for arg in command.get_arguments() {
+                texts.extend(arg.get_help().map(ToString::to_string));
+                for value in arg.get_possible_values() {
+                    texts.extend(value.get_help().map(ToString::to_string));
+                }
+            }
+            for subcommand in command.get_subcommands() {
+                collect(subcommand, texts);
+            }
+        }
+
+        // Building adds the help flags and subcommand, which get translated too
+        let mut command = Cli::command();
+        command.build();
+        let mut texts = Vec::new();
+        collect(&command, &mut texts);
+        let missing: Vec<&String> = texts
+            .iter()
+            .filter(|text| Language::Somali.translate(text).is_none())
+            .collect();
+        assert!(
+            missing.is_empty(),
+            "No Somali translation for {:#?}",
+            missing
+        );
+    }
+
+    #[test]
+    fn arguments_are_consistent() {
+        // Catches clashes such as a global flag sharing a short name with a command's own
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn frozen_commands_stay_offline() {
+        let offline = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).unwrap();
+            cli.command.as_ref().is_some_and(Commands::is_offline)
+        };
+        assert!(offline(&["sop", "setup", "--frozen"]));
+        assert!(offline(&["sop", "add", "json", "--frozen"]));
+        assert!(offline(&["sop", "update", "--frozen"]));
+        assert!(offline(&["sop", "lock", "--resolve", "--offline"]));
+        assert!(!offline(&["sop", "add", "json", "--locked"]));
+        assert!(!offline(&["sop", "update"]));
+    }
+}
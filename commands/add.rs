@@ -1,47 +1,127 @@
 use anyhow::{anyhow, Result};
 use colored::Colorize;
+use dialoguer::{FuzzySelect, Select};
+use std::io::{self, IsTerminal};
 use std::path::Path;
+use tracing::info;
 
-use crate::toml_parser::{read_sop_toml, write_sop_toml};
-use crate::utils::{ensure_dir_exists, file_exists, get_sop_modules_path, get_sop_toml_path};
+use crate::cache;
+use crate::config::{self, read_config};
+use crate::git;
+use crate::i18n::{tr, tr_args};
+use crate::install::{
+    declared_name, install_git_dependency, install_path_dependency, install_with_dependencies,
+    prefetch_packages, short_commit, PackageRequest,
+};
+use crate::lockfile::{read_sop_lock_or_default, write_sop_lock};
+use crate::naming::validate_package_name;
+use crate::project_lock::ProjectLock;
+use crate::registry;
+use crate::style;
+use crate::toml_parser::{read_sop_toml, write_sop_toml, Dependency, DependencyDetail};
+use crate::utils::{
+    ensure_dir_exists, file_exists, get_sop_lock_path, get_sop_modules_path, get_sop_toml_path,
+    relative_to_project,
+};
+use crate::version::{parse_version, SavePrefix};
 
 /// Execute the add command
-pub fn execute(package: &str, version: &Option<String>) -> Result<()> {
+///
+/// `registry` names a registry from `[registries]` in ~/.sop/config.toml to add the package
+/// from instead of the public one. The resolved version is written to sop.toml with
+/// `save_prefix`, or the `save-prefix` setting, in front; a requirement typed with its own
+/// operator or range is written as given.
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    package: &str,
+    version: &Option<String>,
+    dev: bool,
+    optional: bool,
+    branch: &Option<String>,
+    tag: &Option<String>,
+    rev: &Option<String>,
+    registry: &Option<String>,
+    save_prefix: Option<SavePrefix>,
+) -> Result<()> {
+    if git::is_git_url(package) {
+        if registry.is_some() {
+            return Err(anyhow!("--registry can't be used when adding a git URL."));
+        }
+        return add_git(package, branch, tag, rev, dev, optional);
+    }
+    if branch.is_some() || tag.is_some() || rev.is_some() {
+        return Err(anyhow!(
+            "--branch, --tag and --rev can only be used when adding a git URL."
+        ));
+    }
+    validate_package_name(package)?;
+    let registry_url = registry
+        .as_deref()
+        .map(registry::named_registry_url)
+        .transpose()?;
+
     // Resolve the version
     let version_str = match version {
         Some(v) => v.clone(),
         None => "latest".to_string(), // Default to latest version
     };
 
-    println!("Adding package: {} ({})", package, version_str);
+    info!("Adding package: {} ({})", package, version_str);
 
     // Check if sop.toml exists
     let sop_toml_path = get_sop_toml_path();
     if !file_exists(&sop_toml_path) {
-        return Err(anyhow!(
+        return Err(anyhow!(tr(
             "sop.toml not found. Are you in a Soplang project directory? Run 'sop init' to create a new project."
-        ));
+        )));
     }
 
+    // Keep other sop processes out of sop_modules and sop.lock until this one is done
+    let _lock = ProjectLock::acquire()?;
+
     // Read the sop.toml file
     let mut config = read_sop_toml(&sop_toml_path)?;
 
-    // Check if dependencies section exists
-    if config.dependencies.is_none() {
-        config.dependencies = Some(std::collections::HashMap::new());
-    }
-
     // Check if the package is already in dependencies
-    let dependencies = config.dependencies.as_mut().unwrap();
-    if dependencies.contains_key(package) {
+    if config.has_dependency(package) {
         return Err(anyhow!(
             "Package '{}' is already in your dependencies.",
             package
         ));
     }
 
-    // Add the package to dependencies
-    dependencies.insert(package.to_string(), version_str.clone());
+    // Resolve before touching sop.toml so an unknown package leaves it unchanged
+    let resolved_version = registry::resolve_version(package, &version_str)?;
+    let requirement = if version_str == "latest" || parse_version(&version_str).is_some() {
+        save_prefix
+            .or(read_config()?.save_prefix)
+            .unwrap_or_default()
+            .requirement(&resolved_version)
+    } else {
+        version_str.clone()
+    };
+
+    // Optional dependencies and other registries need the table form to carry the setting
+    let dependency = if optional || registry.is_some() {
+        Dependency::Detailed(DependencyDetail {
+            version: Some(requirement.clone()),
+            registry: registry.clone(),
+            optional,
+            ..Default::default()
+        })
+    } else {
+        Dependency::Simple(requirement.clone())
+    };
+
+    // Add the package to the right dependency table, creating it if needed
+    let table = if dev {
+        &mut config.dev_dependencies
+    } else {
+        &mut config.dependencies
+    };
+    table
+        .get_or_insert_with(Default::default)
+        .insert(package.to_string(), dependency);
 
     // Write updated config back to sop.toml
     write_sop_toml(&sop_toml_path, &config)?;
@@ -49,63 +129,279 @@ pub fn execute(package: &str, version: &Option<String>) -> Result<()> {
     // Install the package
     let modules_dir = get_sop_modules_path();
     ensure_dir_exists(&modules_dir)?;
-    install_package(package, &version_str, &modules_dir)?;
 
-    println!(
-        "{} Added {} ({}) to dependencies",
-        "✓".green().bold(),
+    // Install the package with its dependencies and record them in sop.lock
+    let sop_lock_path = get_sop_lock_path();
+    let locked = read_sop_lock_or_default(&sop_lock_path)?;
+    let mut lock = locked.clone();
+    let offline = read_config()?.offline;
+    if !offline {
+        let request = PackageRequest {
+            name: package.to_string(),
+            version: resolved_version.clone(),
+            registry: registry_url.clone(),
+        };
+        prefetch_packages(vec![request], &locked, config::jobs());
+    }
+    install_with_dependencies(
+        package,
+        &resolved_version,
+        registry_url.as_deref(),
+        &modules_dir,
+        &locked,
+        &mut lock,
+        offline,
+    )?;
+    write_sop_lock(&sop_lock_path, &lock)?;
+
+    info!(
+        "{} Added {} ({}){} to {}{}",
+        style::ok_mark().bold(),
         package,
-        version_str
+        requirement,
+        registry
+            .as_ref()
+            .map(|name| format!(" from the {} registry", name))
+            .unwrap_or_default(),
+        if dev {
+            "dev-dependencies"
+        } else {
+            "dependencies"
+        },
+        if optional { " as optional" } else { "" }
     );
 
     Ok(())
 }
 
-/// Install a single package
-fn install_package(package: &str, version: &str, modules_dir: &Path) -> Result<()> {
-    println!("Installing {} v{}", package, version);
+/// Add a dependency from a git repository
+fn add_git(
+    url: &str,
+    branch: &Option<String>,
+    tag: &Option<String>,
+    rev: &Option<String>,
+    dev: bool,
+    optional: bool,
+) -> Result<()> {
+    info!("Adding package from git: {}", url);
 
-    // Create a directory for the package
-    let package_dir = modules_dir.join(package);
+    // Check if sop.toml exists
+    let sop_toml_path = get_sop_toml_path();
+    if !file_exists(&sop_toml_path) {
+        return Err(anyhow!(tr(
+            "sop.toml not found. Are you in a Soplang project directory? Run 'sop init' to create a new project."
+        )));
+    }
+
+    // Keep other sop processes out of sop_modules and sop.lock until this one is done
+    let _lock = ProjectLock::acquire()?;
+
+    let mut config = read_sop_toml(&sop_toml_path)?;
+
+    // Clone first: the package name comes from the repository's own sop.toml
+    let reference = rev.as_deref().or(tag.as_deref()).or(branch.as_deref());
+    let (cached_dir, commit) = cache::fetch_git_package(url, reference, false)?;
+    let package = declared_name(&cached_dir).unwrap_or_else(|| git::name_from_url(url));
+    validate_package_name(&package)?;
 
-    // If package already exists, check if it's the right version
-    if package_dir.exists() {
-        println!("  {} {} is already installed", "✓".yellow(), package);
-        // In a real implementation, we would check version compatibility here
-        return Ok(());
+    if config.has_dependency(&package) {
+        return Err(anyhow!(
+            "Package '{}' is already in your dependencies.",
+            package
+        ));
     }
 
-    ensure_dir_exists(&package_dir)?;
-
-    // For now, we'll just create a placeholder file
-    // In a real implementation, this would download the package from a registry
-    let metadata_file = package_dir.join("sop.toml");
-    let metadata_content = format!(
-        r#"[package]
-name = "{}"
-version = "{}"
-description = "A Soplang package"
-"#,
-        package, version
-    );
+    let dependency = Dependency::Detailed(DependencyDetail {
+        git: Some(url.to_string()),
+        branch: branch.clone(),
+        tag: tag.clone(),
+        rev: rev.clone(),
+        optional,
+        ..Default::default()
+    });
+    let table = if dev {
+        &mut config.dev_dependencies
+    } else {
+        &mut config.dependencies
+    };
+    table
+        .get_or_insert_with(Default::default)
+        .insert(package.clone(), dependency);
 
-    std::fs::write(metadata_file, metadata_content)?;
+    write_sop_toml(&sop_toml_path, &config)?;
 
-    // Create a simple placeholder .so file
-    let lib_file = package_dir.join("lib.so");
-    let lib_content = format!(
-        r#"// This is a placeholder for the {} library
+    // Install the checkout that was just cloned and record its commit in sop.lock
+    let modules_dir = get_sop_modules_path();
+    ensure_dir_exists(&modules_dir)?;
+    let sop_lock_path = get_sop_lock_path();
+    let locked = read_sop_lock_or_default(&sop_lock_path)?;
+    let mut lock = locked.clone();
+    install_git_dependency(
+        &package,
+        url,
+        Some(&commit),
+        &modules_dir,
+        &locked,
+        &mut lock,
+        false,
+    )?;
+    write_sop_lock(&sop_lock_path, &lock)?;
 
-export fn hello() {{
-    println("Hello from {}!");
-}}
-"#,
-        package, package
+    info!(
+        "{} Added {} (git {}) to {}{}",
+        style::ok_mark().bold(),
+        package,
+        short_commit(&commit),
+        if dev {
+            "dev-dependencies"
+        } else {
+            "dependencies"
+        },
+        if optional { " as optional" } else { "" }
     );
 
-    std::fs::write(lib_file, lib_content)?;
+    Ok(())
+}
+
+/// Add a package from a local directory as a path dependency
+pub fn add_path(path: &str, dev: bool, optional: bool) -> Result<()> {
+    info!("Adding package from path: {}", path);
+
+    // Check if sop.toml exists
+    let sop_toml_path = get_sop_toml_path();
+    if !file_exists(&sop_toml_path) {
+        return Err(anyhow!(tr(
+            "sop.toml not found. Are you in a Soplang project directory? Run 'sop init' to create a new project."
+        )));
+    }
+
+    // Keep other sop processes out of sop_modules and sop.lock until this one is done
+    let _lock = ProjectLock::acquire()?;
+
+    let mut config = read_sop_toml(&sop_toml_path)?;
+
+    // The package name comes from the target directory's own sop.toml
+    let source_dir = Path::new(path);
+    if !file_exists(&source_dir.join("sop.toml")) {
+        return Err(anyhow!("No sop.toml found in '{}'.", path));
+    }
+    let package = declared_name(source_dir)
+        .ok_or_else(|| anyhow!("The sop.toml in '{}' does not declare a name.", path))?;
+    validate_package_name(&package)?;
+
+    if config.has_dependency(&package) {
+        return Err(anyhow!(
+            "Package '{}' is already in your dependencies.",
+            package
+        ));
+    }
 
-    println!("  {} {}", "✓".green(), package);
+    // sop.toml records the path relative to the project root
+    let path = &relative_to_project(path)?;
+    let dependency = Dependency::Detailed(DependencyDetail {
+        path: Some(path.to_string()),
+        optional,
+        ..Default::default()
+    });
+    let table = if dev {
+        &mut config.dev_dependencies
+    } else {
+        &mut config.dependencies
+    };
+    table
+        .get_or_insert_with(Default::default)
+        .insert(package.clone(), dependency);
+
+    write_sop_toml(&sop_toml_path, &config)?;
+
+    // Link the package into sop_modules and record it in sop.lock
+    let modules_dir = get_sop_modules_path();
+    ensure_dir_exists(&modules_dir)?;
+    let sop_lock_path = get_sop_lock_path();
+    let locked = read_sop_lock_or_default(&sop_lock_path)?;
+    let mut lock = locked.clone();
+    let version = install_path_dependency(
+        &package,
+        path,
+        &modules_dir,
+        &locked,
+        &mut lock,
+        read_config()?.offline,
+    )?;
+    write_sop_lock(&sop_lock_path, &lock)?;
+
+    info!(
+        "{} Added {} ({}, path {}) to {}{}",
+        style::ok_mark().bold(),
+        package,
+        version,
+        path,
+        if dev {
+            "dev-dependencies"
+        } else {
+            "dependencies"
+        },
+        if optional { " as optional" } else { "" }
+    );
 
     Ok(())
 }
+
+/// Pick a package and version from the registry index interactively, then add it
+pub fn pick(dev: bool, optional: bool, save_prefix: Option<SavePrefix>) -> Result<()> {
+    if !io::stdin().is_terminal() {
+        return Err(anyhow!(
+            "No package given. Run 'sop add <package>', or run 'sop add' in a terminal to pick one."
+        ));
+    }
+
+    let index = registry::search_index()?;
+    let width = index
+        .iter()
+        .map(|entry| entry.name.len())
+        .max()
+        .unwrap_or(0);
+    let items: Vec<String> = index
+        .iter()
+        .map(|entry| format!("{:width$}  {}", entry.name, style::dim(&entry.description)))
+        .collect();
+
+    let Some(selected) = FuzzySelect::new()
+        .with_prompt(tr("Search packages"))
+        .items(&items)
+        .interact_opt()?
+    else {
+        return Err(anyhow!(tr("No package selected.")));
+    };
+    let package = &index[selected].name;
+
+    let yanked = registry::yanked_versions(package)?;
+    let versions: Vec<String> = registry::available_versions(package)?
+        .into_iter()
+        .filter(|version| !yanked.contains(version))
+        .filter(|version| {
+            registry::prereleases_allowed()
+                || parse_version(version).is_some_and(|version| version.pre.is_empty())
+        })
+        .collect();
+    let Some(selected) = Select::new()
+        .with_prompt(tr_args("Version of {}", &[package]))
+        .items(&versions)
+        .default(0)
+        .interact_opt()?
+    else {
+        return Err(anyhow!(tr("No version selected.")));
+    };
+
+    execute(
+        package,
+        &Some(versions[selected].clone()),
+        dev,
+        optional,
+        &None,
+        &None,
+        &None,
+        &None,
+        save_prefix,
+    )
+}
@@ -0,0 +1,164 @@
+use anyhow::{anyhow, Result};
+use colored::{ColoredString, Colorize};
+use serde::Serialize;
+
+use crate::advisory::{load_advisory_db, AdvisoryDb, Severity};
+use crate::exit_code::{with_exit_code, ExitCode};
+use crate::graph::DependencyGraph;
+use crate::lockfile::{read_sop_lock_or_default, SopLock};
+use crate::style;
+use crate::toml_parser::read_sop_toml;
+use crate::utils::{ensure_in_project, get_sop_lock_path, get_sop_toml_path};
+
+/// A locked package affected by an advisory
+#[derive(Debug, Serialize)]
+pub struct Vulnerability<'a> {
+    id: &'a str,
+    package: &'a str,
+    version: &'a str,
+    severity: Severity,
+    title: &'a str,
+    patched: Option<&'a str>,
+    url: Option<&'a str>,
+    /// How the project depends on the package, starting with a direct dependency
+    path: Vec<String>,
+}
+
+/// JSON report of an audit
+#[derive(Debug, Serialize)]
+struct AuditReport<'a> {
+    packages: usize,
+    advisories: usize,
+    vulnerabilities: &'a [Vulnerability<'a>],
+}
+
+/// Execute the audit command
+///
+/// Exits with the audit exit code when a vulnerability at or above `fail_on` is found.
+pub fn execute(json: bool, fail_on: Severity, ignore: &[String]) -> Result<()> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+    let graph = DependencyGraph::from_manifest_and_lock(&config, &lock);
+    let db = load_advisory_db()?;
+    let (audited, vulnerabilities) = find_vulnerabilities(&lock, &graph, &db, ignore);
+
+    if json {
+        let report = AuditReport {
+            packages: audited,
+            advisories: db.advisories.len(),
+            vulnerabilities: &vulnerabilities,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!(
+            "Audited {} packages against {} advisories",
+            audited.to_string().bold(),
+            db.advisories.len()
+        );
+        for vulnerability in &vulnerabilities {
+            print_vulnerability(&config.project.name, vulnerability);
+        }
+        if vulnerabilities.is_empty() {
+            println!("{} No known vulnerabilities", style::ok_mark().bold());
+        }
+    }
+
+    let failing = vulnerabilities
+        .iter()
+        .filter(|vulnerability| vulnerability.severity >= fail_on)
+        .count();
+    if failing > 0 {
+        return Err(with_exit_code(
+            ExitCode::Audit,
+            anyhow!(
+                "{} vulnerabilit{} at or above {} severity",
+                failing,
+                if failing == 1 { "y" } else { "ies" },
+                fail_on.label().to_lowercase()
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Match the locked registry packages against the advisory database
+///
+/// Returns how many packages were audited and the vulnerabilities found, most severe first.
+/// Advisories listed in `ignore` are left out.
+pub fn find_vulnerabilities<'a>(
+    lock: &'a SopLock,
+    graph: &DependencyGraph,
+    db: &'a AdvisoryDb,
+    ignore: &[String],
+) -> (usize, Vec<Vulnerability<'a>>) {
+    // Advisories are published against public registry releases, so packages from git, local
+    // paths and other registries are skipped
+    let mut packages: Vec<_> = lock
+        .packages
+        .iter()
+        .filter(|package| package.source.is_none())
+        .collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut vulnerabilities = Vec::new();
+    for package in &packages {
+        for advisory in db.matching(&package.name, &package.version) {
+            if ignore.contains(&advisory.id) {
+                continue;
+            }
+            vulnerabilities.push(Vulnerability {
+                id: &advisory.id,
+                package: &package.name,
+                version: &package.version,
+                severity: advisory.severity,
+                title: &advisory.title,
+                patched: advisory.patched.as_deref(),
+                url: advisory.url.as_deref(),
+                path: graph
+                    .path_to(&package.name)
+                    .unwrap_or_else(|| vec![package.name.clone()]),
+            });
+        }
+    }
+    vulnerabilities.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.package.cmp(b.package)));
+    (packages.len(), vulnerabilities)
+}
+
+/// Print one vulnerability with how to fix it
+fn print_vulnerability(project: &str, vulnerability: &Vulnerability) {
+    println!(
+        "\n{} {} v{} {}",
+        colorize_severity(vulnerability.severity),
+        vulnerability.package.bold(),
+        vulnerability.version,
+        style::dim(vulnerability.id)
+    );
+    println!("  {}", vulnerability.title);
+    println!(
+        "  patched: {}",
+        vulnerability.patched.unwrap_or("no fix published yet")
+    );
+
+    let arrow = format!(" {} ", style::symbols().arrow);
+    println!(
+        "  path:    {}{}{}",
+        project,
+        arrow,
+        vulnerability.path.join(&arrow)
+    );
+    if let Some(url) = vulnerability.url {
+        println!("  {}", style::accent(url));
+    }
+}
+
+/// Color a severity label, most severe in the error color
+fn colorize_severity(severity: Severity) -> ColoredString {
+    match severity {
+        Severity::Critical | Severity::High => style::error(severity.label()).bold(),
+        Severity::Medium => style::warning(severity.label()).bold(),
+        Severity::Low => style::info(severity.label()),
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::cache;
+use crate::install::{installed_version, package_field};
+use crate::registry;
+use crate::style;
+use crate::utils::{get_sop_modules_path, is_soplang_project, open_in_browser};
+
+/// Which of a package's pages `sop browse` opens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrowseTarget {
+    /// The homepage, or the repository or registry page when there is none
+    Default,
+    Repository,
+    Homepage,
+    Documentation,
+}
+
+/// Execute the browse command
+///
+/// The URLs come from the package's sop.toml: the installed copy inside a project, or else the
+/// latest release, fetched into the cache. Documentation falls back to the pages the registry
+/// builds for every release.
+pub fn execute(package: &str, target: BrowseTarget) -> Result<()> {
+    let (version, dir) = package_dir(package)?;
+    let field = |key: &str| {
+        package_field(&dir, key)
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+    };
+
+    let url = match target {
+        BrowseTarget::Default => field("homepage")
+            .or_else(|| field("repository"))
+            .unwrap_or_else(|| registry::package_url(package, &version)),
+        BrowseTarget::Repository => field("repository").ok_or_else(|| {
+            anyhow!(
+                "{} doesn't list a repository in its sop.toml. {}",
+                package,
+                alternative(package, field("homepage").map(|_| ("--home", "homepage")))
+            )
+        })?,
+        BrowseTarget::Homepage => field("homepage").ok_or_else(|| {
+            anyhow!(
+                "{} doesn't list a homepage in its sop.toml. {}",
+                package,
+                alternative(
+                    package,
+                    field("repository").map(|_| ("--repo", "repository"))
+                )
+            )
+        })?,
+        BrowseTarget::Documentation => {
+            field("documentation").unwrap_or_else(|| registry::docs_url(package, &version))
+        }
+    };
+
+    open_in_browser(&url)?;
+    info!("{} Opened {}", style::ok_mark().bold(), style::accent(&url));
+    Ok(())
+}
+
+/// Find a package's files, with the version they are: the installed copy inside a project,
+/// or else the latest release, fetched into the cache
+fn package_dir(package: &str) -> Result<(String, PathBuf)> {
+    let modules_dir = get_sop_modules_path();
+    let installed = is_soplang_project()
+        .then(|| installed_version(&modules_dir, package))
+        .flatten();
+    if let Some(version) = installed {
+        return Ok((version, modules_dir.join(package)));
+    }
+    let version = registry::latest_version(package)?;
+    let dir = cache::fetch_package(package, &version, None, false)?;
+    Ok((version, dir))
+}
+
+/// Suggest the page to open instead of one the package doesn't have
+fn alternative(package: &str, other: Option<(&str, &str)>) -> String {
+    match other {
+        Some((flag, page)) => format!(
+            "Run 'sop browse {} {}' to open its {}.",
+            package, flag, page
+        ),
+        None => format!("Run 'sop browse {}' to open its registry page.", package),
+    }
+}
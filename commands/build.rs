@@ -0,0 +1,298 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use fs_extra::dir::CopyOptions;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::checksum::hash_dir;
+use crate::graph::runtime_packages;
+use crate::interpreter::interpreter_version;
+use crate::lockfile::{read_sop_lock_or_default, SopLock};
+use crate::style;
+use crate::toml_parser::{read_sop_toml, write_sop_toml, BinTarget, SopToml};
+use crate::utils::{
+    dir_exists, ensure_dir_exists, ensure_in_project, file_exists, get_sop_lock_path,
+    get_sop_modules_path, get_sop_toml_path, get_src_path, project_path, DEFAULT_MODULES_DIR,
+};
+
+/// Directory bundles are written to
+const DIST_DIR: &str = "dist";
+
+/// Describes exactly what went into a bundle, written next to it with `--manifest`
+#[derive(Debug, Serialize)]
+struct BundleManifest {
+    project: ProjectInfo,
+    profile: &'static str,
+    toolchain: Toolchain,
+    packages: Vec<PackageInfo>,
+    /// Checksum of the whole bundle directory
+    checksum: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectInfo {
+    name: String,
+    version: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Toolchain {
+    sop: &'static str,
+    /// Output of `soplang --version`, if the interpreter is available
+    soplang: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PackageInfo {
+    name: String,
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    checksum: String,
+}
+
+/// Execute the build command
+///
+/// The bundle is built around the project's `entry`, or around a `[[bin]]` target from
+/// sop.toml: the one named by `bin`, or the only one there is. A project with several
+/// targets has to say which one to bundle.
+pub fn execute(release: bool, manifest: bool, bin: &Option<String>) -> Result<()> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let target = bundled_bin(&config, bin.as_deref())?;
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+    let profile = if release { "release" } else { "debug" };
+
+    println!(
+        "Building {} v{} ({})",
+        config.project.name, config.project.version, profile
+    );
+
+    let packages = bundled_packages(&config, &lock, release);
+    let modules_dir = get_sop_modules_path();
+    for package in &packages {
+        if !dir_exists(&modules_dir.join(package)) {
+            return Err(anyhow!(
+                "Package '{}' is not installed. Run 'sop setup' before building.",
+                package
+            ));
+        }
+    }
+
+    // Start from an empty bundle so nothing stale is shipped
+    let bundle_name = match &target {
+        Some(target) => format!("{}-{}", target.name, config.project.version),
+        None => format!("{}-{}", config.project.name, config.project.version),
+    };
+    let dist_dir = project_path(DIST_DIR);
+    let bundle_dir = dist_dir.join(&bundle_name);
+    if bundle_dir.exists() {
+        fs::remove_dir_all(&bundle_dir)?;
+    }
+    ensure_dir_exists(&bundle_dir)?;
+
+    // Bundles always use the default modules directory, whatever the project is configured with
+    let mut bundled_config = read_sop_toml(&get_sop_toml_path())?;
+    bundled_config.modules = None;
+    bundled_config.workspace = None;
+    bundled_config.vendor = None;
+    bundled_config.project.version_from_workspace = false;
+    if let Some(target) = &target {
+        // The bundle runs the chosen target
+        bundled_config.project.entry = target.path.clone();
+        bundled_config.bins = vec![target.clone()];
+    }
+    write_sop_toml(&bundle_dir.join("sop.toml"), &bundled_config)?;
+    if file_exists(&get_sop_lock_path()) {
+        fs::copy(get_sop_lock_path(), bundle_dir.join("sop.lock"))?;
+    }
+
+    let src_path = get_src_path();
+    if dir_exists(&src_path) {
+        copy_dir(&src_path, &bundle_dir.join("src"))?;
+    }
+    let entry = Path::new(&bundled_config.project.entry);
+    if target.is_some() && !file_exists(&project_path(entry)) {
+        return Err(anyhow!("Script file not found: {}", entry.display()));
+    }
+    if file_exists(&project_path(entry)) && !entry.starts_with("src") {
+        copy_file(&project_path(entry), &bundle_dir.join(entry))?;
+    }
+
+    for package in &packages {
+        copy_dir(
+            &modules_dir.join(package),
+            &bundle_dir.join(DEFAULT_MODULES_DIR).join(package),
+        )?;
+    }
+
+    println!(
+        "{} Built {} ({} packages)",
+        style::ok_mark().bold(),
+        bundle_dir.display(),
+        packages.len()
+    );
+
+    if manifest {
+        let manifest_path = dist_dir.join(format!("{}.manifest.json", bundle_name));
+        let manifest = build_manifest(&config, &lock, &packages, &bundle_dir, profile)?;
+        fs::write(
+            &manifest_path,
+            format!("{}\n", serde_json::to_string_pretty(&manifest)?),
+        )?;
+        println!(
+            "{} Wrote {}",
+            style::ok_mark().bold(),
+            manifest_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Pick the `[[bin]]` target to bundle, if the project has any
+fn bundled_bin(config: &SopToml, bin: Option<&str>) -> Result<Option<BinTarget>> {
+    let available = || {
+        config
+            .bins
+            .iter()
+            .map(|bin| bin.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match bin {
+        Some(name) => match config.find_bin(name) {
+            Some(target) => Ok(Some(target.clone())),
+            None => Err(anyhow!(
+                "No [[bin]] target named '{}' in sop.toml. Available targets: {}",
+                name,
+                if config.bins.is_empty() {
+                    "none".to_string()
+                } else {
+                    available()
+                }
+            )),
+        },
+        None => match config.bins.as_slice() {
+            [] => Ok(None),
+            [target] => Ok(Some(target.clone())),
+            _ => Err(anyhow!(
+                "sop.toml has several [[bin]] targets ({}). Choose one with 'sop build --bin <name>'.",
+                available()
+            )),
+        },
+    }
+}
+
+/// Get the packages that go into the bundle
+///
+/// Debug bundles ship everything in sop.lock for the current platform; release bundles leave
+/// out packages that are only reachable through dev-dependencies.
+fn bundled_packages(config: &SopToml, lock: &SopLock, release: bool) -> BTreeSet<String> {
+    if !release {
+        return lock
+            .packages
+            .iter()
+            .filter(|p| p.for_current_platform())
+            .map(|p| p.name.clone())
+            .collect();
+    }
+    runtime_packages(config, lock)
+}
+
+/// Describe a finished bundle
+fn build_manifest(
+    config: &SopToml,
+    lock: &SopLock,
+    packages: &BTreeSet<String>,
+    bundle_dir: &Path,
+    profile: &'static str,
+) -> Result<BundleManifest> {
+    let modules_dir = bundle_dir.join(DEFAULT_MODULES_DIR);
+    let packages = packages
+        .iter()
+        .filter_map(|name| lock.get(name))
+        .map(|entry| {
+            Ok(PackageInfo {
+                name: entry.name.clone(),
+                version: entry.version.clone(),
+                source: entry.source.clone(),
+                checksum: hash_dir(&modules_dir.join(&entry.name))?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(BundleManifest {
+        project: ProjectInfo {
+            name: config.project.name.clone(),
+            version: config.project.version.clone(),
+        },
+        profile,
+        toolchain: Toolchain {
+            sop: env!("CARGO_PKG_VERSION"),
+            soplang: interpreter_version(),
+        },
+        packages,
+        checksum: hash_dir(bundle_dir)?,
+    })
+}
+
+/// Copy the contents of a directory, creating the destination
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    ensure_dir_exists(to)?;
+    let options = CopyOptions::new().content_only(true);
+    fs_extra::dir::copy(from, to, &options)?;
+    Ok(())
+}
+
+/// Copy a single file, creating its parent directory
+fn copy_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        ensure_dir_exists(parent)?;
+    }
+    fs::copy(from, to)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml_parser::create_default_config;
+
+    fn with_bins(names: &[&str]) -> SopToml {
+        let mut config = create_default_config("app");
+        config.bins = names
+            .iter()
+            .map(|name| BinTarget {
+                name: name.to_string(),
+                path: format!("bin/{}.so", name),
+            })
+            .collect();
+        config
+    }
+
+    #[test]
+    fn bin_targets_are_selected_by_name() {
+        let none = with_bins(&[]);
+        assert!(bundled_bin(&none, None).unwrap().is_none());
+        assert!(bundled_bin(&none, Some("cli")).is_err());
+
+        let one = with_bins(&["cli"]);
+        assert_eq!(bundled_bin(&one, None).unwrap().unwrap().path, "bin/cli.so");
+
+        let several = with_bins(&["cli", "server"]);
+        let error = bundled_bin(&several, None).unwrap_err().to_string();
+        assert!(error.contains("cli, server"), "{}", error);
+        assert_eq!(
+            bundled_bin(&several, Some("server")).unwrap().unwrap().path,
+            "bin/server.so"
+        );
+        let error = bundled_bin(&several, Some("worker"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("'worker'"), "{}", error);
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, SystemTime};
+
+use crate::cache::{cached_entries, find_garbage, remove_entry, sweep_content, CacheEntry};
+use crate::mirrors::{read_mirror_health, MirrorHealth};
+use crate::stats::{get_stats_path, read_cache_stats, RegistryStats};
+use crate::style;
+use crate::utils::{format_bytes, get_cache_path};
+
+/// What the cache holds, as printed by `sop cache stats`
+#[derive(Debug, Serialize)]
+struct CacheContents {
+    size: u64,
+    packages: usize,
+    versions: usize,
+    git_checkouts: usize,
+}
+
+impl CacheContents {
+    fn of(entries: &[CacheEntry]) -> Self {
+        let packages: BTreeSet<&str> = entries
+            .iter()
+            .filter(|entry| !entry.git)
+            .map(|entry| entry.package.as_str())
+            .collect();
+        let git_checkouts = entries.iter().filter(|entry| entry.git).count();
+        CacheContents {
+            size: entries.iter().map(|entry| entry.size).sum(),
+            packages: packages.len(),
+            versions: entries.len() - git_checkouts,
+            git_checkouts,
+        }
+    }
+}
+
+/// A cache entry removed by `sop cache clean`, as printed with `--json`
+#[derive(Debug, Serialize)]
+struct RemovedEntry<'a> {
+    name: &'a str,
+    version: &'a str,
+    size: u64,
+}
+
+/// Execute the cache dir command
+pub fn dir(json: bool) -> Result<()> {
+    let path = get_cache_path()?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "path": path }))?
+        );
+    } else {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+/// Execute the cache stats command
+pub fn stats(json: bool) -> Result<()> {
+    let contents = CacheContents::of(&cached_entries()?);
+    let stats = read_cache_stats(&get_stats_path()?)?;
+    let health = read_mirror_health()?;
+    if json {
+        let report = json!({
+            "path": get_cache_path()?,
+            "contents": contents,
+            "registries": stats.registries,
+            "health": health.registries,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("{}", style::success("Cache contents").bold());
+    println!("  {}", get_cache_path()?.display());
+    println!(
+        "    {} package{} ({} version{}), {} git checkout{}, {}",
+        contents.packages,
+        if contents.packages == 1 { "" } else { "s" },
+        contents.versions,
+        if contents.versions == 1 { "" } else { "s" },
+        contents.git_checkouts,
+        if contents.git_checkouts == 1 { "" } else { "s" },
+        format_bytes(contents.size)
+    );
+
+    if stats.registries.is_empty() && health.registries.is_empty() {
+        println!("\n{}", style::warning("No cache statistics recorded yet."));
+        return Ok(());
+    }
+
+    if !stats.registries.is_empty() {
+        println!("\n{}", style::success("Cache statistics").bold());
+        print_registry_stats(&stats.registries);
+    }
+
+    if !health.registries.is_empty() {
+        println!("\n{}", style::success("Registry health").bold());
+        print_mirror_health(&health.registries);
+    }
+
+    Ok(())
+}
+
+/// Execute the cache clean command
+///
+/// Without limits the whole package cache goes. `older_than` removes what hasn't been
+/// downloaded or used for that long, and `max_size` then removes the least recently used
+/// packages until the cache fits. Statistics and other bookkeeping files are kept.
+pub fn clean(
+    older_than: Option<Duration>,
+    max_size: Option<u64>,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    let entries = cached_entries()?;
+    let now = SystemTime::now();
+    let stale = |entry: &CacheEntry| match older_than {
+        Some(age) => now
+            .duration_since(entry.last_used)
+            .is_ok_and(|unused| unused >= age),
+        None => max_size.is_none(),
+    };
+
+    // Entries are least recently used first, so the size budget evicts from the front
+    let (mut removed, kept): (Vec<&CacheEntry>, Vec<&CacheEntry>) =
+        entries.iter().partition(|entry| stale(entry));
+    if let Some(max_size) = max_size {
+        let mut size: u64 = kept.iter().map(|entry| entry.size).sum();
+        for entry in kept {
+            if size <= max_size {
+                break;
+            }
+            size -= entry.size;
+            removed.push(entry);
+        }
+    }
+
+    if !dry_run {
+        for entry in &removed {
+            remove_entry(entry)?;
+        }
+    }
+    let kept: Vec<&CacheEntry> = entries
+        .iter()
+        .filter(|entry| !removed.iter().any(|removed| removed.path == entry.path))
+        .collect();
+    let swept = sweep_content(&kept, dry_run)?;
+    report_removed(&removed, swept, dry_run, json)
+}
+
+/// Execute the cache gc command
+///
+/// Every project whose sop.lock sop has written is remembered, and the cached packages none of
+/// their lockfiles refer to any more are deleted, with the content store files only they used.
+pub fn gc(dry_run: bool, json: bool) -> Result<()> {
+    let garbage = find_garbage(dry_run)?;
+    if !json {
+        for lock_path in &garbage.forgotten {
+            println!(
+                "  {} {} no longer exists",
+                style::skip_mark(),
+                lock_path.display()
+            );
+        }
+    }
+
+    if !dry_run {
+        for entry in &garbage.unused {
+            remove_entry(entry)?;
+        }
+    }
+    let kept: Vec<&CacheEntry> = garbage.used.iter().collect();
+    let swept = sweep_content(&kept, dry_run)?;
+    let removed: Vec<&CacheEntry> = garbage.unused.iter().collect();
+    report_removed(&removed, swept, dry_run, json)
+}
+
+/// Print what `sop cache clean` or `sop cache gc` removed, and the content store files swept
+/// along with it
+fn report_removed(
+    removed: &[&CacheEntry],
+    (swept_files, swept_size): (usize, u64),
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    let freed: u64 = removed.iter().map(|entry| entry.size).sum();
+
+    if json {
+        let removed: Vec<RemovedEntry> = removed
+            .iter()
+            .map(|entry| RemovedEntry {
+                name: &entry.package,
+                version: &entry.version,
+                size: entry.size,
+            })
+            .collect();
+        let report = json!({
+            "removed": removed,
+            "freed": freed,
+            "content_files_removed": swept_files,
+            "content_freed": swept_size,
+            "dry_run": dry_run,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if removed.is_empty() && swept_files == 0 {
+        println!("{} Nothing to clean", style::ok_mark().bold());
+        return Ok(());
+    }
+    for entry in removed {
+        let name = if entry.git {
+            format!("{} @ {}", entry.package, entry.version)
+        } else {
+            format!("{} v{}", entry.package, entry.version)
+        };
+        if dry_run {
+            println!("  Would remove {} ({})", name, format_bytes(entry.size));
+        } else {
+            println!(
+                "  {} Removed {} ({})",
+                style::ok_mark(),
+                name,
+                format_bytes(entry.size)
+            );
+        }
+    }
+    // Package files are links into the content store, so only the store's files free space
+    println!(
+        "{} {} {} package{} ({}) and {} unused content file{} ({})",
+        style::ok_mark().bold(),
+        if dry_run { "Would remove" } else { "Removed" },
+        removed.len(),
+        if removed.len() == 1 { "" } else { "s" },
+        format_bytes(freed),
+        swept_files,
+        if swept_files == 1 { "" } else { "s" },
+        format_bytes(swept_size)
+    );
+    Ok(())
+}
+
+/// Print how downloads from each registry and mirror have fared
+fn print_mirror_health(registries: &BTreeMap<String, MirrorHealth>) {
+    for (registry, health) in registries {
+        let status = if health.is_unhealthy() {
+            format!("{} unhealthy, tried last", style::error_mark())
+        } else if health.consecutive_failures > 0 {
+            format!(
+                "{} {} recent failure{}",
+                style::warn_mark(),
+                health.consecutive_failures,
+                if health.consecutive_failures == 1 {
+                    ""
+                } else {
+                    "s"
+                }
+            )
+        } else {
+            format!("{} healthy", style::ok_mark())
+        };
+
+        println!("  {} {}", registry.bold(), status);
+        println!(
+            "    downloads: {}, failures: {}",
+            health.successes, health.failures
+        );
+    }
+}
+
+/// Print download and cache counters per registry
+pub fn print_registry_stats(registries: &BTreeMap<String, RegistryStats>) {
+    for (registry, counters) in registries {
+        let hit_rate = counters
+            .hit_rate()
+            .map(|rate| format!("{:.0}%", rate * 100.0))
+            .unwrap_or_else(|| "-".to_string());
+
+        println!("  {}", registry.bold());
+        println!(
+            "    cache hits: {}, misses: {}, hit rate: {}",
+            counters.cache_hits, counters.cache_misses, hit_rate
+        );
+        println!(
+            "    downloaded: {}",
+            format_bytes(counters.bytes_downloaded)
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::git;
+use crate::style;
+use crate::utils::{ensure_in_project, file_exists, project_dir};
+
+/// Changelog kept at the project root
+pub const CHANGELOG: &str = "CHANGELOG.md";
+
+/// Release notes gathered from conventional commits (`feat(scope)!: description`)
+#[derive(Debug, Default)]
+pub struct Notes {
+    breaking: Vec<String>,
+    features: Vec<String>,
+    fixes: Vec<String>,
+    /// Commits that aren't features or fixes, or don't follow the convention
+    skipped: usize,
+}
+
+impl Notes {
+    pub fn is_empty(&self) -> bool {
+        self.breaking.is_empty() && self.features.is_empty() && self.fixes.is_empty()
+    }
+
+    /// Each changelog subsection with its entries, in the order they are written
+    fn sections(&self) -> [(&'static str, &Vec<String>); 3] {
+        [
+            ("Breaking changes", &self.breaking),
+            ("Features", &self.features),
+            ("Bug fixes", &self.fixes),
+        ]
+    }
+
+    /// Render the notes as markdown subsections, as they appear in the changelog
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = Vec::new();
+        for (title, entries) in self.sections() {
+            if entries.is_empty() {
+                continue;
+            }
+            let entries: Vec<String> = entries.iter().map(|entry| format!("- {}", entry)).collect();
+            markdown.push(format!("### {}\n\n{}", title, entries.join("\n")));
+        }
+        markdown.join("\n\n")
+    }
+}
+
+/// Execute the changelog command
+///
+/// Adds the features, fixes and breaking changes committed since the last tag to the
+/// Unreleased section of CHANGELOG.md, creating the file or section if needed. Entries that
+/// are already there are left alone, so running it again only adds new commits. With
+/// `dry_run`, the entries are only listed.
+pub fn execute(dry_run: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let repository = project_dir()?;
+    let since = git::latest_tag(&repository)?;
+    let notes = collect_notes(&repository, since.as_deref())?;
+    let range = since
+        .as_deref()
+        .map_or("in the history".to_string(), |tag| format!("since {}", tag));
+    if notes.is_empty() {
+        info!(
+            "{} No features, fixes or breaking changes {}",
+            style::ok_mark().bold(),
+            range
+        );
+        return Ok(());
+    }
+
+    let changelog_path = repository.join(CHANGELOG);
+    let text = if file_exists(&changelog_path) {
+        fs::read_to_string(&changelog_path)?
+    } else {
+        String::new()
+    };
+    let (updated, added) = add_to_unreleased(&text, &notes);
+    if added.is_empty() {
+        info!(
+            "{} {} already lists every change {}",
+            style::ok_mark().bold(),
+            CHANGELOG,
+            range
+        );
+        return Ok(());
+    }
+
+    for entry in &added {
+        if dry_run {
+            info!("  Would add {}", entry);
+        } else {
+            info!("  {} Added {}", style::ok_mark(), entry);
+        }
+    }
+    if notes.skipped > 0 {
+        info!(
+            "  {} {} other commit{} left out",
+            style::skip_mark(),
+            notes.skipped,
+            if notes.skipped == 1 { "" } else { "s" }
+        );
+    }
+
+    if dry_run {
+        info!("\n{} {} was not changed", "Dry run:".bold(), CHANGELOG);
+        return Ok(());
+    }
+    fs::write(&changelog_path, updated)?;
+    info!(
+        "\n{} Updated {} with {} entr{}",
+        style::ok_mark().bold(),
+        CHANGELOG,
+        added.len(),
+        if added.len() == 1 { "y" } else { "ies" }
+    );
+
+    Ok(())
+}
+
+/// Gather release notes from the commits after `since`, or from every commit
+pub fn collect_notes(repository: &Path, since: Option<&str>) -> Result<Notes> {
+    let mut notes = Notes::default();
+    // Oldest first, so entries read in the order the changes were made
+    for message in git::commit_messages(repository, since)?.iter().rev() {
+        match parse_commit(message) {
+            Some(Change::Breaking(entry)) => notes.breaking.push(entry),
+            Some(Change::Feature(entry)) => notes.features.push(entry),
+            Some(Change::Fix(entry)) => notes.fixes.push(entry),
+            None => notes.skipped += 1,
+        }
+    }
+    Ok(notes)
+}
+
+/// A commit worth a changelog entry
+enum Change {
+    Breaking(String),
+    Feature(String),
+    Fix(String),
+}
+
+/// Read a conventional commit message
+///
+/// A `!` after the type or scope, or a `BREAKING CHANGE:` footer, makes any commit a breaking
+/// change; the footer's text is used as its entry when there is one. Other commits only count
+/// if they are `feat` or `fix`.
+fn parse_commit(message: &str) -> Option<Change> {
+    let mut lines = message.lines();
+    let header = lines.next()?.trim();
+    let (prefix, description) = header.split_once(": ")?;
+    let (prefix, bang) = match prefix.strip_suffix('!') {
+        Some(prefix) => (prefix, true),
+        None => (prefix, false),
+    };
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+        None => (prefix, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    let entry = |text: &str| match scope {
+        Some(scope) => format!("**{}:** {}", scope, text.trim()),
+        None => text.trim().to_string(),
+    };
+    let footer = lines.find_map(|line| {
+        line.strip_prefix("BREAKING CHANGE:")
+            .or_else(|| line.strip_prefix("BREAKING-CHANGE:"))
+    });
+    match (kind.to_lowercase().as_str(), footer) {
+        (_, Some(footer)) if !footer.trim().is_empty() => Some(Change::Breaking(entry(footer))),
+        _ if bang || footer.is_some() => Some(Change::Breaking(entry(description))),
+        ("feat", _) => Some(Change::Feature(entry(description))),
+        ("fix", _) => Some(Change::Fix(entry(description))),
+        _ => None,
+    }
+}
+
+/// Check whether a line is the changelog's Unreleased heading, and whether it is bracketed
+/// as in Keep a Changelog's `## [Unreleased]`
+fn unreleased_heading(line: &str) -> Option<bool> {
+    let title = line.trim_end().strip_prefix("## ")?.trim().to_lowercase();
+    match title.as_str() {
+        "unreleased" => Some(false),
+        "[unreleased]" => Some(true),
+        _ => None,
+    }
+}
+
+/// Add release notes to the Unreleased section of a changelog, returning the new text and the
+/// entries that weren't already in the section
+///
+/// Entries go under the matching `###` subsection, which is created at the end of the section
+/// if it doesn't exist. The section itself is created above the first release if needed.
+pub fn add_to_unreleased(text: &str, notes: &Notes) -> (String, Vec<String>) {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    if lines.is_empty() {
+        lines = vec!["# Changelog".to_string(), String::new()];
+    }
+
+    let start = match lines
+        .iter()
+        .position(|line| unreleased_heading(line).is_some())
+    {
+        Some(heading) => heading + 1,
+        None => {
+            let at = lines
+                .iter()
+                .position(|line| line.starts_with("## "))
+                .unwrap_or(lines.len());
+            let mut block = Vec::new();
+            if at > 0 && !lines[at - 1].trim().is_empty() {
+                block.push(String::new());
+            }
+            let heading = at + block.len();
+            block.push("## Unreleased".to_string());
+            if at < lines.len() {
+                block.push(String::new());
+            }
+            lines.splice(at..at, block);
+            heading + 1
+        }
+    };
+    let mut end = lines[start..]
+        .iter()
+        .position(|line| line.starts_with("## "))
+        .map_or(lines.len(), |offset| start + offset);
+
+    let mut added = Vec::new();
+    for (title, entries) in notes.sections() {
+        let mut new: Vec<String> = Vec::new();
+        for entry in entries.iter().map(|entry| format!("- {}", entry)) {
+            if !lines[start..end].contains(&entry) && !new.contains(&entry) {
+                new.push(entry);
+            }
+        }
+        if new.is_empty() {
+            continue;
+        }
+
+        let heading = format!("### {}", title);
+        let (at, block) = match lines[start..end].iter().position(|line| *line == heading) {
+            Some(offset) => {
+                // Append to the end of the existing subsection's entries
+                let subsection = start + offset + 1;
+                let mut at = lines[subsection..end]
+                    .iter()
+                    .position(|line| line.starts_with("### "))
+                    .map_or(end, |offset| subsection + offset);
+                while at > subsection && lines[at - 1].trim().is_empty() {
+                    at -= 1;
+                }
+                (at, new.clone())
+            }
+            None => {
+                let mut at = end;
+                while at > start && lines[at - 1].trim().is_empty() {
+                    at -= 1;
+                }
+                let mut block = vec![String::new(), heading, String::new()];
+                block.extend(new.iter().cloned());
+                if lines.get(at).is_some_and(|line| !line.trim().is_empty()) {
+                    block.push(String::new());
+                }
+                (at, block)
+            }
+        };
+        end += block.len();
+        lines.splice(at..at, block);
+        added.extend(new);
+    }
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    (updated, added)
+}
+
+/// Turn the Unreleased section of a changelog into the section for `version`
+///
+/// A fresh, empty Unreleased heading is left above it for the next release. Both
+/// `## Unreleased` and Keep a Changelog's `## [Unreleased]` are recognized. Returns `None`
+/// if there is no Unreleased section.
+pub fn release_section(text: &str, version: &str, date: &str) -> Option<String> {
+    let mut found = false;
+    let mut released = String::with_capacity(text.len() + 64);
+    for line in text.split_inclusive('\n') {
+        match unreleased_heading(line) {
+            Some(bracketed) if !found => {
+                found = true;
+                released.push_str(line.trim_end());
+                released.push_str("\n\n");
+                if bracketed {
+                    released.push_str(&format!("## [{}] - {}\n", version, date));
+                } else {
+                    released.push_str(&format!("## {} - {}\n", version, date));
+                }
+            }
+            _ => released.push_str(line),
+        }
+    }
+    found.then_some(released)
+}
@@ -0,0 +1,387 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use semver::VersionReq;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::exit_code::{with_exit_code, ExitCode};
+use crate::style;
+use crate::toml_parser::{read_sop_toml, Dependency, DependencyKind, SopToml};
+use crate::utils::{ensure_in_project, get_sop_toml_path, project_path};
+use crate::version::parse_version;
+
+/// How a rule's findings are reported, before `--strict`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    Warning,
+    Error,
+}
+
+/// A check of sop.toml, which the project's `[check]` table can turn on or off by name
+struct Rule {
+    name: &'static str,
+    level: Level,
+    /// Whether the rule runs when `[check]` doesn't mention it
+    enabled: bool,
+    /// Describe every problem the rule finds
+    check: fn(&SopToml) -> Vec<String>,
+}
+
+/// A problem a rule found in sop.toml
+#[derive(Debug, Serialize)]
+struct Problem {
+    rule: &'static str,
+    /// How it is reported, which is `Error` for every problem with `--strict`
+    level: Level,
+    message: String,
+}
+
+/// JSON report of a check
+#[derive(Debug, Serialize)]
+struct CheckReport<'a> {
+    errors: usize,
+    warnings: usize,
+    problems: &'a [Problem],
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        name: "invalid-version",
+        level: Level::Error,
+        enabled: true,
+        check: invalid_version,
+    },
+    Rule {
+        name: "missing-entry",
+        level: Level::Error,
+        enabled: true,
+        check: missing_entry,
+    },
+    Rule {
+        name: "invalid-requirement",
+        level: Level::Error,
+        enabled: true,
+        check: invalid_requirements,
+    },
+    Rule {
+        name: "empty-description",
+        level: Level::Warning,
+        enabled: true,
+        check: empty_description,
+    },
+    Rule {
+        name: "missing-license",
+        level: Level::Warning,
+        enabled: true,
+        check: missing_license,
+    },
+    Rule {
+        name: "wildcard-version",
+        level: Level::Warning,
+        enabled: true,
+        check: wildcard_versions,
+    },
+    Rule {
+        name: "latest-version",
+        level: Level::Warning,
+        enabled: true,
+        check: latest_versions,
+    },
+    Rule {
+        name: "missing-repository",
+        level: Level::Warning,
+        enabled: false,
+        check: missing_repository,
+    },
+];
+
+/// Execute the check command
+///
+/// Every enabled rule runs, so one run lists everything to fix. Errors fail the command; with
+/// `strict`, so do warnings. sop.toml's `[check]` table turns rules on or off, such as
+/// `missing-license = false`. With `json`, prints `{"errors", "warnings", "problems"}` where
+/// each problem has its `rule`, `level` and `message`.
+pub fn execute(strict: bool, json: bool) -> Result<()> {
+    ensure_in_project()?;
+    let config = read_sop_toml(&get_sop_toml_path())?;
+
+    let settings = config.check.clone().unwrap_or_default();
+    if let Some(unknown) = settings
+        .keys()
+        .find(|name| !RULES.iter().any(|rule| rule.name == name.as_str()))
+    {
+        return Err(anyhow!(
+            "Unknown rule '{}' in [check] in sop.toml. The rules are: {}.",
+            unknown,
+            RULES
+                .iter()
+                .map(|rule| rule.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let problems = find_problems(&config, &settings, strict);
+    let errors = problems
+        .iter()
+        .filter(|problem| problem.level == Level::Error)
+        .count();
+    let warnings = problems.len() - errors;
+
+    if json {
+        let report = CheckReport {
+            errors,
+            warnings,
+            problems: &problems,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for problem in &problems {
+            let mark = match problem.level {
+                Level::Error => style::error_mark(),
+                Level::Warning => style::warn_mark(),
+            };
+            println!(
+                "  {} {} {}",
+                mark,
+                problem.message,
+                style::dim(&format!("[{}]", problem.rule))
+            );
+        }
+    }
+
+    if errors > 0 {
+        return Err(with_exit_code(
+            ExitCode::Check,
+            anyhow!(
+                "sop.toml has {} problem{}.",
+                errors,
+                if errors == 1 { "" } else { "s" }
+            ),
+        ));
+    }
+    if json {
+        return Ok(());
+    }
+    if warnings > 0 {
+        println!(
+            "{} sop.toml is valid, with {} warning{}. Pass --strict to treat warnings as errors.",
+            style::ok_mark().bold(),
+            warnings,
+            if warnings == 1 { "" } else { "s" }
+        );
+    } else {
+        println!("{} sop.toml is valid", style::ok_mark().bold());
+    }
+    Ok(())
+}
+
+/// Run every rule `settings` leaves enabled, reporting warnings as errors with `strict`
+fn find_problems(
+    config: &SopToml,
+    settings: &BTreeMap<String, bool>,
+    strict: bool,
+) -> Vec<Problem> {
+    RULES
+        .iter()
+        .filter(|rule| settings.get(rule.name).copied().unwrap_or(rule.enabled))
+        .flat_map(|rule| {
+            let level = if strict { Level::Error } else { rule.level };
+            (rule.check)(config)
+                .into_iter()
+                .map(move |message| Problem {
+                    rule: rule.name,
+                    level,
+                    message,
+                })
+        })
+        .collect()
+}
+
+/// List the version requirement of every registry dependency, in every table, with a label
+/// saying where it is
+fn requirements(config: &SopToml) -> Vec<(String, &str)> {
+    let table = |kind: DependencyKind| match kind {
+        DependencyKind::Normal => "dependencies",
+        DependencyKind::Dev => "dev-dependencies",
+    };
+    let unconditional = [
+        (&config.dependencies, DependencyKind::Normal),
+        (&config.dev_dependencies, DependencyKind::Dev),
+    ]
+    .into_iter()
+    .flat_map(|(dependencies, kind)| {
+        dependencies
+            .iter()
+            .flatten()
+            .map(move |(name, dependency)| (format!("{} in [{}]", name, table(kind)), dependency))
+    });
+    let platform =
+        config
+            .platform_dependencies()
+            .into_iter()
+            .map(|(qualifier, name, dependency, kind)| {
+                (
+                    format!("{} in [target.{}.{}]", name, qualifier, table(kind)),
+                    dependency,
+                )
+            });
+
+    let mut requirements: Vec<(String, &str)> = unconditional
+        .chain(platform)
+        .filter_map(|(label, dependency): (String, &Dependency)| {
+            Some((label, dependency.requirement()?))
+        })
+        .collect();
+    requirements.sort();
+    requirements
+}
+
+fn invalid_version(config: &SopToml) -> Vec<String> {
+    match parse_version(&config.project.version) {
+        Some(_) => Vec::new(),
+        None => vec![format!(
+            "version '{}' isn't a semantic version such as 1.0.0",
+            config.project.version
+        )],
+    }
+}
+
+fn missing_entry(config: &SopToml) -> Vec<String> {
+    let entry = &config.project.entry;
+    if entry.is_empty() || project_path(entry).is_file() {
+        return Vec::new();
+    }
+    vec![format!("entry {} doesn't exist", entry)]
+}
+
+fn invalid_requirements(config: &SopToml) -> Vec<String> {
+    requirements(config)
+        .into_iter()
+        .filter(|(_, requirement)| {
+            *requirement != "latest"
+                && parse_version(requirement.trim_start_matches(['^', '~', '='])).is_none()
+                && VersionReq::parse(requirement).is_err()
+        })
+        .map(|(label, requirement)| {
+            format!(
+                "{} asks for '{}', which isn't a version requirement",
+                label, requirement
+            )
+        })
+        .collect()
+}
+
+fn empty_description(config: &SopToml) -> Vec<String> {
+    if config.project.description.trim().is_empty() {
+        vec!["no description, so the package is hard to find in search".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+fn missing_license(config: &SopToml) -> Vec<String> {
+    if config.project.license.trim().is_empty() {
+        vec!["no license, so others can't tell whether they may use the package".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+fn wildcard_versions(config: &SopToml) -> Vec<String> {
+    requirements(config)
+        .into_iter()
+        .filter(|(_, requirement)| {
+            requirement
+                .split(|c: char| c == '.' || c == ',' || c.is_whitespace())
+                .any(|part| {
+                    matches!(
+                        part.trim_start_matches(['^', '~', '=', '>', '<']),
+                        "*" | "x" | "X"
+                    )
+                })
+        })
+        .map(|(label, requirement)| format!("{} accepts any version with '{}'", label, requirement))
+        .collect()
+}
+
+fn latest_versions(config: &SopToml) -> Vec<String> {
+    requirements(config)
+        .into_iter()
+        .filter(|(_, requirement)| *requirement == "latest")
+        .map(|(label, _)| {
+            format!(
+                "{} asks for 'latest', which can pick up breaking changes",
+                label
+            )
+        })
+        .collect()
+}
+
+fn missing_repository(config: &SopToml) -> Vec<String> {
+    if config.project.repository.trim().is_empty() {
+        vec!["no repository, so users can't find the source or report issues".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml_parser::parse_sop_toml;
+    use std::path::Path;
+
+    /// A manifest with no entry, so the result doesn't depend on the working directory
+    fn manifest(license: &str, dependencies: &str) -> SopToml {
+        let content = format!(
+            "[project]\nname = \"app\"\nversion = \"1.0.0\"\nstatus = \"experimental\"\ndescription = \"An app\"\nlicense = \"{}\"\nauthor = \"\"\nrepository = \"\"\nhomepage = \"\"\nentry = \"\"\nkeywords = []\ncategories = []\n\n[dependencies]\n{}",
+            license, dependencies
+        );
+        parse_sop_toml(&content, Path::new("sop.toml")).unwrap()
+    }
+
+    #[test]
+    fn problems_carry_their_rule_and_level() {
+        let config = manifest("", "json = \"latest\"\nhttp = \"one\"\n");
+        let problems = find_problems(&config, &BTreeMap::new(), false);
+        let found: Vec<(&str, Level)> = problems
+            .iter()
+            .map(|problem| (problem.rule, problem.level))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("invalid-requirement", Level::Error),
+                ("missing-license", Level::Warning),
+                ("latest-version", Level::Warning),
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_reports_warnings_as_errors_and_settings_turn_rules_off() {
+        let config = manifest("", "json = \"latest\"\n");
+        let settings = BTreeMap::from([("latest-version".to_string(), false)]);
+        let problems = find_problems(&config, &settings, true);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].rule, "missing-license");
+        assert_eq!(problems[0].level, Level::Error);
+    }
+
+    #[test]
+    fn report_serializes_levels_in_lowercase() {
+        let config = manifest("MIT", "json = \"latest\"\n");
+        let problems = find_problems(&config, &BTreeMap::new(), false);
+        let report = CheckReport {
+            errors: 0,
+            warnings: problems.len(),
+            problems: &problems,
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["warnings"], 1);
+        assert_eq!(json["problems"][0]["rule"], "latest-version");
+        assert_eq!(json["problems"][0]["level"], "warning");
+    }
+}
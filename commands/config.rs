@@ -0,0 +1,210 @@
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+use toml::{Table, Value};
+
+use crate::config::{
+    config_key_kind, get_project_config_path, get_user_config_path, read_config_layers,
+    read_config_table, write_config_table, ConfigValueKind, UserConfig, CONFIG_KEYS,
+    USER_ONLY_KEYS,
+};
+use crate::style;
+use crate::utils::ensure_in_project;
+
+/// Print the value of a setting
+///
+/// Without `project`, this is the effective value, taking the project config into account.
+pub fn get(key: &str, project: bool, json: bool) -> Result<()> {
+    let table = if project {
+        read_config_table(&config_path(true)?)?
+    } else {
+        read_config_layers()?
+    };
+    match lookup(&table, key) {
+        Some(value) if json => {
+            println!("{}", serde_json::to_string_pretty(value)?);
+            Ok(())
+        }
+        Some(value) => {
+            println!("{}", display_value(value));
+            Ok(())
+        }
+        None => Err(anyhow!("'{}' is not set", key)),
+    }
+}
+
+/// Change a setting, refusing unknown keys and values of the wrong type
+///
+/// With `project`, the setting goes into the project's `.sop/config.toml` instead of the user
+/// config.
+pub fn set(key: &str, value: &str, project: bool) -> Result<()> {
+    let kind = config_key_kind(key).ok_or_else(|| unknown_key(key))?;
+    if project && USER_ONLY_KEYS.contains(&key) {
+        return Err(anyhow!(
+            "'{}' can only be set in ~/.sop/config.toml, not per project",
+            key
+        ));
+    }
+    let value = parse_value(key, value, kind)?;
+
+    let path = config_path(project)?;
+    let mut table = read_config_table(&path)?;
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or(key);
+    let mut current = &mut table;
+    for part in parts {
+        current = current
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Table(Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("'{}' in {} is not a table", part, path.display()))?;
+    }
+    current.insert(last.to_string(), value.clone());
+
+    // Check the result parses as a config, e.g. that a theme name exists
+    Value::Table(table.clone())
+        .try_into::<UserConfig>()
+        .map_err(|e| anyhow!("Invalid value for '{}': {}", key, e.message()))?;
+
+    write_config_table(&path, &table)?;
+    println!(
+        "{} {} = {} in {}",
+        style::ok_mark(),
+        key,
+        display_value(&value),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Remove a setting so the user config's value, or the default, applies again
+pub fn unset(key: &str, project: bool) -> Result<()> {
+    let path = config_path(project)?;
+    let mut table = read_config_table(&path)?;
+    if !remove(&mut table, &key.split('.').collect::<Vec<_>>()) {
+        return Err(anyhow!("'{}' is not set in {}", key, path.display()));
+    }
+
+    write_config_table(&path, &table)?;
+    println!("{} Unset {}", style::ok_mark(), key);
+    Ok(())
+}
+
+/// Print every effective setting as `key = value`, or only the project's with `project`
+///
+/// With `json`, the settings are printed as one nested object instead.
+pub fn list(project: bool, json: bool) -> Result<()> {
+    let table = if project {
+        read_config_table(&config_path(true)?)?
+    } else {
+        read_config_layers()?
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&table)?);
+        return Ok(());
+    }
+
+    let mut entries = Vec::new();
+    flatten("", &table, &mut entries);
+    if entries.is_empty() {
+        println!("{}", style::warning("No settings."));
+        return Ok(());
+    }
+
+    for (key, value) in entries {
+        println!("{} = {}", key, value);
+    }
+    Ok(())
+}
+
+/// Get the config file to read or change: the project's, or the user's
+fn config_path(project: bool) -> Result<PathBuf> {
+    if project {
+        ensure_in_project()?;
+        Ok(get_project_config_path())
+    } else {
+        get_user_config_path()
+    }
+}
+
+fn unknown_key(key: &str) -> anyhow::Error {
+    let known: Vec<&str> = CONFIG_KEYS.iter().map(|(key, _)| *key).collect();
+    anyhow!(
+        "Unknown config key '{}'. Known keys: {}",
+        key,
+        known.join(", ")
+    )
+}
+
+/// Turn a value from the command line into the TOML type the key expects
+fn parse_value(key: &str, value: &str, kind: ConfigValueKind) -> Result<Value> {
+    match kind {
+        ConfigValueKind::String => Ok(Value::String(value.to_string())),
+        ConfigValueKind::Bool => value
+            .parse()
+            .map(Value::Boolean)
+            .map_err(|_| anyhow!("'{}' must be true or false", key)),
+        ConfigValueKind::Integer => value
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| anyhow!("'{}' must be a whole number", key)),
+        ConfigValueKind::StringList => Ok(Value::Array(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        )),
+    }
+}
+
+/// Find the value at a dotted key
+fn lookup<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+/// Remove the value at a dotted key, dropping tables it leaves empty
+fn remove(table: &mut Table, parts: &[&str]) -> bool {
+    match parts {
+        [] => false,
+        [last] => table.remove(*last).is_some(),
+        [first, rest @ ..] => {
+            let Some(inner) = table.get_mut(*first).and_then(Value::as_table_mut) else {
+                return false;
+            };
+            let removed = remove(inner, rest);
+            if inner.is_empty() {
+                table.remove(*first);
+            }
+            removed
+        }
+    }
+}
+
+/// Collect every leaf value of a table under its dotted key
+fn flatten(prefix: &str, table: &Table, entries: &mut Vec<(String, String)>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            Value::Table(inner) => flatten(&key, inner, entries),
+            value => entries.push((key, value.to_string())),
+        }
+    }
+}
+
+/// Show strings without quotes, and everything else as TOML
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use tracing::info;
+
+use crate::commands::{add, list, outdated, remove};
+use crate::exit_code::exit_code_of;
+use crate::registry;
+use crate::version::SavePrefix;
+
+/// JSON-RPC error codes from the specification
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Error code for a sop operation that failed; its exit code is in the error data
+const OPERATION_FAILED: i64 = -32000;
+
+/// A JSON-RPC 2.0 request, or a notification when it has no id
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(error: anyhow::Error) -> Self {
+        RpcError {
+            code: OPERATION_FAILED,
+            message: error.to_string(),
+            data: Some(json!({ "exit_code": exit_code_of(&error) as i32 })),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ResolveParams {
+    package: String,
+    /// Version requirement, `latest` when left out
+    #[serde(default)]
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddParams {
+    /// Package name or git repository URL
+    package: String,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    dev: bool,
+    #[serde(default)]
+    optional: bool,
+    /// Registry from `[registries]` in ~/.sop/config.toml
+    #[serde(default)]
+    registry: Option<String>,
+    /// `caret`, `tilde` or `exact`, overriding the `save-prefix` setting
+    #[serde(default)]
+    save_prefix: Option<SavePrefix>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RemoveParams {
+    packages: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OutdatedParams {
+    #[serde(default)]
+    suggest: bool,
+}
+
+/// Execute the daemon command
+///
+/// Requests are read from stdin and responses written to stdout, one JSON-RPC 2.0 message
+/// per line, until stdin closes or a `shutdown` request arrives. Every request works on the
+/// project in the directory the daemon was started in. Log output goes to stderr so it
+/// never mixes with responses.
+///
+/// | Method     | Params                                              | Result                      |
+/// |------------|-----------------------------------------------------|-----------------------------|
+/// | `resolve`  | `{package, version?}`                               | `{name, version}`           |
+/// | `list`     |                                                     | dependencies, as `sop list --json` |
+/// | `add`      | `{package, version?, dev?, optional?, registry?}`   | dependencies after adding   |
+/// | `remove`   | `{packages}`                                        | dependencies after removing |
+/// | `outdated` | `{suggest?}`                                        | as `sop outdated --json`    |
+/// | `shutdown` |                                                     | `null`                      |
+pub fn execute() -> Result<()> {
+    info!(
+        "sop v{} daemon reading JSON-RPC requests on stdin",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request = match parse_request(&line) {
+            Ok(request) => request,
+            Err(error) => {
+                respond(Value::Null, Err(error))?;
+                continue;
+            }
+        };
+
+        let shutdown = request.method == "shutdown";
+        let result = call(&request.method, request.params);
+        // Notifications get no response, even when they fail
+        if let Some(id) = request.id {
+            respond(id, result)?;
+        }
+        if shutdown {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_request(line: &str) -> Result<Request, RpcError> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|e| RpcError::new(PARSE_ERROR, format!("Invalid JSON: {}", e)))?;
+    let request: Request = serde_json::from_value(value)
+        .map_err(|e| RpcError::new(INVALID_REQUEST, format!("Invalid request: {}", e)))?;
+    if request.jsonrpc != "2.0" {
+        return Err(RpcError::new(
+            INVALID_REQUEST,
+            "Only JSON-RPC 2.0 is supported.",
+        ));
+    }
+    Ok(request)
+}
+
+/// Run one method and turn its outcome into a JSON result
+fn call(method: &str, params: Option<Value>) -> Result<Value, RpcError> {
+    match method {
+        "resolve" => {
+            let params: ResolveParams = parse_params(params)?;
+            let requirement = params.version.as_deref().unwrap_or("latest");
+            let version = registry::resolve_version(&params.package, requirement)?;
+            Ok(json!({ "name": params.package, "version": version }))
+        }
+        "list" => to_result(list::project_dependencies()?),
+        "add" => {
+            let params: AddParams = parse_params(params)?;
+            add::execute(
+                &params.package,
+                &params.version,
+                params.dev,
+                params.optional,
+                &None,
+                &None,
+                &None,
+                &params.registry,
+                params.save_prefix,
+            )?;
+            to_result(list::project_dependencies()?)
+        }
+        "remove" => {
+            let params: RemoveParams = parse_params(params)?;
+            remove::execute(&params.packages)?;
+            to_result(list::project_dependencies()?)
+        }
+        "outdated" => {
+            let params: OutdatedParams = match params {
+                Some(_) => parse_params(params)?,
+                None => OutdatedParams::default(),
+            };
+            let (outdated, failures) = outdated::find_outdated(params.suggest)?;
+            failures.into_result()?;
+            to_result(outdated)
+        }
+        "shutdown" => Ok(Value::Null),
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{}'.", method),
+        )),
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Option<Value>) -> Result<T, RpcError> {
+    serde_json::from_value(params.unwrap_or(Value::Null))
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e)))
+}
+
+fn to_result<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| anyhow::Error::from(e).into())
+}
+
+/// Write one response line to stdout
+fn respond(id: Value, outcome: Result<Value, RpcError>) -> Result<()> {
+    let (result, error) = match outcome {
+        Ok(result) => (Some(result), None),
+        Err(error) => (None, Some(error)),
+    };
+    let response = Response {
+        jsonrpc: "2.0",
+        id,
+        result,
+        error,
+    };
+
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", serde_json::to_string(&response)?)?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,303 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::UNIX_EPOCH;
+use tracing::{debug, info};
+
+use crate::advisory::load_advisory_db;
+use crate::commands::audit::{find_vulnerabilities, Vulnerability};
+use crate::commands::outdated::{find_outdated, OutdatedDependency};
+use crate::graph::DependencyGraph;
+use crate::lockfile::read_sop_lock_or_default;
+use crate::style;
+use crate::toml_parser::read_sop_toml;
+use crate::utils::{ensure_in_project, get_sop_lock_path, get_sop_toml_path};
+
+/// Everything the dashboard page shows, as served at `/report.json`
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    /// Changes whenever sop.toml or sop.lock does, so the page knows to reload
+    stamp: String,
+    name: &'a str,
+    version: &'a str,
+    graph: Vec<Node<'a>>,
+    outdated: Vec<OutdatedDependency>,
+    /// Why outdated packages couldn't be looked up, such as being offline
+    outdated_error: Option<String>,
+    vulnerabilities: Vec<Vulnerability<'a>>,
+    audit_error: Option<String>,
+}
+
+/// A package in the dependency graph, with the project itself first
+#[derive(Debug, Serialize)]
+struct Node<'a> {
+    name: &'a str,
+    version: &'a str,
+    dependencies: &'a [String],
+}
+
+/// Execute the dashboard command
+///
+/// Serves a page on localhost showing the dependency graph, outdated packages and audit
+/// findings. The page checks every few seconds whether sop.toml or sop.lock changed and
+/// reloads the report when they did. Runs until interrupted.
+pub fn execute(port: u16) -> Result<()> {
+    ensure_in_project()?;
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| {
+        anyhow!(
+            "Couldn't listen on port {}: {}. Pass --port to use another.",
+            port,
+            e
+        )
+    })?;
+    info!(
+        "{} Serving the dashboard at {} (press Ctrl+C to stop)",
+        style::ok_mark().bold(),
+        style::accent(&format!("http://127.0.0.1:{}", port))
+    );
+
+    // The report needs the registry and advisory database, so it is only rebuilt when the
+    // project changes
+    let mut report: Option<(String, String)> = None;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                debug!("Dashboard connection failed: {}", e);
+                continue;
+            }
+        };
+        if let Err(e) = serve(stream, &mut report) {
+            debug!("Dashboard request failed: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Answer one request
+fn serve(mut stream: TcpStream, report: &mut Option<(String, String)>) -> Result<()> {
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(&stream);
+    reader.read_line(&mut request_line)?;
+    // The headers aren't needed, but are read so the client sees a clean close
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path = path.split('?').next().unwrap_or(path);
+    match path {
+        "/" => respond(&mut stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        "/stamp" => respond(&mut stream, "200 OK", "text/plain", &stamp()),
+        "/report.json" => {
+            let stamp = stamp();
+            let json = match report {
+                Some((built, json)) if *built == stamp => json.clone(),
+                _ => {
+                    info!(
+                        "{}",
+                        style::info("Rebuilding the dashboard report...").bold()
+                    );
+                    match build_report(stamp.clone()) {
+                        Ok(json) => {
+                            *report = Some((stamp, json.clone()));
+                            json
+                        }
+                        Err(e) => {
+                            let message = e.to_string();
+                            return respond(
+                                &mut stream,
+                                "500 Internal Server Error",
+                                "text/plain",
+                                &message,
+                            );
+                        }
+                    }
+                }
+            };
+            respond(&mut stream, "200 OK", "application/json", &json)
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "Not found"),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Describe when sop.toml and sop.lock last changed
+fn stamp() -> String {
+    [get_sop_toml_path(), get_sop_lock_path()]
+        .iter()
+        .map(|path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |since| since.as_nanos())
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn build_report(stamp: String) -> Result<String> {
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+    let graph = DependencyGraph::from_manifest_and_lock(&config, &lock);
+
+    let root = Node {
+        name: &graph.root_name,
+        version: &graph.root_version,
+        dependencies: &graph.roots,
+    };
+    let packages = graph.nodes.iter().map(|(name, node)| Node {
+        name,
+        version: &node.version,
+        dependencies: &node.dependencies,
+    });
+
+    let (outdated, outdated_error) = match find_outdated(false) {
+        Ok((outdated, failures)) => (outdated, failures.into_result().err()),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    let db = load_advisory_db();
+    let (vulnerabilities, audit_error) = match &db {
+        Ok(db) => (find_vulnerabilities(&lock, &graph, db, &[]).1, None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+
+    let report = Report {
+        stamp,
+        name: &graph.root_name,
+        version: &graph.root_version,
+        graph: [root].into_iter().chain(packages).collect(),
+        outdated,
+        outdated_error: outdated_error.map(|e| e.to_string()),
+        vulnerabilities,
+        audit_error,
+    };
+    Ok(serde_json::to_string(&report)?)
+}
+
+/// The dashboard page, which renders `/report.json` and polls `/stamp` for changes
+const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>sop dashboard</title>
+<style>
+  body { font: 15px/1.5 system-ui, sans-serif; margin: 2rem auto; max-width: 60rem; padding: 0 1rem; color: #222; }
+  h1 small { color: #888; font-weight: normal; }
+  section { border: 1px solid #ddd; border-radius: 6px; padding: 0 1rem 1rem; margin-bottom: 1.5rem; }
+  ul.tree, ul.tree ul { list-style: none; padding-left: 1.2rem; margin: 0; }
+  ul.tree { padding-left: 0; }
+  summary { cursor: pointer; }
+  .version, .muted { color: #888; }
+  .error { color: #b00020; }
+  table { border-collapse: collapse; width: 100%; }
+  th, td { text-align: left; padding: .25rem .5rem; border-bottom: 1px solid #eee; }
+  .major, .critical, .high { color: #b00020; font-weight: bold; }
+  .minor, .medium { color: #b26a00; }
+  .patch, .low { color: #2e7d32; }
+</style>
+</head>
+<body>
+<h1 id="title">sop dashboard</h1>
+<section><h2>Dependencies</h2><ul class="tree" id="graph"></ul></section>
+<section><h2>Outdated</h2><div id="outdated"></div></section>
+<section><h2>Audit</h2><div id="audit"></div></section>
+<p class="muted" id="updated"></p>
+<script>
+let stamp = null;
+
+function el(tag, attrs, ...children) {
+  const node = document.createElement(tag);
+  Object.assign(node, attrs || {});
+  for (const child of children) node.append(child);
+  return node;
+}
+
+function renderTree(nodes, name, ancestors) {
+  const node = nodes.get(name);
+  const label = el("span", {}, name, " ", el("span", { className: "version" }, node ? "v" + node.version : ""));
+  const deps = node ? node.dependencies : [];
+  if (deps.length === 0 || ancestors.includes(name)) return el("li", {}, label);
+  const list = el("ul", {}, ...deps.map(dep => renderTree(nodes, dep, ancestors.concat(name))));
+  return el("li", {}, el("details", { open: ancestors.length < 2 }, el("summary", {}, label), list));
+}
+
+function table(headings, rows) {
+  return el("table", {},
+    el("tr", {}, ...headings.map(heading => el("th", {}, heading))),
+    ...rows.map(cells => el("tr", {}, ...cells.map(cell => el("td", {}, cell)))));
+}
+
+function render(report) {
+  document.getElementById("title").replaceChildren(report.name, " ", el("small", {}, "v" + report.version));
+
+  const nodes = new Map(report.graph.map(node => [node.name, node]));
+  const root = report.graph[0];
+  const graph = document.getElementById("graph");
+  graph.replaceChildren(...root.dependencies.map(dep => renderTree(nodes, dep, [root.name])));
+  if (root.dependencies.length === 0) graph.append(el("li", { className: "muted" }, "No dependencies"));
+
+  const outdated = document.getElementById("outdated");
+  outdated.replaceChildren(report.outdated.length === 0
+    ? el("p", { className: "muted" }, "All dependencies are up to date.")
+    : table(["Package", "Installed", "Required", "Latest", "Update"], report.outdated.map(dep => [
+        dep.name, dep.installed || "-", dep.required, dep.latest,
+        el("span", { className: dep.update || "" }, dep.update || "not installed")])));
+  if (report.outdated_error) outdated.append(el("p", { className: "error" }, report.outdated_error));
+
+  const audit = document.getElementById("audit");
+  audit.replaceChildren(report.vulnerabilities.length === 0
+    ? el("p", { className: "muted" }, "No known vulnerabilities.")
+    : table(["Severity", "Package", "Advisory", "Fixed in", "Path"], report.vulnerabilities.map(v => [
+        el("span", { className: v.severity }, v.severity),
+        v.package + " v" + v.version,
+        v.url ? el("a", { href: v.url }, v.id + ": " + v.title) : v.id + ": " + v.title,
+        v.patched || "no fix yet",
+        v.path.join(" > ")])));
+  if (report.audit_error) audit.append(el("p", { className: "error" }, report.audit_error));
+
+  document.getElementById("updated").textContent = "Updated " + new Date().toLocaleTimeString();
+}
+
+async function refresh() {
+  try {
+    const current = await (await fetch("/stamp")).text();
+    if (current !== stamp) {
+      const response = await fetch("/report.json");
+      stamp = current;
+      if (response.ok) {
+        render(await response.json());
+      } else {
+        document.getElementById("updated").replaceChildren(el("pre", { className: "error" }, await response.text()));
+      }
+    }
+  } catch (e) {
+    document.getElementById("updated").textContent = "Lost connection to sop dashboard";
+  }
+  setTimeout(refresh, 2000);
+}
+
+refresh();
+</script>
+</body>
+</html>
+"#;
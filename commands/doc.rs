@@ -0,0 +1,339 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::Colorize;
+use regex::Regex;
+use std::fs;
+use std::path::{Component, Path};
+use std::sync::OnceLock;
+use tracing::info;
+
+use crate::install::package_field;
+use crate::style;
+use crate::toml_parser::read_sop_toml;
+use crate::utils::{
+    ensure_dir_exists, ensure_in_project, escape_html, find_files_with_extension,
+    get_sop_modules_path, get_sop_toml_path, get_src_path, installed_packages,
+    open_file_in_browser, project_path, project_root,
+};
+
+/// Where the documentation is written, relative to the project root
+const DOC_DIR: &str = "target/doc";
+
+/// Output formats supported by the doc command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DocFormat {
+    /// A page per package, browsable offline
+    Html,
+    /// A Markdown file per package, for wikis and repositories
+    Markdown,
+}
+
+impl DocFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            DocFormat::Html => "html",
+            DocFormat::Markdown => "md",
+        }
+    }
+}
+
+/// The documentation of the project or one of its dependencies
+struct PackageDoc {
+    name: String,
+    version: String,
+    description: String,
+    modules: Vec<ModuleDoc>,
+}
+
+/// The documentation of one source file
+struct ModuleDoc {
+    /// Path of the file within its package
+    path: String,
+    /// The file's `//!` comments
+    docs: String,
+    items: Vec<ItemDoc>,
+}
+
+/// A top-level function or class with the `///` comments above it
+struct ItemDoc {
+    name: String,
+    /// The declaration line, without its opening brace
+    signature: String,
+    docs: String,
+}
+
+/// Execute the doc command
+///
+/// Reads the doc comments of every `.so` file under src/ and, unless `no_deps`, in every
+/// installed package, and writes them to target/doc: `//!` comments describe the file they
+/// are in, and `///` comments the function or class declared below them. `open` opens the
+/// index afterwards.
+pub fn execute(format: DocFormat, open: bool, no_deps: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let mut packages = vec![PackageDoc {
+        name: config.project.name.clone(),
+        version: config.project.version.clone(),
+        description: config.project.description.clone(),
+        modules: document_dir(&get_src_path(), &project_root())?,
+    }];
+    if !no_deps {
+        for (name, dir) in installed_packages(&get_sop_modules_path())? {
+            packages.push(PackageDoc {
+                version: package_field(&dir, "version").unwrap_or_default(),
+                description: package_field(&dir, "description").unwrap_or_default(),
+                modules: document_dir(&dir, &dir)?,
+                name,
+            });
+        }
+    }
+
+    let doc_dir = project_path(DOC_DIR);
+    if doc_dir.exists() {
+        fs::remove_dir_all(&doc_dir)?;
+    }
+    ensure_dir_exists(&doc_dir)?;
+
+    let extension = format.extension();
+    for package in &packages {
+        let page = match format {
+            DocFormat::Html => render_package_html(package, &packages),
+            DocFormat::Markdown => render_package_markdown(package),
+        };
+        fs::write(
+            doc_dir.join(format!("{}.{}", page_name(&package.name), extension)),
+            page,
+        )?;
+    }
+    let index = doc_dir.join(format!("index.{}", extension));
+    let content = match format {
+        DocFormat::Html => render_index_html(&packages),
+        DocFormat::Markdown => render_index_markdown(&packages),
+    };
+    fs::write(&index, content)?;
+
+    let modules: usize = packages.iter().map(|package| package.modules.len()).sum();
+    info!(
+        "{} Documented {} module{} in {} package{}: {}",
+        style::ok_mark().bold(),
+        modules,
+        if modules == 1 { "" } else { "s" },
+        packages.len(),
+        if packages.len() == 1 { "" } else { "s" },
+        style::accent(&format!("{}/index.{}", DOC_DIR, extension))
+    );
+
+    if open {
+        open_file_in_browser(&index)?;
+    }
+    Ok(())
+}
+
+/// Document every `.so` file under `dir`, naming them relative to `base`
+///
+/// Packages vendored inside a package's own sop_modules are left to their own page.
+fn document_dir(dir: &Path, base: &Path) -> Result<Vec<ModuleDoc>> {
+    let mut modules = Vec::new();
+    for file in find_files_with_extension(dir, "so")? {
+        let relative = file.strip_prefix(base).unwrap_or(&file);
+        let nested = relative
+            .components()
+            .any(|component| component == Component::Normal("sop_modules".as_ref()));
+        if nested {
+            continue;
+        }
+        let (docs, items) = parse_module(&fs::read_to_string(&file)?);
+        modules.push(ModuleDoc {
+            path: relative.to_string_lossy().replace('\\', "/"),
+            docs,
+            items,
+        });
+    }
+    Ok(modules)
+}
+
+fn declaration_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"^(?:export\s+)?(?:howl|hawl|fn|fasalka|class)\s+([A-Za-z_][A-Za-z0-9_]*)")
+            .expect("valid declaration regex")
+    })
+}
+
+/// Collect a source file's `//!` comments and its top-level declarations with their `///`
+/// comments
+fn parse_module(source: &str) -> (String, Vec<ItemDoc>) {
+    let comment = |text: &str| {
+        text.strip_prefix(' ')
+            .unwrap_or(text)
+            .trim_end()
+            .to_string()
+    };
+
+    let mut module_docs = Vec::new();
+    let mut pending: Vec<String> = Vec::new();
+    let mut items = Vec::new();
+    for line in source.lines() {
+        let trimmed = line.trim_start();
+        if let Some(text) = trimmed.strip_prefix("//!") {
+            module_docs.push(comment(text));
+        } else if let Some(text) = trimmed.strip_prefix("///") {
+            pending.push(comment(text));
+        } else if let Some(captures) = declaration_regex().captures(line) {
+            items.push(ItemDoc {
+                name: captures[1].to_string(),
+                signature: line.trim().trim_end_matches('{').trim_end().to_string(),
+                docs: pending.join("\n").trim().to_string(),
+            });
+            pending.clear();
+        } else if !trimmed.is_empty() {
+            pending.clear();
+        }
+    }
+    (module_docs.join("\n").trim().to_string(), items)
+}
+
+/// File name, without extension, of a package's page; scoped names lose their slash
+fn page_name(package: &str) -> String {
+    package.replace('/', "-")
+}
+
+fn render_index_markdown(packages: &[PackageDoc]) -> String {
+    let mut output = String::new();
+    for (index, package) in packages.iter().enumerate() {
+        if index == 0 {
+            output.push_str(&format!("# {} documentation\n\n", package.name));
+        } else if index == 1 {
+            output.push_str("\n## Dependencies\n\n");
+        }
+        output.push_str(&format!(
+            "- [{}]({}.md) v{}",
+            package.name,
+            page_name(&package.name),
+            package.version
+        ));
+        if !package.description.is_empty() {
+            output.push_str(&format!(": {}", package.description));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+fn render_package_markdown(package: &PackageDoc) -> String {
+    let mut output = format!("# {} v{}\n", package.name, package.version);
+    if !package.description.is_empty() {
+        output.push_str(&format!("\n{}\n", package.description));
+    }
+    for module in &package.modules {
+        output.push_str(&format!("\n## {}\n", module.path));
+        if !module.docs.is_empty() {
+            output.push_str(&format!("\n{}\n", module.docs));
+        }
+        for item in &module.items {
+            output.push_str(&format!("\n### `{}`\n", item.signature));
+            if !item.docs.is_empty() {
+                output.push_str(&format!("\n{}\n", item.docs));
+            }
+        }
+    }
+    output
+}
+
+const STYLE: &str =
+    "body { font: 15px/1.5 system-ui, sans-serif; margin: 0; display: flex; color: #222; }
+nav { min-width: 14rem; padding: 1rem; background: #f6f6f6; min-height: 100vh; }
+nav a { display: block; color: #333; text-decoration: none; padding: .1rem 0; }
+nav a.current { font-weight: bold; }
+main { padding: 1rem 2rem; max-width: 50rem; }
+h2 { border-bottom: 1px solid #ddd; }
+code, h3 { font-family: ui-monospace, monospace; }
+.version, .empty { color: #888; }";
+
+/// Wrap a page body with the stylesheet and a list of every package
+fn html_page(title: &str, current: Option<&str>, packages: &[PackageDoc], body: &str) -> String {
+    let links: String = packages
+        .iter()
+        .map(|package| {
+            format!(
+                "<a href=\"{}.html\"{}>{}</a>\n",
+                escape_html(&page_name(&package.name)),
+                if current == Some(package.name.as_str()) {
+                    " class=\"current\""
+                } else {
+                    ""
+                },
+                escape_html(&package.name)
+            )
+        })
+        .collect();
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<nav>\n<a href=\"index.html\">Index</a>\n{}</nav>\n<main>\n{}</main>\n</body>\n</html>\n",
+        escape_html(title),
+        STYLE,
+        links,
+        body
+    )
+}
+
+/// Render comment text as paragraphs split at blank lines
+fn html_paragraphs(text: &str) -> String {
+    text.split("\n\n")
+        .filter(|paragraph| !paragraph.trim().is_empty())
+        .map(|paragraph| format!("<p>{}</p>\n", escape_html(paragraph)))
+        .collect()
+}
+
+fn render_index_html(packages: &[PackageDoc]) -> String {
+    let mut body = String::new();
+    for (index, package) in packages.iter().enumerate() {
+        if index == 0 {
+            body.push_str(&format!(
+                "<h1>{} documentation</h1>\n<ul>\n",
+                escape_html(&package.name)
+            ));
+        } else if index == 1 {
+            body.push_str("</ul>\n<h2>Dependencies</h2>\n<ul>\n");
+        }
+        body.push_str(&format!(
+            "<li><a href=\"{}.html\">{}</a> <span class=\"version\">v{}</span> {}</li>\n",
+            escape_html(&page_name(&package.name)),
+            escape_html(&package.name),
+            escape_html(&package.version),
+            escape_html(&package.description)
+        ));
+    }
+    body.push_str("</ul>\n");
+    let title = format!("{} documentation", packages[0].name);
+    html_page(&title, None, packages, &body)
+}
+
+fn render_package_html(package: &PackageDoc, packages: &[PackageDoc]) -> String {
+    let mut body = format!(
+        "<h1>{} <span class=\"version\">v{}</span></h1>\n{}",
+        escape_html(&package.name),
+        escape_html(&package.version),
+        html_paragraphs(&package.description)
+    );
+    if package.modules.is_empty() {
+        body.push_str("<p class=\"empty\">No Soplang source files.</p>\n");
+    }
+    for module in &package.modules {
+        body.push_str(&format!(
+            "<h2>{}</h2>\n{}",
+            escape_html(&module.path),
+            html_paragraphs(&module.docs)
+        ));
+        for item in &module.items {
+            body.push_str(&format!(
+                "<h3 id=\"{}\">{}</h3>\n{}",
+                escape_html(&item.name),
+                escape_html(&item.signature),
+                html_paragraphs(&item.docs)
+            ));
+        }
+    }
+    let title = format!("{} v{}", package.name, package.version);
+    html_page(&title, Some(&package.name), packages, &body)
+}
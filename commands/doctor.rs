@@ -0,0 +1,390 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::commands::remove::orphaned_packages;
+use crate::config::{get_project_config_path, get_user_config_path, read_config, UserConfig};
+use crate::interpreter::{
+    interpreter_program, interpreter_semver, interpreter_version, soplang_requirement,
+};
+use crate::lockfile::read_sop_lock;
+use crate::mirrors::{read_mirror_health, registry_urls};
+use crate::registry;
+use crate::style;
+use crate::toml_parser::{read_sop_toml, SopToml};
+use crate::utils::{
+    get_cache_path, get_global_bin_path, get_sop_home, get_sop_lock_path, get_sop_modules_path,
+    get_sop_toml_path, is_soplang_project,
+};
+
+/// How a single check came out
+enum Outcome {
+    Pass(String),
+    /// Works, but something deserves attention
+    Warn(String, String),
+    /// Broken, with what to do about it
+    Fail(String, String),
+    Skip(String),
+}
+
+/// Execute the doctor command
+///
+/// Every check runs even when an earlier one fails, so one run lists everything that needs
+/// fixing. The command fails if any check did.
+pub fn execute() -> Result<()> {
+    let config = read_config();
+
+    let mut checks: Vec<(&str, Outcome)> = vec![
+        ("Interpreter", check_interpreter()),
+        ("Config", check_config(&config)),
+    ];
+    checks.extend(check_registries(&config));
+    checks.push(("Cache", check_cache_health()));
+    checks.extend(check_writable_paths());
+    if is_soplang_project() {
+        checks.extend(check_project());
+    } else {
+        checks.push((
+            "Project",
+            Outcome::Skip("not in a Soplang project".to_string()),
+        ));
+    }
+
+    let mut failed = 0;
+    let mut warned = 0;
+    for (name, outcome) in &checks {
+        match outcome {
+            Outcome::Pass(detail) => info!("  {} {}: {}", style::ok_mark(), name.bold(), detail),
+            Outcome::Skip(detail) => {
+                info!(
+                    "  {} {}: {}",
+                    style::skip_mark(),
+                    name.bold(),
+                    style::dim(detail)
+                )
+            }
+            Outcome::Warn(detail, fix) => {
+                warned += 1;
+                info!("  {} {}: {}", style::warn_mark(), name.bold(), detail);
+                info!("      {} {}", style::info("fix:"), fix);
+            }
+            Outcome::Fail(detail, fix) => {
+                failed += 1;
+                info!("  {} {}: {}", style::error_mark(), name.bold(), detail);
+                info!("      {} {}", style::info("fix:"), fix);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} check{} failed",
+            failed,
+            if failed == 1 { "" } else { "s" }
+        ));
+    }
+    if warned > 0 {
+        info!(
+            "{} No problems found, {} warning{}",
+            style::ok_mark().bold(),
+            warned,
+            if warned == 1 { "" } else { "s" }
+        );
+    } else {
+        info!("{} No problems found", style::ok_mark().bold());
+    }
+    Ok(())
+}
+
+/// Check that the interpreter runs and reports its version
+fn check_interpreter() -> Outcome {
+    let program = interpreter_program();
+    match interpreter_version() {
+        Some(version) => Outcome::Pass(format!(
+            "{} ({})",
+            version.lines().next().unwrap_or_default(),
+            program
+        )),
+        None => Outcome::Fail(
+            format!("'{} --version' did not run", program),
+            "Install Soplang and put 'soplang' on PATH, or set $SOPLANG to the interpreter."
+                .to_string(),
+        ),
+    }
+}
+
+/// Check the interpreter against the project's `soplang` requirement, if it has one
+fn check_soplang_requirement(config: &SopToml) -> Option<Outcome> {
+    let requirement = match soplang_requirement(config) {
+        Ok(requirement) => requirement?,
+        Err(e) => {
+            return Some(Outcome::Fail(
+                format!("{:#}", e),
+                "Use a version requirement like \">=0.4\" for soplang in sop.toml.".to_string(),
+            ))
+        }
+    };
+    Some(match interpreter_semver() {
+        Some(version) if requirement.matches(&version) => Outcome::Pass(format!(
+            "{} satisfies soplang = \"{}\"",
+            version, requirement
+        )),
+        Some(version) => Outcome::Fail(
+            format!("{} doesn't satisfy soplang = \"{}\"", version, requirement),
+            "Install a matching Soplang, or set $SOPLANG to one.".to_string(),
+        ),
+        None => Outcome::Skip(format!(
+            "interpreter version unknown, soplang = \"{}\" not checked",
+            requirement
+        )),
+    })
+}
+
+/// Check that the user and project config files parse
+fn check_config(config: &Result<UserConfig>) -> Outcome {
+    match config {
+        Ok(_) => {
+            let mut files = vec![get_user_config_path()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default()];
+            let project_config = get_project_config_path();
+            if is_soplang_project() && project_config.exists() {
+                files.push(project_config.display().to_string());
+            }
+            Outcome::Pass(format!("valid ({})", files.join(", ")))
+        }
+        Err(e) => Outcome::Fail(
+            format!("{:#}", e),
+            "Correct the file, or run 'sop config unset <key>' for the offending setting."
+                .to_string(),
+        ),
+    }
+}
+
+/// Check that every registry and mirror downloads would use answers
+fn check_registries(config: &Result<UserConfig>) -> Vec<(&'static str, Outcome)> {
+    if config.as_ref().is_ok_and(|config| config.offline) {
+        return vec![(
+            "Registry",
+            Outcome::Skip("offline mode is on, registries are not contacted".to_string()),
+        )];
+    }
+
+    registry_urls()
+        .into_iter()
+        .map(|url| {
+            let outcome = match registry::ping(&url) {
+                Ok(()) => Outcome::Pass(format!("{} is reachable", url)),
+                Err(e) => Outcome::Fail(
+                    format!("{} is unreachable: {:#}", url, e),
+                    "Check your network and proxy settings, or correct the URL with 'sop config set registry.url <url>'."
+                        .to_string(),
+                ),
+            };
+            ("Registry", outcome)
+        })
+        .collect()
+}
+
+/// Check the recorded health of the registries packages were downloaded from
+fn check_cache_health() -> Outcome {
+    let health = match read_mirror_health() {
+        Ok(health) => health,
+        Err(e) => {
+            return Outcome::Warn(
+                format!("registry health records are unreadable: {:#}", e),
+                "Delete the file under ~/.sop/cache; it is rebuilt on the next download."
+                    .to_string(),
+            )
+        }
+    };
+
+    let unhealthy: Vec<&str> = health
+        .registries
+        .iter()
+        .filter(|(_, health)| health.is_unhealthy())
+        .map(|(url, _)| url.as_str())
+        .collect();
+    if unhealthy.is_empty() {
+        Outcome::Pass(format!(
+            "{} registr{} with no recent failures",
+            health.registries.len(),
+            if health.registries.len() == 1 {
+                "y"
+            } else {
+                "ies"
+            }
+        ))
+    } else {
+        Outcome::Warn(
+            format!("recent downloads failed from {}", unhealthy.join(", ")),
+            "These are tried last for a few minutes. Check them with 'sop cache stats', or remove a dead mirror from the config."
+                .to_string(),
+        )
+    }
+}
+
+/// Check that sop can write where it keeps its own files and installs packages
+fn check_writable_paths() -> Vec<(&'static str, Outcome)> {
+    let mut paths: Vec<(&str, Result<PathBuf>)> = vec![
+        ("Home", get_sop_home()),
+        ("Cache dir", get_cache_path()),
+        ("Bin dir", get_global_bin_path()),
+    ];
+    if is_soplang_project() {
+        paths.push(("Modules dir", Ok(get_sop_modules_path())));
+    }
+
+    paths
+        .into_iter()
+        .map(|(name, path)| {
+            let outcome = match path {
+                Ok(path) => match check_writable(&path) {
+                    Ok(()) => Outcome::Pass(format!("{} is writable", path.display())),
+                    Err(e) => Outcome::Fail(
+                        format!("{} is not writable: {:#}", path.display(), e),
+                        format!(
+                            "Fix the permissions of {}, or point $SOP_HOME somewhere writable.",
+                            path.display()
+                        ),
+                    ),
+                },
+                Err(e) => Outcome::Fail(
+                    format!("{:#}", e),
+                    "Set $SOP_HOME to the directory sop should use.".to_string(),
+                ),
+            };
+            (name, outcome)
+        })
+        .collect()
+}
+
+/// Try creating a file in a directory, or in its nearest existing parent if it doesn't exist yet
+fn check_writable(path: &Path) -> Result<()> {
+    let dir = path
+        .ancestors()
+        .find(|dir| dir.is_dir())
+        .ok_or_else(|| anyhow!("no parent directory exists"))?;
+    let probe = dir.join(format!(".sop-doctor-{}", std::process::id()));
+    fs::write(&probe, b"")?;
+    fs::remove_file(&probe)?;
+    Ok(())
+}
+
+/// Check that sop.toml, sop.lock and sop_modules agree
+fn check_project() -> Vec<(&'static str, Outcome)> {
+    let config = match read_sop_toml(&get_sop_toml_path()) {
+        Ok(config) => config,
+        Err(e) => {
+            return vec![(
+                "Manifest",
+                Outcome::Fail(
+                    format!("sop.toml is invalid: {:#}", e),
+                    "Correct sop.toml, or restore sop.toml.bak if a recent change broke it."
+                        .to_string(),
+                ),
+            )]
+        }
+    };
+    let mut checks = vec![(
+        "Manifest",
+        Outcome::Pass(format!(
+            "{} v{}",
+            config.project.name, config.project.version
+        )),
+    )];
+
+    if let Some(outcome) = check_soplang_requirement(&config) {
+        checks.push(("Soplang", outcome));
+    }
+
+    let lock_path = get_sop_lock_path();
+    if !lock_path.exists() {
+        let outcome = if config.all_dependencies().is_empty() {
+            Outcome::Skip("no sop.lock, and no dependencies to lock".to_string())
+        } else {
+            Outcome::Fail(
+                "sop.lock is missing".to_string(),
+                "Run 'sop setup' to resolve and install the dependencies.".to_string(),
+            )
+        };
+        checks.push(("Lockfile", outcome));
+        return checks;
+    }
+    let lock = match read_sop_lock(&lock_path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            checks.push((
+                "Lockfile",
+                Outcome::Fail(
+                    format!("sop.lock is invalid: {:#}", e),
+                    "Delete sop.lock and run 'sop setup' to resolve the dependencies again."
+                        .to_string(),
+                ),
+            ));
+            return checks;
+        }
+    };
+
+    let mut unlocked: Vec<&str> = config
+        .all_dependencies()
+        .into_iter()
+        .map(|(name, _, _)| name.as_str())
+        .filter(|name| lock.get(name).is_none())
+        .collect();
+    unlocked.sort();
+    let orphans = orphaned_packages(&config, &lock);
+    checks.push((
+        "Lockfile",
+        if !unlocked.is_empty() {
+            Outcome::Fail(
+                format!("not in sop.lock: {}", unlocked.join(", ")),
+                "Run 'sop setup' to resolve the new dependencies.".to_string(),
+            )
+        } else if !orphans.is_empty() {
+            Outcome::Warn(
+                format!(
+                    "locked but no longer needed: {}",
+                    orphans.into_iter().collect::<Vec<_>>().join(", ")
+                ),
+                "Run 'sop setup' to drop them from sop.lock.".to_string(),
+            )
+        } else {
+            Outcome::Pass(format!(
+                "{} locked package{} match sop.toml",
+                lock.packages.len(),
+                if lock.packages.len() == 1 { "" } else { "s" }
+            ))
+        },
+    ));
+
+    let modules_dir = get_sop_modules_path();
+    let mut missing: Vec<&str> = lock
+        .packages
+        .iter()
+        .filter(|package| {
+            package.for_current_platform() && !modules_dir.join(&package.name).is_dir()
+        })
+        .map(|package| package.name.as_str())
+        .collect();
+    missing.sort();
+    checks.push((
+        "Installed",
+        if missing.is_empty() {
+            Outcome::Pass(format!(
+                "every locked package is in {}",
+                modules_dir.display()
+            ))
+        } else {
+            Outcome::Fail(
+                format!("not installed: {}", missing.join(", ")),
+                "Run 'sop setup' to install them, then 'sop verify' to check their contents."
+                    .to_string(),
+            )
+        },
+    ));
+
+    checks
+}
@@ -0,0 +1,213 @@
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::install::package_dependencies;
+use crate::lockfile::{read_sop_lock_or_default, SopLock};
+use crate::style;
+use crate::toml_parser::{read_sop_toml, SopToml};
+use crate::utils::{
+    ensure_in_project, get_sop_lock_path, get_sop_modules_path, get_sop_toml_path, project_root,
+};
+use crate::version::parse_version;
+use crate::workspace::load_members;
+
+/// A package resolved at more than one version
+#[derive(Debug, Serialize)]
+struct Duplicate {
+    name: String,
+    versions: Vec<VersionUse>,
+}
+
+/// One of the versions of a duplicated package, and what pulls it in
+#[derive(Debug, Serialize)]
+struct VersionUse {
+    version: String,
+    /// Projects whose sop.lock resolves the package to this version
+    projects: Vec<String>,
+    dependents: Vec<Dependent>,
+}
+
+/// A project or package asking for a duplicated package
+#[derive(Debug, PartialEq, Serialize)]
+struct Dependent {
+    name: String,
+    /// Resolved version of the package asking, `None` for a project
+    version: Option<String>,
+    /// What it asks for, `None` when that isn't a version requirement (a git or path
+    /// dependency) or the package isn't installed to read it from
+    requirement: Option<String>,
+    /// The project whose sop.lock this comes from
+    project: String,
+}
+
+/// A project whose resolved dependencies are checked
+struct Project {
+    config: SopToml,
+    lock: SopLock,
+    modules_dir: PathBuf,
+}
+
+/// Execute the duplicates command
+///
+/// Each project resolves one version of every package, so duplicates come from workspace
+/// members resolving a package differently. In a workspace root the member projects are
+/// compared with each other and with the root. With `json`, prints a list of
+/// `{"name", "versions"}` where each version has its `projects` and `dependents`.
+pub fn execute(json: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let in_workspace = config
+        .workspace
+        .as_ref()
+        .is_some_and(|workspace| !workspace.members.is_empty());
+    let mut projects = Vec::new();
+    if in_workspace {
+        for member in load_members(&project_root(), &config)? {
+            projects.push(Project {
+                lock: read_sop_lock_or_default(&member.dir.join("sop.lock"))?,
+                modules_dir: member.dir.join("sop_modules"),
+                config: member.config,
+            });
+        }
+    }
+    projects.push(Project {
+        lock: read_sop_lock_or_default(&get_sop_lock_path())?,
+        modules_dir: get_sop_modules_path(),
+        config,
+    });
+
+    let duplicates = find_duplicates(&projects);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&duplicates)?);
+        return Ok(());
+    }
+
+    if duplicates.is_empty() {
+        println!(
+            "{} No package is resolved at more than one version.",
+            style::ok_mark().bold()
+        );
+        if !in_workspace {
+            println!(
+                "{}",
+                style::dim("A project resolves one version of each package, so duplicates only show up across workspace members.")
+            );
+        }
+        return Ok(());
+    }
+
+    let several_projects = projects.len() > 1;
+    for duplicate in &duplicates {
+        println!(
+            "{} has {} versions:",
+            duplicate.name.bold(),
+            duplicate.versions.len()
+        );
+        for version in &duplicate.versions {
+            println!(
+                "  {} {}",
+                style::accent(&format!("v{}", version.version)),
+                style::dim(&format!("in {}", version.projects.join(", ")))
+            );
+            for dependent in &version.dependents {
+                let mut line = match &dependent.version {
+                    Some(version) => format!("{} v{}", dependent.name, version),
+                    None => dependent.name.clone(),
+                };
+                match &dependent.requirement {
+                    Some(requirement) => line.push_str(&format!(" requires {}", requirement)),
+                    None => line.push_str(" depends on it"),
+                }
+                // A dependent inside another project is ambiguous without naming the project
+                if several_projects && dependent.version.is_some() {
+                    line.push_str(&format!(
+                        " {}",
+                        style::dim(&format!("(in {})", dependent.project))
+                    ));
+                }
+                println!("    {} {}", style::arrow(), line);
+            }
+        }
+    }
+
+    println!(
+        "\n{} {} package{} resolved at more than one version. Aligning the requirements above leaves one version of each to update and audit.",
+        style::warn_mark().bold(),
+        duplicates.len(),
+        if duplicates.len() == 1 { " is" } else { "s are" }
+    );
+    Ok(())
+}
+
+/// Find the packages the projects resolve to different versions, with what asks for each
+fn find_duplicates(projects: &[Project]) -> Vec<Duplicate> {
+    let mut uses: BTreeMap<&str, BTreeMap<&str, VersionUse>> = BTreeMap::new();
+    for project in projects {
+        let project_name = &project.config.project.name;
+        for package in &project.lock.packages {
+            let entry = uses
+                .entry(&package.name)
+                .or_default()
+                .entry(&package.version)
+                .or_insert_with(|| VersionUse {
+                    version: package.version.clone(),
+                    projects: Vec::new(),
+                    dependents: Vec::new(),
+                });
+            if !entry.projects.contains(project_name) {
+                entry.projects.push(project_name.clone());
+            }
+
+            let from_project = project
+                .config
+                .find_dependency(&package.name)
+                .map(|dependency| Dependent {
+                    name: project_name.clone(),
+                    version: None,
+                    requirement: dependency.requirement().map(str::to_string),
+                    project: project_name.clone(),
+                });
+            let from_packages = project
+                .lock
+                .packages
+                .iter()
+                .filter(|other| other.dependencies.contains(&package.name))
+                .map(|other| Dependent {
+                    name: other.name.clone(),
+                    version: Some(other.version.clone()),
+                    requirement: package_dependencies(&project.modules_dir.join(&other.name))
+                        .into_iter()
+                        .find(|(name, _)| *name == package.name)
+                        .map(|(_, requirement)| requirement),
+                    project: project_name.clone(),
+                });
+            for dependent in from_project.into_iter().chain(from_packages) {
+                if !entry.dependents.contains(&dependent) {
+                    entry.dependents.push(dependent);
+                }
+            }
+        }
+    }
+
+    uses.into_iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .map(|(name, versions)| {
+            let mut versions: Vec<VersionUse> = versions.into_values().collect();
+            versions.sort_by(
+                |a, b| match (parse_version(&a.version), parse_version(&b.version)) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    _ => a.version.cmp(&b.version),
+                },
+            );
+            Duplicate {
+                name: name.to_string(),
+                versions,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,226 @@
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config::{get_project_config_path, get_user_config_path, read_config};
+use crate::interpreter::{interpreter_path, interpreter_program, interpreter_version};
+use crate::mirrors::registry_urls;
+use crate::registry;
+use crate::style;
+use crate::utils::{
+    get_cache_path, get_global_bin_path, get_sop_home, get_sop_lock_path, get_sop_modules_path,
+    get_sop_toml_path, is_soplang_project, project_dir,
+};
+
+/// Environment variables that change how sop behaves
+const SOP_VARIABLES: &[&str] = &[
+    "SOP_ENV",
+    "SOP_HOME",
+    "SOP_LOG",
+    "SOP_SYSTEM_STORE",
+    "SOP_THEME",
+    "SOPLANG",
+    "NO_COLOR",
+];
+
+/// Everything sop resolved about its surroundings, as printed by `sop env --json`
+#[derive(Debug, Serialize)]
+struct Environment {
+    version: String,
+    executable: Option<PathBuf>,
+    home: Option<PathBuf>,
+    cache_dir: Option<PathBuf>,
+    bin_dir: Option<PathBuf>,
+    /// `None` outside a project
+    project: Option<ProjectEnvironment>,
+    registry: RegistryEnvironment,
+    interpreter: InterpreterEnvironment,
+    config_files: Vec<ConfigFile>,
+    /// The variables from [`SOP_VARIABLES`] that are set
+    variables: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectEnvironment {
+    root: PathBuf,
+    manifest: PathBuf,
+    lockfile: PathBuf,
+    modules_dir: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+struct RegistryEnvironment {
+    url: String,
+    /// Every URL downloads are tried from, in order
+    download_order: Vec<String>,
+    offline: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct InterpreterEnvironment {
+    program: String,
+    /// `None` when the interpreter can't be found
+    path: Option<PathBuf>,
+    version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ConfigFile {
+    scope: &'static str,
+    path: PathBuf,
+    exists: bool,
+}
+
+/// Execute the env command
+pub fn execute(json: bool) -> Result<()> {
+    let environment = resolve()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&environment)?);
+    } else {
+        print_environment(&environment);
+    }
+    Ok(())
+}
+
+/// Gather the environment, with every path made absolute
+fn resolve() -> Result<Environment> {
+    let current_dir = std::env::current_dir()?;
+    let project = if is_soplang_project() {
+        Some(ProjectEnvironment {
+            root: project_dir()?,
+            manifest: current_dir.join(get_sop_toml_path()),
+            lockfile: current_dir.join(get_sop_lock_path()),
+            modules_dir: current_dir.join(get_sop_modules_path()),
+        })
+    } else {
+        None
+    };
+
+    let mut config_files = Vec::new();
+    if let Ok(path) = get_user_config_path() {
+        config_files.push(ConfigFile {
+            scope: "user",
+            exists: path.exists(),
+            path,
+        });
+    }
+    if project.is_some() {
+        let path = current_dir.join(get_project_config_path());
+        config_files.push(ConfigFile {
+            scope: "project",
+            exists: path.exists(),
+            path,
+        });
+    }
+
+    let program = interpreter_program();
+    let interpreter_path = interpreter_path();
+    Ok(Environment {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        executable: std::env::current_exe().ok(),
+        home: get_sop_home().ok(),
+        cache_dir: get_cache_path().ok(),
+        bin_dir: get_global_bin_path().ok(),
+        project,
+        registry: RegistryEnvironment {
+            url: registry::registry_url(),
+            download_order: registry_urls(),
+            offline: read_config()?.offline,
+        },
+        interpreter: InterpreterEnvironment {
+            version: interpreter_path
+                .as_ref()
+                .and_then(|_| interpreter_version())
+                .and_then(|version| version.lines().next().map(str::to_string)),
+            path: interpreter_path,
+            program,
+        },
+        config_files,
+        variables: SOP_VARIABLES
+            .iter()
+            .filter_map(|name| {
+                std::env::var(name)
+                    .ok()
+                    .map(|value| (name.to_string(), value))
+            })
+            .collect(),
+    })
+}
+
+/// Print the environment as aligned `name  value` lines
+fn print_environment(environment: &Environment) {
+    let display = |path: &Option<PathBuf>| {
+        path.as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| style::warning("unknown").to_string())
+    };
+    let row = |name: &str, value: &str| println!("{:<17} {}", name.bold(), value);
+
+    row(
+        "sop",
+        &format!(
+            "v{} ({})",
+            environment.version,
+            display(&environment.executable)
+        ),
+    );
+    row("home", &display(&environment.home));
+    row("cache", &display(&environment.cache_dir));
+    row("bin", &display(&environment.bin_dir));
+
+    match &environment.project {
+        Some(project) => {
+            row("project", &project.root.display().to_string());
+            row("manifest", &project.manifest.display().to_string());
+            row("lockfile", &project.lockfile.display().to_string());
+            row("modules", &project.modules_dir.display().to_string());
+        }
+        None => row("project", &style::dim("none").to_string()),
+    }
+
+    row(
+        "registry",
+        &format!(
+            "{}{}",
+            environment.registry.url,
+            if environment.registry.offline {
+                " (offline)"
+            } else {
+                ""
+            }
+        ),
+    );
+    if environment.registry.download_order.len() > 1 {
+        row(
+            "download from",
+            &environment.registry.download_order.join(", "),
+        );
+    }
+
+    let interpreter = &environment.interpreter;
+    row(
+        "interpreter",
+        &match (&interpreter.path, &interpreter.version) {
+            (Some(path), Some(version)) => format!("{} ({})", path.display(), version),
+            (Some(path), None) => format!("{} (version unknown)", path.display()),
+            (None, _) => style::warning(&format!("{} not found", interpreter.program)).to_string(),
+        },
+    );
+
+    for file in &environment.config_files {
+        row(
+            &format!("config ({})", file.scope),
+            &if file.exists {
+                file.path.display().to_string()
+            } else {
+                format!("{} {}", file.path.display(), style::dim("(not present)"))
+            },
+        );
+    }
+
+    for (name, value) in &environment.variables {
+        row(&format!("${}", name), value);
+    }
+}
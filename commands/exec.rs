@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::install::package_binaries;
+use crate::interpreter::{script_command, spawn_error};
+use crate::style;
+use crate::toml_parser::read_sop_toml;
+use crate::utils::{
+    dir_exists, ensure_in_project, get_sop_modules_path, get_sop_toml_path, installed_packages,
+};
+
+/// Execute the exec command
+pub fn execute(tool: &str, args: &[String]) -> Result<()> {
+    ensure_in_project()?;
+
+    let modules_dir = get_sop_modules_path();
+    let binary = match find_binary(&modules_dir, tool)? {
+        Some(binary) => binary,
+        None => {
+            // The tool may come from a dependency that hasn't been installed yet
+            if !has_missing_dependencies(&modules_dir)? {
+                return Err(anyhow!(
+                    "No installed package provides a binary named '{}'.",
+                    tool
+                ));
+            }
+
+            warn!(
+                "{} '{}' not found, installing missing dependencies...",
+                style::warn_mark(),
+                tool
+            );
+            crate::commands::setup::execute(false, None, false)?;
+
+            find_binary(&modules_dir, tool)?.ok_or_else(|| {
+                anyhow!("No installed package provides a binary named '{}'.", tool)
+            })?
+        }
+    };
+
+    info!("Running {} from {}", tool, binary.package);
+
+    let status = script_command(&binary.path)?
+        .args(args)
+        .status()
+        .map_err(spawn_error)?;
+
+    if !status.success() {
+        // Pass the binary's exit code through so scripts can branch on it
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    Ok(())
+}
+
+/// A binary exposed by an installed package through its `[bin]` table
+struct PackageBinary {
+    package: String,
+    path: PathBuf,
+}
+
+/// Look for a binary with the given name among the installed packages
+fn find_binary(modules_dir: &Path, tool: &str) -> Result<Option<PackageBinary>> {
+    if !dir_exists(modules_dir) {
+        return Ok(None);
+    }
+
+    for (package, package_dir) in installed_packages(modules_dir)? {
+        let found = package_binaries(&package_dir)
+            .into_iter()
+            .find(|(name, _)| name == tool);
+        if let Some((_, path)) = found {
+            return Ok(Some(PackageBinary { package, path }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Check whether any dependency declared in sop.toml is missing from the modules directory
+fn has_missing_dependencies(modules_dir: &Path) -> Result<bool> {
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    Ok(config
+        .all_dependencies()
+        .iter()
+        .any(|(package, _, _)| !dir_exists(&modules_dir.join(package))))
+}
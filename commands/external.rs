@@ -0,0 +1,54 @@
+use anyhow::{anyhow, Result};
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::exit_code::{with_exit_code, ExitCode};
+use crate::i18n::{tr, tr_args};
+use crate::plugin::PluginEnv;
+use crate::utils::{find_on_path, get_global_bin_path, is_executable};
+
+/// Execute an external subcommand: `sop <name> [args]` runs `sop-<name> [args]`
+///
+/// The executable is looked up on PATH, then among the binaries of globally installed
+/// packages, so extensions like `sop-deploy` can be installed either way. It gets the project
+/// context described by [`PluginEnv`], and its exit code is passed through.
+pub fn execute(args: &[String]) -> Result<()> {
+    let (name, rest) = args
+        .split_first()
+        .ok_or_else(|| anyhow!(tr("No command specified.")))?;
+    let program = find_external(name).ok_or_else(|| {
+        with_exit_code(
+            ExitCode::Usage,
+            anyhow!(tr_args(
+                "Unknown command '{}'. Run 'sop --help' for the built-in commands, or put an executable named 'sop-{}' on PATH to add it.",
+                &[name, name]
+            )),
+        )
+    })?;
+
+    let plugin_env = PluginEnv::prepare()?;
+    let status = plugin_env
+        .apply(Command::new(&program).args(rest))
+        .status()
+        .map_err(|e| anyhow!(tr_args("Failed to run {}: {}", &[&program.display(), &e])))?;
+    drop(plugin_env);
+
+    if !status.success() {
+        // Pass the extension's exit code through so scripts can branch on it
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    Ok(())
+}
+
+/// Find the executable implementing an external subcommand
+fn find_external(name: &str) -> Option<PathBuf> {
+    let file_name = format!("sop-{}{}", name, env::consts::EXE_SUFFIX);
+    find_on_path(&file_name).or_else(|| {
+        get_global_bin_path()
+            .ok()
+            .map(|dir| dir.join(&file_name))
+            .filter(|candidate| is_executable(candidate))
+    })
+}
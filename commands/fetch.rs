@@ -0,0 +1,82 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use tracing::info;
+
+use crate::cache;
+use crate::lockfile::read_sop_lock;
+use crate::style;
+use crate::utils::{ensure_in_project, file_exists, get_sop_lock_path};
+
+/// Execute the fetch command
+pub fn execute() -> Result<()> {
+    ensure_in_project()?;
+
+    // Fetching works from the lockfile so the cache matches exactly what setup will install
+    let sop_lock_path = get_sop_lock_path();
+    if !file_exists(&sop_lock_path) {
+        return Err(anyhow!(
+            "sop.lock not found. Run 'sop setup' to resolve dependencies and create it."
+        ));
+    }
+
+    let lock = read_sop_lock(&sop_lock_path)?;
+    if lock.packages.is_empty() {
+        info!("{}", style::warning("No packages in sop.lock."));
+        return Ok(());
+    }
+
+    info!(
+        "{}",
+        style::info("Fetching packages into the cache...").bold()
+    );
+
+    // Path dependencies are linked straight from disk and never cached
+    let packages: Vec<_> = lock
+        .packages
+        .iter()
+        .filter(|package| package.path_source().is_none())
+        .collect();
+
+    let mut downloaded_count = 0;
+    for package in &packages {
+        let was_cached = match package.git_source() {
+            Some((url, commit)) => {
+                let was_cached = cache::is_git_cached(url, commit)?;
+                cache::fetch_git_package(url, Some(commit), false)?;
+                was_cached
+            }
+            None => {
+                let registry = package.registry_source();
+                let was_cached = cache::is_cached(&package.name, &package.version, registry)?;
+                cache::fetch_package(&package.name, &package.version, registry, false)?;
+                was_cached
+            }
+        };
+
+        if was_cached {
+            info!(
+                "  {} {} v{} is already cached",
+                style::skip_mark(),
+                package.name,
+                package.version
+            );
+        } else {
+            info!(
+                "  {} {} v{}",
+                style::ok_mark(),
+                package.name,
+                package.version
+            );
+            downloaded_count += 1;
+        }
+    }
+
+    info!(
+        "\n{} Fetched {} packages ({} already cached). 'sop setup --offline' can now run without network access.",
+        style::ok_mark().bold(),
+        downloaded_count,
+        packages.len() - downloaded_count
+    );
+
+    Ok(())
+}
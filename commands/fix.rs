@@ -0,0 +1,392 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+
+use crate::style;
+use crate::toml_parser::{parse_sop_toml, render_sop_toml, write_sop_toml, Dependency, SopToml};
+use crate::utils::{ensure_in_project, get_sop_toml_path, project_path};
+use crate::version::{parse_version, split_requirement};
+
+/// The entry `sop init` creates, which `sop fix` points `entry` at when it is missing
+const DEFAULT_ENTRY: &str = "src/main.so";
+
+/// Lines of unchanged context shown around each change in the diff
+const DIFF_CONTEXT: usize = 2;
+
+/// A `key = value` line of a TOML document, found without parsing it
+struct KeyLine {
+    /// The table the key is in, `""` before the first header
+    table: String,
+    key: String,
+    /// The lines the key and its value take up, more than one for a multi-line value
+    lines: Range<usize>,
+}
+
+/// Execute the fix command
+///
+/// Applies the corrections that can't change what the project means: duplicate keys are
+/// dropped (the last one wins, as it would be read last), versions are written as full semantic
+/// versions, a missing `entry` is pointed at src/main.so if that exists, empty dependency names,
+/// tables and keywords go, and dependency tables are sorted. The changes are shown as a diff
+/// before sop.toml is written, which keeps the previous version as sop.toml.bak; with
+/// `dry_run`, nothing is written.
+pub fn execute(dry_run: bool) -> Result<()> {
+    ensure_in_project()?;
+    let path = get_sop_toml_path();
+    let original = fs::read_to_string(&path)?;
+
+    let mut fixes = Vec::new();
+    let content = remove_duplicate_keys(&original, &mut fixes);
+    let mut config = parse_sop_toml(&content, &path)
+        .map_err(|e| anyhow!("sop.toml has problems 'sop fix' can't repair: {:#}", e))?;
+    normalize_versions(&mut config, &mut fixes);
+    fill_entry(&mut config, &mut fixes);
+    remove_empty(&mut config, &mut fixes);
+
+    let fixed = render_sop_toml(&config)?;
+    report_sorted_tables(&content, &fixed, &mut fixes);
+    if fixed == original {
+        println!("{} sop.toml has nothing to fix", style::ok_mark().bold());
+        return Ok(());
+    }
+    if fixes.is_empty() {
+        fixes.push("Rewrote sop.toml in the form sop writes it".to_string());
+    }
+
+    for fix in &fixes {
+        println!("  {} {}", style::ok_mark(), fix);
+    }
+    println!();
+    print_diff(&original, &fixed);
+
+    if dry_run {
+        println!("\n{} sop.toml was not changed", "Dry run:".bold());
+        return Ok(());
+    }
+    write_sop_toml(&path, &config)?;
+    println!(
+        "\n{} Fixed sop.toml (the previous version is in sop.toml.bak)",
+        style::ok_mark().bold()
+    );
+    Ok(())
+}
+
+/// Drop every `key = value` a later one in the same table repeats, which TOML rejects
+fn remove_duplicate_keys(content: &str, fixes: &mut Vec<String>) -> String {
+    let mut last: HashMap<(&str, &str), usize> = HashMap::new();
+    let keys = key_lines(content);
+    for (index, line) in keys.iter().enumerate() {
+        last.insert((&line.table, &line.key), index);
+    }
+
+    let mut dropped = HashSet::new();
+    for (index, line) in keys.iter().enumerate() {
+        if last[&(line.table.as_str(), line.key.as_str())] != index {
+            dropped.extend(line.lines.clone());
+            fixes.push(match line.table.as_str() {
+                "" => format!("Removed the duplicate {}", line.key),
+                table => format!("Removed the duplicate {} in [{}]", line.key, table),
+            });
+        }
+    }
+
+    content
+        .split_inclusive('\n')
+        .enumerate()
+        .filter(|(index, _)| !dropped.contains(index))
+        .map(|(_, line)| line)
+        .collect()
+}
+
+/// Find the `key = value` lines of a TOML document, by table
+///
+/// This only needs to be good enough for sop.toml files, which may not even parse: values that
+/// span lines are followed by counting brackets outside of strings, and each `[[array]]` entry
+/// counts as a table of its own.
+fn key_lines(content: &str) -> Vec<KeyLine> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut keys = Vec::new();
+    let mut table = String::new();
+    let mut array_entries = 0;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index].trim();
+        let start = index;
+        index += 1;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("[[") {
+            array_entries += 1;
+            table = format!(
+                "{}#{}",
+                name.split("]]").next().unwrap_or("").trim(),
+                array_entries
+            );
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            table = name.split(']').next().unwrap_or("").trim().to_string();
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let mut depth = bracket_depth(value);
+        while depth > 0 && index < lines.len() {
+            depth += bracket_depth(lines[index]);
+            index += 1;
+        }
+        keys.push(KeyLine {
+            table: table.clone(),
+            key: key.trim().trim_matches('"').to_string(),
+            lines: start..index,
+        });
+    }
+    keys
+}
+
+/// How many more brackets and braces a line opens than it closes, outside strings and comments
+fn bracket_depth(line: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for c in line.chars() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                '#' => break,
+                _ => {}
+            },
+        }
+    }
+    depth
+}
+
+/// Write the project version and dependency versions as full semantic versions (`1.2` becomes
+/// `1.2.0`, `v2.0.0` becomes `2.0.0`), keeping the operator of a requirement
+fn normalize_versions(config: &mut SopToml, fixes: &mut Vec<String>) {
+    if !config.project.version_from_workspace {
+        if let Some(version) = normalized_version(&config.project.version) {
+            fixes.push(format!(
+                "Wrote version {} as {}",
+                config.project.version, version
+            ));
+            config.project.version = version;
+        }
+    }
+
+    for (table, dependencies) in dependency_tables(config) {
+        let mut dependencies: Vec<_> = dependencies.iter_mut().collect();
+        dependencies.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, dependency) in dependencies {
+            let Some(requirement) = dependency.requirement() else {
+                continue;
+            };
+            let (operator, version) = split_requirement(requirement);
+            if let Some(version) = normalized_version(version) {
+                let normalized = format!("{}{}", operator, version);
+                fixes.push(format!(
+                    "Wrote {} in [{}] as {} instead of {}",
+                    name, table, normalized, requirement
+                ));
+                dependency.set_requirement(&normalized);
+            }
+        }
+    }
+}
+
+/// Get the full semantic version a shorthand version stands for, if it isn't one already
+fn normalized_version(version: &str) -> Option<String> {
+    let normalized = parse_version(version)?.to_string();
+    (normalized != version).then_some(normalized)
+}
+
+/// Point `entry` at src/main.so when it is unset or names a file that doesn't exist
+fn fill_entry(config: &mut SopToml, fixes: &mut Vec<String>) {
+    let entry = &config.project.entry;
+    if entry == DEFAULT_ENTRY
+        || (!entry.is_empty() && project_path(entry).is_file())
+        || !project_path(DEFAULT_ENTRY).is_file()
+    {
+        return;
+    }
+    fixes.push(match entry.as_str() {
+        "" => format!("Set the entry to {}", DEFAULT_ENTRY),
+        missing => format!(
+            "Set the entry to {} instead of {}, which doesn't exist",
+            DEFAULT_ENTRY, missing
+        ),
+    });
+    config.project.entry = DEFAULT_ENTRY.to_string();
+}
+
+/// Remove dependencies without a name, empty dependency tables apart from [dependencies], and
+/// empty or repeated keywords and categories
+fn remove_empty(config: &mut SopToml, fixes: &mut Vec<String>) {
+    for (table, dependencies) in dependency_tables(config) {
+        let before = dependencies.len();
+        dependencies.retain(|name, _| !name.trim().is_empty());
+        if dependencies.len() < before {
+            fixes.push(format!(
+                "Removed a dependency without a name from [{}]",
+                table
+            ));
+        }
+    }
+
+    let empty =
+        |table: &Option<HashMap<String, Dependency>>| table.as_ref().is_some_and(HashMap::is_empty);
+    if empty(&config.dev_dependencies) {
+        config.dev_dependencies = None;
+        fixes.push("Removed the empty [dev-dependencies]".to_string());
+    }
+    if empty(&config.patch) {
+        config.patch = None;
+        fixes.push("Removed the empty [patch]".to_string());
+    }
+    config.target.retain(|qualifier, target| {
+        if empty(&target.dependencies) {
+            target.dependencies = None;
+        }
+        if empty(&target.dev_dependencies) {
+            target.dev_dependencies = None;
+        }
+        let keep = target.dependencies.is_some() || target.dev_dependencies.is_some();
+        if !keep {
+            fixes.push(format!("Removed the empty [target.{}]", qualifier));
+        }
+        keep
+    });
+
+    for (field, values) in [
+        ("keywords", &mut config.project.keywords),
+        ("categories", &mut config.project.categories),
+    ] {
+        let before = values.len();
+        let mut seen = HashSet::new();
+        values.retain(|value| !value.trim().is_empty() && seen.insert(value.clone()));
+        if values.len() < before {
+            fixes.push(format!("Removed empty and repeated {}", field));
+        }
+    }
+}
+
+/// Get every dependency table of sop.toml, with its name
+fn dependency_tables(config: &mut SopToml) -> Vec<(String, &mut HashMap<String, Dependency>)> {
+    let mut tables = Vec::new();
+    if let Some(dependencies) = &mut config.dependencies {
+        tables.push(("dependencies".to_string(), dependencies));
+    }
+    if let Some(dev_dependencies) = &mut config.dev_dependencies {
+        tables.push(("dev-dependencies".to_string(), dev_dependencies));
+    }
+    for (qualifier, target) in &mut config.target {
+        if let Some(dependencies) = &mut target.dependencies {
+            tables.push((format!("target.{}.dependencies", qualifier), dependencies));
+        }
+        if let Some(dev_dependencies) = &mut target.dev_dependencies {
+            tables.push((
+                format!("target.{}.dev-dependencies", qualifier),
+                dev_dependencies,
+            ));
+        }
+    }
+    if let Some(patch) = &mut config.patch {
+        tables.push(("patch".to_string(), patch));
+    }
+    tables
+}
+
+/// Note every dependency table whose entries writing sop.toml puts in a different order
+fn report_sorted_tables(before: &str, after: &str, fixes: &mut Vec<String>) {
+    let order = |content: &str| {
+        let mut tables: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for line in key_lines(content) {
+            if line.table.ends_with("dependencies") || line.table == "patch" {
+                tables.entry(line.table).or_default().push(line.key);
+            }
+        }
+        tables
+    };
+    let after = order(after);
+    for (table, keys) in order(before) {
+        let Some(sorted) = after.get(&table) else {
+            continue;
+        };
+        let kept: Vec<&String> = keys.iter().filter(|key| sorted.contains(key)).collect();
+        let sorted: Vec<&String> = sorted.iter().filter(|key| keys.contains(key)).collect();
+        if kept != sorted {
+            fixes.push(format!("Sorted [{}]", table));
+        }
+    }
+}
+
+/// Print the lines that differ between two versions of a file, with some context
+fn print_diff(before: &str, after: &str) {
+    let before: Vec<&str> = before.lines().collect();
+    let after: Vec<&str> = after.lines().collect();
+
+    // Longest common subsequence, from the end so the walk below can go forwards
+    let mut common = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            common[i][j] = if before[i] == after[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    // None marks an unchanged line, Some(true) an added one and Some(false) a removed one
+    let mut lines: Vec<(Option<bool>, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            lines.push((None, before[i]));
+            i += 1;
+            j += 1;
+        } else if i < before.len() && (j == after.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push((Some(false), before[i]));
+            i += 1;
+        } else {
+            lines.push((Some(true), after[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = (0..lines.len())
+        .filter(|&index| lines[index].0.is_some())
+        .collect();
+    let near_change = |index: usize| {
+        changed
+            .iter()
+            .any(|&change| change.abs_diff(index) <= DIFF_CONTEXT)
+    };
+    let mut skipped = false;
+    for (index, (change, line)) in lines.iter().enumerate() {
+        if !near_change(index) {
+            skipped = true;
+            continue;
+        }
+        if skipped && index > 0 {
+            println!("{}", style::dim("  ..."));
+        }
+        skipped = false;
+        match change {
+            Some(true) => println!("{}", style::added(&format!("+ {}", line))),
+            Some(false) => println!("{}", style::removed(&format!("- {}", line))),
+            None => println!("{}", style::dim(&format!("  {}", line))),
+        }
+    }
+}
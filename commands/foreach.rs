@@ -0,0 +1,233 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+
+use crate::config;
+use crate::style;
+use crate::toml_parser::read_sop_toml;
+use crate::utils::{ensure_in_project, get_sop_toml_path, project_root};
+use crate::workspace::{dependency_waves, load_members, WorkspaceMember};
+
+/// How the foreach command schedules members
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ForeachOptions {
+    /// Run every member after the members it depends on
+    pub topological: bool,
+    /// Run independent members at the same time
+    pub parallel: bool,
+    /// Treat the command as a shell command instead of a sop subcommand
+    pub shell: bool,
+    /// Stop starting members once one has failed
+    pub fail_fast: bool,
+}
+
+/// Execute the foreach command
+///
+/// With `filter`, only members whose name matches one of the names or globs run. Every
+/// member runs even if others fail, unless `fail_fast` is set or members run in dependency
+/// order, where later members may rely on the ones that failed.
+pub fn execute(command: &[String], filter: &[String], options: ForeachOptions) -> Result<()> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let mut members = load_members(&project_root(), &config)?;
+    if !filter.is_empty() {
+        let patterns = filter
+            .iter()
+            .map(|pattern| {
+                glob::Pattern::new(pattern)
+                    .map_err(|e| anyhow!("Invalid member pattern '{}': {}", pattern, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        members.retain(|member| patterns.iter().any(|pattern| pattern.matches(&member.name)));
+        if members.is_empty() {
+            return Err(anyhow!("No workspace member matches {}", filter.join(", ")));
+        }
+    }
+    let waves = schedule(&members, options)?;
+
+    let display = if options.shell {
+        command.join(" ")
+    } else {
+        format!("sop {}", command.join(" "))
+    };
+    println!(
+        "Running '{}' in {} workspace members{}",
+        display.bold(),
+        members.len(),
+        if options.parallel { " in parallel" } else { "" }
+    );
+
+    let width = members
+        .iter()
+        .map(|member| member.name.len())
+        .max()
+        .unwrap_or(0);
+    let mut failed = Vec::new();
+    let mut skipped = 0;
+    for (position, wave) in waves.iter().enumerate() {
+        let results = thread::scope(|scope| {
+            let handles: Vec<_> = wave
+                .iter()
+                .map(|&index| {
+                    let member = &members[index];
+                    scope.spawn(move || run_member(member, command, options.shell, width))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .zip(wave)
+                .map(|(handle, &index)| {
+                    let result = handle
+                        .join()
+                        .unwrap_or_else(|_| Err(anyhow!("the worker thread panicked")));
+                    (index, result)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        for (index, result) in results {
+            let member = &members[index];
+            match result {
+                Ok(status) if status.success() => {
+                    println!("{} {}", style::ok_mark(), member.name);
+                }
+                Ok(status) => {
+                    let code = status
+                        .code()
+                        .map(|code| format!("exit code {}", code))
+                        .unwrap_or_else(|| "terminated by a signal".to_string());
+                    println!("{} {} ({})", style::error_mark(), member.name, code);
+                    failed.push(member.name.clone());
+                }
+                Err(e) => {
+                    println!("{} {} ({})", style::error_mark(), member.name, e);
+                    failed.push(member.name.clone());
+                }
+            }
+        }
+
+        // In dependency order, later members may rely on the ones that just failed
+        if !failed.is_empty() && (options.fail_fast || options.topological) {
+            skipped = waves[position + 1..].iter().map(Vec::len).sum();
+            break;
+        }
+    }
+    if skipped > 0 {
+        println!(
+            "{} Skipped {} member{} after the failure",
+            style::warn_mark(),
+            skipped,
+            if skipped == 1 { "" } else { "s" }
+        );
+    }
+
+    if !failed.is_empty() {
+        return Err(anyhow!(
+            "'{}' failed in {}: {}",
+            display,
+            if failed.len() == 1 {
+                "1 member".to_string()
+            } else {
+                format!("{} members", failed.len())
+            },
+            failed.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Split members into waves that run one after the other; members within a wave run together
+fn schedule(members: &[WorkspaceMember], options: ForeachOptions) -> Result<Vec<Vec<usize>>> {
+    let waves = if options.topological {
+        dependency_waves(members)?
+    } else {
+        vec![(0..members.len()).collect()]
+    };
+
+    if options.parallel {
+        // Never run more members at once than `jobs` allows
+        let jobs = config::jobs();
+        Ok(waves
+            .into_iter()
+            .flat_map(|wave| wave.chunks(jobs).map(<[usize]>::to_vec).collect::<Vec<_>>())
+            .collect())
+    } else {
+        Ok(waves
+            .into_iter()
+            .flatten()
+            .map(|index| vec![index])
+            .collect())
+    }
+}
+
+/// Run the command in one member, printing its output with the member's name in front
+fn run_member(
+    member: &WorkspaceMember,
+    command: &[String],
+    shell: bool,
+    width: usize,
+) -> Result<ExitStatus> {
+    let mut process = build_command(command, shell)?;
+    let mut child = process
+        .current_dir(&member.dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("failed to start: {}", e))?;
+
+    let prefix = format!(
+        "{} {}",
+        style::accent(&format!("{:width$}", member.name)),
+        style::gutter()
+    );
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    thread::scope(|scope| {
+        if let Some(stdout) = stdout {
+            scope.spawn(|| stream_lines(stdout, &prefix, false));
+        }
+        if let Some(stderr) = stderr {
+            scope.spawn(|| stream_lines(stderr, &prefix, true));
+        }
+    });
+
+    Ok(child.wait()?)
+}
+
+/// Build the process for a sop subcommand, or for a shell command with `shell`
+fn build_command(command: &[String], shell: bool) -> Result<Command> {
+    if shell {
+        let script = command.join(" ");
+        let mut process = if cfg!(windows) {
+            let mut process = Command::new("cmd");
+            process.arg("/C");
+            process
+        } else {
+            let mut process = Command::new("sh");
+            process.arg("-c");
+            process
+        };
+        process.arg(script);
+        return Ok(process);
+    }
+
+    let mut process = Command::new(std::env::current_exe()?);
+    process.args(command);
+    Ok(process)
+}
+
+/// Print each line of a child's output with the member prefix
+fn stream_lines(output: impl Read, prefix: &str, to_stderr: bool) {
+    for line in BufReader::new(output).lines().map_while(Result::ok) {
+        if to_stderr {
+            eprintln!("{} {}", prefix, line);
+        } else {
+            println!("{} {}", prefix, line);
+        }
+    }
+}
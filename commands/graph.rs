@@ -0,0 +1,161 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::graph::{load_project_graph, DependencyGraph};
+use crate::utils::ensure_in_project;
+
+/// Output formats supported by the graph command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+    /// Nodes and edges as JSON
+    Json,
+}
+
+/// JSON form of the dependency graph
+#[derive(Debug, Serialize)]
+struct GraphJson<'a> {
+    root: NodeJson<'a>,
+    packages: Vec<NodeJson<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct NodeJson<'a> {
+    name: &'a str,
+    version: &'a str,
+    dependencies: &'a [String],
+}
+
+/// Execute the graph command
+pub fn execute(format: GraphFormat) -> Result<()> {
+    ensure_in_project()?;
+
+    let graph = load_project_graph()?;
+    let output = match format {
+        GraphFormat::Dot => render_dot(&graph),
+        GraphFormat::Mermaid => render_mermaid(&graph),
+        GraphFormat::Json => format!("{}\n", render_json(&graph)?),
+    };
+    print!("{}", output);
+
+    Ok(())
+}
+
+/// Get every edge of the graph, starting with the project's direct dependencies
+fn edges(graph: &DependencyGraph) -> Vec<(&str, &str)> {
+    let root_edges = graph
+        .roots
+        .iter()
+        .map(|root| (graph.root_name.as_str(), root.as_str()));
+    let package_edges = graph.nodes.iter().flat_map(|(name, node)| {
+        node.dependencies
+            .iter()
+            .map(move |dependency| (name.as_str(), dependency.as_str()))
+    });
+    root_edges.chain(package_edges).collect()
+}
+
+/// Label a node with its name and version
+fn label<'a>(graph: &'a DependencyGraph, name: &'a str) -> (&'a str, &'a str) {
+    let version = if name == graph.root_name {
+        graph.root_version.as_str()
+    } else {
+        graph.version_of(name).unwrap_or("?")
+    };
+    (name, version)
+}
+
+/// Render the graph as Graphviz DOT
+fn render_dot(graph: &DependencyGraph) -> String {
+    let mut output = String::from("digraph dependencies {\n    node [shape=box];\n");
+
+    let names = std::iter::once(&graph.root_name).chain(graph.nodes.keys());
+    for name in names {
+        let (name, version) = label(graph, name);
+        output.push_str(&format!(
+            "    \"{}\" [label=\"{}\\nv{}\"];\n",
+            escape_dot(name),
+            escape_dot(name),
+            escape_dot(version)
+        ));
+    }
+    for (from, to) in edges(graph) {
+        output.push_str(&format!(
+            "    \"{}\" -> \"{}\";\n",
+            escape_dot(from),
+            escape_dot(to)
+        ));
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+/// Render the graph as a Mermaid flowchart
+fn render_mermaid(graph: &DependencyGraph) -> String {
+    let mut output = String::from("graph TD\n");
+
+    let names = std::iter::once(&graph.root_name).chain(graph.nodes.keys());
+    for name in names {
+        let (name, version) = label(graph, name);
+        output.push_str(&format!(
+            "    {}[\"{} v{}\"]\n",
+            mermaid_id(name),
+            escape_mermaid(name),
+            escape_mermaid(version)
+        ));
+    }
+    for (from, to) in edges(graph) {
+        output.push_str(&format!(
+            "    {} --> {}\n",
+            mermaid_id(from),
+            mermaid_id(to)
+        ));
+    }
+
+    output
+}
+
+/// Render the graph as JSON
+fn render_json(graph: &DependencyGraph) -> Result<String> {
+    let json = GraphJson {
+        root: NodeJson {
+            name: &graph.root_name,
+            version: &graph.root_version,
+            dependencies: &graph.roots,
+        },
+        packages: graph
+            .nodes
+            .iter()
+            .map(|(name, node)| NodeJson {
+                name,
+                version: &node.version,
+                dependencies: &node.dependencies,
+            })
+            .collect(),
+    };
+    Ok(serde_json::to_string_pretty(&json)?)
+}
+
+/// Escape text for a double-quoted DOT string
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escape text for a double-quoted Mermaid label
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+/// Turn a package name into a Mermaid node id (letters, digits and underscores only)
+fn mermaid_id(name: &str) -> String {
+    let id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("n_{}", id)
+}
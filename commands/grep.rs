@@ -0,0 +1,61 @@
+use anyhow::{anyhow, Result};
+use regex::RegexBuilder;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::graph::load_project_graph;
+use crate::style;
+use crate::utils::{
+    ensure_in_project, find_files_with_extension, get_sop_modules_path, get_src_path,
+};
+
+/// Execute the grep command
+pub fn execute(pattern: &str, ignore_case: bool, fixed_strings: bool, no_deps: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let pattern = if fixed_strings {
+        regex::escape(pattern)
+    } else {
+        pattern.to_string()
+    };
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| anyhow!("Invalid pattern: {}", e))?;
+
+    // Search the project's own sources first, then every package in the resolved graph
+    let mut files: Vec<PathBuf> = find_files_with_extension(&get_src_path(), "so")?;
+    if !no_deps {
+        let graph = load_project_graph()?;
+        let modules_dir = get_sop_modules_path();
+        for package in graph.nodes.keys() {
+            files.extend(find_files_with_extension(&modules_dir.join(package), "so")?);
+        }
+    }
+
+    let mut match_count = 0;
+    for file in &files {
+        // Skip files that aren't valid UTF-8 rather than failing the whole search
+        let Ok(content) = fs::read_to_string(file) else {
+            continue;
+        };
+
+        for (index, line) in content.lines().enumerate() {
+            if regex.is_match(line) {
+                println!(
+                    "{}:{}:{}",
+                    style::accent(&file.display().to_string()),
+                    style::success(&(index + 1).to_string()),
+                    line
+                );
+                match_count += 1;
+            }
+        }
+    }
+
+    if match_count == 0 {
+        println!("{}", style::warning("No matches found."));
+    }
+
+    Ok(())
+}
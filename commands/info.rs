@@ -0,0 +1,114 @@
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::install::{installed_version, package_license};
+use crate::lockfile::read_sop_lock_or_default;
+use crate::registry;
+use crate::style;
+use crate::utils::{get_sop_lock_path, get_sop_modules_path, is_soplang_project};
+
+/// What `sop info --json` reports about a package
+#[derive(Debug, Serialize)]
+struct PackageInfo {
+    name: String,
+    scope: Option<String>,
+    latest: String,
+    versions: Vec<String>,
+    /// Versions pulled by their publisher, which new resolutions skip
+    yanked: Vec<String>,
+    registry: String,
+    /// Only reported inside a project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    installed: Option<InstalledInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct InstalledInfo {
+    /// `None` when the package is locked but missing from sop_modules
+    version: Option<String>,
+    license: Option<String>,
+}
+
+/// Execute the info command
+///
+/// Shows what the registry knows about a package and, inside a project, which version of it is
+/// installed.
+pub fn execute(package: &str, json: bool) -> Result<()> {
+    let versions = registry::available_versions(package)?;
+    let latest = registry::latest_version(package)?;
+    let yanked = registry::yanked_versions(package)?;
+    let (scope, name) = registry::split_scope(package);
+
+    if json {
+        let modules_dir = get_sop_modules_path();
+        let info = PackageInfo {
+            name: package.to_string(),
+            scope: scope.map(str::to_string),
+            registry: registry::package_url(package, &latest),
+            latest,
+            versions,
+            yanked,
+            installed: is_soplang_project().then(|| InstalledInfo {
+                version: installed_version(&modules_dir, package),
+                license: package_license(&modules_dir.join(package)),
+            }),
+        };
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    println!("{} v{}", package.bold(), latest);
+    if let Some(scope) = scope {
+        println!("  scope:     @{} ({})", scope, name);
+    }
+    let versions: Vec<String> = versions
+        .iter()
+        .map(|version| {
+            if yanked.contains(version) {
+                format!("{} {}", version, style::warning("(yanked)"))
+            } else {
+                version.clone()
+            }
+        })
+        .collect();
+    println!("  versions:  {}", versions.join(", "));
+    println!(
+        "  registry:  {}",
+        style::accent(&registry::package_url(package, &latest))
+    );
+
+    if !is_soplang_project() {
+        return Ok(());
+    }
+
+    let modules_dir = get_sop_modules_path();
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+    match installed_version(&modules_dir, package) {
+        Some(version) => {
+            let outdated = if version == latest {
+                String::new()
+            } else {
+                format!(" {}", style::warning(&format!("(latest is {})", latest)))
+            };
+            println!(
+                "  installed: v{} in {}{}",
+                version,
+                modules_dir.join(package).display(),
+                outdated
+            );
+            if let Some(license) = package_license(&modules_dir.join(package)) {
+                println!("  license:   {}", license);
+            }
+        }
+        None if lock.get(package).is_some() => {
+            println!(
+                "  installed: {}",
+                style::warning("locked but missing, run 'sop setup'")
+            );
+        }
+        None => println!("  installed: {}", style::dim("no")),
+    }
+
+    Ok(())
+}
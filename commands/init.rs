@@ -1,16 +1,27 @@
 use anyhow::{anyhow, Result};
-use dialoguer::{Confirm, Input, Select};
+use colored::Colorize;
+use dialoguer::{Confirm, Input};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::toml_parser::{write_sop_toml, ProjectConfig, SopToml};
-use crate::utils::{ensure_dir_exists, file_exists, get_sop_toml_path, get_src_path};
+use crate::config::read_config;
+use crate::i18n::{tr, tr_args};
+use crate::imports::{file_imports, Import};
+use crate::naming::validate_package_name;
+use crate::registry;
+use crate::style;
+use crate::toml_parser::{write_sop_toml, Dependency, ProjectConfig, SopToml};
+use crate::utils::{ensure_dir_exists, file_exists, SOP_TOML};
+
+/// Directories that never contain project sources
+const SKIPPED_DIRS: [&str; 2] = ["sop_modules", "target"];
 
 /// Execute the init command
 pub fn execute(yes: bool) -> Result<()> {
     // Print explanation
-    println!("Initializing a new Soplang project.");
+    println!("{}", tr("Initializing a new Soplang project."));
 
     // Check if current directory is empty
     let current_dir = env::current_dir()?;
@@ -19,7 +30,7 @@ pub fn execute(yes: bool) -> Result<()> {
 
     // If directory is not empty and -y is specified, throw an error
     if !is_empty && yes {
-        return Err(anyhow!("Current directory is not empty. Cannot initialize with -y flag. Use interactive mode or empty the directory."));
+        return Err(anyhow!(tr("Current directory is not empty. Cannot initialize with -y flag. Use interactive mode or empty the directory.")));
     }
 
     let project_dir: PathBuf;
@@ -34,6 +45,7 @@ pub fn execute(yes: bool) -> Result<()> {
             .ok_or_else(|| anyhow!("Unable to determine current directory name"))?
             .to_string_lossy()
             .to_string();
+        validate_directory_name(&project_name)?;
         in_current_dir = true;
     } else {
         // In interactive mode, ask for project name
@@ -43,16 +55,20 @@ pub fn execute(yes: bool) -> Result<()> {
             .to_string_lossy()
             .to_string();
 
-        let name_prompt = "Project name? (Enter a name, or '.' for current directory)";
+        let name_prompt = tr("Project name? (Enter a name, or '.' for current directory)");
         let input = Input::new()
             .with_prompt(name_prompt)
             .default(current_dir_name)
+            .validate_with(|input: &String| match input.as_str() {
+                "." | "./" => Ok(()),
+                name => validate_package_name(name).map_err(|e| e.to_string()),
+            })
             .interact_text()?;
 
         if input == "." || input == "./" {
             // User wants to use current directory
             if !is_empty {
-                return Err(anyhow!("Current directory is not empty. Please enter a project name or empty the directory."));
+                return Err(anyhow!(tr("Current directory is not empty. Please enter a project name or empty the directory.")));
             }
 
             // Current directory is empty, initialize here
@@ -62,6 +78,7 @@ pub fn execute(yes: bool) -> Result<()> {
                 .ok_or_else(|| anyhow!("Unable to determine current directory name"))?
                 .to_string_lossy()
                 .to_string();
+            validate_directory_name(&project_name)?;
             in_current_dir = true;
         } else {
             // User specified a project name, create a new directory
@@ -71,14 +88,14 @@ pub fn execute(yes: bool) -> Result<()> {
 
             if project_dir.exists() {
                 if !Confirm::new()
-                    .with_prompt(format!(
+                    .with_prompt(tr_args(
                         "Directory '{}' already exists. Do you want to overwrite it?",
-                        project_name
+                        &[&project_name],
                     ))
                     .default(false)
                     .interact()?
                 {
-                    return Err(anyhow!("Initialization aborted."));
+                    return Err(anyhow!(tr("Initialization aborted.")));
                 }
                 // If confirmed, delete the existing directory
                 fs::remove_dir_all(&project_dir)?;
@@ -93,16 +110,15 @@ pub fn execute(yes: bool) -> Result<()> {
     env::set_current_dir(&project_dir)?;
 
     // Check if sop.toml already exists (shouldn't happen in a new directory, but just in case)
-    let sop_toml_path = get_sop_toml_path();
-    if file_exists(&sop_toml_path) {
-        if !yes
-            && !Confirm::new()
-                .with_prompt("A sop.toml file already exists. Overwrite?")
-                .default(false)
-                .interact()?
-        {
-            return Err(anyhow!("Initialization aborted."));
-        }
+    let sop_toml_path = PathBuf::from(SOP_TOML);
+    if file_exists(&sop_toml_path)
+        && !yes
+        && !Confirm::new()
+            .with_prompt(tr("A sop.toml file already exists. Overwrite?"))
+            .default(false)
+            .interact()?
+    {
+        return Err(anyhow!(tr("Initialization aborted.")));
     }
 
     // Create the project configuration
@@ -115,7 +131,7 @@ pub fn execute(yes: bool) -> Result<()> {
     };
 
     // Create directories
-    let src_path = get_src_path();
+    let src_path = PathBuf::from("src");
     ensure_dir_exists(&src_path)?;
 
     // Create sop.toml
@@ -139,35 +155,236 @@ pub fn execute(yes: bool) -> Result<()> {
     Ok(())
 }
 
+/// Execute `sop init --adopt`: write a sop.toml for sources that already exist
+///
+/// Source files are only read, never created or modified.
+pub fn adopt() -> Result<()> {
+    println!("Adopting existing Soplang sources.");
+
+    let current_dir = env::current_dir()?;
+    let sop_toml_path = PathBuf::from(SOP_TOML);
+    if file_exists(&sop_toml_path) {
+        return Err(anyhow!(
+            "sop.toml already exists. This directory is already a Soplang project."
+        ));
+    }
+
+    let sources = collect_sources(&current_dir)?;
+    if sources.is_empty() {
+        return Err(anyhow!(
+            "No .so files found in {}. Run 'sop init' to start a new project instead.",
+            current_dir.display()
+        ));
+    }
+
+    let project_name = current_dir
+        .file_name()
+        .ok_or_else(|| anyhow!("Unable to determine current directory name"))?
+        .to_string_lossy()
+        .to_string();
+    validate_directory_name(&project_name)?;
+
+    // Resolve every file's imports once; they drive both the entry and the dependencies
+    let imports: BTreeMap<&PathBuf, Vec<Import>> = sources
+        .iter()
+        .map(|file| (file, file_imports(file)))
+        .collect();
+
+    let entry = infer_entry(&current_dir, &sources, &imports);
+    let entry_display = entry
+        .strip_prefix(&current_dir)
+        .unwrap_or(&entry)
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    // Package imports that name a local file (`import utils` next to utils.so) aren't packages
+    let local_modules: BTreeSet<String> = sources
+        .iter()
+        .filter_map(|file| file.file_stem())
+        .map(|stem| stem.to_string_lossy().to_string())
+        .collect();
+    let imported_packages: BTreeSet<String> = imports
+        .values()
+        .flatten()
+        .filter_map(|import| match import {
+            Import::Package(name) if !local_modules.contains(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let mut dependencies = HashMap::new();
+    let mut unknown = Vec::new();
+    for package in imported_packages {
+        if registry::package_exists(&package)? {
+            let version = registry::latest_version(&package)?;
+            dependencies.insert(package, Dependency::Simple(version));
+        } else {
+            unknown.push(package);
+        }
+    }
+
+    let mut config = create_default_project(&project_name)?;
+    config.project.entry = entry_display.clone();
+    config.dependencies = Some(dependencies);
+    write_sop_toml(&sop_toml_path, &config)?;
+
+    println!(
+        "{} Created sop.toml for {} ({} source files)",
+        style::ok_mark().bold(),
+        project_name,
+        sources.len()
+    );
+    println!("  entry: {}", entry_display);
+    let mut names: Vec<_> = config.dependencies.iter().flatten().collect();
+    names.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, dependency) in names {
+        println!("  dependency: {} ({})", name, dependency);
+    }
+    for name in unknown {
+        println!(
+            "  {} '{}' is imported but isn't a registry package; add it manually if needed",
+            style::warn_mark(),
+            name
+        );
+    }
+    println!("Run 'sop setup' to install the dependencies.");
+
+    Ok(())
+}
+
+/// Find every .so file in a directory tree, skipping hidden and generated directories
+fn collect_sources(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if path.is_dir() {
+                if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                    pending.push(path);
+                }
+            } else if path.extension().and_then(|ext| ext.to_str()) == Some("so") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Guess the entry point of an existing source tree
+///
+/// A conventional `src/main.so` or `main.so` wins; otherwise a `main.so` anywhere, then the
+/// only file no other file imports, falling back to the first file found.
+fn infer_entry(
+    root: &Path,
+    sources: &[PathBuf],
+    imports: &BTreeMap<&PathBuf, Vec<Import>>,
+) -> PathBuf {
+    for conventional in [root.join("src").join("main.so"), root.join("main.so")] {
+        if sources.contains(&conventional) {
+            return conventional;
+        }
+    }
+    if let Some(main) = sources
+        .iter()
+        .find(|file| file.file_name().is_some_and(|name| name == "main.so"))
+    {
+        return main.clone();
+    }
+
+    let imported: BTreeSet<&PathBuf> = imports
+        .values()
+        .flatten()
+        .filter_map(|import| match import {
+            Import::File(path) => Some(path),
+            _ => None,
+        })
+        .collect();
+    let roots: Vec<&PathBuf> = sources
+        .iter()
+        .filter(|file| !imported.contains(file))
+        .collect();
+
+    match roots.as_slice() {
+        [only] => (*only).clone(),
+        _ => sources[0].clone(),
+    }
+}
+
 /// Create a default project configuration (used with -y flag)
+///
+/// The author and license come from `[init]` in ~/.sop/config.toml when set.
 fn create_default_project(project_name: &str) -> Result<SopToml> {
+    let defaults = read_config()?.init;
     Ok(SopToml {
         project: ProjectConfig {
             name: project_name.to_string(),
             version: "1.0.0".to_string(),
             status: "experimental".to_string(), // Changed from "stable" to "experimental"
             description: String::new(),
-            license: String::new(),
-            author: String::new(),
+            license: defaults.license.unwrap_or_default(),
+            author: defaults.author.unwrap_or_default(),
             repository: String::new(),
             homepage: String::new(),
+            documentation: String::new(),
             entry: "src/main.so".to_string(),
             keywords: Vec::new(),
             categories: Vec::new(),
+            soplang: None,
+            version_from_workspace: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
         },
         dependencies: Some(std::collections::HashMap::new()),
+        dev_dependencies: None,
+        target: Default::default(),
+        patch: None,
+        bins: Vec::new(),
+        manifest: None,
+        modules: None,
+        workspace: None,
+        vendor: None,
+        check: None,
     })
 }
 
+/// Check that a project named after its directory gets a valid package name
+fn validate_directory_name(name: &str) -> Result<()> {
+    validate_package_name(name).map_err(|e| {
+        anyhow!(
+            "{} The project is named after its directory, so rename the directory first.",
+            e
+        )
+    })
+}
+
+/// Ask for an optional field, offering the default from the user config if there is one
+fn prompt_with_default(prompt: &str, default: Option<String>) -> Result<String> {
+    let mut input = Input::new().with_prompt(prompt).allow_empty(true);
+    if let Some(default) = default {
+        input = input.default(default);
+    }
+    Ok(input.interact_text()?)
+}
+
 /// Create a project configuration interactively
 fn create_interactive_project(default_name: &str) -> Result<SopToml> {
     // We don't need to ask for project name again, use the one already provided
     let name = default_name.to_string();
 
-    println!("Press Enter to skip optional fields and use defaults:");
+    println!(
+        "{}",
+        tr("Press Enter to skip optional fields and use defaults:")
+    );
 
     let version: String = Input::new()
-        .with_prompt("Version (1.0.0)")
+        .with_prompt(tr("Version (1.0.0)"))
         .allow_empty(true)
         .default("1.0.0".to_string())
         .interact_text()?;
@@ -181,27 +398,21 @@ fn create_interactive_project(default_name: &str) -> Result<SopToml> {
     let status = "experimental".to_string();
 
     let description: String = Input::new()
-        .with_prompt("Description")
-        .allow_empty(true)
-        .interact_text()?;
-
-    let license: String = Input::new()
-        .with_prompt("License")
+        .with_prompt(tr("Description"))
         .allow_empty(true)
         .interact_text()?;
 
-    let author: String = Input::new()
-        .with_prompt("Author")
-        .allow_empty(true)
-        .interact_text()?;
+    let defaults = read_config()?.init;
+    let license = prompt_with_default(tr("License"), defaults.license)?;
+    let author = prompt_with_default(tr("Author"), defaults.author)?;
 
     let repository: String = Input::new()
-        .with_prompt("Repository")
+        .with_prompt(tr("Repository"))
         .allow_empty(true)
         .interact_text()?;
 
     let homepage: String = Input::new()
-        .with_prompt("Homepage")
+        .with_prompt(tr("Homepage"))
         .allow_empty(true)
         .interact_text()?;
 
@@ -218,11 +429,25 @@ fn create_interactive_project(default_name: &str) -> Result<SopToml> {
             author,
             repository,
             homepage,
+            documentation: String::new(),
             entry,
             keywords: Vec::new(),
             categories: Vec::new(),
+            soplang: None,
+            version_from_workspace: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
         },
         dependencies: Some(std::collections::HashMap::new()),
+        dev_dependencies: None,
+        target: Default::default(),
+        patch: None,
+        bins: Vec::new(),
+        manifest: None,
+        modules: None,
+        workspace: None,
+        vendor: None,
+        check: None,
     })
 }
 
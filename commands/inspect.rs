@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::cache;
+use crate::registry;
+use crate::style;
+use crate::utils::format_bytes;
+
+/// What `sop inspect --json` reports about a package
+#[derive(Debug, Serialize)]
+struct Inspection {
+    /// The archive path, or `name@version` for a package from the registry
+    source: String,
+    files: Vec<InspectedFile>,
+    /// Bytes of every file together
+    size: u64,
+    /// Contents of the package's sop.toml, if it has one
+    manifest: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct InspectedFile {
+    path: String,
+    size: u64,
+}
+
+/// Execute the inspect command
+///
+/// `target` is a package archive such as one `sop publish` builds, or `name@version` (or just
+/// `name` for the latest version) to fetch from the registry into the cache. Either way, the
+/// files and sop.toml of the package are shown without installing anything.
+pub fn execute(target: &str, json: bool) -> Result<()> {
+    let is_archive = target.ends_with(".tar.gz") || target.ends_with(".tgz");
+    let (source, files, manifest) = if is_archive || Path::new(target).is_file() {
+        let (files, manifest) = read_archive(Path::new(target))?;
+        (target.to_string(), files, manifest)
+    } else {
+        // Scoped names start with `@`, so only a later `@` separates the version
+        let (name, requirement) = match target.rfind('@') {
+            Some(at) if at > 0 => (&target[..at], &target[at + 1..]),
+            _ => (target, "latest"),
+        };
+        let version = registry::resolve_version(name, requirement)?;
+        let dir = cache::fetch_package(name, &version, None, false)?;
+        let files = read_dir_files(&dir)?;
+        let manifest = fs::read_to_string(dir.join("sop.toml")).ok();
+        (format!("{}@{}", name, version), files, manifest)
+    };
+
+    let inspection = Inspection {
+        size: files.iter().map(|file| file.size).sum(),
+        source,
+        files,
+        manifest,
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&inspection)?);
+    } else {
+        print_inspection(&inspection);
+    }
+    Ok(())
+}
+
+/// List the files of a `.tar.gz` package archive and read its sop.toml, without extracting it
+///
+/// Archives hold the package under a single `<name>-<version>/` directory, which is left out
+/// of the listed paths.
+fn read_archive(path: &Path) -> Result<(Vec<InspectedFile>, Option<String>)> {
+    let file =
+        fs::File::open(path).map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let not_an_archive =
+        |e: std::io::Error| anyhow!("{} isn't a .tar.gz package archive: {}", path.display(), e);
+    let mut files = Vec::new();
+    let mut manifest = None;
+    for entry in archive.entries().map_err(not_an_archive)? {
+        let mut entry = entry.map_err(not_an_archive)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let full = entry.path()?.to_string_lossy().replace('\\', "/");
+        let relative = full
+            .split_once('/')
+            .map_or(full.as_str(), |(_, rest)| rest)
+            .to_string();
+        if relative == "sop.toml" {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            manifest = Some(content);
+        }
+        files.push(InspectedFile {
+            path: relative,
+            size: entry.header().size()?,
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok((files, manifest))
+}
+
+/// List the files under a package directory, with paths relative to it
+fn read_dir_files(dir: &Path) -> Result<Vec<InspectedFile>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let relative = path
+                .strip_prefix(dir)?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push(InspectedFile {
+                path: relative,
+                size: fs::metadata(&path)?.len(),
+            });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn print_inspection(inspection: &Inspection) {
+    println!("{} {}", "Package:".bold(), inspection.source);
+    println!(
+        "{} {} file{}, {}",
+        "Contents:".bold(),
+        inspection.files.len(),
+        if inspection.files.len() == 1 { "" } else { "s" },
+        format_bytes(inspection.size)
+    );
+    for file in &inspection.files {
+        println!("  {:>9}  {}", format_bytes(file.size), file.path);
+    }
+
+    match &inspection.manifest {
+        Some(manifest) => {
+            println!("\n{}", "sop.toml:".bold());
+            for line in manifest.lines() {
+                println!("  {}", style::dim(line));
+            }
+        }
+        None => println!("\n{}", style::warning("The package has no sop.toml.")),
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::install::{install_package, package_binaries};
+use crate::interpreter::interpreter_program;
+use crate::naming::validate_package_name;
+use crate::registry;
+use crate::style;
+use crate::utils::{ensure_dir_exists, get_global_bin_path, get_global_packages_path};
+
+/// Execute the install command
+pub fn execute(package: &str, version: &Option<String>, global: bool) -> Result<()> {
+    if !global {
+        return Err(anyhow!(
+            "Only global installs are supported. Use 'sop install --global {}', or 'sop add {}' to add it to this project.",
+            package,
+            package
+        ));
+    }
+
+    validate_package_name(package)?;
+
+    let requirement = version.clone().unwrap_or_else(|| "latest".to_string());
+    let resolved_version = registry::resolve_version(package, &requirement)?;
+
+    println!("Installing {} globally", package);
+
+    let packages_dir = get_global_packages_path()?;
+    ensure_dir_exists(&packages_dir)?;
+    install_package(package, &resolved_version, None, &packages_dir, false, None)?;
+
+    // Create a launcher shim for every binary the package exposes
+    let bin_dir = get_global_bin_path()?;
+    ensure_dir_exists(&bin_dir)?;
+    let binaries = package_binaries(&packages_dir.join(package));
+    for (name, script) in &binaries {
+        create_shim(&bin_dir, name, script)?;
+        println!("  {} Linked {}", style::ok_mark(), name);
+    }
+
+    println!(
+        "{} Installed {} v{} globally",
+        style::ok_mark().bold(),
+        package,
+        resolved_version
+    );
+
+    if binaries.is_empty() {
+        println!(
+            "  {} {} does not expose any binaries",
+            style::warn_mark(),
+            package
+        );
+    } else if !is_on_path(&bin_dir) {
+        println!(
+            "  {} Add {} to your PATH to use the installed binaries",
+            style::warn_mark(),
+            bin_dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Get the path of the launcher shim for a binary
+pub fn shim_path(bin_dir: &Path, name: &str) -> PathBuf {
+    if cfg!(windows) {
+        bin_dir.join(format!("{}.cmd", name))
+    } else {
+        bin_dir.join(name)
+    }
+}
+
+/// Write a launcher shim that runs a package script with the Soplang interpreter
+fn create_shim(bin_dir: &Path, name: &str, script: &Path) -> Result<()> {
+    let path = shim_path(bin_dir, name);
+
+    if cfg!(windows) {
+        let content = format!(
+            "@echo off\r\n{} \"{}\" %*\r\n",
+            interpreter_program(),
+            script.display()
+        );
+        fs::write(&path, content)?;
+    } else {
+        let content = format!(
+            "#!/bin/sh\nexec {} \"{}\" \"$@\"\n",
+            interpreter_program(),
+            script.display()
+        );
+        fs::write(&path, content)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check whether a directory is listed in the PATH environment variable
+fn is_on_path(dir: &Path) -> bool {
+    env::var_os("PATH")
+        .map(|path| env::split_paths(&path).any(|entry| entry == dir))
+        .unwrap_or(false)
+}
@@ -0,0 +1,163 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::exit_code::{with_exit_code, ExitCode};
+use crate::install::package_license;
+use crate::lockfile::read_sop_lock_or_default;
+use crate::style;
+use crate::utils::{ensure_in_project, get_sop_lock_path, get_sop_modules_path, project_path};
+
+/// Policy file read when `--policy` isn't given
+const DEFAULT_POLICY_PATH: &str = "license-policy.toml";
+
+/// Which licenses dependencies may use
+///
+/// A license is forbidden when it is in `deny`, or when `allow` is non-empty and doesn't list
+/// it. Packages without a license only pass when there is no allow list.
+#[derive(Debug, Default, Deserialize)]
+pub struct LicensePolicy {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl LicensePolicy {
+    /// Check an SPDX expression: any `OR` alternative may be chosen, but every `AND` part applies
+    fn permits(&self, expression: &str) -> bool {
+        expression.split(" OR ").any(|alternative| {
+            alternative
+                .split(" AND ")
+                .map(|license| license.trim().trim_matches(|c| c == '(' || c == ')'))
+                .all(|license| self.permits_license(license))
+        })
+    }
+
+    fn permits_license(&self, license: &str) -> bool {
+        let listed = |list: &[String]| list.iter().any(|entry| entry.eq_ignore_ascii_case(license));
+        !listed(&self.deny) && (self.allow.is_empty() || listed(&self.allow))
+    }
+}
+
+/// The license of one installed package
+#[derive(Debug, Serialize)]
+struct PackageLicense {
+    name: String,
+    version: String,
+    license: Option<String>,
+    /// `None` when no policy was checked
+    allowed: Option<bool>,
+}
+
+/// Execute the licenses command
+pub fn execute(policy_path: &Option<String>, json: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let policy = read_policy(policy_path)?;
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+    let modules_dir = get_sop_modules_path();
+
+    let mut packages: Vec<PackageLicense> = lock
+        .packages
+        .iter()
+        .map(|package| {
+            let license = package_license(&modules_dir.join(&package.name));
+            let allowed = policy.as_ref().map(|policy| match &license {
+                Some(license) => policy.permits(license),
+                None => policy.allow.is_empty(),
+            });
+            PackageLicense {
+                name: package.name.clone(),
+                version: package.version.clone(),
+                license,
+                allowed,
+            }
+        })
+        .collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&packages)?);
+    } else {
+        print_report(&packages);
+    }
+
+    let forbidden: Vec<&str> = packages
+        .iter()
+        .filter(|package| package.allowed == Some(false))
+        .map(|package| package.name.as_str())
+        .collect();
+    if !forbidden.is_empty() {
+        return Err(with_exit_code(
+            ExitCode::License,
+            anyhow!(
+                "{} package{} use{} a license the policy forbids: {}",
+                forbidden.len(),
+                if forbidden.len() == 1 { "" } else { "s" },
+                if forbidden.len() == 1 { "s" } else { "" },
+                forbidden.join(", ")
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Read the policy file, which is optional unless it was named explicitly
+fn read_policy(policy_path: &Option<String>) -> Result<Option<LicensePolicy>> {
+    // An explicit policy path is relative to the current directory, the default to the project
+    let path = match policy_path {
+        Some(policy_path) => PathBuf::from(policy_path),
+        None => project_path(DEFAULT_POLICY_PATH),
+    };
+    if !path.is_file() {
+        if policy_path.is_some() {
+            return Err(anyhow!("License policy not found at {}", path.display()));
+        }
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)?;
+    let policy = toml::from_str(&content)
+        .map_err(|e| anyhow!("Invalid license policy {}: {}", path.display(), e))?;
+    Ok(Some(policy))
+}
+
+/// Print every package with its license, then how many packages use each license
+fn print_report(packages: &[PackageLicense]) {
+    if packages.is_empty() {
+        println!("No installed dependencies.");
+        return;
+    }
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for package in packages {
+        let license = package.license.as_deref().unwrap_or("unknown");
+        *counts.entry(license).or_default() += 1;
+
+        let mark = match package.allowed {
+            Some(true) => style::ok_mark(),
+            Some(false) => style::error_mark(),
+            None => style::dim("-"),
+        };
+        println!(
+            "  {} {:<24} {:<10} {}",
+            mark,
+            package.name,
+            package.version,
+            match &package.license {
+                Some(license) => license.normal(),
+                None => style::warning("unknown"),
+            }
+        );
+    }
+
+    println!("\n{}", "Licenses:".bold());
+    for (license, count) in counts {
+        println!("  {:<24} {}", license, count);
+    }
+}
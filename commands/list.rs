@@ -0,0 +1,173 @@
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::install::{installed_version, package_binaries};
+use crate::style;
+use crate::toml_parser::{read_sop_toml, DependencyKind};
+use crate::utils::{
+    ensure_in_project, get_global_packages_path, get_sop_modules_path, get_sop_toml_path,
+    installed_packages,
+};
+
+/// A dependency of the project, as printed by `sop list --json`
+#[derive(Debug, Serialize)]
+pub struct ListedDependency {
+    name: String,
+    requirement: String,
+    dev: bool,
+    optional: bool,
+    /// `None` when the dependency isn't in sop_modules
+    installed: Option<String>,
+}
+
+/// A globally installed package, as printed by `sop list --global --json`
+#[derive(Debug, Serialize)]
+struct ListedGlobalPackage {
+    name: String,
+    version: Option<String>,
+    binaries: Vec<String>,
+}
+
+/// Execute the list command
+pub fn execute(global: bool, json: bool) -> Result<()> {
+    if global {
+        list_global(json)
+    } else {
+        list_project(json)
+    }
+}
+
+/// List the dependencies of the current project with their installed versions
+fn list_project(json: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let modules_dir = get_sop_modules_path();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&project_dependencies()?)?
+        );
+        return Ok(());
+    }
+
+    let mut dependencies = config.all_dependencies();
+    dependencies.sort_by(|a, b| a.0.cmp(b.0));
+
+    if dependencies.is_empty() {
+        println!(
+            "{}",
+            style::warning("No dependencies specified in sop.toml.")
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} {}",
+        style::success("Project:").bold(),
+        config.project.name
+    );
+    for (package, requirement, kind) in dependencies {
+        let mut markers = String::new();
+        if kind == DependencyKind::Dev {
+            markers.push_str(" [dev]");
+        }
+        if requirement.is_optional() {
+            markers.push_str(" [optional]");
+        }
+
+        match installed_version(&modules_dir, package) {
+            Some(version) => println!(
+                "  {} v{} ({}){}",
+                package,
+                version,
+                requirement,
+                style::dim(&markers)
+            ),
+            None => println!(
+                "  {} ({}){} {}",
+                package,
+                requirement,
+                style::dim(&markers),
+                style::warning("not installed")
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect the dependencies of the current project, sorted by name
+pub fn project_dependencies() -> Result<Vec<ListedDependency>> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let modules_dir = get_sop_modules_path();
+
+    let mut dependencies = config.all_dependencies();
+    dependencies.sort_by(|a, b| a.0.cmp(b.0));
+    Ok(dependencies
+        .into_iter()
+        .map(|(package, requirement, kind)| ListedDependency {
+            name: package.clone(),
+            requirement: requirement.to_string(),
+            dev: kind == DependencyKind::Dev,
+            optional: requirement.is_optional(),
+            installed: installed_version(&modules_dir, package),
+        })
+        .collect())
+}
+
+/// List globally installed packages and the binaries they provide
+fn list_global(json: bool) -> Result<()> {
+    let packages_dir = get_global_packages_path()?;
+
+    let packages: Vec<String> = installed_packages(&packages_dir)?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+
+    if json {
+        let listed: Vec<ListedGlobalPackage> = packages
+            .into_iter()
+            .map(|package| ListedGlobalPackage {
+                version: installed_version(&packages_dir, &package),
+                binaries: package_binaries(&packages_dir.join(&package))
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect(),
+                name: package,
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&listed)?);
+        return Ok(());
+    }
+
+    if packages.is_empty() {
+        println!("{}", style::warning("No packages installed globally."));
+        return Ok(());
+    }
+
+    println!(
+        "{} {}",
+        style::success("Global packages:").bold(),
+        packages_dir.display()
+    );
+    for package in packages {
+        let version = installed_version(&packages_dir, &package).unwrap_or_else(|| "?".to_string());
+        let binaries: Vec<String> = package_binaries(&packages_dir.join(&package))
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+
+        if binaries.is_empty() {
+            println!("  {} v{}", package, version);
+        } else {
+            println!("  {} v{} [{}]", package, version, binaries.join(", "));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::commands::setup;
+use crate::lockfile::{
+    ensure_lock_may_change, render_sop_lock, write_sop_lock, LockedPackage, SopLock,
+};
+use crate::project_lock::ProjectLock;
+use crate::style;
+use crate::utils::{ensure_in_project, get_sop_lock_path, get_sop_toml_path, write_atomic};
+use crate::version::parse_version;
+
+/// The versions of a file on either side of git's conflict markers
+struct ConflictSides {
+    /// The common ancestor, when git wrote it (`merge.conflictStyle = diff3` or `zdiff3`)
+    base: Option<String>,
+    ours: String,
+    theirs: String,
+}
+
+/// Execute `sop lock --resolve`
+///
+/// Takes a sop.lock that a merge or rebase left with conflict markers, merges the packages
+/// locked on either side, then re-resolves it against the merged sop.toml the way `sop setup`
+/// does, so dependencies added on one side get locked and those removed drop out.
+pub fn resolve(offline: bool) -> Result<()> {
+    ensure_in_project()?;
+    ensure_lock_may_change("Resolving sop.lock")?;
+    let _lock = ProjectLock::acquire()?;
+
+    let lock_path = get_sop_lock_path();
+    let content = fs::read_to_string(&lock_path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", lock_path.display(), e))?;
+    let Some(sides) = split_conflicts(&content)? else {
+        info!(
+            "{} sop.lock has no merge conflicts.",
+            style::skip_mark().bold()
+        );
+        return Ok(());
+    };
+    let manifest = fs::read_to_string(get_sop_toml_path())?;
+    if !matches!(split_conflicts(&manifest), Ok(None)) {
+        return Err(anyhow!(
+            "sop.toml has merge conflicts too. Resolve them first, then run 'sop lock --resolve' again."
+        ));
+    }
+
+    let base = sides
+        .base
+        .as_deref()
+        .map(|base| parse_side(base, "the common ancestor of sop.lock"))
+        .transpose()?;
+    let ours = parse_side(&sides.ours, "our side of sop.lock")?;
+    let theirs = parse_side(&sides.theirs, "their side of sop.lock")?;
+    let merged = merge_locks(base.as_ref(), &ours, &theirs);
+    write_sop_lock(&lock_path, &merged)?;
+
+    // The lock now has every package either side locked; setup keeps those sop.toml still
+    // allows, locks what's new and drops what nothing needs anymore
+    info!(
+        "{}",
+        style::info("Re-resolving sop.lock against sop.toml...").bold()
+    );
+    setup::execute(offline, None, false)?;
+
+    info!(
+        "{} Resolved the conflicts in sop.lock. Run 'git add sop.lock' to mark it resolved.",
+        style::ok_mark().bold()
+    );
+    Ok(())
+}
+
+/// Execute `sop lock --merge-driver BASE OURS THEIRS`
+///
+/// Meant to be run by git as a merge driver, which is set up with
+///
+/// ```text
+/// git config merge.sop-lock.driver "sop lock --merge-driver %O %A %B"
+/// echo "sop.lock merge=sop-lock" >> .gitattributes
+/// ```
+///
+/// The three versions are merged package by package and the result is written over `ours`,
+/// as git expects. sop.toml may not be merged yet when git runs the driver, so nothing is
+/// re-resolved: run `sop setup` after the merge to lock what sop.toml changed.
+pub fn merge_driver(base: &Path, ours: &Path, theirs: &Path) -> Result<()> {
+    let read = |path: &Path, side: &str| -> Result<SopLock> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {} ({}): {}", side, path.display(), e))?;
+        parse_side(&content, side)
+    };
+    let base = read(base, "the common ancestor of sop.lock")?;
+    let merged = merge_locks(
+        Some(&base),
+        &read(ours, "our side of sop.lock")?,
+        &read(theirs, "their side of sop.lock")?,
+    );
+    write_atomic(ours, &render_sop_lock(&merged)?)
+}
+
+/// Split a file with git conflict markers into the versions on either side
+///
+/// Returns `None` when there are no conflict markers. Lines outside the conflicts belong to
+/// every side.
+fn split_conflicts(content: &str) -> Result<Option<ConflictSides>> {
+    enum Section {
+        Shared,
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    let mut section = Section::Shared;
+    let mut base = String::new();
+    let mut ours = String::new();
+    let mut theirs = String::new();
+    let mut has_conflicts = false;
+    let mut has_base = false;
+    for line in content.split_inclusive('\n') {
+        let marker = line.trim_end_matches(['\r', '\n']);
+        section = match (section, marker) {
+            (Section::Shared, _) if marker.starts_with("<<<<<<<") => {
+                has_conflicts = true;
+                Section::Ours
+            }
+            (Section::Ours, _) if marker.starts_with("|||||||") => {
+                has_base = true;
+                Section::Base
+            }
+            (Section::Ours | Section::Base, "=======") => Section::Theirs,
+            (Section::Theirs, _) if marker.starts_with(">>>>>>>") => Section::Shared,
+            (section, _) => {
+                match section {
+                    Section::Shared => {
+                        base.push_str(line);
+                        ours.push_str(line);
+                        theirs.push_str(line);
+                    }
+                    Section::Ours => ours.push_str(line),
+                    Section::Base => base.push_str(line),
+                    Section::Theirs => theirs.push_str(line),
+                }
+                section
+            }
+        };
+    }
+    if !matches!(section, Section::Shared) {
+        return Err(anyhow!(
+            "A conflict in the file isn't closed with '>>>>>>>'."
+        ));
+    }
+
+    Ok(has_conflicts.then_some(ConflictSides {
+        base: has_base.then_some(base),
+        ours,
+        theirs,
+    }))
+}
+
+fn parse_side(content: &str, side: &str) -> Result<SopLock> {
+    toml::from_str(content).map_err(|e| anyhow!("Failed to parse {}: {}", side, e))
+}
+
+/// Merge the packages locked on two sides of a merge
+///
+/// With the common ancestor, a package changed on one side only takes that change, and one
+/// removed on one side and untouched on the other is removed. A package changed differently on
+/// both sides, or any difference without an ancestor, keeps the newer version.
+fn merge_locks(base: Option<&SopLock>, ours: &SopLock, theirs: &SopLock) -> SopLock {
+    let names: BTreeSet<&String> = ours
+        .packages
+        .iter()
+        .chain(&theirs.packages)
+        .map(|package| &package.name)
+        .collect();
+
+    let mut merged = SopLock::default();
+    for name in names {
+        let ancestor = base.and_then(|base| base.get(name));
+        let package = match (ours.get(name), theirs.get(name)) {
+            (Some(ours), Some(theirs)) if ours == theirs => ours,
+            (Some(ours), Some(theirs)) if ancestor == Some(ours) => theirs,
+            (Some(ours), Some(theirs)) if ancestor == Some(theirs) => ours,
+            (Some(ours), Some(theirs)) => {
+                let (kept, dropped) = newer(ours, theirs);
+                info!(
+                    "  {} Both sides changed {}: keeping v{} over v{}",
+                    style::warn_mark(),
+                    name,
+                    kept.version,
+                    dropped.version
+                );
+                kept
+            }
+            (Some(only), None) | (None, Some(only)) if ancestor == Some(only) => continue,
+            (Some(only), None) | (None, Some(only)) => only,
+            (None, None) => continue,
+        };
+        merged.packages.push(package.clone());
+    }
+    merged
+}
+
+/// Order two lock entries for the same package newest first, preferring ours on a tie
+fn newer<'a>(
+    ours: &'a LockedPackage,
+    theirs: &'a LockedPackage,
+) -> (&'a LockedPackage, &'a LockedPackage) {
+    match (parse_version(&ours.version), parse_version(&theirs.version)) {
+        (Some(a), Some(b)) if b > a => (theirs, ours),
+        _ => (ours, theirs),
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use dialoguer::Password;
+use tracing::warn;
+
+use crate::credentials::{load_token, save_token, warn_if_expiring};
+use crate::i18n::tr_args;
+use crate::registry;
+use crate::style;
+
+/// Execute the login command
+pub fn execute(token: &Option<String>, rotate: bool) -> Result<()> {
+    let registry_url = registry::registry_url();
+    let (new_token, previous) = if rotate {
+        let current = load_token(&registry_url)?.ok_or_else(|| {
+            anyhow!(
+                "Not logged in to {}. Run 'sop login' before rotating the token.",
+                registry_url
+            )
+        })?;
+
+        println!("Rotating token for {}", registry_url);
+        (registry::create_token(&current)?, Some(current))
+    } else {
+        let token = match token {
+            Some(token) => token.clone(),
+            None => Password::new()
+                .with_prompt(tr_args("API token for {}", &[&registry_url]))
+                .interact()?,
+        };
+        (registry::token_metadata(token.trim())?, None)
+    };
+
+    let storage = save_token(&registry_url, &new_token)?;
+
+    // Only revoke the old token once the new one is safely stored, so a failed save leaves the
+    // user logged in with the old one
+    if let Some(previous) = previous {
+        match registry::revoke_token(&previous) {
+            Ok(()) => println!("  {} Revoked the previous token", style::ok_mark()),
+            Err(e) => warn!(
+                "  {} Couldn't revoke the previous token, revoke it on the registry instead: {}",
+                style::warn_mark(),
+                e
+            ),
+        }
+    }
+
+    println!(
+        "{} Logged in to {} (token stored in {})",
+        style::ok_mark().bold(),
+        registry_url,
+        storage.describe()
+    );
+    if let Some(days) = new_token.days_until_expiry() {
+        println!("  Token expires in {} days", days);
+    }
+    warn_if_expiring(&registry_url, &new_token);
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::lockfile::{read_sop_lock_or_default, SopLock};
+use crate::toml_parser::{
+    read_sop_toml, render_sop_toml, BinTarget, Dependency, DependencyKind, DependencyOrigin,
+    ManifestStyle, SopToml, VendorConfig, WorkspaceConfig,
+};
+use crate::utils::{ensure_in_project, get_sop_lock_path, get_sop_toml_path, DEFAULT_MODULES_DIR};
+
+/// The project's manifest as printed by `sop manifest --json`, with every default filled in
+#[derive(Debug, Serialize)]
+struct ExportedManifest<'a> {
+    /// Every `[project]` field, including those left out of sop.toml
+    project: serde_json::Value,
+    /// Every dependency from every table, sorted by name
+    dependencies: Vec<ExportedDependency<'a>>,
+    bins: &'a [BinTarget],
+    /// Directory packages are installed into, relative to the project root
+    modules: String,
+    manifest_style: ManifestStyle,
+    workspace: Option<&'a WorkspaceConfig>,
+    vendor: Option<&'a VendorConfig>,
+    patch: BTreeMap<&'a String, &'a Dependency>,
+    /// `sop check` rules turned on or off
+    check: Option<&'a BTreeMap<String, bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lock: Option<SopLock>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportedDependency<'a> {
+    name: &'a str,
+    /// `dependencies` or `dev-dependencies`
+    kind: &'static str,
+    /// The `[target.<platform>]` qualifier of the table it is in, if any
+    platform: Option<&'a str>,
+    /// `registry`, `git` or `path`
+    origin: &'static str,
+    /// The version requirement, for registry dependencies
+    requirement: Option<&'a str>,
+    optional: bool,
+    /// The entry exactly as sop.toml has it
+    entry: &'a Dependency,
+}
+
+/// Execute the manifest command
+///
+/// Prints the project's sop.toml with everything taken from elsewhere, such as a version
+/// inherited from the workspace, filled in. With `json`, every setting is included with its
+/// default, dependencies are listed with their table and origin, and `lock` adds sop.lock.
+pub fn execute(lock: bool, json: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let mut config = read_sop_toml(&get_sop_toml_path())?;
+    if !json {
+        if lock {
+            return Err(anyhow!("--lock only works together with --json."));
+        }
+        config.project.version_from_workspace = false;
+        print!("{}", render_sop_toml(&config)?);
+        return Ok(());
+    }
+
+    let lock = if lock {
+        Some(read_sop_lock_or_default(&get_sop_lock_path())?)
+    } else {
+        None
+    };
+    println!("{}", serde_json::to_string_pretty(&export(&config, lock)?)?);
+    Ok(())
+}
+
+fn export(config: &SopToml, lock: Option<SopLock>) -> Result<ExportedManifest<'_>> {
+    let mut project = serde_json::to_value(&config.project)?;
+    if let Some(fields) = project.as_object_mut() {
+        fields.entry("documentation").or_insert("".into());
+        fields.entry("soplang").or_insert(serde_json::Value::Null);
+        fields.entry("include").or_insert(serde_json::json!([]));
+        fields.entry("exclude").or_insert(serde_json::json!([]));
+    }
+
+    let tables = [
+        (&config.dependencies, DependencyKind::Normal),
+        (&config.dev_dependencies, DependencyKind::Dev),
+    ];
+    let unconditional = tables.into_iter().flat_map(|(table, kind)| {
+        table
+            .iter()
+            .flatten()
+            .map(move |(name, dependency)| (None, name, dependency, kind))
+    });
+    let platform = config
+        .platform_dependencies()
+        .into_iter()
+        .map(|(qualifier, name, dependency, kind)| (Some(qualifier), name, dependency, kind));
+    let mut dependencies: Vec<ExportedDependency> = unconditional
+        .chain(platform)
+        .map(|(platform, name, dependency, kind)| ExportedDependency {
+            name,
+            kind: match kind {
+                DependencyKind::Normal => "dependencies",
+                DependencyKind::Dev => "dev-dependencies",
+            },
+            platform,
+            origin: match dependency.origin() {
+                DependencyOrigin::Registry => "registry",
+                DependencyOrigin::Git => "git",
+                DependencyOrigin::Path => "path",
+            },
+            requirement: dependency.requirement(),
+            optional: dependency.is_optional(),
+            entry: dependency,
+        })
+        .collect();
+    dependencies.sort_by(|a, b| (a.name, a.kind, a.platform).cmp(&(b.name, b.kind, b.platform)));
+
+    Ok(ExportedManifest {
+        project,
+        dependencies,
+        bins: &config.bins,
+        modules: config
+            .modules
+            .as_ref()
+            .and_then(|modules| modules.path.clone())
+            .unwrap_or_else(|| DEFAULT_MODULES_DIR.to_string()),
+        manifest_style: config
+            .manifest
+            .as_ref()
+            .map(|manifest| manifest.style)
+            .unwrap_or_default(),
+        workspace: config.workspace.as_ref(),
+        vendor: config.vendor.as_ref(),
+        patch: config.patch.iter().flatten().collect(),
+        check: config.check.as_ref(),
+        lock,
+    })
+}
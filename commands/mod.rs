@@ -3,9 +3,52 @@
 
 // Each command will be implemented in a separate file and exposed here.
 pub mod add;
+pub mod audit;
+pub mod browse;
+pub mod build;
+pub mod cache;
+pub mod changelog;
+pub mod check;
+pub mod config;
+pub mod daemon;
+pub mod dashboard;
+pub mod doc;
+pub mod doctor;
+pub mod duplicates;
+pub mod env;
+pub mod exec;
+pub mod external;
+pub mod fetch;
+pub mod fix;
+pub mod foreach;
+pub mod graph;
+pub mod grep;
+pub mod info;
 pub mod init;
+pub mod inspect;
+pub mod install;
+pub mod licenses;
+pub mod list;
+pub mod lock;
+pub mod login;
+pub mod manifest;
+pub mod outdated;
+pub mod prune;
+pub mod publish;
+pub mod release;
 pub mod remove;
 pub mod run;
+pub mod sbom;
 pub mod setup;
+pub mod size;
+pub mod test;
+pub mod tree;
+pub mod udeps;
+pub mod ui;
+pub mod uninstall;
 pub mod update;
+pub mod vendor;
+pub mod verify;
+pub mod version;
+pub mod why;
 // etc.
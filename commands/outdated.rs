@@ -0,0 +1,197 @@
+use anyhow::{anyhow, Result};
+use colored::{ColoredString, Colorize};
+use serde::Serialize;
+
+use crate::exit_code::{with_exit_code, ExitCode};
+use crate::lockfile::read_sop_lock_or_default;
+use crate::registry::{self, RegistryFailures};
+use crate::style;
+use crate::toml_parser::read_sop_toml;
+use crate::utils::{ensure_in_project, get_sop_lock_path, get_sop_toml_path};
+use crate::version::{parse_version, update_kind, UpdateKind};
+
+/// A dependency with a newer version available on the registry
+#[derive(Debug, Serialize)]
+pub struct OutdatedDependency {
+    pub name: String,
+    pub installed: Option<String>,
+    pub required: String,
+    pub latest: String,
+    /// `None` when the dependency hasn't been resolved into sop.lock yet
+    pub update: Option<UpdateKind>,
+    /// How to apply the update, filled in with `--suggest`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestion: Option<Suggestion>,
+}
+
+/// What an update bot needs to open a pull request for one dependency
+#[derive(Debug, Serialize)]
+struct Suggestion {
+    command: String,
+    changelog: String,
+}
+
+/// Execute the outdated command
+pub fn execute(json: bool, suggest: bool) -> Result<()> {
+    let (outdated, failures) = find_outdated(suggest)?;
+
+    // Report what could be checked before the packages that couldn't
+    if json {
+        println!("{}", serde_json::to_string_pretty(&outdated)?);
+        failures.into_result()?;
+        return outdated_error(&outdated);
+    }
+
+    if outdated.is_empty() {
+        if failures.is_empty() {
+            println!(
+                "{} All dependencies are up to date",
+                style::ok_mark().bold()
+            );
+        }
+        return failures.into_result();
+    }
+
+    if suggest {
+        print_suggestions(&outdated)?;
+    } else {
+        print_table(&outdated);
+    }
+
+    failures.into_result()?;
+    outdated_error(&outdated)
+}
+
+/// Compare the project's registry dependencies with the latest versions on the registry
+///
+/// Dependencies whose latest version couldn't be looked up are gathered in the returned
+/// failures rather than stopping the check.
+pub fn find_outdated(suggest: bool) -> Result<(Vec<OutdatedDependency>, RegistryFailures)> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+
+    // Only registry dependencies have a latest version to compare against
+    let mut dependencies: Vec<(String, String)> = config
+        .all_dependencies()
+        .into_iter()
+        .filter_map(|(name, dependency, _)| {
+            dependency
+                .requirement()
+                .map(|requirement| (name.clone(), requirement.to_string()))
+        })
+        .collect();
+    dependencies.sort();
+
+    let mut outdated = Vec::new();
+    let mut failures = RegistryFailures::default();
+    for (name, required) in dependencies {
+        let installed = lock.get(&name).map(|entry| entry.version.clone());
+        let latest = match registry::latest_version(&name) {
+            Ok(latest) => latest,
+            Err(e) => {
+                failures.record(&name, e);
+                continue;
+            }
+        };
+
+        let kind = installed
+            .as_deref()
+            .and_then(parse_version)
+            .zip(parse_version(&latest))
+            .and_then(|(current, latest)| update_kind(&current, &latest));
+
+        // Dependencies that were never resolved are reported too, since they need installing
+        if kind.is_none() && installed.is_some() {
+            continue;
+        }
+
+        let suggestion = suggest.then(|| Suggestion {
+            // Unresolved dependencies just need installing at the version sop.toml asks for
+            command: if installed.is_some() {
+                format!("sop update {}", name)
+            } else {
+                "sop setup".to_string()
+            },
+            changelog: registry::changelog_url(&name, &latest),
+        });
+
+        outdated.push(OutdatedDependency {
+            name,
+            installed,
+            required,
+            latest,
+            update: kind,
+            suggestion,
+        });
+    }
+
+    Ok((outdated, failures))
+}
+
+/// Print the outdated dependencies as a table
+fn print_table(outdated: &[OutdatedDependency]) {
+    println!(
+        "{:<24} {:<12} {:<12} {:<12}",
+        "Package".bold(),
+        "Installed".bold(),
+        "Required".bold(),
+        "Latest".bold()
+    );
+    for dependency in outdated {
+        println!(
+            "{:<24} {:<12} {:<12} {}",
+            dependency.name,
+            dependency.installed.as_deref().unwrap_or("-"),
+            dependency.required,
+            colorize_latest(&dependency.latest, dependency.update)
+        );
+    }
+}
+
+/// Print how to update each dependency, followed by the same data as JSON for bots
+fn print_suggestions(outdated: &[OutdatedDependency]) -> Result<()> {
+    for dependency in outdated {
+        println!(
+            "{} {} {} {}",
+            dependency.name.bold(),
+            dependency.installed.as_deref().unwrap_or("-"),
+            style::symbols().arrow,
+            colorize_latest(&dependency.latest, dependency.update)
+        );
+        if let Some(suggestion) = &dependency.suggestion {
+            println!("  run:       {}", suggestion.command);
+            println!("  changelog: {}", suggestion.changelog);
+        }
+    }
+
+    println!("\n{}", "Machine-readable summary:".bold());
+    println!("{}", serde_json::to_string_pretty(outdated)?);
+    Ok(())
+}
+
+/// Exit with a distinct code when anything is outdated, so automation can act on it
+fn outdated_error(outdated: &[OutdatedDependency]) -> Result<()> {
+    if outdated.is_empty() {
+        return Ok(());
+    }
+    Err(with_exit_code(
+        ExitCode::Outdated,
+        anyhow!(
+            "{} dependenc{} can be updated",
+            outdated.len(),
+            if outdated.len() == 1 { "y" } else { "ies" }
+        ),
+    ))
+}
+
+/// Color the latest version by how big the update is
+fn colorize_latest(latest: &str, update: Option<UpdateKind>) -> ColoredString {
+    match update {
+        Some(UpdateKind::Major) => style::error(latest).bold(),
+        Some(UpdateKind::Minor) => style::warning(latest),
+        Some(UpdateKind::Patch) => style::success(latest),
+        None => latest.normal(),
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+use colored::Colorize;
+use tracing::info;
+
+use crate::commands::remove::orphaned_packages;
+use crate::graph::required_packages;
+use crate::install::{installed_version, remove_installed};
+use crate::lockfile::{read_sop_lock_or_default, write_sop_lock};
+use crate::project_lock::ProjectLock;
+use crate::style;
+use crate::toml_parser::read_sop_toml;
+use crate::utils::{
+    ensure_in_project, get_sop_lock_path, get_sop_modules_path, get_sop_toml_path,
+    installed_packages,
+};
+
+/// Execute the prune command
+///
+/// Installed packages that no dependency in sop.toml needs, according to sop.lock, are
+/// deleted from sop_modules, and locked packages nothing needs any more are dropped from
+/// sop.lock. With `dry_run`, they are only listed.
+pub fn execute(dry_run: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    // Keep other sop processes out of sop_modules and sop.lock until this one is done
+    let _lock = ProjectLock::acquire()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let sop_lock_path = get_sop_lock_path();
+    let mut lock = read_sop_lock_or_default(&sop_lock_path)?;
+    let modules_dir = get_sop_modules_path();
+
+    let required = required_packages(&config, &lock);
+    let extraneous: Vec<(String, _)> = installed_packages(&modules_dir)?
+        .into_iter()
+        .filter(|(name, _)| !required.contains(name))
+        .collect();
+    let orphans = orphaned_packages(&config, &lock);
+
+    if extraneous.is_empty() && orphans.is_empty() {
+        info!("{} Nothing to prune", style::ok_mark().bold());
+        return Ok(());
+    }
+
+    for (name, dir) in &extraneous {
+        let version = installed_version(&modules_dir, name)
+            .map(|version| format!(" v{}", version))
+            .unwrap_or_default();
+        if dry_run {
+            info!("  Would remove {}{}", name, version);
+        } else {
+            remove_installed(dir)?;
+            info!("  {} Removed {}{}", style::ok_mark(), name, version);
+        }
+    }
+
+    // Packages that were locked but never installed only need their lock entry dropped
+    let unlocked: Vec<&String> = orphans
+        .iter()
+        .filter(|orphan| !extraneous.iter().any(|(name, _)| name == *orphan))
+        .collect();
+    for orphan in &unlocked {
+        if dry_run {
+            info!("  Would drop {} from sop.lock", orphan);
+        } else {
+            info!("  {} Dropped {} from sop.lock", style::ok_mark(), orphan);
+        }
+    }
+
+    if dry_run {
+        info!(
+            "\n{} {} package{} would be pruned",
+            "Dry run:".bold(),
+            extraneous.len() + unlocked.len(),
+            if extraneous.len() + unlocked.len() == 1 {
+                ""
+            } else {
+                "s"
+            }
+        );
+        return Ok(());
+    }
+
+    if !orphans.is_empty() {
+        for orphan in &orphans {
+            lock.remove(orphan);
+        }
+        write_sop_lock(&sop_lock_path, &lock)?;
+    }
+
+    info!(
+        "{} Pruned {} package{}",
+        style::ok_mark().bold(),
+        extraneous.len() + unlocked.len(),
+        if extraneous.len() + unlocked.len() == 1 {
+            ""
+        } else {
+            "s"
+        }
+    );
+    Ok(())
+}
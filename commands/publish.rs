@@ -0,0 +1,495 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::credentials::{load_token, warn_if_expiring};
+use crate::interpreter::ensure_interpreter_satisfies;
+use crate::naming::validate_package_name;
+use crate::registry;
+use crate::style;
+use crate::toml_parser::{read_sop_toml, render_sop_toml, Dependency, DependencyOrigin, SopToml};
+use crate::utils::{
+    ensure_dir_exists, ensure_in_project, format_bytes, get_sop_toml_path, project_dir,
+    DEFAULT_MODULES_DIR,
+};
+use crate::version::{parse_version, SavePrefix};
+use crate::workspace::{dependency_waves, load_members};
+
+/// How often to check whether a published version has shown up on the registry
+const VISIBLE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait for a published version to show up before giving up
+const VISIBLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// File listing paths that are never published, in .gitignore syntax
+const SOPIGNORE: &str = ".sopignore";
+
+/// Directory `sop build` writes bundles to, which is never published
+const DIST_DIR: &str = "dist";
+
+/// Files from the project root that are published along with the sources by default
+const PUBLISHED_FILES: [&str; 5] = [
+    "README.md",
+    "LICENSE",
+    "LICENSE-MIT",
+    "LICENSE-APACHE",
+    "CHANGELOG.md",
+];
+
+/// Execute the publish command
+///
+/// Publishes the project to the registry from `[registry] url`, as an archive built in
+/// `dist/`, once the interpreter satisfies its `soplang` requirement. Path dependencies are
+/// published as caret requirements on the version in their sop.toml; git dependencies can't
+/// be published. With `workspace`, every member is published after the members it depends
+/// on, waiting for each to show up on the registry before publishing its dependents.
+/// Members whose version is already published are skipped, so a failed run can simply be
+/// repeated. With `dry_run`, every check still runs and the archive is still built, with its
+/// files listed, but nothing is uploaded.
+pub fn execute(workspace: bool, dry_run: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let registry_url = registry::registry_url();
+    if !dry_run && !registry_url.starts_with("file://") {
+        let token = load_token(&registry_url)?.ok_or_else(|| {
+            anyhow!(
+                "Not logged in to {}. Run 'sop login' before publishing.",
+                registry_url
+            )
+        })?;
+        if token.is_expired() {
+            return Err(anyhow!(
+                "The token for {} has expired. Run 'sop login' with a fresh token.",
+                registry_url
+            ));
+        }
+        warn_if_expiring(&registry_url, &token);
+    }
+
+    let root = project_dir()?;
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    if !workspace {
+        return publish_project(&root, &config, dry_run);
+    }
+
+    let members = load_members(&root, &config)?;
+    // Check every name first, so an invalid one doesn't stop the run halfway through
+    for member in &members {
+        validate_package_name(&member.name)?;
+    }
+    let order: Vec<usize> = dependency_waves(&members)?.into_iter().flatten().collect();
+    info!(
+        "Publishing {} workspace members to {}",
+        members.len(),
+        registry_url
+    );
+
+    for &index in &order {
+        let member = &members[index];
+        let version = &member.config.project.version;
+        if registry::is_published(&member.name, version)? {
+            info!(
+                "{} {} v{} is already published",
+                style::skip_mark(),
+                member.name,
+                version
+            );
+            continue;
+        }
+
+        publish_project(&member.dir, &member.config, dry_run)?;
+
+        let has_dependents = members
+            .iter()
+            .any(|other| other.name != member.name && other.config.has_dependency(&member.name));
+        if has_dependents && !dry_run {
+            wait_until_visible(&member.name, version)?;
+        }
+    }
+
+    if dry_run {
+        info!("\n{} nothing was published", "Dry run:".bold());
+    }
+    Ok(())
+}
+
+/// Publish the project in `dir`, with its path dependencies turned into version requirements
+fn publish_project(dir: &Path, config: &SopToml, dry_run: bool) -> Result<()> {
+    let name = &config.project.name;
+    let version = &config.project.version;
+    validate_package_name(name)?;
+    if parse_version(version).is_none() {
+        return Err(anyhow!(
+            "{} has version '{}', which isn't a valid version. Set it with 'sop version <version>'.",
+            name,
+            version
+        ));
+    }
+    if registry::is_published(name, version)? {
+        return Err(anyhow!(
+            "{} v{} is already published. Bump the version with 'sop version patch'.",
+            name,
+            version
+        ));
+    }
+    // Publishing a project its own Soplang can't run would ship something nobody tested
+    ensure_interpreter_satisfies(config)?;
+
+    // The published sop.toml only keeps what makes sense to the projects depending on it
+    let mut published = read_sop_toml(&dir.join("sop.toml"))?;
+    published.workspace = None;
+    published.modules = None;
+    published.vendor = None;
+    published.patch = None;
+    published.project.version_from_workspace = false;
+    let rewritten = rewrite_local_dependencies(dir, &mut published)?;
+
+    let files = published_files(dir, config)?;
+    info!("{} {} v{}", "Publishing".bold(), name, version);
+    for (dependency, requirement) in &rewritten {
+        info!(
+            "  {} {} {} {}",
+            dependency,
+            style::removed("path"),
+            style::symbols().arrow,
+            style::added(requirement)
+        );
+    }
+    warn_missing_metadata(config, &files);
+
+    let archive = build_archive(dir, &files, &published)?;
+    if dry_run {
+        for (file, size) in &archive.files {
+            info!("    {:>9}  {}", format_bytes(*size), file);
+        }
+    }
+    info!(
+        "  {} file{}, {} ({} compressed) in {}",
+        archive.files.len(),
+        if archive.files.len() == 1 { "" } else { "s" },
+        format_bytes(archive.size),
+        format_bytes(archive.compressed),
+        archive.path.display()
+    );
+    if dry_run {
+        info!("  Would upload {} v{}", name, version);
+        return Ok(());
+    }
+
+    registry::upload_package(name, version, &archive.path)?;
+
+    info!(
+        "{} Published {} v{}",
+        style::ok_mark().bold(),
+        name,
+        version
+    );
+    Ok(())
+}
+
+/// Replace path dependencies with a caret requirement on the version they have now
+///
+/// Returns the dependencies that were rewritten with their new requirement. Git dependencies
+/// are an error, since the registry can't fetch them for the projects depending on this one.
+fn rewrite_local_dependencies(dir: &Path, config: &mut SopToml) -> Result<Vec<(String, String)>> {
+    let name = config.project.name.clone();
+    let targets = config
+        .target
+        .values_mut()
+        .flat_map(|target| [&mut target.dependencies, &mut target.dev_dependencies]);
+    let tables: Vec<&mut HashMap<String, Dependency>> =
+        [&mut config.dependencies, &mut config.dev_dependencies]
+            .into_iter()
+            .chain(targets)
+            .flatten()
+            .collect();
+
+    let mut rewritten = Vec::new();
+    for table in tables {
+        for (dependency, entry) in table.iter_mut() {
+            match entry.origin() {
+                DependencyOrigin::Registry => {}
+                DependencyOrigin::Git => {
+                    return Err(anyhow!(
+                        "{} depends on {} from git, which the registry can't serve. Publish {} to the registry and depend on a version of it.",
+                        name,
+                        dependency,
+                        dependency
+                    ))
+                }
+                DependencyOrigin::Path => {
+                    let Dependency::Detailed(detail) = entry else {
+                        continue;
+                    };
+                    let path = dir.join(detail.path.as_deref().unwrap_or_default());
+                    let target = read_sop_toml(&path.join("sop.toml")).map_err(|e| {
+                        anyhow!(
+                            "Failed to read the version of {} from {}: {}",
+                            dependency,
+                            path.display(),
+                            e
+                        )
+                    })?;
+                    let requirement = parse_version(&target.project.version)
+                        .map(|version| SavePrefix::Caret.requirement(&version.to_string()))
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "{} has version '{}', which isn't a valid version",
+                                dependency,
+                                target.project.version
+                            )
+                        })?;
+                    detail.path = None;
+                    detail.version = Some(requirement.clone());
+                    rewritten.push((dependency.clone(), requirement));
+                }
+            }
+        }
+    }
+    rewritten.sort();
+    rewritten.dedup();
+    Ok(rewritten)
+}
+
+/// Work out which files of the project in `dir` are published, as paths relative to `dir`
+///
+/// Without `include` in sop.toml, that is `src/`, the entry point, and the readme, license
+/// and changelog. Files matching `exclude` or a line of `.sopignore` are left out either way,
+/// as are version control and build directories. sop.toml itself is always published, so it
+/// isn't listed.
+fn published_files(dir: &Path, config: &SopToml) -> Result<Vec<String>> {
+    let include = parse_rules(&config.project.include)?;
+    let mut exclude = parse_rules(&config.project.exclude)?;
+    let sopignore = dir.join(SOPIGNORE);
+    if sopignore.is_file() {
+        let lines: Vec<String> = fs::read_to_string(&sopignore)?
+            .lines()
+            .map(str::to_string)
+            .collect();
+        exclude.extend(parse_rules(&lines)?);
+    }
+
+    let entry = config.project.entry.trim_start_matches("./");
+    let modules_dir = config
+        .modules
+        .as_ref()
+        .and_then(|modules| modules.path.clone())
+        .unwrap_or_else(|| DEFAULT_MODULES_DIR.to_string());
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            let relative = path
+                .strip_prefix(dir)?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if path.is_dir() {
+                let skipped = path.file_name().is_some_and(|name| name == ".git")
+                    || relative == modules_dir.trim_end_matches('/')
+                    || relative == DIST_DIR;
+                if !skipped {
+                    pending.push(path);
+                }
+            } else if relative != "sop.toml" && relative != SOPIGNORE {
+                files.push(relative);
+            }
+        }
+    }
+
+    files.retain(|file| {
+        let selected = if include.is_empty() {
+            file.starts_with("src/") || file == entry || PUBLISHED_FILES.contains(&file.as_str())
+        } else {
+            decides(&include, file)
+        };
+        selected && !decides(&exclude, file)
+    });
+    files.sort();
+    Ok(files)
+}
+
+/// An `include`/`exclude` glob or a `.sopignore` line, in .gitignore syntax
+struct PathRule {
+    pattern: glob::Pattern,
+    /// Starts with `!`, taking back an earlier rule
+    negated: bool,
+    /// Contains a `/`, so it matches from the project root rather than any name in the tree
+    anchored: bool,
+    /// Ends with `/`, so it only matches directories
+    dir_only: bool,
+}
+
+impl PathRule {
+    /// Check the rule against a file, or any of the directories it is in
+    fn matches(&self, file: &str) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        let components: Vec<&str> = file.split('/').collect();
+        (1..=components.len()).any(|len| {
+            if self.dir_only && len == components.len() {
+                return false;
+            }
+            let candidate = if self.anchored {
+                components[..len].join("/")
+            } else {
+                components[len - 1].to_string()
+            };
+            self.pattern.matches_with(&candidate, options)
+        })
+    }
+}
+
+/// Parse rules, skipping blank lines and `#` comments
+fn parse_rules(lines: &[String]) -> Result<Vec<PathRule>> {
+    let mut rules = Vec::new();
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (negated, rule) = match line.strip_prefix('!') {
+            Some(rule) => (true, rule),
+            None => (false, line),
+        };
+        let dir_only = rule.ends_with('/');
+        let rule = rule.trim_end_matches('/');
+        let pattern = glob::Pattern::new(rule.trim_start_matches('/'))
+            .map_err(|e| anyhow!("Invalid pattern '{}': {}", line, e))?;
+        rules.push(PathRule {
+            pattern,
+            negated,
+            anchored: rule.contains('/'),
+            dir_only,
+        });
+    }
+    Ok(rules)
+}
+
+/// Check whether the last rule matching a file selects it
+fn decides(rules: &[PathRule], file: &str) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(file))
+        .is_some_and(|rule| !rule.negated)
+}
+
+/// A package archive built for publishing
+struct Archive {
+    path: PathBuf,
+    /// Every file in the archive with its size, sop.toml first
+    files: Vec<(String, u64)>,
+    /// Total size of the files before compression
+    size: u64,
+    /// Size of the archive itself
+    compressed: u64,
+}
+
+/// Pack sop.toml as it will be published and `files` into `dist/<name>-<version>.tar.gz`
+///
+/// Entries sit under a `<name>-<version>/` directory and carry no timestamps or owners, so
+/// the same files always produce the same archive.
+fn build_archive(dir: &Path, files: &[String], published: &SopToml) -> Result<Archive> {
+    let base = format!(
+        "{}-{}",
+        published
+            .project
+            .name
+            .trim_start_matches('@')
+            .replace('/', "-"),
+        published.project.version
+    );
+    let dist = dir.join(DIST_DIR);
+    ensure_dir_exists(&dist)?;
+    let path = dist.join(format!("{}.tar.gz", base));
+
+    let mut builder = tar::Builder::new(GzEncoder::new(
+        fs::File::create(&path)?,
+        Compression::default(),
+    ));
+    builder.mode(tar::HeaderMode::Deterministic);
+
+    let manifest = render_sop_toml(published)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(
+        &mut header,
+        format!("{}/sop.toml", base),
+        manifest.as_bytes(),
+    )?;
+    let mut listing = vec![("sop.toml".to_string(), manifest.len() as u64)];
+    for file in files {
+        let source = dir.join(file);
+        builder.append_path_with_name(&source, format!("{}/{}", base, file))?;
+        listing.push((file.clone(), fs::metadata(&source)?.len()));
+    }
+    builder.into_inner()?.finish()?;
+
+    Ok(Archive {
+        size: listing.iter().map(|(_, size)| size).sum(),
+        compressed: fs::metadata(&path)?.len(),
+        path,
+        files: listing,
+    })
+}
+
+/// Warn about metadata the registry page would be missing
+fn warn_missing_metadata(config: &SopToml, files: &[String]) {
+    if config.project.description.trim().is_empty() {
+        warn!(
+            "{} No description in sop.toml, so the package is hard to find in search",
+            style::warn_mark()
+        );
+    }
+    if config.project.license.trim().is_empty() {
+        warn!(
+            "{} No license in sop.toml, so others can't tell whether they may use the package",
+            style::warn_mark()
+        );
+    }
+    if !files
+        .iter()
+        .any(|file| file.to_uppercase().starts_with("README"))
+    {
+        warn!("{} No README is published", style::warn_mark());
+    }
+}
+
+/// Wait for a version that was just published to show up on the registry
+fn wait_until_visible(package: &str, version: &str) -> Result<()> {
+    let started = Instant::now();
+    let mut announced = false;
+    while !registry::is_published(package, version)? {
+        if started.elapsed() > VISIBLE_TIMEOUT {
+            return Err(anyhow!(
+                "{} v{} was published but hasn't shown up on the registry after {} minutes. Run 'sop publish --workspace' again to publish the rest.",
+                package,
+                version,
+                VISIBLE_TIMEOUT.as_secs() / 60
+            ));
+        }
+        if !announced {
+            info!(
+                "  Waiting for {} v{} to show up on the registry",
+                package, version
+            );
+            announced = true;
+        }
+        thread::sleep(VISIBLE_POLL_INTERVAL);
+    }
+    Ok(())
+}
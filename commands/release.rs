@@ -0,0 +1,149 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::fs;
+use tracing::{info, warn};
+
+use crate::commands::changelog::{self, CHANGELOG};
+use crate::commands::{build, test, verify, version};
+use crate::credentials::now;
+use crate::git;
+use crate::style;
+use crate::toml_parser::{read_sop_toml, write_sop_toml};
+use crate::utils::{
+    ensure_in_project, file_exists, format_timestamp, get_sop_toml_path, project_dir,
+};
+
+/// Execute the release command
+///
+/// Runs the whole release in order, stopping at the first failure: check the working tree is
+/// clean and the tag is free, verify sop_modules and run the tests, bump the version in
+/// sop.toml, add the conventional commits since the last tag to CHANGELOG.md and move its
+/// Unreleased section under the new version, build the release bundle, then commit and tag
+/// `v<version>` with the new notes as the tag message. If the build fails, sop.toml and
+/// CHANGELOG.md are put back. With `dry_run`, the checks and tests still run but nothing is
+/// changed.
+pub fn execute(bump: &str, dry_run: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let sop_toml_path = get_sop_toml_path();
+    let mut config = read_sop_toml(&sop_toml_path)?;
+    let current = version::bumped_version(&config)?;
+    let new = version::next_version(&current, bump)?.to_string();
+    let name = format!("v{}", new);
+
+    let repository = project_dir()?;
+    if !git::is_clean(&repository)? {
+        return Err(anyhow!(
+            "The working tree has uncommitted changes. Commit or stash them before releasing."
+        ));
+    }
+    if git::tag_exists(&repository, &name)? {
+        return Err(anyhow!("Tag {} already exists.", name));
+    }
+
+    info!(
+        "{} {} {} {} {}",
+        "Releasing".bold(),
+        config.project.name,
+        style::removed(&current),
+        style::symbols().arrow,
+        style::added(&new)
+    );
+
+    verify::execute(false)?;
+    test::execute(&None, false)?;
+
+    let notes = changelog::collect_notes(&repository, git::latest_tag(&repository)?.as_deref())?;
+    let changelog_path = repository.join(CHANGELOG);
+    let original = if file_exists(&changelog_path) {
+        Some(fs::read_to_string(&changelog_path)?)
+    } else {
+        None
+    };
+    let changelog = if original.is_some() || !notes.is_empty() {
+        let text = original.clone().unwrap_or_default();
+        let (text, _) = changelog::add_to_unreleased(&text, &notes);
+        let date = &format_timestamp(now())[..10];
+        let released = changelog::release_section(&text, &new, date);
+        if released.is_none() {
+            warn!(
+                "{} {} has no Unreleased section, leaving it as it is",
+                style::warn_mark(),
+                CHANGELOG
+            );
+        }
+        released
+    } else {
+        None
+    };
+
+    if dry_run {
+        info!("  Would set the version in sop.toml to {}", new);
+        if changelog.is_some() {
+            info!(
+                "  Would move the Unreleased notes in {} under {}",
+                CHANGELOG, new
+            );
+        }
+        if !notes.is_empty() {
+            info!("  Would note these changes:");
+            for line in notes.to_markdown().lines() {
+                info!("    {}", line);
+            }
+        }
+        info!("  Would build the release bundle");
+        info!("  Would commit and tag {}", name);
+        info!("\n{} nothing was changed", "Dry run:".bold());
+        return Ok(());
+    }
+
+    let original_sop_toml = fs::read_to_string(&sop_toml_path)?;
+    version::set_bumped_version(&mut config, &new);
+    write_sop_toml(&sop_toml_path, &config)?;
+    let mut files = vec![sop_toml_path.clone()];
+    if let Some(released) = &changelog {
+        fs::write(&changelog_path, released)?;
+        files.push(changelog_path.clone());
+    }
+
+    // Every [[bin]] target has to bundle, or the entry when there are none
+    let targets: Vec<Option<String>> = if config.bins.is_empty() {
+        vec![None]
+    } else {
+        config
+            .bins
+            .iter()
+            .map(|bin| Some(bin.name.clone()))
+            .collect()
+    };
+    let built = targets
+        .iter()
+        .try_for_each(|target| build::execute(true, false, target));
+    if let Err(e) = built {
+        // Leave the tree as it was found so the release can simply be retried
+        fs::write(&sop_toml_path, original_sop_toml)?;
+        match &original {
+            Some(original) => fs::write(&changelog_path, original)?,
+            None if changelog.is_some() => fs::remove_file(&changelog_path)?,
+            None => {}
+        }
+        return Err(e.context(format!("Release {} stopped, nothing was committed", name)));
+    }
+
+    git::commit_files(&repository, &files, &name)?;
+    info!("  {} Committed {}", style::ok_mark(), name);
+    let message = if notes.is_empty() {
+        name.clone()
+    } else {
+        format!("{}\n\n{}", name, notes.to_markdown())
+    };
+    git::create_tag(&repository, &name, &message)?;
+    info!("  {} Tagged {}", style::ok_mark(), name);
+    info!(
+        "\n{} Released {}. Push it with 'git push --follow-tags'.",
+        style::ok_mark().bold(),
+        name
+    );
+
+    Ok(())
+}
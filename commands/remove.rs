@@ -1,62 +1,109 @@
 use anyhow::{anyhow, Result};
 use colored::Colorize;
-use std::fs;
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use tracing::info;
 
-use crate::toml_parser::{read_sop_toml, write_sop_toml};
-use crate::utils::{file_exists, get_sop_modules_path, get_sop_toml_path};
+use crate::graph::required_packages;
+use crate::i18n::tr;
+use crate::install::remove_installed;
+use crate::lockfile::{read_sop_lock, write_sop_lock, SopLock};
+use crate::project_lock::ProjectLock;
+use crate::style;
+use crate::toml_parser::{read_sop_toml, write_sop_toml, SopToml};
+use crate::utils::{file_exists, get_sop_lock_path, get_sop_modules_path, get_sop_toml_path};
 
 /// Execute the remove command
-pub fn execute(package: &str) -> Result<()> {
-    println!("Removing package: {}", package);
+pub fn execute(packages: &[String]) -> Result<()> {
+    info!("Removing packages: {}", packages.join(", "));
 
     // Check if sop.toml exists
     let sop_toml_path = get_sop_toml_path();
     if !file_exists(&sop_toml_path) {
-        return Err(anyhow!(
+        return Err(anyhow!(tr(
             "sop.toml not found. Are you in a Soplang project directory? Run 'sop init' to create a new project."
-        ));
+        )));
     }
 
+    // Keep other sop processes out of sop_modules and sop.lock until this one is done
+    let _lock = ProjectLock::acquire()?;
+
     // Read the sop.toml file
     let mut config = read_sop_toml(&sop_toml_path)?;
 
-    // Check if dependencies section exists
-    if config.dependencies.is_none() {
-        return Err(anyhow!("No dependencies found in sop.toml."));
-    }
-
-    // Check if the package is in dependencies
-    let dependencies = config.dependencies.as_mut().unwrap();
-    if !dependencies.contains_key(package) {
+    // Check every name up front so a typo doesn't leave a half-finished removal
+    let missing: Vec<&str> = packages
+        .iter()
+        .filter(|package| !config.has_dependency(package))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
         return Err(anyhow!(
-            "Package '{}' not found in your dependencies.",
-            package
+            "Not found in your dependencies: {}",
+            missing.join(", ")
         ));
     }
 
-    // Remove the package from dependencies
-    dependencies.remove(package);
+    // Remove the packages from whichever dependency table declares them
+    for package in packages {
+        config.remove_dependency(package);
+    }
 
     // Write updated config back to sop.toml
     write_sop_toml(&sop_toml_path, &config)?;
 
-    // Remove the package directory
-    let package_dir = get_sop_modules_path().join(package);
-    if package_dir.exists() {
-        fs::remove_dir_all(&package_dir)?;
+    // Without a lockfile only the named packages are known to be installed
+    let sop_lock_path = get_sop_lock_path();
+    let to_uninstall: Vec<String> = if file_exists(&sop_lock_path) {
+        let mut lock = read_sop_lock(&sop_lock_path)?;
+        let orphans = orphaned_packages(&config, &lock);
+        for orphan in &orphans {
+            lock.remove(orphan);
+        }
+        write_sop_lock(&sop_lock_path, &lock)?;
+        orphans.into_iter().collect()
     } else {
-        println!(
-            "  {} Package directory not found, skipping deletion",
-            "!".yellow()
-        );
+        packages.to_vec()
+    };
+
+    // Remove the package directories
+    let modules_dir = get_sop_modules_path();
+    for package in &to_uninstall {
+        remove_installed(&modules_dir.join(package))?;
+        if !packages.contains(package) {
+            info!(
+                "  {} Removed {} (no longer needed)",
+                style::ok_mark(),
+                package
+            );
+        }
+    }
+
+    for package in packages {
+        // A package that is still required by another dependency stays installed
+        if !to_uninstall.contains(package) {
+            info!(
+                "  {} {} is still needed by another dependency and stays installed",
+                style::warn_mark(),
+                package
+            );
+        }
     }
 
-    println!(
+    info!(
         "{} Removed {} from dependencies",
-        "✓".green().bold(),
-        package
+        style::ok_mark().bold(),
+        packages.join(", ")
     );
 
     Ok(())
 }
+
+/// Find the locked packages that no remaining dependency needs, directly or transitively
+pub fn orphaned_packages(config: &SopToml, lock: &SopLock) -> BTreeSet<String> {
+    let needed = required_packages(config, lock);
+    lock.packages
+        .iter()
+        .map(|package| package.name.clone())
+        .filter(|name| !needed.contains(name))
+        .collect()
+}
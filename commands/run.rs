@@ -1,55 +1,211 @@
 use anyhow::{anyhow, Result};
 use colored::Colorize;
-use std::path::Path;
-use std::process::Command;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::i18n::tr;
+use crate::interpreter::{
+    ensure_interpreter_satisfies, interpreter_program, interpreter_version, script_command,
+    spawn_error,
+};
+use crate::style;
 use crate::toml_parser::read_sop_toml;
-use crate::utils::{file_exists, get_sop_toml_path};
+use crate::utils::{ensure_dir_exists, file_exists, get_sop_toml_path, project_path};
+
+/// Number of trailing stderr lines kept for the crash summary
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Where to file bugs against the Soplang interpreter
+const ISSUE_TRACKER_URL: &str = "https://github.com/soplang/soplang/issues";
 
 /// Execute the run command
-pub fn execute(script_path: &Option<String>) -> Result<()> {
+pub fn execute(
+    script_path: &Option<String>,
+    bin: &Option<String>,
+    show_command: bool,
+    crash_report: bool,
+) -> Result<()> {
     // Check if sop.toml exists
     let sop_toml_path = get_sop_toml_path();
     if !file_exists(&sop_toml_path) {
-        return Err(anyhow!(
+        return Err(anyhow!(tr(
             "sop.toml not found. Are you in a Soplang project directory? Run 'sop init' to create a new project."
-        ));
+        )));
     }
 
     // Read the sop.toml file
     let config = read_sop_toml(&sop_toml_path)?;
+    ensure_interpreter_satisfies(&config)?;
 
-    // Determine which script to run
-    let script_to_run = match script_path {
-        Some(path) => path.clone(),
-        None => config.project.entry,
+    // Determine which script to run; paths from sop.toml are relative to the project root
+    let (script_to_run, script_file) = match (script_path, bin) {
+        (Some(path), _) => (path.clone(), PathBuf::from(path)),
+        (None, Some(name)) => match config.find_bin(name) {
+            Some(target) => (target.path.clone(), project_path(&target.path)),
+            None => {
+                let available: Vec<&str> =
+                    config.bins.iter().map(|bin| bin.name.as_str()).collect();
+                return Err(anyhow!(
+                    "No [[bin]] target named '{}' in sop.toml. Available targets: {}",
+                    name,
+                    if available.is_empty() {
+                        "none".to_string()
+                    } else {
+                        available.join(", ")
+                    }
+                ));
+            }
+        },
+        (None, None) => {
+            let script_file = project_path(&config.project.entry);
+            (config.project.entry, script_file)
+        }
     };
 
     // Check if the script exists
-    let script_file = Path::new(&script_to_run);
-    if !file_exists(script_file) {
+    if !file_exists(&script_file) {
         return Err(anyhow!("Script file not found: {}", script_to_run));
     }
 
     println!("Running Soplang script: {}", script_to_run);
 
-    // In a real implementation, this would call the Soplang interpreter
-    // For the mock implementation, we'll just print the script contents
-    println!("{}", "=".repeat(40));
-    println!("{}", std::fs::read_to_string(script_file)?);
-    println!("{}", "=".repeat(40));
-
-    // Simulate running the Soplang script
-    println!("\n{} Script executed successfully", "✓".green().bold());
-
-    // In a real implementation, this would look something like:
-    // let status = Command::new("soplang")
-    //     .arg(script_file)
-    //     .status()?;
-    //
-    // if !status.success() {
-    //     return Err(anyhow!("Script execution failed"));
-    // }
-
-    Ok(())
+    let mut command = script_command(&script_file)?;
+    if show_command {
+        println!(
+            "  {} {} {}",
+            style::dim("$"),
+            interpreter_program(),
+            script_to_run
+        );
+        for (key, value) in command.get_envs() {
+            if let Some(value) = value {
+                println!(
+                    "  {} {}={}",
+                    style::dim("env"),
+                    key.to_string_lossy(),
+                    value.to_string_lossy()
+                );
+            }
+        }
+    }
+
+    let mut child = command
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+
+    // Forward stderr to the terminal while keeping its tail for the crash summary
+    let mut stderr_tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+    if let Some(stderr) = child.stderr.take() {
+        for line in BufReader::new(stderr).lines() {
+            let line = line?;
+            eprintln!("{}", line);
+            if stderr_tail.len() == STDERR_TAIL_LINES {
+                stderr_tail.pop_front();
+            }
+            stderr_tail.push_back(line);
+        }
+    }
+
+    let status = child.wait()?;
+    if status.success() {
+        println!("\n{} Script executed successfully", style::ok_mark().bold());
+        return Ok(());
+    }
+
+    let report = CrashReport {
+        script: script_to_run,
+        status: describe_status(&status),
+        toolchain: interpreter_version()
+            .unwrap_or_else(|| format!("{} (version unknown)", interpreter_program())),
+        stderr_tail: stderr_tail.into_iter().collect(),
+    };
+    report.print(show_command);
+
+    if crash_report {
+        let path = report.write()?;
+        println!(
+            "  {} Crash report written to {}. Please attach it when filing a bug at {}",
+            style::arrow(),
+            path.display(),
+            ISSUE_TRACKER_URL
+        );
+    }
+
+    Err(anyhow!("Script execution failed"))
+}
+
+/// Details collected when the interpreter exits unsuccessfully
+struct CrashReport {
+    script: String,
+    status: String,
+    toolchain: String,
+    stderr_tail: Vec<String>,
+}
+
+impl CrashReport {
+    /// Print a summary of the crash
+    fn print(&self, show_command: bool) {
+        println!("\n{} Script {}", style::error_mark().bold(), self.status);
+        println!("  Script:    {}", self.script);
+        println!("  Toolchain: {}", self.toolchain);
+
+        if !self.stderr_tail.is_empty() {
+            println!("  Last stderr output:");
+            for line in &self.stderr_tail {
+                println!("    {} {}", style::gutter(), line);
+            }
+        }
+
+        if !show_command {
+            println!(
+                "  {} Re-run with 'sop run -x' to see how the interpreter was invoked",
+                style::warning("hint:")
+            );
+        }
+    }
+
+    /// Write the crash report to `.sop/crash-reports` and return its path
+    fn write(&self) -> Result<PathBuf> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let dir = project_path(".sop").join("crash-reports");
+        ensure_dir_exists(&dir)?;
+
+        let path = dir.join(format!("crash-{}.txt", timestamp));
+        let content = format!(
+            "sop crash report\n\nscript: {}\nstatus: {}\ntoolchain: {}\nsop: {}\nplatform: {}-{}\ntimestamp: {}\n\nstderr (last {} lines):\n{}\n",
+            self.script,
+            self.status,
+            self.toolchain,
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            timestamp,
+            self.stderr_tail.len(),
+            self.stderr_tail.join("\n")
+        );
+        fs::write(&path, content)?;
+
+        Ok(path)
+    }
+}
+
+/// Describe how the interpreter process ended
+fn describe_status(status: &ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return format!("was killed by signal {}", signal);
+        }
+    }
+
+    match status.code() {
+        Some(code) => format!("exited with status {}", code),
+        None => "terminated abnormally".to_string(),
+    }
 }
@@ -0,0 +1,329 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+
+use crate::credentials::now;
+use crate::graph::runtime_packages;
+use crate::install::package_license;
+use crate::lockfile::{read_sop_lock_or_default, LockedPackage};
+use crate::registry;
+use crate::style;
+use crate::toml_parser::read_sop_toml;
+use crate::utils::{
+    ensure_in_project, format_timestamp, get_sop_lock_path, get_sop_modules_path, get_sop_toml_path,
+};
+
+/// SBOM standards supported by the sbom command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SbomFormat {
+    /// SPDX 2.3 JSON
+    Spdx,
+    /// CycloneDX 1.5 JSON
+    Cyclonedx,
+}
+
+/// What the SBOM says about the project or one of its packages
+struct Component {
+    name: String,
+    version: String,
+    license: Option<String>,
+    /// Hex SHA-256 digest from sop.lock
+    sha256: Option<String>,
+    /// Package URL, for packages from the registry
+    purl: Option<String>,
+    /// Where the package can be downloaded, in SPDX download location syntax
+    download_location: Option<String>,
+    dependencies: Vec<String>,
+}
+
+impl Component {
+    fn from_locked(package: &LockedPackage, license: Option<String>) -> Self {
+        let (purl, download_location) = match (package.git_source(), package.path_source()) {
+            (Some((url, commit)), _) => (None, Some(format!("git+{}@{}", url, commit))),
+            (None, Some(_)) => (None, None),
+            (None, None) => match package.registry_source() {
+                Some(url) => (
+                    Some(format!(
+                        "{}?repository_url={}",
+                        purl(&package.name, &package.version),
+                        url
+                    )),
+                    Some(format!(
+                        "{}/packages/{}/{}",
+                        url, package.name, package.version
+                    )),
+                ),
+                None => (
+                    Some(purl(&package.name, &package.version)),
+                    Some(registry::package_url(&package.name, &package.version)),
+                ),
+            },
+        };
+
+        Component {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            license,
+            sha256: package
+                .checksum
+                .as_deref()
+                .and_then(|checksum| checksum.strip_prefix("sha256:"))
+                .map(str::to_string),
+            purl,
+            download_location,
+            dependencies: package.dependencies.clone(),
+        }
+    }
+}
+
+/// Execute the sbom command
+///
+/// Dev-only packages are left out unless `dev` is set, since they don't ship with the project.
+pub fn execute(format: SbomFormat, output: &Option<String>, dev: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+    let modules_dir = get_sop_modules_path();
+
+    let included = if dev {
+        lock.packages.iter().map(|p| p.name.clone()).collect()
+    } else {
+        runtime_packages(&config, &lock)
+    };
+    let mut packages: Vec<Component> = lock
+        .packages
+        .iter()
+        .filter(|package| included.contains(&package.name))
+        .map(|package| {
+            Component::from_locked(package, package_license(&modules_dir.join(&package.name)))
+        })
+        .collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut roots: Vec<String> = config
+        .all_dependencies()
+        .into_iter()
+        .map(|(name, _, _)| name.clone())
+        .filter(|name| included.contains(name))
+        .collect();
+    roots.sort();
+    let project = Component {
+        name: config.project.name.clone(),
+        version: config.project.version.clone(),
+        license: Some(config.project.license.clone()).filter(|license| !license.is_empty()),
+        sha256: None,
+        purl: None,
+        download_location: Some(config.project.repository.clone())
+            .filter(|repository| !repository.is_empty()),
+        dependencies: roots,
+    };
+
+    // Honor SOURCE_DATE_EPOCH so the same inputs always produce the same document
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(now);
+    let document = match format {
+        SbomFormat::Spdx => spdx(&project, &packages, &format_timestamp(timestamp)),
+        SbomFormat::Cyclonedx => cyclonedx(&project, &packages, &format_timestamp(timestamp)),
+    };
+    let content = format!("{}\n", serde_json::to_string_pretty(&document)?);
+
+    match output {
+        Some(path) => {
+            fs::write(path, content)?;
+            eprintln!(
+                "{} Wrote SBOM for {} packages to {}",
+                style::ok_mark(),
+                packages.len(),
+                path
+            );
+        }
+        None => print!("{}", content),
+    }
+
+    Ok(())
+}
+
+/// Build an SPDX 2.3 document
+fn spdx(project: &Component, packages: &[Component], created: &str) -> Value {
+    let spdx_package = |component: &Component| {
+        let mut package = json!({
+            "name": component.name,
+            "SPDXID": spdx_id(&component.name),
+            "versionInfo": component.version,
+            "downloadLocation": component.download_location.as_deref().unwrap_or("NOASSERTION"),
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": component.license.as_deref().unwrap_or("NOASSERTION"),
+            "copyrightText": "NOASSERTION",
+        });
+        if let Some(sha256) = &component.sha256 {
+            package["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": sha256 }]);
+        }
+        if let Some(purl) = &component.purl {
+            package["externalRefs"] = json!([{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": purl,
+            }]);
+        }
+        package
+    };
+
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": spdx_id(&project.name),
+    })];
+    for component in std::iter::once(project).chain(packages) {
+        for dependency in &component.dependencies {
+            relationships.push(json!({
+                "spdxElementId": spdx_id(&component.name),
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": spdx_id(dependency),
+            }));
+        }
+    }
+
+    let name = format!("{}-{}", project.name, project.version);
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!(
+            "{}/spdx/{}-{}",
+            registry::registry_url(),
+            name,
+            document_uuid(project, packages)
+        ),
+        "creationInfo": {
+            "created": created,
+            "creators": [format!("Tool: sop-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": std::iter::once(project).chain(packages).map(spdx_package).collect::<Vec<_>>(),
+        "relationships": relationships,
+    })
+}
+
+/// Build a CycloneDX 1.5 document
+fn cyclonedx(project: &Component, packages: &[Component], timestamp: &str) -> Value {
+    let component = |component: &Component, kind: &str| {
+        let mut entry = json!({
+            "type": kind,
+            "bom-ref": bom_ref(component),
+            "name": component.name,
+            "version": component.version,
+        });
+        if let Some(purl) = &component.purl {
+            entry["purl"] = json!(purl);
+        }
+        if let Some(license) = &component.license {
+            entry["licenses"] = json!([{ "expression": license }]);
+        }
+        if let Some(sha256) = &component.sha256 {
+            entry["hashes"] = json!([{ "alg": "SHA-256", "content": sha256 }]);
+        }
+        if let Some(location) = &component.download_location {
+            entry["externalReferences"] = json!([{ "type": "distribution", "url": location }]);
+        }
+        entry
+    };
+
+    let find_ref = |name: &str| {
+        packages
+            .iter()
+            .find(|package| package.name == name)
+            .map(bom_ref)
+            .unwrap_or_else(|| name.to_string())
+    };
+    let dependencies: Vec<Value> = std::iter::once(project)
+        .chain(packages)
+        .map(|component| {
+            json!({
+                "ref": bom_ref(component),
+                "dependsOn": component
+                    .dependencies
+                    .iter()
+                    .map(|dependency| find_ref(dependency))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", document_uuid(project, packages)),
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp,
+            "tools": [{ "vendor": "Soplang", "name": "sop", "version": env!("CARGO_PKG_VERSION") }],
+            "component": component(project, "application"),
+        },
+        "components": packages
+            .iter()
+            .map(|package| component(package, "library"))
+            .collect::<Vec<_>>(),
+        "dependencies": dependencies,
+    })
+}
+
+/// Get the package URL of a registry package (`pkg:soplang/json@1.2.0`)
+fn purl(name: &str, version: &str) -> String {
+    format!("pkg:soplang/{}@{}", name.replace('@', "%40"), version)
+}
+
+/// Identify a component within a CycloneDX document
+fn bom_ref(component: &Component) -> String {
+    component
+        .purl
+        .clone()
+        .unwrap_or_else(|| format!("{}@{}", component.name, component.version))
+}
+
+/// Turn a package name into an SPDX element id (letters, digits, `.` and `-` only)
+fn spdx_id(name: &str) -> String {
+    let id: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("SPDXRef-Package-{}", id)
+}
+
+/// Derive a UUID from the project and its packages, so unchanged inputs keep the same id
+fn document_uuid(project: &Component, packages: &[Component]) -> String {
+    let mut hasher = Sha256::new();
+    for component in std::iter::once(project).chain(packages) {
+        hasher.update(component.name.as_bytes());
+        hasher.update([0]);
+        hasher.update(component.version.as_bytes());
+        hasher.update([0]);
+        hasher.update(component.sha256.as_deref().unwrap_or("").as_bytes());
+        hasher.update([0]);
+    }
+    let mut bytes: [u8; 16] = hasher.finalize()[..16].try_into().expect("16 bytes");
+
+    // Mark it as a name-based (version 5) RFC 4122 UUID
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
@@ -1,98 +1,469 @@
 use anyhow::{anyhow, Result};
 use colored::Colorize;
-use std::fs;
 use std::path::Path;
+use tracing::{info, warn};
 
-use crate::toml_parser::read_sop_toml;
-use crate::utils::{ensure_dir_exists, file_exists, get_sop_modules_path, get_sop_toml_path};
+use crate::config::{self, read_config};
+use crate::conflict::{locked_version, satisfies};
+use crate::graph::{platform_qualifiers, required_packages, runtime_packages};
+use crate::i18n::tr;
+use crate::install::{
+    install_dependency, lock_with_dependencies, patch_for, prefetch_packages, remove_installed,
+    PackageRequest,
+};
+use crate::interpreter::ensure_interpreter_satisfies;
+use crate::lockfile::{
+    is_frozen, lock_changes_forbidden, read_sop_lock_or_default, restricting_flag, write_sop_lock,
+    LockedPackage, SopLock,
+};
+use crate::project_lock::ProjectLock;
+use crate::registry::{self, RegistryFailures};
+use crate::style;
+use crate::toml_parser::{
+    platform_matches, read_sop_toml, Dependency, DependencyDetail, DependencyKind, SopToml,
+};
+use crate::utils::{
+    ensure_dir_exists, file_exists, get_sop_lock_path, get_sop_modules_path, get_sop_toml_path,
+};
 
 /// Execute the setup command
-pub fn execute() -> Result<()> {
+///
+/// Registry packages are downloaded up to `jobs` at a time (by default the `jobs` setting or
+/// the number of CPUs) before being installed in order. With `production`, or with
+/// `SOP_ENV=production`, dev-dependencies are neither installed nor kept in sop_modules, but
+/// stay locked in sop.lock.
+pub fn execute(offline: bool, jobs: Option<usize>, production: bool) -> Result<()> {
+    let offline = offline || is_frozen() || read_config()?.offline;
+    let production = production || std::env::var("SOP_ENV").is_ok_and(|env| env == "production");
+
     // Check if sop.toml exists
     let sop_toml_path = get_sop_toml_path();
     if !file_exists(&sop_toml_path) {
-        return Err(anyhow!(
+        return Err(anyhow!(tr(
             "sop.toml not found. Are you in a Soplang project directory? Run 'sop init' to create a new project."
-        ));
+        )));
     }
 
+    // Keep other sop processes out of sop_modules and sop.lock until this one is done
+    let _lock = ProjectLock::acquire()?;
+
     // Read the sop.toml file
     let config = read_sop_toml(&sop_toml_path)?;
+    ensure_interpreter_satisfies(&config)?;
+
+    // Read the existing lockfile, if any, so locked versions are reused
+    let sop_lock_path = get_sop_lock_path();
+    let lock = read_sop_lock_or_default(&sop_lock_path)?;
+    if lock_changes_forbidden() {
+        ensure_locked(&config, &lock)?;
+    }
 
     // Print project info
-    println!("{} {}", "Project:".green().bold(), config.project.name);
+    info!(
+        "{} {}",
+        style::success("Project:").bold(),
+        config.project.name
+    );
 
     // Create sop_modules directory if it doesn't exist
     let modules_dir = get_sop_modules_path();
     ensure_dir_exists(&modules_dir)?;
 
     // Install dependencies if there are any
-    match &config.dependencies {
-        Some(dependencies) if !dependencies.is_empty() => {
-            println!("{}", "Installing dependencies...".blue().bold());
+    let mut new_lock = SopLock::default();
+    let mut failures = RegistryFailures::default();
+    let mut dependencies = config.all_dependencies();
+    if production {
+        dependencies.retain(|(_, _, kind)| *kind == DependencyKind::Normal);
+    }
+    dependencies.sort_by(|a, b| a.0.cmp(b.0));
+    if !dependencies.is_empty() {
+        if production {
+            info!(
+                "{}",
+                style::info("Installing dependencies (production, skipping dev-dependencies)...")
+                    .bold()
+            );
+        } else {
+            info!("{}", style::info("Installing dependencies...").bold());
+        }
 
-            for (package, version) in dependencies {
-                install_package(&package, &version, &modules_dir)?;
-            }
+        if !offline {
+            prefetch_packages(
+                registry_requests(&dependencies, &lock),
+                &lock,
+                jobs.unwrap_or_else(config::jobs),
+            );
+        }
 
-            println!(
-                "{} Successfully installed all dependencies.",
-                "✓".green().bold()
+        for (package, dependency, _) in dependencies {
+            let result = install_dependency(
+                package,
+                dependency,
+                &modules_dir,
+                &lock,
+                &mut new_lock,
+                offline,
             );
+
+            // Keep going after a failure so every problem is reported at once;
+            // a missing optional dependency isn't a problem at all
+            match result {
+                Err(e) if dependency.is_optional() => warn!(
+                    "  {} Skipping optional dependency {}: {}",
+                    style::warn_mark(),
+                    package,
+                    e
+                ),
+                Err(e) => failures.record(package, e),
+                Ok(()) => {}
+            }
         }
-        _ => {
-            println!("{}", "No dependencies specified in sop.toml.".yellow());
+        failures.into_result()?;
+
+        info!(
+            "{} Successfully installed all dependencies.",
+            style::ok_mark().bold()
+        );
+    } else {
+        info!(
+            "{}",
+            style::warning("No dependencies specified in sop.toml.")
+        );
+    }
+
+    if let Some(patch) = &config.patch {
+        let mut unused: Vec<&str> = patch
+            .keys()
+            .filter(|package| new_lock.get(package).is_none())
+            .map(String::as_str)
+            .collect();
+        unused.sort();
+        for package in unused {
+            warn!(
+                "  {} [patch] entry for {} was not used: nothing depends on it",
+                style::warn_mark(),
+                package
+            );
         }
     }
 
+    if production {
+        keep_dev_dependencies(&config, &lock, &mut new_lock, &modules_dir)?;
+    }
+    lock_other_platforms(&config, &lock, &mut new_lock, offline)?;
+    let platforms = platform_qualifiers(&config, &new_lock);
+    for package in &mut new_lock.packages {
+        package.platforms = platforms.get(&package.name).cloned().unwrap_or_default();
+    }
+
+    // Write the resolved versions back to sop.lock
+    write_sop_lock(&sop_lock_path, &new_lock)?;
+
     Ok(())
 }
 
-/// Install a single package
-fn install_package(package: &str, version: &str, modules_dir: &Path) -> Result<()> {
-    println!("Installing {} v{}", package, version);
+/// Carry the locked dev-dependencies over to the new sop.lock after a production install,
+/// removing any that an earlier install left in sop_modules
+fn keep_dev_dependencies(
+    config: &SopToml,
+    lock: &SopLock,
+    new_lock: &mut SopLock,
+    modules_dir: &Path,
+) -> Result<()> {
+    let runtime = runtime_packages(config, new_lock);
+    let required = required_packages(config, lock);
+    let dev_only: Vec<&LockedPackage> = lock
+        .packages
+        .iter()
+        .filter(|package| required.contains(&package.name) && !runtime.contains(&package.name))
+        .collect();
+
+    for package in dev_only {
+        if new_lock.get(&package.name).is_none() {
+            new_lock.packages.push(package.clone());
+        }
+        let package_dir = modules_dir.join(&package.name);
+        if package_dir.exists() {
+            remove_installed(&package_dir)?;
+            info!(
+                "  {} Removed dev-dependency {}",
+                style::skip_mark(),
+                package.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Lock the `[target.<platform>]` dependencies of platforms other than this one
+///
+/// They aren't installed, but stay in sop.lock so the lockfile is the same whichever platform
+/// ran setup. Packages already locked keep their versions while those satisfy sop.toml; new
+/// registry packages are resolved and fetched into the cache to lock them. Git and path
+/// dependencies can only be locked on a platform that installs them.
+fn lock_other_platforms(
+    config: &SopToml,
+    lock: &SopLock,
+    new_lock: &mut SopLock,
+    offline: bool,
+) -> Result<()> {
+    let mut dependencies: Vec<_> = config
+        .platform_dependencies()
+        .into_iter()
+        .filter(|(qualifier, _, _, _)| !platform_matches(qualifier))
+        .collect();
+    dependencies.sort_by(|a, b| a.1.cmp(b.1));
+
+    let mut failures = RegistryFailures::default();
+    for (qualifier, package, dependency, _) in dependencies {
+        if new_lock.get(package).is_some() {
+            continue;
+        }
+        let still_satisfied = lock.get(package).is_some_and(|locked| {
+            dependency
+                .requirement()
+                .is_none_or(|requirement| satisfies(&locked.version, requirement))
+        });
+        if still_satisfied {
+            keep_locked(package, lock, new_lock);
+            continue;
+        }
+        let Some(requirement) = dependency.requirement() else {
+            warn!(
+                "  {} {} is only needed on {}, run 'sop setup' there to lock it",
+                style::warn_mark(),
+                package,
+                qualifier
+            );
+            continue;
+        };
 
-    // Create a directory for the package
-    let package_dir = modules_dir.join(package);
+        let result = registry::resolve_version(package, requirement).and_then(|version| {
+            let registry = registry::dependency_registry_url(dependency)?;
+            lock_with_dependencies(
+                package,
+                &version,
+                registry.as_deref(),
+                lock,
+                new_lock,
+                offline,
+            )
+        });
+        match result {
+            Err(e) if dependency.is_optional() => warn!(
+                "  {} Skipping optional dependency {}: {}",
+                style::warn_mark(),
+                package,
+                e
+            ),
+            Err(e) => failures.record(package, e),
+            Ok(()) => info!(
+                "  {} Locked {} for {}",
+                style::ok_mark(),
+                package,
+                qualifier
+            ),
+        }
+    }
+    failures.into_result()
+}
 
-    // If package already exists, check if it's the right version
-    if package_dir.exists() {
-        println!("  {} {} is already installed", "✓".yellow(), package);
-        // In a real implementation, we would check version compatibility here
+/// Copy a package's entry in the old sop.lock, and those of everything it depends on, into the
+/// new one
+fn keep_locked(package: &str, lock: &SopLock, new_lock: &mut SopLock) {
+    let mut pending = vec![package.to_string()];
+    while let Some(package) = pending.pop() {
+        if new_lock.get(&package).is_some() {
+            continue;
+        }
+        if let Some(entry) = lock.get(&package) {
+            pending.extend(entry.dependencies.iter().cloned());
+            new_lock.packages.push(entry.clone());
+        }
+    }
+}
+
+/// Fail before installing anything if sop.lock doesn't lock every dependency in sop.toml as
+/// sop.toml asks for it
+///
+/// Changes further down the dependency graph are caught when sop.lock would be written.
+fn ensure_locked(config: &SopToml, lock: &SopLock) -> Result<()> {
+    let mut mismatches: Vec<(&str, String)> = config
+        .all_dependencies()
+        .into_iter()
+        .chain(
+            config
+                .platform_dependencies()
+                .into_iter()
+                .map(|(_, package, dependency, kind)| (package, dependency, kind)),
+        )
+        .filter_map(|(package, dependency, _)| {
+            let dependency = patch_for(package).unwrap_or(dependency);
+            Some((package.as_str(), lock_mismatch(package, dependency, lock)?))
+        })
+        .collect();
+    if mismatches.is_empty() {
         return Ok(());
     }
+    mismatches.sort();
+    mismatches.dedup();
+    let details: Vec<String> = mismatches
+        .iter()
+        .map(|(package, reason)| format!("  {}: {}", package, reason))
+        .collect();
+    Err(anyhow!(
+        "sop.lock is out of date with sop.toml, but {} was given:\n{}\nRun 'sop setup' without {} to update sop.lock.",
+        restricting_flag(),
+        details.join("\n"),
+        restricting_flag()
+    ))
+}
+
+/// Describe how sop.lock differs from what sop.toml asks for a dependency, if it does
+fn lock_mismatch(package: &str, dependency: &Dependency, lock: &SopLock) -> Option<String> {
+    let Some(locked) = lock.get(package) else {
+        return Some("not locked".to_string());
+    };
+    let expected = match dependency {
+        Dependency::Detailed(DependencyDetail { git: Some(url), .. }) => {
+            return match locked.git_source() {
+                Some((locked_url, _)) if locked_url == url => None,
+                _ => Some(format!(
+                    "locked from {}, but sop.toml asks for git+{}",
+                    describe_source(locked.source.as_deref()),
+                    url
+                )),
+            };
+        }
+        Dependency::Detailed(DependencyDetail {
+            path: Some(path), ..
+        }) => Some(format!("path+{}", path)),
+        dependency => {
+            let requirement = dependency.requirement()?;
+            if !satisfies(&locked.version, requirement) {
+                return Some(format!(
+                    "locked at {}, but sop.toml requires {}",
+                    locked.version, requirement
+                ));
+            }
+            // An unknown registry name is reported when the package is installed
+            registry::dependency_registry_url(dependency)
+                .ok()?
+                .map(|url| format!("registry+{}", url))
+        }
+    };
+    (locked.source != expected).then(|| {
+        format!(
+            "locked from {}, but sop.toml asks for {}",
+            describe_source(locked.source.as_deref()),
+            describe_source(expected.as_deref())
+        )
+    })
+}
 
-    ensure_dir_exists(&package_dir)?;
+/// Name where a package comes from, as recorded in sop.lock
+fn describe_source(source: Option<&str>) -> String {
+    source.unwrap_or("the public registry").to_string()
+}
 
-    // For now, we'll just create a placeholder file
-    // In a real implementation, this would download the package from a registry
-    let metadata_file = package_dir.join("sop.toml");
-    let metadata_content = format!(
-        r#"[package]
-name = "{}"
-version = "{}"
-description = "A Soplang package"
-"#,
-        package, version
-    );
+/// Work out which version of each registry dependency setup will install, for prefetching
+///
+/// Dependencies whose version or registry can't be determined are left out; installing them
+/// reports the problem.
+fn registry_requests(
+    dependencies: &[(&String, &Dependency, DependencyKind)],
+    lock: &SopLock,
+) -> Vec<PackageRequest> {
+    dependencies
+        .iter()
+        .filter(|(package, _, _)| patch_for(package).is_none())
+        .filter_map(|(package, dependency, _)| {
+            let requirement = dependency.requirement()?;
+            let version = match locked_version(lock, package, requirement) {
+                Some(locked) => locked.to_string(),
+                None => registry::resolve_version(package, requirement).ok()?,
+            };
+            Some(PackageRequest {
+                name: package.to_string(),
+                version,
+                registry: registry::dependency_registry_url(dependency).ok()?,
+            })
+        })
+        .collect()
+}
 
-    fs::write(metadata_file, metadata_content)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml_parser::parse_sop_toml;
 
-    // Create a simple placeholder .so file
-    let lib_file = package_dir.join("lib.so");
-    let lib_content = format!(
-        r#"// This is a placeholder for the {} library
+    fn manifest(dependencies: &str) -> SopToml {
+        let content = format!(
+            "[project]\nname = \"app\"\nversion = \"1.0.0\"\nstatus = \"experimental\"\ndescription = \"\"\nlicense = \"\"\nauthor = \"\"\nrepository = \"\"\nhomepage = \"\"\nentry = \"src/main.so\"\nkeywords = []\ncategories = []\n\n[dependencies]\n{}",
+            dependencies
+        );
+        parse_sop_toml(&content, Path::new("sop.toml")).unwrap()
+    }
 
-export fn hello() {{
-    println("Hello from {}!");
-}}
-"#,
-        package, package
-    );
+    fn lock(packages: &[(&str, &str, Option<&str>)]) -> SopLock {
+        let mut lock = SopLock::default();
+        for (name, version, source) in packages {
+            lock.set(name, version);
+            lock.set_source(name, source.map(str::to_string));
+        }
+        lock
+    }
 
-    fs::write(lib_file, lib_content)?;
+    #[test]
+    fn up_to_date_lock_is_accepted() {
+        let config = manifest("json = \"^1.0\"\nlib = { path = \"../lib\" }\n");
+        let lock = lock(&[
+            ("json", "1.4.0", None),
+            ("lib", "0.1.0", Some("path+../lib")),
+        ]);
+        assert!(ensure_locked(&config, &lock).is_ok());
+    }
 
-    println!("  {} {}", "✓".green(), package);
+    #[test]
+    fn unlocked_dependency_is_rejected() {
+        let config = manifest("json = \"^1.0\"\n");
+        let error = ensure_locked(&config, &SopLock::default()).unwrap_err();
+        assert!(error.to_string().contains("json: not locked"));
+    }
 
-    Ok(())
+    #[test]
+    fn locked_version_outside_the_requirement_is_rejected() {
+        let config = manifest("json = \"2.1.0\"\n");
+        let lock = lock(&[("json", "1.0.0", None)]);
+        let error = ensure_locked(&config, &lock).unwrap_err().to_string();
+        assert!(error.contains("json: locked at 1.0.0, but sop.toml requires 2.1.0"));
+        assert!(error.contains("Run 'sop setup' without --locked"));
+    }
+
+    #[test]
+    fn changed_source_is_rejected() {
+        let config = manifest(
+            "lib = { path = \"../new-lib\" }\nhttp = { git = \"https://example.com/new.git\" }\njson = \"^1.0\"\n",
+        );
+        let lock = lock(&[
+            ("lib", "0.1.0", Some("path+../lib")),
+            (
+                "http",
+                "1.0.0",
+                Some("git+https://example.com/old.git#abc123"),
+            ),
+            (
+                "json",
+                "1.0.0",
+                Some("git+https://example.com/json.git#abc123"),
+            ),
+        ]);
+        let error = ensure_locked(&config, &lock).unwrap_err().to_string();
+        assert!(
+            error.contains("lib: locked from path+../lib, but sop.toml asks for path+../new-lib")
+        );
+        assert!(error.contains("http: locked from git+https://example.com/old.git#abc123"));
+        assert!(error.contains("json: locked from git+https://example.com/json.git#abc123, but sop.toml asks for the public registry"));
+    }
 }
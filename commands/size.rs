@@ -0,0 +1,169 @@
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+use crate::graph::DependencyGraph;
+use crate::lockfile::read_sop_lock_or_default;
+use crate::style;
+use crate::toml_parser::read_sop_toml;
+use crate::utils::{
+    dir_exists, dir_size, ensure_in_project, format_bytes, get_sop_lock_path, get_sop_modules_path,
+    get_sop_toml_path,
+};
+
+/// The footprint of one installed package, as printed by `sop size --json`
+#[derive(Debug, Serialize)]
+struct PackageSize {
+    name: String,
+    version: String,
+    /// Bytes the package takes up in sop_modules
+    size: u64,
+    /// For direct dependencies, bytes of the package and of every package only it brings in,
+    /// which is what removing it would save
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exclusive_size: Option<u64>,
+    /// For transitive dependencies, the shortest chain of packages bringing it in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    via: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+struct SizeReport {
+    packages: Vec<PackageSize>,
+    /// Bytes of every installed package together
+    total: u64,
+    /// Locked packages that aren't in sop_modules, so have no size
+    missing: Vec<String>,
+}
+
+/// Execute the size command
+///
+/// Reports how much space each installed package takes up in sop_modules, largest first,
+/// with the total for the whole tree.
+pub fn execute(json: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+    let graph = DependencyGraph::from_manifest_and_lock(&config, &lock);
+    let modules_dir = get_sop_modules_path();
+
+    let mut packages = Vec::new();
+    let mut missing = Vec::new();
+    for package in lock.packages.iter().filter(|p| p.for_current_platform()) {
+        let dir = modules_dir.join(&package.name);
+        if !dir_exists(&dir) {
+            missing.push(package.name.clone());
+            continue;
+        }
+        let direct = graph.roots.contains(&package.name);
+        packages.push(PackageSize {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            size: dir_size(&dir)?,
+            exclusive_size: None,
+            via: if direct {
+                None
+            } else {
+                graph.path_to(&package.name)
+            },
+        });
+    }
+
+    // What a direct dependency costs is everything no other direct dependency also needs
+    for index in 0..packages.len() {
+        if packages[index].via.is_some() || !graph.roots.contains(&packages[index].name) {
+            continue;
+        }
+        let name = &packages[index].name;
+        let others = reachable(&graph, graph.roots.iter().filter(|root| *root != name));
+        let own = reachable(&graph, [name]);
+        let exclusive = packages
+            .iter()
+            .filter(|package| own.contains(&package.name) && !others.contains(&package.name))
+            .map(|package| package.size)
+            .sum();
+        packages[index].exclusive_size = Some(exclusive);
+    }
+
+    packages.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    let report = SizeReport {
+        total: packages.iter().map(|package| package.size).sum(),
+        packages,
+        missing,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+    Ok(())
+}
+
+/// Collect every package reachable from `starts`, including the starts themselves
+fn reachable<'a>(
+    graph: &'a DependencyGraph,
+    starts: impl IntoIterator<Item = &'a String>,
+) -> BTreeSet<&'a String> {
+    let mut seen = BTreeSet::new();
+    let mut pending: Vec<&String> = starts.into_iter().collect();
+    while let Some(package) = pending.pop() {
+        if seen.insert(package) {
+            pending.extend(graph.dependencies_of(package));
+        }
+    }
+    seen
+}
+
+fn print_report(report: &SizeReport) {
+    if report.packages.is_empty() && report.missing.is_empty() {
+        println!("{}", style::warning("No packages in sop.lock."));
+        return;
+    }
+
+    let width = report
+        .packages
+        .iter()
+        .map(|package| package.name.len() + package.version.len() + 2)
+        .max()
+        .unwrap_or(0);
+    for package in &report.packages {
+        let label = format!("{} v{}", package.name, package.version);
+        let detail = match (&package.exclusive_size, &package.via) {
+            (Some(exclusive), _) if *exclusive > package.size => Some(format!(
+                "{} with what only it needs",
+                format_bytes(*exclusive)
+            )),
+            (_, Some(via)) => Some(format!("via {}", via.join(" > "))),
+            _ => None,
+        };
+        match detail {
+            Some(detail) => println!(
+                "  {:>9}  {:width$}  {}",
+                format_bytes(package.size),
+                label,
+                style::dim(&detail)
+            ),
+            None => println!("  {:>9}  {}", format_bytes(package.size), label),
+        }
+    }
+
+    println!(
+        "\n{} {} in {} package{}",
+        "Total:".bold(),
+        format_bytes(report.total),
+        report.packages.len(),
+        if report.packages.len() == 1 { "" } else { "s" }
+    );
+    if !report.missing.is_empty() {
+        println!(
+            "{}",
+            style::warning(&format!(
+                "Not installed, so not counted: {}. Run 'sop setup' to install them.",
+                report.missing.join(", ")
+            ))
+        );
+    }
+}
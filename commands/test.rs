@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::imports::import_closure;
+use crate::interpreter::{script_command, spawn_error};
+use crate::style;
+use crate::utils::{
+    ensure_in_project, find_files_with_extension, get_sop_modules_path, get_src_path,
+    get_tests_path,
+};
+
+/// How often watch mode polls for file changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Execute the test command
+pub fn execute(filter: &Option<String>, watch: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let tests = discover_tests(filter)?;
+    if tests.is_empty() {
+        println!(
+            "{}",
+            style::warning("No test files found. Add .so files under tests/ to get started.")
+        );
+        if !watch {
+            return Ok(());
+        }
+    }
+
+    let mut results = BTreeMap::new();
+    run_tests(&tests, &mut results)?;
+    print_summary(&results, tests.len());
+
+    if !watch {
+        if results.values().any(|passed| !passed) {
+            return Err(anyhow!("Some tests failed"));
+        }
+        return Ok(());
+    }
+
+    watch_tests(filter, results)
+}
+
+/// Find every test file, optionally keeping only those whose path contains `filter`
+fn discover_tests(filter: &Option<String>) -> Result<Vec<PathBuf>> {
+    let tests = find_files_with_extension(&get_tests_path(), "so")?;
+    Ok(tests
+        .into_iter()
+        .filter(|test| match filter {
+            Some(filter) => test.to_string_lossy().contains(filter.as_str()),
+            None => true,
+        })
+        .collect())
+}
+
+/// Run each test file through the interpreter, recording whether it passed
+fn run_tests(tests: &[PathBuf], results: &mut BTreeMap<PathBuf, bool>) -> Result<()> {
+    for test in tests {
+        let output = script_command(test)?
+            .stdin(Stdio::null())
+            .output()
+            .map_err(spawn_error)?;
+        let passed = output.status.success();
+
+        if passed {
+            println!("  {} {}", style::ok_mark(), test.display());
+        } else {
+            println!("  {} {}", style::error_mark(), test.display());
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            for line in stderr.lines() {
+                println!("    {} {}", style::gutter(), line);
+            }
+        }
+
+        results.insert(test.clone(), passed);
+    }
+
+    Ok(())
+}
+
+/// Print a one-line summary of the latest results
+fn print_summary(results: &BTreeMap<PathBuf, bool>, rerun_count: usize) {
+    let passed = results.values().filter(|passed| **passed).count();
+    let failed = results.len() - passed;
+    let status = format!("{} passed, {} failed ({} run)", passed, failed, rerun_count);
+
+    if failed == 0 {
+        println!("{} {}", style::success("Tests:").bold(), status);
+    } else {
+        println!("{} {}", style::error("Tests:").bold(), status);
+    }
+}
+
+/// Watch sources and re-run only the tests affected by each change
+fn watch_tests(filter: &Option<String>, mut results: BTreeMap<PathBuf, bool>) -> Result<()> {
+    println!(
+        "{}",
+        style::dim("Watching for changes... (press Ctrl+C to stop)")
+    );
+    let mut snapshot = snapshot_sources()?;
+
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+
+        let current = snapshot_sources()?;
+        let changed: BTreeSet<PathBuf> = current
+            .iter()
+            .filter(|(path, modified)| snapshot.get(*path) != Some(*modified))
+            .map(|(path, _)| path.clone())
+            .chain(
+                snapshot
+                    .keys()
+                    .filter(|path| !current.contains_key(*path))
+                    .cloned(),
+            )
+            .collect();
+        snapshot = current;
+        if changed.is_empty() {
+            continue;
+        }
+
+        // Forget results of tests that were deleted
+        let tests = discover_tests(filter)?;
+        results.retain(|test, _| tests.contains(test));
+
+        let affected: Vec<PathBuf> = tests
+            .into_iter()
+            .filter(|test| is_affected(test, &changed))
+            .collect();
+        if affected.is_empty() {
+            continue;
+        }
+
+        println!(
+            "\n{} {} changed, re-running {} test(s)",
+            style::rerun_mark(),
+            describe_changes(&changed),
+            affected.len()
+        );
+        run_tests(&affected, &mut results)?;
+        print_summary(&results, affected.len());
+        println!(
+            "{}",
+            style::dim("Watching for changes... (press Ctrl+C to stop)")
+        );
+    }
+}
+
+/// Check whether a test or anything it imports is among the changed files
+fn is_affected(test: &Path, changed: &BTreeSet<PathBuf>) -> bool {
+    let (files, packages) = import_closure(test);
+    if files.iter().any(|file| changed.contains(file)) {
+        return true;
+    }
+
+    let modules_dir = get_sop_modules_path();
+    changed.iter().any(|path| {
+        path.strip_prefix(&modules_dir)
+            .ok()
+            .and_then(|relative| relative.components().next())
+            .map(|package| packages.contains(&*package.as_os_str().to_string_lossy()))
+            .unwrap_or(false)
+    })
+}
+
+/// Describe the changed files for the status line
+fn describe_changes(changed: &BTreeSet<PathBuf>) -> String {
+    match changed.iter().next() {
+        Some(first) if changed.len() == 1 => first.display().to_string(),
+        _ => format!("{} files", changed.len()),
+    }
+}
+
+/// Record the modification time of every source file that can affect a test
+fn snapshot_sources() -> Result<BTreeMap<PathBuf, SystemTime>> {
+    let mut snapshot = BTreeMap::new();
+    for dir in [get_src_path(), get_tests_path(), get_sop_modules_path()] {
+        for file in find_files_with_extension(&dir, "so")? {
+            if let Ok(modified) = fs::metadata(&file).and_then(|metadata| metadata.modified()) {
+                snapshot.insert(file, modified);
+            }
+        }
+    }
+    Ok(snapshot)
+}
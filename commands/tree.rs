@@ -0,0 +1,306 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashSet;
+
+use crate::graph::{load_project_graph, DependencyGraph};
+use crate::registry::package_url;
+use crate::style;
+use crate::utils::{ensure_in_project, escape_html};
+
+/// Output formats supported by the tree command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TreeFormat {
+    /// Indented text tree for the terminal
+    Text,
+    /// Nested Markdown list with links to registry pages
+    Markdown,
+    /// Nested HTML list with links to registry pages
+    Html,
+}
+
+/// Options that shape which parts of the graph are shown
+pub struct TreeOptions {
+    /// Maximum number of levels below the top of the tree
+    pub depth: Option<usize>,
+    /// Show the packages that depend on this package instead of the project's dependencies
+    pub invert: Option<String>,
+    /// Expand each package only the first time it appears
+    pub dedupe: bool,
+}
+
+/// A package in the rendered tree
+#[derive(Serialize)]
+struct TreeEntry {
+    name: String,
+    version: Option<String>,
+    /// Set when the package already appears among its own ancestors
+    cycle: bool,
+    /// Set when the package already appeared earlier in the tree
+    duplicate: bool,
+    /// Set for the project itself, which ends every path of an inverted tree
+    project: bool,
+    children: Vec<TreeEntry>,
+}
+
+/// Which way the tree follows the edges of the graph
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Dependencies,
+    Dependents,
+}
+
+/// State shared while building one tree
+struct Builder<'a> {
+    graph: &'a DependencyGraph,
+    options: &'a TreeOptions,
+    direction: Direction,
+    ancestors: Vec<String>,
+    seen: HashSet<String>,
+}
+
+/// Execute the tree command
+///
+/// `json` takes precedence over `format` and prints the tree as nested objects.
+pub fn execute(format: TreeFormat, json: bool, options: &TreeOptions) -> Result<()> {
+    ensure_in_project()?;
+
+    let graph = load_project_graph()?;
+    let (title, heading, top, direction) = match &options.invert {
+        Some(package) => {
+            if !graph.nodes.contains_key(package) && !graph.roots.contains(package) {
+                return Err(anyhow!(
+                    "Package '{}' is not a dependency of {}.",
+                    package,
+                    graph.root_name
+                ));
+            }
+            let title = match graph.version_of(package) {
+                Some(version) => format!("{} v{}", package, version),
+                None => package.clone(),
+            };
+            let heading = format!("Packages that depend on {}", title);
+            let top = dependents_of(&graph, package);
+            (title, heading, top, Direction::Dependents)
+        }
+        None => {
+            let title = format!("{} v{}", graph.root_name, graph.root_version);
+            let heading = format!("Dependencies of {}", title);
+            (title, heading, graph.roots.clone(), Direction::Dependencies)
+        }
+    };
+
+    let mut builder = Builder {
+        graph: &graph,
+        options,
+        direction,
+        ancestors: options.invert.iter().cloned().collect(),
+        seen: HashSet::new(),
+    };
+    let entries: Vec<TreeEntry> = if options.depth == Some(0) {
+        Vec::new()
+    } else {
+        top.iter()
+            .map(|name| builder.build_entry(name, 1))
+            .collect()
+    };
+
+    if json {
+        let top = match &options.invert {
+            Some(package) => json!({
+                "name": package,
+                "version": graph.version_of(package),
+                "dependents": entries,
+            }),
+            None => json!({
+                "name": graph.root_name,
+                "version": graph.root_version,
+                "dependencies": entries,
+            }),
+        };
+        println!("{}", serde_json::to_string_pretty(&top)?);
+        return Ok(());
+    }
+
+    let output = match format {
+        TreeFormat::Text => render_text(&title, &entries),
+        TreeFormat::Markdown => render_markdown(&heading, &entries),
+        TreeFormat::Html => render_html(&heading, &entries),
+    };
+    print!("{}", output);
+
+    Ok(())
+}
+
+impl Builder<'_> {
+    /// Build the subtree rooted at a package, stopping at cycles and the depth limit
+    fn build_entry(&mut self, name: &str, level: usize) -> TreeEntry {
+        let project = self.direction == Direction::Dependents && name == self.graph.root_name;
+        let version = if project {
+            Some(self.graph.root_version.clone())
+        } else {
+            self.graph.version_of(name).map(str::to_string)
+        };
+        let cycle = self.ancestors.iter().any(|ancestor| ancestor == name);
+        let duplicate = !self.seen.insert(name.to_string()) && !cycle && !project;
+
+        let pruned = project || cycle || (duplicate && self.options.dedupe);
+        let expand = !pruned && self.options.depth.is_none_or(|depth| level < depth);
+        let children = if expand {
+            self.ancestors.push(name.to_string());
+            let children = self
+                .children_of(name)
+                .iter()
+                .map(|child| self.build_entry(child, level + 1))
+                .collect();
+            self.ancestors.pop();
+            children
+        } else {
+            Vec::new()
+        };
+
+        TreeEntry {
+            name: name.to_string(),
+            version,
+            cycle,
+            duplicate,
+            project,
+            children,
+        }
+    }
+
+    fn children_of(&self, name: &str) -> Vec<String> {
+        match self.direction {
+            Direction::Dependencies => self.graph.dependencies_of(name).to_vec(),
+            Direction::Dependents => dependents_of(self.graph, name),
+        }
+    }
+}
+/// Get the packages that depend directly on a package, with the project last if it does
+fn dependents_of(graph: &DependencyGraph, package: &str) -> Vec<String> {
+    let mut dependents: Vec<String> = graph
+        .nodes
+        .iter()
+        .filter(|(_, node)| node.dependencies.iter().any(|name| name == package))
+        .map(|(name, _)| name.clone())
+        .collect();
+    if graph.roots.iter().any(|root| root == package) {
+        dependents.push(graph.root_name.clone());
+    }
+    dependents
+}
+
+/// Render the tree with box-drawing characters
+fn render_text(title: &str, entries: &[TreeEntry]) -> String {
+    let mut output = format!("{}\n", title);
+    render_text_entries(entries, "", &mut output);
+    output
+}
+
+fn render_text_entries(entries: &[TreeEntry], prefix: &str, output: &mut String) {
+    for (index, entry) in entries.iter().enumerate() {
+        let last = index + 1 == entries.len();
+        let symbols = style::symbols();
+        let (branch, indent) = if last {
+            (symbols.tree_last, "    ")
+        } else {
+            (symbols.tree_branch, symbols.tree_indent)
+        };
+
+        output.push_str(&format!("{}{}{}\n", prefix, branch, describe(entry)));
+        render_text_entries(&entry.children, &format!("{}{}", prefix, indent), output);
+    }
+}
+
+/// Render the tree as a nested Markdown list
+fn render_markdown(heading: &str, entries: &[TreeEntry]) -> String {
+    let mut output = format!("## {}\n\n", heading);
+    if entries.is_empty() {
+        output.push_str("_None._\n");
+    }
+    render_markdown_entries(entries, 0, &mut output);
+    output
+}
+
+fn render_markdown_entries(entries: &[TreeEntry], depth: usize, output: &mut String) {
+    for entry in entries {
+        let label = match &entry.version {
+            Some(version) if entry.project => format!("{} v{}", entry.name, version),
+            Some(version) => format!(
+                "[{}]({}) v{}",
+                entry.name,
+                package_url(&entry.name, version),
+                version
+            ),
+            None => format!("{} (not resolved)", entry.name),
+        };
+        output.push_str(&format!(
+            "{}- {}{}\n",
+            "  ".repeat(depth),
+            label,
+            markers(entry)
+        ));
+        render_markdown_entries(&entry.children, depth + 1, output);
+    }
+}
+
+/// Render the tree as a nested HTML list
+fn render_html(heading: &str, entries: &[TreeEntry]) -> String {
+    let mut output = format!(
+        "<div class=\"sop-dependencies\">\n<h2>{}</h2>\n",
+        escape_html(heading)
+    );
+    if entries.is_empty() {
+        output.push_str("<p>None.</p>\n");
+    } else {
+        render_html_entries(entries, &mut output);
+    }
+    output.push_str("</div>\n");
+    output
+}
+
+fn render_html_entries(entries: &[TreeEntry], output: &mut String) {
+    output.push_str("<ul>\n");
+    for entry in entries {
+        let label = match &entry.version {
+            Some(version) if entry.project => {
+                format!("{} v{}", escape_html(&entry.name), escape_html(version))
+            }
+            Some(version) => format!(
+                "<a href=\"{}\">{}</a> v{}",
+                escape_html(&package_url(&entry.name, version)),
+                escape_html(&entry.name),
+                escape_html(version)
+            ),
+            None => format!("{} (not resolved)", escape_html(&entry.name)),
+        };
+        output.push_str(&format!("<li>{}{}", label, markers(entry)));
+        if !entry.children.is_empty() {
+            output.push('\n');
+            render_html_entries(&entry.children, output);
+        }
+        output.push_str("</li>\n");
+    }
+    output.push_str("</ul>\n");
+}
+
+/// Describe a package for the text tree
+fn describe(entry: &TreeEntry) -> String {
+    let description = match &entry.version {
+        Some(version) => format!("{} v{}", entry.name, version),
+        None => format!("{} (not resolved)", entry.name),
+    };
+    format!("{}{}", description, markers(entry))
+}
+
+/// Suffix marking cycles and packages that already appeared earlier in the tree
+fn markers(entry: &TreeEntry) -> &'static str {
+    if entry.cycle {
+        " (cycle)"
+    } else if entry.duplicate {
+        " (*)"
+    } else {
+        ""
+    }
+}
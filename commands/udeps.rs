@@ -0,0 +1,178 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::commands::{add, remove};
+use crate::imports::{imported_packages, project_sources};
+use crate::install::package_binaries;
+use crate::registry;
+use crate::style;
+use crate::toml_parser::{read_sop_toml, DependencyKind, SopToml};
+use crate::utils::{
+    dir_exists, ensure_in_project, file_exists, get_sop_modules_path, get_sop_toml_path,
+    get_src_path, get_tests_path,
+};
+
+/// Execute the udeps command
+pub fn execute(fix: bool, add_missing: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let sources = project_sources(&config)?;
+    let imported = imported_packages(&sources);
+    let modules_dir = get_sop_modules_path();
+
+    let mut unused = Vec::new();
+    let mut tools = Vec::new();
+    let mut dependencies = config.all_dependencies();
+    dependencies.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, _, kind) in dependencies {
+        if imported.contains(name) {
+            continue;
+        }
+
+        // Packages that ship binaries are used through `sop exec`, not imports
+        if !package_binaries(&modules_dir.join(name)).is_empty() {
+            tools.push(name.clone());
+        } else {
+            unused.push((name.clone(), kind));
+        }
+    }
+    let missing = missing_dependencies(&config, &sources)?;
+
+    println!(
+        "Scanned {} source files for imports.",
+        sources.len().to_string().bold()
+    );
+    for tool in &tools {
+        println!(
+            "  {} {} is never imported but provides binaries; keeping it",
+            style::skip_mark(),
+            tool
+        );
+    }
+
+    if unused.is_empty() && missing.is_empty() {
+        println!(
+            "{} No unused or missing dependencies",
+            style::ok_mark().bold()
+        );
+        return Ok(());
+    }
+
+    if !unused.is_empty() {
+        println!("{}", style::warning("Unused dependencies:"));
+        for (name, kind) in &unused {
+            println!("  {} {} ({})", style::warn_mark(), name, table_name(*kind));
+        }
+    }
+    if !missing.is_empty() {
+        println!(
+            "{}",
+            style::warning("Imported but not declared in sop.toml:")
+        );
+        for (name, kind) in &missing {
+            println!(
+                "  {} {} (needed in {})",
+                style::warn_mark(),
+                name,
+                table_name(*kind)
+            );
+        }
+    }
+
+    if fix && !unused.is_empty() {
+        let names: Vec<String> = unused.iter().map(|(name, _)| name.clone()).collect();
+        remove::execute(&names)?;
+    }
+    if add_missing {
+        for (name, kind) in &missing {
+            let latest = registry::latest_version(name)?;
+            let dev = *kind == DependencyKind::Dev;
+            add::execute(
+                name,
+                &Some(latest),
+                dev,
+                false,
+                &None,
+                &None,
+                &None,
+                &None,
+                None,
+            )?;
+        }
+    }
+
+    let mut hints = Vec::new();
+    if !fix && !unused.is_empty() {
+        hints.push("'sop udeps --fix' to remove the unused ones");
+    }
+    if !add_missing && !missing.is_empty() {
+        hints.push("'sop udeps --add-missing' to add the missing ones");
+    }
+    if !hints.is_empty() {
+        println!("Run {}.", hints.join(", or "));
+    }
+    Ok(())
+}
+
+/// Find imported packages that neither dependency table declares
+///
+/// Packages imported from `src/` belong in `[dependencies]`; ones only tests import belong in
+/// `[dev-dependencies]`. Imports that name a module of the project itself, or that the
+/// registry doesn't know (such as interpreter built-ins), are left out.
+fn missing_dependencies(
+    config: &SopToml,
+    sources: &[PathBuf],
+) -> Result<BTreeMap<String, DependencyKind>> {
+    let tests_path = get_tests_path();
+    let (test_sources, main_sources): (Vec<PathBuf>, Vec<PathBuf>) = sources
+        .iter()
+        .cloned()
+        .partition(|file| file.starts_with(&tests_path));
+
+    let mut missing = BTreeMap::new();
+    let candidates = imported_packages(&main_sources)
+        .into_iter()
+        .map(|name| (name, DependencyKind::Normal))
+        .chain(
+            imported_packages(&test_sources)
+                .into_iter()
+                .map(|name| (name, DependencyKind::Dev)),
+        );
+    for (name, kind) in candidates {
+        if missing.contains_key(&name)
+            || config.has_dependency(&name)
+            || name == config.project.name
+            || is_local_module(&name, sources)
+            || !registry::package_exists(&name)?
+        {
+            continue;
+        }
+        missing.insert(name, kind);
+    }
+
+    Ok(missing)
+}
+
+/// Check whether an import names a module of the project rather than a package
+fn is_local_module(name: &str, sources: &[PathBuf]) -> bool {
+    let src_path = get_src_path();
+    let dirs =
+        std::iter::once(src_path.as_path()).chain(sources.iter().filter_map(|file| file.parent()));
+    for dir in dirs {
+        if file_exists(&dir.join(format!("{}.so", name))) || dir_exists(&dir.join(name)) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Name of the sop.toml table a dependency kind lives in
+fn table_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Normal => "dependencies",
+        DependencyKind::Dev => "dev-dependencies",
+    }
+}
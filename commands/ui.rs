@@ -0,0 +1,462 @@
+use anyhow::{anyhow, Result};
+use colored::{ColoredString, Styles};
+use console::Term;
+use dialoguer::Confirm;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::terminal::{enable_raw_mode, EnterAlternateScreen};
+use ratatui::crossterm::ExecutableCommand;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListState, Paragraph, Tabs};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashSet;
+use std::io::{self, IsTerminal};
+
+use crate::commands::{add, outdated, remove, update};
+use crate::graph::{load_project_graph, DependencyGraph};
+use crate::i18n::tr_args;
+use crate::lockfile::read_sop_lock_or_default;
+use crate::style;
+use crate::utils::{
+    dir_exists, dir_size, ensure_in_project, format_bytes, get_sop_lock_path, get_sop_modules_path,
+};
+use crate::version::UpdateKind;
+
+/// The views `sop ui` switches between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Tree,
+    Outdated,
+    Sizes,
+}
+
+const TABS: [Tab; 3] = [Tab::Tree, Tab::Outdated, Tab::Sizes];
+
+impl Tab {
+    fn title(self) -> &'static str {
+        match self {
+            Tab::Tree => "Tree",
+            Tab::Outdated => "Outdated",
+            Tab::Sizes => "Sizes",
+        }
+    }
+}
+
+/// A line of a view, with the package it is about so actions know what to act on
+struct Row {
+    package: Option<String>,
+    text: Line<'static>,
+}
+
+/// Everything shown on screen, rebuilt after each action changes the project
+struct State {
+    graph: DependencyGraph,
+    tab: Tab,
+    tree: Vec<Row>,
+    sizes: Vec<Row>,
+    /// Looked up from the registry the first time the tab is opened
+    outdated: Option<Vec<Row>>,
+    /// Selected row and scroll position of the list
+    list: ListState,
+    /// Number of rows the list showed when last drawn, for Page Up and Page Down
+    page: usize,
+    status: String,
+}
+
+/// Execute the ui command
+///
+/// Shows the dependency tree, outdated packages and sizes in one screen, with keys to add,
+/// remove and update packages. Actions run the same code as `sop add`, `sop remove` and
+/// `sop update`, with their output shown until a key is pressed.
+pub fn execute() -> Result<()> {
+    ensure_in_project()?;
+    if !io::stdout().is_terminal() {
+        return Err(anyhow!(
+            "sop ui needs an interactive terminal. Use 'sop tree', 'sop outdated' or 'sop size' instead."
+        ));
+    }
+
+    let mut state = State::load(Tab::Tree)?;
+    // Restores the terminal on panic too
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut state);
+    ratatui::restore();
+    result
+}
+
+/// Draw the screen and handle keys until the user quits
+fn run(terminal: &mut DefaultTerminal, state: &mut State) -> Result<()> {
+    loop {
+        if state.tab == Tab::Outdated && state.outdated.is_none() {
+            state.status = "Checking the registry for newer versions...".to_string();
+            terminal.draw(|frame| render(frame, state))?;
+            state.load_outdated();
+        }
+        terminal.draw(|frame| render(frame, state))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        state.status.clear();
+        let page = state.page.max(1) as isize;
+        match key.code {
+            // Raw mode delivers Ctrl+C as a key instead of a signal
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            KeyCode::Up | KeyCode::Char('k') => state.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => state.move_selection(1),
+            KeyCode::PageUp => state.move_selection(-page),
+            KeyCode::PageDown => state.move_selection(page),
+            KeyCode::Home => state.move_selection(isize::MIN / 2),
+            KeyCode::End => state.move_selection(isize::MAX / 2),
+            KeyCode::Tab | KeyCode::Right => state.switch_tab(1),
+            KeyCode::BackTab | KeyCode::Left => state.switch_tab(-1),
+            KeyCode::Char(c @ '1'..='3') => {
+                state.select_tab(TABS[c as usize - '1' as usize]);
+            }
+            KeyCode::Char('r') => {
+                *state = State::load(state.tab)?;
+                state.status = "Refreshed".to_string();
+            }
+            KeyCode::Char('a') => {
+                run_action(terminal, || add::pick(false, false, None))?;
+                *state = State::load(state.tab)?;
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                let Some(package) = state.selected_package() else {
+                    continue;
+                };
+                if !state.graph.roots.contains(&package) {
+                    state.status = format!(
+                        "{} isn't a direct dependency; remove the package that brings it in",
+                        package
+                    );
+                    continue;
+                }
+                run_action(terminal, || {
+                    let confirmed = Confirm::new()
+                        .with_prompt(tr_args("Remove {} from sop.toml?", &[&package]))
+                        .default(false)
+                        .interact()?;
+                    if !confirmed {
+                        return Ok(());
+                    }
+                    remove::execute(std::slice::from_ref(&package))
+                })?;
+                *state = State::load(state.tab)?;
+            }
+            KeyCode::Char('u') | KeyCode::Enter => {
+                let Some(package) = state.selected_package() else {
+                    continue;
+                };
+                run_action(terminal, || {
+                    update::execute(&Some(package.clone()), false, None)
+                })?;
+                *state = State::load(state.tab)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+impl State {
+    fn load(tab: Tab) -> Result<Self> {
+        let graph = load_project_graph()?;
+        let tree = tree_rows(&graph);
+        let sizes = size_rows()?;
+        Ok(State {
+            graph,
+            tab,
+            tree,
+            sizes,
+            outdated: None,
+            list: ListState::default().with_selected(Some(0)),
+            page: 0,
+            status: String::new(),
+        })
+    }
+
+    fn load_outdated(&mut self) {
+        match outdated::find_outdated(false) {
+            Ok((outdated, failures)) => {
+                self.status = if failures.is_empty() {
+                    String::new()
+                } else {
+                    "Some packages couldn't be checked on the registry".to_string()
+                };
+                self.outdated = Some(outdated_rows(&outdated));
+            }
+            Err(e) => {
+                self.status = format!("Couldn't check for newer versions: {}", e);
+                self.outdated = Some(Vec::new());
+            }
+        }
+    }
+
+    fn rows(&self) -> &[Row] {
+        match self.tab {
+            Tab::Tree => &self.tree,
+            Tab::Outdated => self.outdated.as_deref().unwrap_or_default(),
+            Tab::Sizes => &self.sizes,
+        }
+    }
+
+    fn selected_package(&self) -> Option<String> {
+        self.rows().get(self.list.selected()?)?.package.clone()
+    }
+
+    fn move_selection(&mut self, by: isize) {
+        let last = self.rows().len().saturating_sub(1);
+        let selected = self.list.selected().unwrap_or(0);
+        self.list
+            .select(Some(selected.saturating_add_signed(by).min(last)));
+    }
+
+    fn switch_tab(&mut self, by: isize) {
+        let index = TABS.iter().position(|tab| *tab == self.tab).unwrap_or(0);
+        let count = TABS.len() as isize;
+        self.select_tab(TABS[(index as isize + by).rem_euclid(count) as usize]);
+    }
+
+    fn select_tab(&mut self, tab: Tab) {
+        if tab != self.tab {
+            self.tab = tab;
+            self.list = ListState::default().with_selected(Some(0));
+        }
+    }
+}
+
+/// Draw the whole screen: tabs, the current view and the key bindings
+fn render(frame: &mut Frame, state: &mut State) {
+    let [header, body, keys, status] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(3),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let title = Line::from(vec![
+        span(style::info(&state.graph.root_name)).patch_style(Modifier::BOLD),
+        Span::raw(" "),
+        span(style::dim(&format!("v{}", state.graph.root_version))),
+    ]);
+    let [title_area, tabs_area] = Layout::horizontal([
+        Constraint::Length(title.width() as u16 + 3),
+        Constraint::Min(0),
+    ])
+    .areas(header);
+    frame.render_widget(title, title_area);
+    let tabs = Tabs::new(
+        TABS.iter()
+            .enumerate()
+            .map(|(index, tab)| format!("[{}] {}", index + 1, tab.title())),
+    )
+    .select(TABS.iter().position(|tab| *tab == state.tab))
+    .style(style_of(&style::dim("")))
+    .highlight_style(style_of(&style::accent("")).add_modifier(Modifier::BOLD))
+    .divider(" ")
+    .padding("", " ");
+    frame.render_widget(tabs, tabs_area);
+
+    let block = Block::new()
+        .borders(Borders::TOP | Borders::BOTTOM)
+        .border_style(style_of(&style::dim("")));
+    state.page = block.inner(body).height as usize;
+    let rows = state.rows();
+    if rows.is_empty() {
+        let empty = match state.tab {
+            Tab::Tree => "No dependencies. Press 'a' to add one.",
+            Tab::Outdated if state.outdated.is_none() => "",
+            Tab::Outdated => "All dependencies are up to date.",
+            Tab::Sizes => "No packages installed. Run 'sop setup' to install them.",
+        };
+        let empty = Line::from(vec![Span::raw("  "), span(style::dim(empty))]);
+        frame.render_widget(Paragraph::new(empty).block(block), body);
+    } else {
+        let arrow = format!("{} ", style::symbols().arrow);
+        let list = List::new(rows.iter().map(|row| row.text.clone()))
+            .block(block)
+            .highlight_symbol(&arrow)
+            .highlight_spacing(ratatui::widgets::HighlightSpacing::Always)
+            .highlight_style(Modifier::BOLD);
+        frame.render_stateful_widget(list, body, &mut state.list);
+    }
+
+    frame.render_widget(
+        span(style::dim(
+            "↑↓ move  tab switch view  a add  d remove  u update  r refresh  q quit",
+        )),
+        keys,
+    );
+    frame.render_widget(span(style::warning(&state.status)), status);
+}
+
+/// Run an action with the screen handed over to its output and prompts, until a key is pressed
+fn run_action(terminal: &mut DefaultTerminal, action: impl FnOnce() -> Result<()>) -> Result<()> {
+    ratatui::restore();
+    terminal.show_cursor()?;
+    if let Err(e) = action() {
+        eprintln!("{} {}", style::error_mark(), e);
+    }
+    println!("\n{}", style::dim("Press any key to return to sop ui"));
+    Term::stdout().read_key()?;
+
+    enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    terminal.clear()?;
+    Ok(())
+}
+
+/// Carry the color and emphasis `style` gives a piece of text over to the terminal UI, so it
+/// follows the theme and `--color` like the rest of sop's output
+fn span(text: ColoredString) -> Span<'static> {
+    let span_style = style_of(&text);
+    Span::styled(text.input, span_style)
+}
+
+/// The terminal UI style matching what `style` gives a piece of text
+fn style_of(text: &ColoredString) -> Style {
+    let mut span_style = Style::default();
+    if !colored::control::SHOULD_COLORIZE.should_colorize() {
+        return span_style;
+    }
+    if let Some(color) = text.fgcolor {
+        span_style = span_style.fg(tui_color(color));
+    }
+    if text.style.contains(Styles::Bold) {
+        span_style = span_style.add_modifier(Modifier::BOLD);
+    }
+    if text.style.contains(Styles::Dimmed) {
+        span_style = span_style.add_modifier(Modifier::DIM);
+    }
+    span_style
+}
+
+fn tui_color(color: colored::Color) -> Color {
+    match color {
+        colored::Color::Black => Color::Black,
+        colored::Color::Red => Color::Red,
+        colored::Color::Green => Color::Green,
+        colored::Color::Yellow => Color::Yellow,
+        colored::Color::Blue => Color::Blue,
+        colored::Color::Magenta => Color::Magenta,
+        colored::Color::Cyan => Color::Cyan,
+        colored::Color::White => Color::Gray,
+        colored::Color::BrightBlack => Color::DarkGray,
+        colored::Color::BrightRed => Color::LightRed,
+        colored::Color::BrightGreen => Color::LightGreen,
+        colored::Color::BrightYellow => Color::LightYellow,
+        colored::Color::BrightBlue => Color::LightBlue,
+        colored::Color::BrightMagenta => Color::LightMagenta,
+        colored::Color::BrightCyan => Color::LightCyan,
+        colored::Color::BrightWhite => Color::White,
+        colored::Color::TrueColor { r, g, b } => Color::Rgb(r, g, b),
+    }
+}
+
+/// Flatten the dependency tree into rows, expanding each package the first time it appears
+fn tree_rows(graph: &DependencyGraph) -> Vec<Row> {
+    fn walk(
+        graph: &DependencyGraph,
+        names: &[String],
+        prefix: &str,
+        seen: &mut HashSet<String>,
+        rows: &mut Vec<Row>,
+    ) {
+        for (index, name) in names.iter().enumerate() {
+            let last = index + 1 == names.len();
+            let version = graph
+                .version_of(name)
+                .map(|version| format!(" v{}", version))
+                .unwrap_or_default();
+            let repeated = !seen.insert(name.clone());
+            rows.push(Row {
+                package: Some(name.clone()),
+                text: Line::raw(format!(
+                    "{}{}{}{}{}",
+                    prefix,
+                    if last { "└── " } else { "├── " },
+                    name,
+                    version,
+                    if repeated { " (*)" } else { "" }
+                )),
+            });
+            if !repeated {
+                let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                walk(graph, graph.dependencies_of(name), &prefix, seen, rows);
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    walk(graph, &graph.roots, "", &mut HashSet::new(), &mut rows);
+    rows
+}
+
+fn outdated_rows(outdated: &[outdated::OutdatedDependency]) -> Vec<Row> {
+    let width = outdated
+        .iter()
+        .map(|dependency| dependency.name.len())
+        .max()
+        .unwrap_or(0);
+    outdated
+        .iter()
+        .map(|dependency| {
+            let kind = match dependency.update {
+                Some(UpdateKind::Major) => style::error("major"),
+                Some(UpdateKind::Minor) => style::warning("minor"),
+                Some(UpdateKind::Patch) => style::success("patch"),
+                None => style::dim("not installed"),
+            };
+            Row {
+                package: Some(dependency.name.clone()),
+                text: Line::from(vec![
+                    Span::raw(format!(
+                        "{:width$}  {} → {}  ",
+                        dependency.name,
+                        dependency.installed.as_deref().unwrap_or("-"),
+                        dependency.latest
+                    )),
+                    span(kind),
+                ]),
+            }
+        })
+        .collect()
+}
+
+/// List installed packages largest first, with the total on top
+fn size_rows() -> Result<Vec<Row>> {
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+    let modules_dir = get_sop_modules_path();
+    let mut sizes = Vec::new();
+    for package in lock.packages.iter().filter(|p| p.for_current_platform()) {
+        let dir = modules_dir.join(&package.name);
+        if dir_exists(&dir) {
+            sizes.push((dir_size(&dir)?, &package.name, &package.version));
+        }
+    }
+    sizes.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    if sizes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let total: u64 = sizes.iter().map(|(size, _, _)| size).sum();
+    let mut rows = vec![Row {
+        package: None,
+        text: Line::from(vec![
+            Span::raw(format!("{:>9}  ", format_bytes(total))),
+            span(style::dim(&format!("total in {} packages", sizes.len()))),
+        ]),
+    }];
+    rows.extend(sizes.into_iter().map(|(size, name, version)| Row {
+        package: Some(name.clone()),
+        text: Line::raw(format!("{:>9}  {} v{}", format_bytes(size), name, version)),
+    }));
+    Ok(rows)
+}
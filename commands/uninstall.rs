@@ -0,0 +1,42 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::fs;
+
+use crate::commands::install::shim_path;
+use crate::install::{package_binaries, remove_installed};
+use crate::style;
+use crate::utils::{dir_exists, file_exists, get_global_bin_path, get_global_packages_path};
+
+/// Execute the uninstall command
+pub fn execute(package: &str, global: bool) -> Result<()> {
+    if !global {
+        return Err(anyhow!(
+            "Only global uninstalls are supported. Use 'sop uninstall --global {}', or 'sop remove {}' to remove it from this project.",
+            package,
+            package
+        ));
+    }
+
+    let package_dir = get_global_packages_path()?.join(package);
+    if !dir_exists(&package_dir) {
+        return Err(anyhow!("Package '{}' is not installed globally.", package));
+    }
+
+    println!("Uninstalling {} globally", package);
+
+    // Remove the shims before the package, while its [bin] table can still be read
+    let bin_dir = get_global_bin_path()?;
+    for (name, _) in package_binaries(&package_dir) {
+        let shim = shim_path(&bin_dir, &name);
+        if file_exists(&shim) {
+            fs::remove_file(&shim)?;
+            println!("  {} Unlinked {}", style::ok_mark(), name);
+        }
+    }
+
+    remove_installed(&package_dir)?;
+
+    println!("{} Uninstalled {}", style::ok_mark().bold(), package);
+
+    Ok(())
+}
@@ -1,199 +1,246 @@
 use anyhow::{anyhow, Result};
 use colored::Colorize;
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
-
+use tracing::info;
+
+use crate::config::{self, read_config};
+use crate::i18n::tr;
+use crate::install::{install_with_dependencies, patch_for, prefetch_packages, PackageRequest};
+use crate::lockfile::{
+    ensure_lock_may_change, is_frozen, read_sop_lock_or_default, write_sop_lock,
+};
+use crate::project_lock::ProjectLock;
+use crate::registry::{self, RegistryFailures};
+use crate::style;
 use crate::toml_parser::{read_sop_toml, write_sop_toml};
-use crate::utils::{ensure_dir_exists, file_exists, get_sop_modules_path, get_sop_toml_path};
+use crate::utils::{
+    ensure_dir_exists, file_exists, get_sop_lock_path, get_sop_modules_path, get_sop_toml_path,
+};
+use crate::version::{parse_version, split_requirement};
 
 /// Execute the update command
-pub fn execute(package: &Option<String>) -> Result<()> {
+///
+/// New versions are downloaded up to `jobs` at a time before being installed.
+pub fn execute(package: &Option<String>, dry_run: bool, jobs: Option<usize>) -> Result<()> {
+    if is_frozen() {
+        return Err(anyhow!(
+            "Checking for updates needs the registry, but --frozen was given."
+        ));
+    }
+    if !dry_run {
+        ensure_lock_may_change("Updating dependencies")?;
+    }
+
     // Check if sop.toml exists
     let sop_toml_path = get_sop_toml_path();
     if !file_exists(&sop_toml_path) {
-        return Err(anyhow!(
+        return Err(anyhow!(tr(
             "sop.toml not found. Are you in a Soplang project directory? Run 'sop init' to create a new project."
-        ));
+        )));
     }
 
+    // Keep other sop processes out of sop_modules and sop.lock until this one is done
+    let _lock = ProjectLock::acquire()?;
+
     // Read the sop.toml file
     let mut config = read_sop_toml(&sop_toml_path)?;
 
-    // Check if dependencies section exists
-    if config.dependencies.is_none() || config.dependencies.as_ref().unwrap().is_empty() {
-        println!("{}", "No dependencies specified in sop.toml.".yellow());
+    // Check if there are any dependencies
+    if config.all_dependencies().is_empty() {
+        info!(
+            "{}",
+            style::warning("No dependencies specified in sop.toml.")
+        );
         return Ok(());
     }
 
-    let dependencies = config.dependencies.as_mut().unwrap();
-    let modules_dir = get_sop_modules_path();
-    ensure_dir_exists(&modules_dir)?;
+    let sop_lock_path = get_sop_lock_path();
+    let locked = read_sop_lock_or_default(&sop_lock_path)?;
+    let mut lock = locked.clone();
 
     // If a specific package is specified, only update that package
-    if let Some(pkg_name) = package {
-        if !dependencies.contains_key(pkg_name) {
-            return Err(anyhow!(
-                "Package '{}' not found in your dependencies.",
+    let mut packages_to_check: Vec<String> = match package {
+        Some(pkg_name) => {
+            if !config.has_dependency(pkg_name) {
+                return Err(anyhow!(
+                    "Package '{}' not found in your dependencies.",
+                    pkg_name
+                ));
+            }
+            vec![pkg_name.clone()]
+        }
+        None => {
+            info!("Checking for updates for all dependencies...");
+            config
+                .all_dependencies()
+                .into_iter()
+                .map(|(name, _, _)| name.clone())
+                .collect()
+        }
+    };
+    packages_to_check.sort();
+
+    // Work out every change up front so a dry run can report them without touching anything
+    let mut planned_updates = Vec::new();
+    let mut failures = RegistryFailures::default();
+    for pkg_name in packages_to_check {
+        if patch_for(&pkg_name).is_some() {
+            info!(
+                "  {} Skipping {}: patched in sop.toml",
+                style::warn_mark(),
                 pkg_name
-            ));
+            );
+            continue;
         }
-
-        let version = dependencies.get(pkg_name).unwrap().clone();
-        println!(
-            "Checking for updates for {} (current: {})",
-            pkg_name, version
-        );
+        // Git and path dependencies aren't versioned through the registry
+        let Some(version) = config.find_dependency(&pkg_name).unwrap().requirement() else {
+            info!(
+                "  {} Skipping {}: not a registry dependency",
+                style::warn_mark(),
+                pkg_name
+            );
+            continue;
+        };
+        let version = version.to_string();
+        info!("Checking {} (current: {})", pkg_name, version);
 
         // Get the latest version (in a real implementation, this would check a registry)
-        let latest_version = check_latest_version(pkg_name, &version)?;
+        let (_, mut current_version) = split_requirement(&version);
+        let is_range = version != "latest" && parse_version(current_version).is_none();
+        let latest = if is_range {
+            // A range stays in sop.toml; only the locked version moves to the newest it allows
+            current_version = locked
+                .get(&pkg_name)
+                .map(|entry| entry.version.as_str())
+                .unwrap_or_default();
+            registry::resolve_version(&pkg_name, &version)
+        } else {
+            check_latest_version(&pkg_name, current_version)
+        };
+        let latest_version = match latest {
+            Ok(latest_version) => latest_version,
+            Err(e) => {
+                failures.record(&pkg_name, e);
+                continue;
+            }
+        };
 
-        if latest_version == version {
-            println!("  {} {} is already up to date", "✓".green(), pkg_name);
+        if latest_version == current_version {
+            info!("  {} {} is already up to date", style::ok_mark(), pkg_name);
         } else {
-            // Update the dependency in sop.toml
-            dependencies.insert(pkg_name.clone(), latest_version.clone());
+            // Show the installed version, unless only the requirement in sop.toml changes
+            let current = locked
+                .get(&pkg_name)
+                .map(|entry| entry.version.clone())
+                .filter(|installed| *installed != latest_version)
+                .unwrap_or(version);
+            planned_updates.push((pkg_name, current, latest_version));
+        }
+    }
 
-            // Remove old version
-            let package_dir = modules_dir.join(pkg_name);
-            if package_dir.exists() {
-                fs::remove_dir_all(&package_dir)?;
-            }
+    // Nothing is changed unless every dependency could be checked
+    failures.into_result()?;
 
-            // Install new version
-            install_package(pkg_name, &latest_version, &modules_dir)?;
-            println!(
-                "  {} Updated {} to version {}",
-                "✓".green(),
-                pkg_name,
-                latest_version
+    if dry_run {
+        if planned_updates.is_empty() {
+            info!("\n{} All packages are up to date", style::ok_mark().bold());
+        } else {
+            info!(
+                "\n{}",
+                "Dry run: the following packages would be updated".bold()
             );
-        }
-    } else {
-        // Update all dependencies
-        println!("Checking for updates for all dependencies...");
-        let mut updated_count = 0;
-
-        // Create a copy of dependencies to iterate through
-        let deps_to_update: HashMap<String, String> = dependencies.clone();
-
-        for (pkg_name, version) in deps_to_update {
-            println!("Checking {} (current: {})", pkg_name, version);
-
-            // Get the latest version
-            let latest_version = check_latest_version(&pkg_name, &version)?;
-
-            if latest_version == version {
-                println!("  {} {} is already up to date", "✓".green(), pkg_name);
-            } else {
-                // Update the dependency in sop.toml
-                dependencies.insert(pkg_name.clone(), latest_version.clone());
-
-                // Remove old version
-                let package_dir = modules_dir.join(&pkg_name);
-                if package_dir.exists() {
-                    fs::remove_dir_all(&package_dir)?;
-                }
-
-                // Install new version
-                install_package(&pkg_name, &latest_version, &modules_dir)?;
-                println!(
-                    "  {} Updated {} to version {}",
-                    "✓".green(),
+            for (pkg_name, current, latest_version) in &planned_updates {
+                info!(
+                    "  {} {} {} {}",
                     pkg_name,
-                    latest_version
+                    style::removed(current),
+                    style::symbols().arrow,
+                    style::added(latest_version)
                 );
-
-                updated_count += 1;
             }
+            info!("No changes were made to sop.toml, sop.lock, or sop_modules.");
         }
+        return Ok(());
+    }
 
-        if updated_count > 0 {
-            println!(
-                "\n{} Updated {} packages",
-                "✓".green().bold(),
-                updated_count
-            );
-        } else {
-            println!("\n{} All packages are up to date", "✓".green().bold());
+    let modules_dir = get_sop_modules_path();
+    let offline = read_config()?.offline;
+    ensure_dir_exists(&modules_dir)?;
+
+    if !offline {
+        let mut requests = Vec::new();
+        for (pkg_name, _, latest_version) in &planned_updates {
+            let dependency = config.find_dependency(pkg_name).unwrap();
+            requests.push(PackageRequest {
+                name: pkg_name.clone(),
+                version: latest_version.clone(),
+                registry: registry::dependency_registry_url(dependency)?,
+            });
         }
+        prefetch_packages(requests, &locked, jobs.unwrap_or_else(config::jobs));
+    }
+
+    for (pkg_name, _, latest_version) in &planned_updates {
+        // Update the dependency in sop.toml
+        // Keep the operator the requirement was written with, such as `^` from `sop add`
+        if let Some(dependency) = config.find_dependency_mut(pkg_name) {
+            let requirement = dependency.requirement().unwrap_or_default();
+            let (operator, single) = split_requirement(requirement);
+            if requirement == "latest" || parse_version(single).is_some() {
+                let requirement = format!("{}{}", operator, latest_version);
+                dependency.set_requirement(&requirement);
+            }
+        }
+
+        // Install new version, replacing the old one
+        let registry =
+            registry::dependency_registry_url(config.find_dependency(pkg_name).unwrap())?;
+        install_with_dependencies(
+            pkg_name,
+            latest_version,
+            registry.as_deref(),
+            &modules_dir,
+            &locked,
+            &mut lock,
+            offline,
+        )?;
+        info!(
+            "  {} Updated {} to version {}",
+            style::ok_mark(),
+            pkg_name,
+            latest_version
+        );
+    }
+
+    if planned_updates.is_empty() {
+        info!("\n{} All packages are up to date", style::ok_mark().bold());
+    } else {
+        info!(
+            "\n{} Updated {} packages",
+            style::ok_mark().bold(),
+            planned_updates.len()
+        );
     }
 
     // Write updated config back to sop.toml
     write_sop_toml(&sop_toml_path, &config)?;
+    write_sop_lock(&sop_lock_path, &lock)?;
 
     Ok(())
 }
 
 /// Check for the latest version of a package
 fn check_latest_version(package: &str, current_version: &str) -> Result<String> {
-    // In a real implementation, this would check a registry
-    // For simulation, we'll just increment the version number
-
-    // Parse the version (assuming semver format: major.minor.patch)
-    let version_parts: Vec<&str> = current_version.split('.').collect();
-
-    // For simplicity, if the current version is "latest", we'll return a specific version
-    if current_version == "latest" {
-        return Ok("1.0.0".to_string());
-    }
-
-    if version_parts.len() != 3 {
-        // If the version doesn't match semver format, just return a simulated new version
-        return Ok("1.0.0".to_string());
-    }
-
-    // Try to parse each part as a number
-    let major: u32 = version_parts[0].parse().unwrap_or(0);
-    let minor: u32 = version_parts[1].parse().unwrap_or(0);
-    let patch: u32 = version_parts[2].parse().unwrap_or(0);
-
-    // Increment the patch version for the simulation
-    let new_patch = patch + 1;
-
-    // For simulation, 50% chance of having an update
-    if rand::random() {
-        Ok(format!("{}.{}.{}", major, minor, new_patch))
-    } else {
-        Ok(current_version.to_string())
+    // Depending on a pre-release already opts in to newer ones, as --pre does
+    let on_prerelease = parse_version(current_version).is_some_and(|v| !v.pre.is_empty());
+    let latest_version =
+        registry::newest_version(package, on_prerelease || registry::prereleases_allowed())?;
+
+    // Only ever move forward: keep the current version if it is already at or past the latest
+    match (
+        parse_version(current_version),
+        parse_version(&latest_version),
+    ) {
+        (Some(current), Some(latest)) if current >= latest => Ok(current_version.to_string()),
+        _ => Ok(latest_version),
     }
 }
-
-/// Install a single package
-fn install_package(package: &str, version: &str, modules_dir: &Path) -> Result<()> {
-    println!("Installing {} v{}", package, version);
-
-    // Create a directory for the package
-    let package_dir = modules_dir.join(package);
-    ensure_dir_exists(&package_dir)?;
-
-    // For now, we'll just create a placeholder file
-    // In a real implementation, this would download the package from a registry
-    let metadata_file = package_dir.join("sop.toml");
-    let metadata_content = format!(
-        r#"[package]
-name = "{}"
-version = "{}"
-description = "A Soplang package"
-"#,
-        package, version
-    );
-
-    fs::write(metadata_file, metadata_content)?;
-
-    // Create a simple placeholder .so file
-    let lib_file = package_dir.join("lib.so");
-    let lib_content = format!(
-        r#"// This is a placeholder for the {} library
-
-export fn hello() {{
-    println("Hello from {}!");
-}}
-"#,
-        package, package
-    );
-
-    fs::write(lib_file, lib_content)?;
-
-    Ok(())
-}
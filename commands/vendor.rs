@@ -0,0 +1,119 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use fs_extra::dir::CopyOptions;
+use std::fs;
+use std::path::Path;
+
+use crate::cache;
+use crate::lockfile::read_sop_lock;
+use crate::style;
+use crate::toml_parser::{read_sop_toml, write_sop_toml, VendorConfig};
+use crate::utils::{
+    ensure_dir_exists, ensure_in_project, get_sop_lock_path, get_sop_toml_path, project_path,
+    relative_to_project,
+};
+
+/// Directory packages are vendored into unless sop.toml already names one
+const DEFAULT_VENDOR_DIR: &str = "vendor";
+
+/// Execute the vendor command
+///
+/// Every package in sop.lock is copied into the vendor directory using the cache layout
+/// (`<name>/<version>`, `git/<repo>/<commit>`), and sop.toml records the directory so
+/// `setup` installs from it before the cache or the network.
+pub fn execute(path: &Option<String>) -> Result<()> {
+    ensure_in_project()?;
+
+    let sop_toml_path = get_sop_toml_path();
+    let mut config = read_sop_toml(&sop_toml_path)?;
+    let lock = read_sop_lock(&get_sop_lock_path())
+        .map_err(|_| anyhow!("sop.lock not found. Run 'sop setup' before vendoring."))?;
+
+    // sop.toml records the directory relative to the project root
+    let vendor_path = match path {
+        Some(path) => relative_to_project(path)?,
+        None => config
+            .vendor
+            .as_ref()
+            .map(|vendor| vendor.path.clone())
+            .unwrap_or_else(|| DEFAULT_VENDOR_DIR.to_string()),
+    };
+    let vendor_dir = &project_path(&vendor_path);
+
+    // Start from scratch so packages that left the lockfile don't linger
+    if vendor_dir.exists() {
+        fs::remove_dir_all(vendor_dir)?;
+    }
+    ensure_dir_exists(vendor_dir)?;
+
+    println!(
+        "Vendoring dependencies into {}",
+        style::accent(&vendor_path)
+    );
+    let mut packages: Vec<_> = lock.packages.iter().collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut vendored = 0;
+    for package in packages {
+        if package.path_source().is_some() {
+            println!(
+                "  {} {} (local path, not vendored)",
+                style::skip_mark(),
+                package.name
+            );
+            continue;
+        }
+
+        if let Some((url, commit)) = package.git_source() {
+            let (checkout, _) = cache::fetch_git_package(url, Some(commit), false)?;
+            let target = vendor_dir.join(cache::git_relative_path(url)).join(commit);
+            copy_package(&checkout, &target)?;
+        } else {
+            let registry = package.registry_source();
+            let cached = cache::fetch_package(&package.name, &package.version, registry, false)?;
+            let package_dir =
+                vendor_dir.join(cache::package_relative_path(&package.name, registry));
+            copy_package(&cached, &package_dir.join(&package.version))?;
+
+            let signature = cache::signature_cache_path(&package.name, &package.version, registry)?;
+            if signature.is_file() {
+                fs::copy(
+                    &signature,
+                    package_dir.join(format!("{}.minisig", package.version)),
+                )?;
+            }
+        }
+
+        println!(
+            "  {} {} v{}",
+            style::ok_mark(),
+            package.name,
+            package.version
+        );
+        vendored += 1;
+    }
+
+    if config.vendor.as_ref().map(|vendor| vendor.path.as_str()) != Some(vendor_path.as_str()) {
+        config.vendor = Some(VendorConfig {
+            path: vendor_path.clone(),
+        });
+        write_sop_toml(&sop_toml_path, &config)?;
+        println!("Recorded [vendor] path = \"{}\" in sop.toml", vendor_path);
+    }
+
+    println!(
+        "{} Vendored {} package{}. Commit {} so 'sop setup' works without network access.",
+        style::ok_mark().bold(),
+        vendored,
+        if vendored == 1 { "" } else { "s" },
+        vendor_path.bold()
+    );
+    Ok(())
+}
+
+/// Copy a cached package into the vendor directory
+fn copy_package(from: &Path, to: &Path) -> Result<()> {
+    ensure_dir_exists(to)?;
+    let options = CopyOptions::new().content_only(true);
+    fs_extra::dir::copy(from, to, &options)?;
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::checksum::{hash_dir, hash_tree};
+use crate::exit_code::{with_exit_code, ExitCode};
+use crate::install::{installed_version, is_normalized};
+use crate::lockfile::{read_sop_lock_or_default, render_sop_lock};
+use crate::project_lock::LOCK_FILE;
+use crate::style;
+use crate::utils::{
+    ensure_in_project, get_sop_lock_path, get_sop_modules_path, installed_packages,
+};
+
+/// Execute the verify command
+///
+/// Every package in sop.lock must be installed at its locked version with its locked checksum,
+/// and sop_modules must hold nothing else, so CI can gate on an exact install.
+///
+/// With `reproducible`, installed files must also have the timestamps and permissions sop
+/// gives them and sop.lock must be written exactly as sop writes it, and a checksum of the
+/// whole installed tree is printed to compare with other machines.
+pub fn execute(reproducible: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+    let modules_dir = get_sop_modules_path();
+    if lock.packages.is_empty() && installed_packages(&modules_dir)?.is_empty() {
+        println!("No packages in sop.lock to verify.");
+        return Ok(());
+    }
+
+    // Packages only other platforms need are locked but never installed here
+    let mut packages: Vec<_> = lock
+        .packages
+        .iter()
+        .filter(|package| package.for_current_platform())
+        .collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut problems = 0;
+    let mut unchecked = 0;
+    for package in packages {
+        let package_dir = modules_dir.join(&package.name);
+        if package.path_source().is_some() {
+            println!(
+                "  {} {} (local path, not checksummed)",
+                style::skip_mark(),
+                package.name
+            );
+            continue;
+        }
+        if !package_dir.is_dir() {
+            println!(
+                "  {} {} v{} is missing",
+                style::error_mark(),
+                package.name,
+                package.version
+            );
+            problems += 1;
+            continue;
+        }
+        if let Some(installed) = installed_version(&modules_dir, &package.name) {
+            if installed != package.version {
+                println!(
+                    "  {} {} v{} is installed, but sop.lock has v{}",
+                    style::error_mark(),
+                    package.name,
+                    installed,
+                    package.version
+                );
+                problems += 1;
+                continue;
+            }
+        }
+        let Some(expected) = &package.checksum else {
+            println!(
+                "  {} {} v{} has no checksum in sop.lock",
+                style::warn_mark(),
+                package.name,
+                package.version
+            );
+            unchecked += 1;
+            continue;
+        };
+
+        let actual = hash_dir(&package_dir)?;
+        let unnormalized = if reproducible {
+            unnormalized_entries(&package_dir)?
+        } else {
+            Vec::new()
+        };
+        if &actual == expected && !unnormalized.is_empty() {
+            println!(
+                "  {} {} v{} has timestamps or permissions sop didn't give it ({}{})",
+                style::error_mark(),
+                package.name,
+                package.version,
+                unnormalized[0].display(),
+                match unnormalized.len() {
+                    1 => String::new(),
+                    more => format!(" and {} more", more - 1),
+                }
+            );
+            problems += 1;
+        } else if &actual == expected {
+            println!(
+                "  {} {} v{}",
+                style::ok_mark(),
+                package.name,
+                package.version
+            );
+        } else {
+            println!(
+                "  {} {} v{} was modified ({} {} {})",
+                style::error_mark(),
+                package.name,
+                package.version,
+                style::removed(expected),
+                style::arrow(),
+                style::added(&actual)
+            );
+            problems += 1;
+        }
+    }
+
+    // Anything else in sop_modules was left behind or put there by hand
+    let extraneous: Vec<String> = installed_packages(&modules_dir)?
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| lock.get(name).is_none())
+        .collect();
+    for name in &extraneous {
+        println!(
+            "  {} {} is installed but not in sop.lock",
+            style::error_mark(),
+            name
+        );
+    }
+    problems += extraneous.len();
+    if !extraneous.is_empty() {
+        println!(
+            "{} Run 'sop prune' to remove packages that aren't in sop.lock.",
+            style::warn_mark()
+        );
+    }
+
+    // Formatting that differs from what sop writes would differ after the next `sop setup`
+    let lock_is_canonical = !reproducible
+        || fs::read_to_string(get_sop_lock_path()).map_or(Ok(true), |content| {
+            render_sop_lock(&lock).map(|rendered| rendered == content)
+        })?;
+    if !lock_is_canonical {
+        println!(
+            "  {} sop.lock isn't written the way sop writes it",
+            style::error_mark()
+        );
+    }
+
+    if unchecked > 0 {
+        println!(
+            "{} Run 'sop setup' to record checksums for packages locked by an older sop.",
+            style::warn_mark()
+        );
+    }
+
+    if problems > 0 {
+        return Err(with_exit_code(
+            ExitCode::Integrity,
+            anyhow!(
+                "{} package{} in {} {} match sop.lock. Run 'sop setup' to reinstall.",
+                problems,
+                if problems == 1 { "" } else { "s" },
+                modules_dir.display(),
+                if problems == 1 { "doesn't" } else { "don't" }
+            ),
+        ));
+    }
+
+    if !lock_is_canonical {
+        return Err(with_exit_code(
+            ExitCode::Integrity,
+            anyhow!("sop.lock isn't reproducible. Run 'sop setup' to rewrite it."),
+        ));
+    }
+
+    println!(
+        "{} Installed packages match sop.lock",
+        style::ok_mark().bold()
+    );
+    if reproducible && modules_dir.is_dir() {
+        println!(
+            "{} sop_modules is reproducible: {}",
+            style::ok_mark().bold(),
+            hash_tree(&modules_dir, &[LOCK_FILE])?
+        );
+    }
+    Ok(())
+}
+
+/// List what in an installed package, including the package directory itself, doesn't have
+/// the timestamp and permissions installing gives it, relative to the modules directory
+fn unnormalized_entries(package_dir: &Path) -> Result<Vec<PathBuf>> {
+    let modules_dir = get_sop_modules_path();
+    let mut found = Vec::new();
+    let mut pending = vec![package_dir.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        let metadata = fs::symlink_metadata(&path)?;
+        if metadata.is_dir() {
+            for entry in fs::read_dir(&path)? {
+                pending.push(entry?.path());
+            }
+        }
+        if (metadata.is_dir() || metadata.is_file()) && !is_normalized(&metadata) {
+            found.push(
+                path.strip_prefix(&modules_dir)
+                    .unwrap_or(&path)
+                    .to_path_buf(),
+            );
+        }
+    }
+    found.sort();
+    Ok(found)
+}
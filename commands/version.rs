@@ -0,0 +1,164 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use semver::Version;
+use tracing::info;
+
+use crate::git;
+use crate::style;
+use crate::toml_parser::{read_sop_toml, write_sop_toml, SopToml};
+use crate::utils::{ensure_in_project, get_sop_toml_path, project_dir, project_root};
+use crate::version::parse_version;
+use crate::workspace::load_members;
+
+/// Execute the version command
+///
+/// Without `bump`, prints the project's version. Otherwise `bump` is `major`, `minor`,
+/// `patch`, or an explicit version, which must be greater than the current one. With
+/// `commit`, sop.toml is committed as `v<version>`; `tag` also tags that commit.
+///
+/// In a workspace root with `[workspace] version`, that shared version is the one shown and
+/// bumped, which moves every member declaring `version.workspace = true` along with it.
+pub fn execute(bump: &Option<String>, commit: bool, tag: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let sop_toml_path = get_sop_toml_path();
+    let mut config = read_sop_toml(&sop_toml_path)?;
+    let Some(bump) = bump else {
+        let shared = config
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.version.as_ref());
+        println!("{}", shared.unwrap_or(&config.project.version));
+        return Ok(());
+    };
+    let current_text = bumped_version(&config)?;
+
+    let new = next_version(&current_text, bump)?;
+
+    let name = format!("v{}", new);
+    let repository = project_dir()?;
+    if tag && git::tag_exists(&repository, &name)? {
+        return Err(anyhow!("Tag {} already exists.", name));
+    }
+
+    set_bumped_version(&mut config, &new.to_string());
+    write_sop_toml(&sop_toml_path, &config)?;
+    info!(
+        "{} {} {} {}",
+        style::ok_mark().bold(),
+        style::removed(&current_text),
+        style::symbols().arrow,
+        style::added(&new.to_string())
+    );
+    if config
+        .workspace
+        .as_ref()
+        .is_some_and(|workspace| workspace.version.is_some())
+    {
+        let following: Vec<String> = load_members(&project_root(), &config)?
+            .into_iter()
+            .filter(|member| member.config.project.version_from_workspace)
+            .map(|member| member.name)
+            .collect();
+        if !following.is_empty() {
+            info!("  Members sharing the version: {}", following.join(", "));
+        }
+    }
+
+    if commit || tag {
+        git::commit_files(&repository, &[repository.join("sop.toml")], &name)?;
+        info!("  {} Committed sop.toml as {}", style::ok_mark(), name);
+    }
+    if tag {
+        git::create_tag(&repository, &name, &name)?;
+        info!("  {} Tagged {}", style::ok_mark(), name);
+    }
+
+    Ok(())
+}
+
+/// Get the version that gets bumped: the shared `[workspace] version` in a workspace root that
+/// sets one, otherwise the project's own
+///
+/// A member taking its version from the workspace can't be bumped on its own.
+pub fn bumped_version(config: &SopToml) -> Result<String> {
+    if let Some(shared) = config
+        .workspace
+        .as_ref()
+        .and_then(|workspace| workspace.version.as_ref())
+    {
+        return Ok(shared.clone());
+    }
+    if config.project.version_from_workspace {
+        return Err(anyhow!(
+            "{} takes its version from the workspace. Run 'sop version' in the workspace root to bump every member together.",
+            config.project.name
+        ));
+    }
+    Ok(config.project.version.clone())
+}
+
+/// Set the version `bumped_version` reads
+pub fn set_bumped_version(config: &mut SopToml, version: &str) {
+    match config
+        .workspace
+        .as_mut()
+        .and_then(|workspace| workspace.version.as_mut())
+    {
+        Some(shared) => {
+            *shared = version.to_string();
+            if config.project.version_from_workspace {
+                config.project.version = version.to_string();
+            }
+        }
+        None => config.project.version = version.to_string(),
+    }
+}
+
+/// Work out the version `bump` leads to from the project's current version
+///
+/// `bump` is `major`, `minor`, `patch`, or an explicit version, which must be greater than
+/// the current one.
+pub fn next_version(current_text: &str, bump: &str) -> Result<Version> {
+    let current = parse_version(current_text).ok_or_else(|| {
+        anyhow!(
+            "The version in sop.toml, '{}', isn't a valid version. Set it with 'sop version <version>'.",
+            current_text
+        )
+    });
+    Ok(match bump {
+        "major" => Version::new(current?.major + 1, 0, 0),
+        "minor" => {
+            let current = current?;
+            Version::new(current.major, current.minor + 1, 0)
+        }
+        "patch" => {
+            let current = current?;
+            // Releasing a pre-release's version is a patch bump of its own
+            if current.pre.is_empty() {
+                Version::new(current.major, current.minor, current.patch + 1)
+            } else {
+                Version::new(current.major, current.minor, current.patch)
+            }
+        }
+        explicit => {
+            let new = Version::parse(explicit.trim_start_matches('v')).map_err(|e| {
+                anyhow!(
+                    "'{}' isn't major, minor, patch or a version like 1.2.3: {}",
+                    explicit,
+                    e
+                )
+            })?;
+            if let Ok(current) = current {
+                if new <= current {
+                    return Err(anyhow!(
+                        "The new version must be greater than the current one ({}), but {} isn't.",
+                        current,
+                        new
+                    ));
+                }
+            }
+            new
+        }
+    })
+}
@@ -0,0 +1,93 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde_json::json;
+
+use crate::graph::{load_project_graph, DependencyGraph};
+use crate::style;
+use crate::utils::ensure_in_project;
+
+/// Execute the why command
+///
+/// With `json`, prints `{"package", "version", "paths"}` where each path starts at the project.
+pub fn execute(package: &str, json: bool) -> Result<()> {
+    ensure_in_project()?;
+
+    let graph = load_project_graph()?;
+    if !graph.nodes.contains_key(package) && !graph.roots.iter().any(|root| root == package) {
+        return Err(anyhow!(
+            "Package '{}' is not a dependency of {}.",
+            package,
+            graph.root_name
+        ));
+    }
+
+    let mut paths = Vec::new();
+    for root in &graph.roots {
+        collect_paths(&graph, root, package, &mut vec![root.clone()], &mut paths);
+    }
+
+    if json {
+        let paths: Vec<Vec<&String>> = paths
+            .iter()
+            .map(|path| std::iter::once(&graph.root_name).chain(path).collect())
+            .collect();
+        let report = json!({
+            "package": package,
+            "version": graph.version_of(package),
+            "paths": paths,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let version = graph
+        .version_of(package)
+        .map(|version| format!(" v{}", version))
+        .unwrap_or_default();
+    println!(
+        "{}{} is needed through {} path{}:",
+        package.bold(),
+        version,
+        paths.len(),
+        if paths.len() == 1 { "" } else { "s" }
+    );
+    for path in &paths {
+        let chain: Vec<String> = path
+            .iter()
+            .map(|name| {
+                if name == package {
+                    name.bold().to_string()
+                } else {
+                    name.clone()
+                }
+            })
+            .collect();
+        let arrow = format!(" {} ", style::symbols().arrow);
+        println!("  {}{}{}", graph.root_name, arrow, chain.join(&arrow));
+    }
+
+    Ok(())
+}
+
+/// Depth-first search for every path from `current` to `target`, skipping cycles
+fn collect_paths(
+    graph: &DependencyGraph,
+    current: &str,
+    target: &str,
+    path: &mut Vec<String>,
+    paths: &mut Vec<Vec<String>>,
+) {
+    if current == target {
+        paths.push(path.clone());
+        return;
+    }
+
+    for dependency in graph.dependencies_of(current) {
+        if path.contains(dependency) {
+            continue;
+        }
+        path.push(dependency.clone());
+        collect_paths(graph, dependency, target, path, paths);
+        path.pop();
+    }
+}
@@ -0,0 +1,402 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use toml::{Table, Value};
+use tracing::warn;
+
+use crate::i18n::Language;
+use crate::install::InstallStrategy;
+use crate::scripts::ScriptPolicy;
+use crate::style::{self, ColorChoice, ThemeName};
+use crate::utils::{
+    ensure_dir_exists, get_sop_home, is_soplang_project, project_path, write_atomic,
+};
+use crate::version::SavePrefix;
+
+/// Settings from ~/.sop/config.toml, overridden by a project's `.sop/config.toml`
+///
+/// `sop config` reads and edits these files; [`CONFIG_KEYS`] lists the settings it accepts.
+#[derive(Debug, Default, Deserialize)]
+pub struct UserConfig {
+    /// Output theme (`default`, `colorblind`, `high-contrast` or `ascii`)
+    #[serde(default)]
+    pub theme: Option<ThemeName>,
+    /// Language of prompts, help and messages (`en` or `so`)
+    #[serde(default)]
+    pub language: Option<Language>,
+    /// When to color output (`auto`, `always` or `never`)
+    #[serde(default)]
+    pub color: Option<ColorChoice>,
+    /// How many tasks, such as workspace members, may run at once
+    #[serde(default)]
+    pub jobs: Option<usize>,
+    /// Proxy for network access, e.g. `http://proxy.example.com:8080`
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// How many times a download that failed transiently is retried
+    #[serde(default)]
+    pub retries: Option<u32>,
+    /// How packages are installed from the cache (`copy` or `link`)
+    #[serde(default, rename = "install-strategy")]
+    pub install_strategy: Option<InstallStrategy>,
+    /// Operator `sop add` writes before versions (`caret`, `tilde` or `exact`)
+    #[serde(default, rename = "save-prefix")]
+    pub save_prefix: Option<SavePrefix>,
+    /// Whether package scripts run during installs (`ask`, `allow` or `deny`)
+    #[serde(default)]
+    pub scripts: Option<ScriptPolicy>,
+    /// Never download packages, as if `--offline` were passed to every command
+    #[serde(default)]
+    pub offline: bool,
+    /// Check once a day whether a newer sop has been released (on unless set to false)
+    #[serde(default, rename = "update-check")]
+    pub update_check: Option<bool>,
+    /// Defaults for new projects
+    #[serde(default)]
+    pub init: InitConfig,
+    /// Keys trusted to sign registry packages
+    #[serde(default)]
+    pub signatures: SignatureConfig,
+    /// Registry mirrors to download packages from
+    #[serde(default)]
+    pub registry: RegistryConfig,
+    /// Additional registries that dependencies can name with `registry = "<name>"`
+    #[serde(default)]
+    pub registries: BTreeMap<String, NamedRegistry>,
+    /// How registry tokens are stored
+    #[serde(default)]
+    pub credentials: CredentialsConfig,
+}
+
+/// The `[credentials]` section of the user config
+#[derive(Debug, Default, Deserialize)]
+pub struct CredentialsConfig {
+    /// Command that provides registry tokens instead of the system keychain
+    ///
+    /// It is run as `<helper> get <registry-url>` and prints the token on stdout, or as
+    /// `<helper> store <registry-url>` with the token on stdin.
+    #[serde(default)]
+    pub helper: Option<String>,
+}
+
+/// The `[init]` section of the user config
+#[derive(Debug, Default, Deserialize)]
+pub struct InitConfig {
+    /// Author written into projects created with `sop init`
+    #[serde(default)]
+    pub author: Option<String>,
+    /// License written into projects created with `sop init`
+    #[serde(default)]
+    pub license: Option<String>,
+}
+
+/// The `[registry]` section of the user config
+#[derive(Debug, Default, Deserialize)]
+pub struct RegistryConfig {
+    /// Registry to use instead of the public one
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Mirror URLs tried in order before the public registry, e.g. a corporate proxy or a
+    /// regional CDN. `file://` URLs point at a directory laid out as `<name>/<version>/`.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+}
+
+/// A `[registries.<name>]` entry of the user config
+#[derive(Debug, Deserialize)]
+pub struct NamedRegistry {
+    /// Base URL of the registry, e.g. `https://sop.example.com`
+    pub url: String,
+}
+
+/// The `[signatures]` section of the user config
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SignatureConfig {
+    /// Base64 minisign public keys (the second line of a `minisign.pub` file)
+    #[serde(default)]
+    pub trusted_keys: Vec<String>,
+    /// Refuse registry packages without a valid signature, as `--require-signatures` does
+    #[serde(default)]
+    pub require: bool,
+}
+
+/// The type of value a config key holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigValueKind {
+    String,
+    Bool,
+    Integer,
+    /// Written as a comma-separated list on the command line
+    StringList,
+}
+
+/// Retries for transient network failures when `retries` isn't set
+const DEFAULT_RETRIES: u32 = 3;
+
+/// Keys `sop config set` accepts, with the type of their value
+///
+/// `*` stands for any name, as in `registries.<name>.url`.
+pub const CONFIG_KEYS: &[(&str, ConfigValueKind)] = &[
+    ("theme", ConfigValueKind::String),
+    ("color", ConfigValueKind::String),
+    ("language", ConfigValueKind::String),
+    ("jobs", ConfigValueKind::Integer),
+    ("proxy", ConfigValueKind::String),
+    ("retries", ConfigValueKind::Integer),
+    ("install-strategy", ConfigValueKind::String),
+    ("offline", ConfigValueKind::Bool),
+    ("save-prefix", ConfigValueKind::String),
+    ("scripts", ConfigValueKind::String),
+    ("update-check", ConfigValueKind::Bool),
+    ("init.author", ConfigValueKind::String),
+    ("init.license", ConfigValueKind::String),
+    ("registry.url", ConfigValueKind::String),
+    ("registry.mirrors", ConfigValueKind::StringList),
+    ("registries.*.url", ConfigValueKind::String),
+    ("signatures.trusted-keys", ConfigValueKind::StringList),
+    ("signatures.require", ConfigValueKind::Bool),
+    ("credentials.helper", ConfigValueKind::String),
+];
+
+/// Keys only the user config may set, since a project checked out from elsewhere shouldn't be
+/// able to run commands on the user's behalf or choose which keys its packages are signed with
+pub const USER_ONLY_KEYS: &[&str] = &["credentials.helper", "signatures.trusted-keys"];
+
+/// Keys a project config may only make stricter than the user config: it can require
+/// signatures or stop package scripts, but not the reverse
+pub const TIGHTEN_ONLY_KEYS: &[&str] = &["signatures.require", "scripts"];
+
+/// Look up the type of value a config key holds, or `None` for an unknown key
+pub fn config_key_kind(key: &str) -> Option<ConfigValueKind> {
+    let parts: Vec<&str> = key.split('.').collect();
+    CONFIG_KEYS.iter().find_map(|(pattern, kind)| {
+        let pattern: Vec<&str> = pattern.split('.').collect();
+        let matches = pattern.len() == parts.len()
+            && pattern
+                .iter()
+                .zip(&parts)
+                .all(|(expected, part)| *expected == "*" || expected == part);
+        matches.then_some(*kind)
+    })
+}
+
+/// Get the number of tasks that may run at once, from `jobs` or the number of CPUs
+pub fn jobs() -> usize {
+    read_config()
+        .ok()
+        .and_then(|config| config.jobs)
+        .or_else(|| std::thread::available_parallelism().map(usize::from).ok())
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Get how many times to retry a network operation that failed transiently, from `retries`
+pub fn retries() -> u32 {
+    read_config()
+        .ok()
+        .and_then(|config| config.retries)
+        .unwrap_or(DEFAULT_RETRIES)
+}
+
+/// Get the path to the user config file
+pub fn get_user_config_path() -> Result<PathBuf> {
+    Ok(get_sop_home()?.join("config.toml"))
+}
+
+/// Get the path to the project config file, `.sop/config.toml` in the project root
+pub fn get_project_config_path() -> PathBuf {
+    project_path(".sop").join("config.toml")
+}
+
+/// Read the effective config: the user config with the project config layered on top
+///
+/// Inside a project, settings from `.sop/config.toml` override those from ~/.sop/config.toml,
+/// table by table, so a project can pin its registry without repeating the rest. Settings in
+/// [`USER_ONLY_KEYS`] are ignored in the project config, as are those in [`TIGHTEN_ONLY_KEYS`]
+/// when they would loosen the user's. Missing files count as empty.
+pub fn read_config() -> Result<UserConfig> {
+    Value::Table(read_config_layers()?)
+        .try_into()
+        .map_err(|e| anyhow!("Invalid config: {}", e.message()))
+}
+
+/// Read the effective config as a raw TOML table, merged as [`read_config`] does
+pub fn read_config_layers() -> Result<Table> {
+    let mut table = read_config_table(&get_user_config_path()?)?;
+
+    let project_path = get_project_config_path();
+    if is_soplang_project() && project_path.exists() {
+        layer_project_config(&mut table, read_config_table(&project_path)?);
+    }
+
+    Ok(table)
+}
+
+/// Read a config file as a raw TOML table, or an empty table if it doesn't exist
+pub fn read_config_table(path: &Path) -> Result<Table> {
+    if !path.exists() {
+        return Ok(Table::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    content
+        .parse()
+        .map_err(|e| anyhow!("Invalid config file {}: {}", path.display(), e))
+}
+
+/// Write a raw TOML table to a config file, creating its directory if needed
+pub fn write_config_table(path: &Path, table: &Table) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        ensure_dir_exists(parent)?;
+    }
+    write_atomic(path, &toml::to_string_pretty(table)?)
+}
+
+/// Layer a project config over the user config, leaving out what a project may not set
+fn layer_project_config(user: &mut Table, mut project: Table) {
+    strip_user_only_keys(&mut project);
+    strip_loosened_keys(user, &mut project);
+    merge_tables(user, project);
+}
+
+/// Copy `overrides` into `base`, merging tables key by key and replacing everything else
+fn merge_tables(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(override_table)) => {
+                merge_tables(base_table, override_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Remove settings a project may not override, warning about them once
+fn strip_user_only_keys(project: &mut Table) {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    for key in USER_ONLY_KEYS {
+        let removed = remove_key(project, key).is_some();
+        if removed && !WARNED.swap(true, Ordering::Relaxed) {
+            warn!(
+                "{} Ignoring {} in {}: it can only be set in ~/.sop/config.toml",
+                style::warn_mark(),
+                key,
+                get_project_config_path().display()
+            );
+        }
+    }
+}
+
+/// Remove settings in [`TIGHTEN_ONLY_KEYS`] that are looser in the project than in the user
+/// config, warning about them once
+fn strip_loosened_keys(user: &Table, project: &mut Table) {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    for key in TIGHTEN_ONLY_KEYS {
+        let Some(value) = get_key(project, key) else {
+            continue;
+        };
+        let user_value = get_key(user, key);
+        let loosened = match *key {
+            "signatures.require" => {
+                user_value.and_then(Value::as_bool) == Some(true) && value.as_bool() != Some(true)
+            }
+            _ => {
+                // An unset policy asks, and an invalid one is left for parsing to report
+                let user_strictness = user_value
+                    .map_or(Some(1), script_policy_strictness)
+                    .unwrap_or(0);
+                script_policy_strictness(value)
+                    .is_some_and(|project_strictness| project_strictness < user_strictness)
+            }
+        };
+        if !loosened {
+            continue;
+        }
+        remove_key(project, key);
+        if !WARNED.swap(true, Ordering::Relaxed) {
+            warn!(
+                "{} Ignoring {} in {}: a project can only make it stricter than ~/.sop/config.toml",
+                style::warn_mark(),
+                key,
+                get_project_config_path().display()
+            );
+        }
+    }
+}
+
+/// Rank a `scripts` policy from `allow` (0) to `deny` (2), or `None` if it isn't one
+fn script_policy_strictness(value: &Value) -> Option<u8> {
+    match value.as_str()? {
+        "allow" => Some(0),
+        "ask" => Some(1),
+        "deny" => Some(2),
+        _ => None,
+    }
+}
+
+/// Look up a dotted key such as `signatures.require` in a config table
+fn get_key<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    match key.split_once('.') {
+        Some((section, rest)) => get_key(table.get(section)?.as_table()?, rest),
+        None => table.get(key),
+    }
+}
+
+/// Remove a dotted key from a config table, returning its value
+fn remove_key(table: &mut Table, key: &str) -> Option<Value> {
+    match key.split_once('.') {
+        Some((section, rest)) => remove_key(table.get_mut(section)?.as_table_mut()?, rest),
+        None => table.remove(key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layered(user: &str, project: &str) -> UserConfig {
+        let mut table: Table = user.parse().unwrap();
+        layer_project_config(&mut table, project.parse().unwrap());
+        Value::Table(table).try_into().unwrap()
+    }
+
+    #[test]
+    fn project_cannot_weaken_signatures_or_scripts() {
+        let config = layered(
+            "scripts = \"deny\"\n[signatures]\nrequire = true\ntrusted-keys = [\"user-key\"]\n",
+            "scripts = \"allow\"\n[signatures]\nrequire = false\ntrusted-keys = [\"project-key\"]\n[credentials]\nhelper = \"steal-tokens\"\n",
+        );
+        assert!(config.signatures.require);
+        assert_eq!(config.signatures.trusted_keys, ["user-key"]);
+        assert_eq!(config.scripts, Some(ScriptPolicy::Deny));
+        assert_eq!(config.credentials.helper, None);
+    }
+
+    #[test]
+    fn project_cannot_allow_scripts_the_user_would_be_asked_about() {
+        let config = layered("", "scripts = \"allow\"\n");
+        assert_eq!(config.scripts, None);
+    }
+
+    #[test]
+    fn project_can_tighten_signatures_and_scripts() {
+        let config = layered(
+            "scripts = \"allow\"\n",
+            "scripts = \"deny\"\n[signatures]\nrequire = true\n[registry]\nurl = \"https://sop.example.com\"\n",
+        );
+        assert!(config.signatures.require);
+        assert_eq!(config.scripts, Some(ScriptPolicy::Deny));
+        assert_eq!(
+            config.registry.url.as_deref(),
+            Some("https://sop.example.com")
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::anyhow;
+use semver::VersionReq;
+use std::path::Path;
+
+use crate::exit_code::{with_exit_code, ExitCode};
+use crate::install::package_dependencies;
+use crate::lockfile::SopLock;
+use crate::registry;
+use crate::toml_parser::{read_sop_toml, SopToml};
+use crate::utils::get_sop_toml_path;
+use crate::version::parse_version;
+
+/// A package, or the project itself, asking for a version of a dependency
+#[derive(Debug, Clone)]
+struct Requirement {
+    /// Name of the package asking, or of the project
+    dependent: String,
+    /// Resolved version of the package asking, `None` for the project
+    version: Option<String>,
+    requirement: String,
+}
+
+impl Requirement {
+    fn describe(&self, package: &str) -> String {
+        match &self.version {
+            Some(version) => format!(
+                "{} v{} requires {} {}",
+                self.dependent, version, package, self.requirement
+            ),
+            None => format!(
+                "{} requires {} {}",
+                self.dependent, package, self.requirement
+            ),
+        }
+    }
+}
+
+/// Check whether a version satisfies a requirement from sop.toml
+///
+/// A bare version like `1.2.0` allows compatible versions here, as a caret requirement does,
+/// so only genuinely incompatible versions count as conflicts. `latest` and requirements that
+/// don't parse allow anything.
+pub fn satisfies(version: &str, requirement: &str) -> bool {
+    let (Some(version), Ok(requirement)) = (parse_version(version), VersionReq::parse(requirement))
+    else {
+        return true;
+    };
+    requirement.matches(&version)
+}
+
+/// Get the version sop.lock has for a package, as long as it still satisfies `requirement`
+///
+/// When sop.toml asks for something the locked version no longer satisfies, the package has
+/// to be resolved again instead of reinstalling the stale version.
+pub fn locked_version<'a>(lock: &'a SopLock, package: &str, requirement: &str) -> Option<&'a str> {
+    lock.get(package)
+        .map(|locked| locked.version.as_str())
+        .filter(|version| satisfies(version, requirement))
+}
+
+/// Fail if the version already resolved for `package` doesn't satisfy what `dependent` asks for
+///
+/// `dependent` is `None` when the requirement comes from the project's sop.toml. The error
+/// explains which packages asked for which versions and suggests changes that would resolve
+/// the conflict.
+pub fn check_requirement(
+    package: &str,
+    requirement: &str,
+    dependent: Option<&str>,
+    new_lock: &SopLock,
+    modules_dir: &Path,
+) -> anyhow::Result<()> {
+    let Some(resolved) = new_lock.get(package) else {
+        return Ok(());
+    };
+    if satisfies(&resolved.version, requirement) {
+        return Ok(());
+    }
+
+    let config = read_sop_toml(&get_sop_toml_path()).ok();
+    let project = config
+        .as_ref()
+        .map_or("the project", |config| config.project.name.as_str());
+    let failing = Requirement {
+        dependent: dependent.unwrap_or(project).to_string(),
+        version: dependent.and_then(|dependent| Some(new_lock.get(dependent)?.version.clone())),
+        requirement: requirement.to_string(),
+    };
+    let mut others = requirements_on(package, config.as_ref(), new_lock, modules_dir);
+    others.retain(|other| other.dependent != failing.dependent);
+    Err(with_exit_code(
+        ExitCode::Resolution,
+        anyhow!(explain(
+            package,
+            &resolved.version,
+            &failing,
+            &others,
+            config.as_ref()
+        )),
+    ))
+}
+
+/// Gather what the project and every resolved package ask for `package`
+fn requirements_on(
+    package: &str,
+    config: Option<&SopToml>,
+    new_lock: &SopLock,
+    modules_dir: &Path,
+) -> Vec<Requirement> {
+    let from_project = config.and_then(|config| {
+        Some(Requirement {
+            dependent: config.project.name.clone(),
+            version: None,
+            requirement: config.find_dependency(package)?.requirement()?.to_string(),
+        })
+    });
+    let from_packages = new_lock
+        .packages
+        .iter()
+        .filter(|entry| entry.dependencies.iter().any(|name| name == package))
+        .filter_map(|entry| {
+            let (_, requirement) = package_dependencies(&modules_dir.join(&entry.name))
+                .into_iter()
+                .find(|(name, _)| name == package)?;
+            Some(Requirement {
+                dependent: entry.name.clone(),
+                version: Some(entry.version.clone()),
+                requirement,
+            })
+        });
+    from_project.into_iter().chain(from_packages).collect()
+}
+
+/// Describe a conflict, derivation style, with the changes that would get out of it
+fn explain(
+    package: &str,
+    resolved: &str,
+    failing: &Requirement,
+    others: &[Requirement],
+    config: Option<&SopToml>,
+) -> String {
+    // Lead with whoever got the version that was picked, since that is what the failing
+    // requirement runs into
+    let (chosen, rest): (Vec<&Requirement>, Vec<&Requirement>) = others
+        .iter()
+        .partition(|other| satisfies(resolved, &other.requirement));
+    let mut report = match chosen.first() {
+        Some(chosen) => format!(
+            "Conflicting requirements for {}: {}, but {} (v{} was picked)",
+            package,
+            failing.describe(package),
+            chosen.describe(package),
+            resolved
+        ),
+        None => format!(
+            "Conflicting requirements for {}: {}, but v{} was picked",
+            package,
+            failing.describe(package),
+            resolved
+        ),
+    };
+    for other in chosen.iter().skip(1).chain(&rest) {
+        report.push_str(&format!("\n    and {}", other.describe(package)));
+    }
+
+    let everyone: Vec<&Requirement> = others.iter().chain([failing]).collect();
+    let yanked = registry::yanked_versions(package).unwrap_or_default();
+    let common = registry::available_versions(package)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|version| !yanked.contains(version))
+        .find(|version| {
+            everyone
+                .iter()
+                .all(|requirement| satisfies(version, &requirement.requirement))
+        });
+    let from_project = |requirement: &Requirement| requirement.version.is_none();
+    // Only registry dependencies of the project can be moved with `sop update`
+    let is_direct = |name: &str| {
+        config.is_some_and(|config| {
+            config
+                .find_dependency(name)
+                .is_some_and(|dependency| dependency.requirement().is_some())
+        })
+    };
+
+    let mut fixes = Vec::new();
+    match &common {
+        Some(version) if everyone.iter().any(|requirement| from_project(requirement)) => fixes
+            .push(format!(
+                "change {} in sop.toml to \"={}\", which every requirement allows",
+                package, version
+            )),
+        Some(version) => fixes.push(format!(
+            "add {} = \"={}\" to [patch] in sop.toml, which every requirement allows",
+            package, version
+        )),
+        None => report.push_str(&format!(
+            "\n  No version of {} satisfies them all.",
+            package
+        )),
+    }
+    if common.is_none() {
+        if let Some(project) = everyone
+            .iter()
+            .find(|requirement| from_project(requirement))
+        {
+            for other in everyone.iter().filter(|other| !from_project(other)) {
+                fixes.push(format!(
+                    "change {} in sop.toml to \"{}\", as {} v{} needs",
+                    package,
+                    other.requirement,
+                    other.dependent,
+                    other.version.as_deref().unwrap_or_default()
+                ));
+            }
+            for other in everyone.iter().filter(|other| !from_project(other)) {
+                if is_direct(&other.dependent) {
+                    fixes.push(format!(
+                        "update {} to a release that allows {} {} ('sop update {}')",
+                        other.dependent, package, project.requirement, other.dependent
+                    ));
+                }
+            }
+        } else {
+            for requirement in &everyone {
+                if is_direct(&requirement.dependent) {
+                    fixes.push(format!(
+                        "update {} to a release that agrees on {} ('sop update {}')",
+                        requirement.dependent, package, requirement.dependent
+                    ));
+                }
+            }
+        }
+        fixes.push(format!(
+            "add {} to [patch] in sop.toml to use one version of it everywhere",
+            package
+        ));
+    }
+
+    report.push_str("\n  Possible fixes:");
+    for fix in fixes {
+        report.push_str(&format!("\n    - {}", fix));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_versions_allow_compatible_updates() {
+        assert!(satisfies("1.4.0", "1.2.0"));
+        assert!(satisfies("1.2.0", "^1.2"));
+        assert!(!satisfies("2.0.0", "1.2.0"));
+        assert!(!satisfies("1.0.0", "2.1.0"));
+        assert!(!satisfies("1.1.0", ">=1.2, <2"));
+    }
+
+    #[test]
+    fn latest_and_unparsable_requirements_allow_anything() {
+        assert!(satisfies("0.1.0", "latest"));
+        assert!(satisfies("not-a-version", "^1.0"));
+    }
+
+    #[test]
+    fn locked_version_is_reused_while_it_satisfies_the_requirement() {
+        let mut lock = SopLock::default();
+        lock.set("json", "1.0.0");
+
+        assert_eq!(locked_version(&lock, "json", "^1.0"), Some("1.0.0"));
+        assert_eq!(locked_version(&lock, "json", "latest"), Some("1.0.0"));
+        assert_eq!(locked_version(&lock, "json", "2.1.0"), None);
+        assert_eq!(locked_version(&lock, "yaml", "^1.0"), None);
+    }
+}
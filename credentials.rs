@@ -0,0 +1,308 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::config::read_config;
+use crate::registry;
+use crate::style;
+use crate::utils::{ensure_dir_exists, get_sop_home, write_private};
+
+/// Warn about tokens that expire within this many days
+pub const EXPIRY_WARNING_DAYS: u64 = 14;
+
+/// Service name registry tokens are stored under in the system keychain
+const KEYRING_SERVICE: &str = "sop";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Structure representing ~/.sop/credentials.toml
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Credentials {
+    /// Tokens keyed by registry URL
+    #[serde(default)]
+    pub registries: BTreeMap<String, RegistryToken>,
+}
+
+/// An API token for a registry together with its lifetime metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryToken {
+    /// The token itself; left out of credentials.toml when it is kept in the system keychain
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub token: String,
+    /// Unix timestamp (seconds) when the token was issued
+    pub created_at: u64,
+    /// Unix timestamp (seconds) when the token stops working, if it expires at all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// Where the token is kept
+    #[serde(default, skip_serializing_if = "TokenStorage::is_file")]
+    pub storage: TokenStorage,
+}
+
+/// Where a registry token is kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenStorage {
+    /// In plain text in credentials.toml, when no system keychain is available
+    #[default]
+    File,
+    /// In the system keychain (macOS Keychain, Windows Credential Manager, Secret Service)
+    Keyring,
+    /// With the credential helper from ~/.sop/config.toml
+    Helper,
+}
+
+impl TokenStorage {
+    fn is_file(&self) -> bool {
+        *self == TokenStorage::File
+    }
+
+    /// Describe the storage for messages
+    pub fn describe(&self) -> &'static str {
+        match self {
+            TokenStorage::File => "~/.sop/credentials.toml",
+            TokenStorage::Keyring => "the system keychain",
+            TokenStorage::Helper => "the credential helper",
+        }
+    }
+}
+
+impl RegistryToken {
+    /// Number of whole days until the token expires (0 if already expired)
+    pub fn days_until_expiry(&self) -> Option<u64> {
+        self.expires_at
+            .map(|expires_at| expires_at.saturating_sub(now()) / SECONDS_PER_DAY)
+    }
+
+    /// Check whether the token has already expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| expires_at <= now())
+            .unwrap_or(false)
+    }
+}
+
+/// Get the path to the credentials file
+pub fn get_credentials_path() -> Result<PathBuf> {
+    Ok(get_sop_home()?.join("credentials.toml"))
+}
+
+/// Read the credentials file, returning empty credentials if it doesn't exist
+pub fn read_credentials(path: &Path) -> Result<Credentials> {
+    if !path.exists() {
+        return Ok(Credentials::default());
+    }
+
+    let content = fs::read_to_string(path)?;
+    let credentials: Credentials = toml::from_str(&content)?;
+    Ok(credentials)
+}
+
+/// Write the credentials file, readable only by the current user
+pub fn write_credentials(path: &Path, credentials: &Credentials) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        ensure_dir_exists(parent)?;
+    }
+
+    let content = toml::to_string_pretty(credentials)?;
+    write_private(path, &content)
+}
+
+/// Look up the token for a registry
+///
+/// A credential helper configured in ~/.sop/config.toml is asked first, and its token gets the
+/// lifetime metadata `sop login` left in credentials.toml. Otherwise the token comes from
+/// credentials.toml, which holds either the token itself or a note that it is in the system
+/// keychain.
+pub fn load_token(registry: &str) -> Result<Option<RegistryToken>> {
+    let credentials = read_credentials(&get_credentials_path()?)?;
+    if let Some(helper) = read_config()?.credentials.helper {
+        let output = run_helper(&helper, "get", registry, None)?;
+        let token = output.lines().next().unwrap_or("").trim();
+        if token.is_empty() {
+            return Ok(None);
+        }
+        return helper_token(token, credentials.registries.get(registry)).map(Some);
+    }
+
+    let Some(mut token) = credentials.registries.get(registry).cloned() else {
+        return Ok(None);
+    };
+    if token.storage == TokenStorage::Keyring {
+        token.token = keyring_entry(registry)?.get_password().map_err(|e| {
+            anyhow!(
+                "Could not read the token for {} from the system keychain: {}. Run 'sop login' to store it again.",
+                registry,
+                e
+            )
+        })?;
+    }
+    Ok(Some(token))
+}
+
+/// Give a token from the credential helper the lifetime metadata stored for it, if any
+///
+/// A token the helper got some other way than `sop login` is looked up with the registry.
+fn helper_token(token: &str, stored: Option<&RegistryToken>) -> Result<RegistryToken> {
+    let mut token = match stored.filter(|stored| stored.storage == TokenStorage::Helper) {
+        Some(stored) => RegistryToken {
+            token: token.to_string(),
+            ..stored.clone()
+        },
+        None => registry::token_metadata(token)?,
+    };
+    token.storage = TokenStorage::Helper;
+    Ok(token)
+}
+
+/// Store the token for a registry, returning where it ended up
+///
+/// Tokens go to the credential helper when one is configured, otherwise to the system
+/// keychain. Only when no keychain is available is the token written to credentials.toml in
+/// plain text. Its lifetime metadata always stays in credentials.toml.
+pub fn save_token(registry: &str, token: &RegistryToken) -> Result<TokenStorage> {
+    let config = read_config()?;
+    let mut stored = token.clone();
+    if let Some(helper) = config.credentials.helper {
+        run_helper(&helper, "store", registry, Some(&token.token))?;
+        stored.token = String::new();
+        stored.storage = TokenStorage::Helper;
+    } else {
+        match keyring_entry(registry).and_then(|entry| Ok(entry.set_password(&token.token)?)) {
+            Ok(()) => {
+                stored.token = String::new();
+                stored.storage = TokenStorage::Keyring;
+            }
+            Err(e) => {
+                warn!(
+                    "{} No system keychain available ({}), storing the token in {}",
+                    style::warn_mark(),
+                    e,
+                    TokenStorage::File.describe()
+                );
+                stored.storage = TokenStorage::File;
+            }
+        }
+    }
+
+    let credentials_path = get_credentials_path()?;
+    let mut credentials = read_credentials(&credentials_path)?;
+    let storage = stored.storage;
+    credentials.registries.insert(registry.to_string(), stored);
+    write_credentials(&credentials_path, &credentials)?;
+    Ok(storage)
+}
+
+/// Get the system keychain entry holding the token for a registry
+fn keyring_entry(registry: &str) -> Result<keyring::Entry> {
+    Ok(keyring::Entry::new(KEYRING_SERVICE, registry)?)
+}
+
+/// Run the credential helper for one action of the credential-helper protocol
+///
+/// The helper is invoked as `<helper> get <registry-url>`, printing the token on stdout, or as
+/// `<helper> store <registry-url>` with the token on stdin. Returns what it printed.
+fn run_helper(helper: &str, action: &str, registry: &str, input: Option<&str>) -> Result<String> {
+    let mut parts = helper.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow!("The credential helper in ~/.sop/config.toml is empty"))?;
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .args([action, registry])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Could not run credential helper '{}': {}", program, e))?;
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        writeln!(stdin, "{}", input)?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Credential helper '{}' failed to {} the token for {} ({})",
+            program,
+            action,
+            registry,
+            output.status
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Print a warning if a token has expired or is about to
+pub fn warn_if_expiring(registry: &str, token: &RegistryToken) {
+    if token.is_expired() {
+        warn!(
+            "{} The token for {} has expired. Run 'sop login --rotate' or 'sop login' to replace it.",
+            style::error(style::symbols().warning).bold(),
+            registry
+        );
+        return;
+    }
+
+    if let Some(days) = token.days_until_expiry() {
+        if days <= EXPIRY_WARNING_DAYS {
+            warn!(
+                "{} The token for {} expires in {} days. Run 'sop login --rotate' to replace it.",
+                style::warn_mark().bold(),
+                registry,
+                days
+            );
+        }
+    }
+}
+
+/// Current Unix time in seconds
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn helper_tokens_keep_their_stored_lifetime() {
+        let dir = std::env::temp_dir().join(format!("sop-helper-token-{}", std::process::id()));
+        let path = dir.join("credentials.toml");
+        let registry = "https://registry.soplang.org";
+
+        // What save_token keeps for a token handed to the credential helper
+        let mut credentials = Credentials::default();
+        credentials.registries.insert(
+            registry.to_string(),
+            RegistryToken {
+                token: String::new(),
+                created_at: 1_700_000_000,
+                expires_at: Some(1_700_000_000 + 5 * SECONDS_PER_DAY),
+                storage: TokenStorage::Helper,
+            },
+        );
+        write_credentials(&path, &credentials).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("storage = \"helper\""));
+        assert!(!content.contains("token ="));
+
+        let credentials = read_credentials(&path).unwrap();
+        let token = helper_token("secret", credentials.registries.get(registry)).unwrap();
+        assert_eq!(token.token, "secret");
+        assert_eq!(token.created_at, 1_700_000_000);
+        assert_eq!(token.expires_at, Some(1_700_000_000 + 5 * SECONDS_PER_DAY));
+        assert_eq!(token.storage, TokenStorage::Helper);
+        assert!(token.is_expired());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
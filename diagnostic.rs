@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Error};
+use colored::Colorize;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::style;
+
+/// Turn an error from parsing a TOML file into one that points at where it went wrong
+///
+/// The error shows the line the problem is on with a caret under it, and a hint on how to fix
+/// common mistakes:
+///
+/// ```text
+/// Failed to parse sop.toml: invalid type: integer `1`, expected a string
+///  --> sop.toml:3:11
+///   |
+/// 3 | version = 1
+///   |           ^
+///   = hint: strings go in double quotes, as in key = "value"
+/// ```
+pub fn toml_error(path: &Path, content: &str, error: &toml::de::Error) -> Error {
+    let message = error.message().trim_end();
+    let mut rendered = format!("Failed to parse {}: {}", path.display(), message);
+    let mut pad = String::new();
+    if let Some(span) = error.span() {
+        let (snippet, line_number) = snippet(path, content, span);
+        pad = " ".repeat(line_number.to_string().len());
+        rendered.push('\n');
+        rendered.push_str(&snippet);
+    }
+    if let Some(hint) = hint(message) {
+        rendered.push_str(&format!(
+            "\n{} {} {}",
+            pad,
+            style::info("= hint:").bold(),
+            hint
+        ));
+    }
+    anyhow!(rendered)
+}
+
+/// Show the line a span starts on, with the span underlined up to the end of that line, and
+/// return it with the line's number
+fn snippet(path: &Path, content: &str, span: Range<usize>) -> (String, usize) {
+    let start = floor_char_boundary(content, span.start.min(content.len()));
+    let end = floor_char_boundary(content, span.end.clamp(start, content.len()));
+
+    let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[start..]
+        .find('\n')
+        .map_or(content.len(), |i| start + i);
+    let line = content[line_start..line_end].trim_end_matches('\r');
+    let line_number = content[..start].matches('\n').count() + 1;
+    let column = content[line_start..start].chars().count() + 1;
+    let width = content[start..end.min(line_end)].chars().count().max(1);
+
+    let number = line_number.to_string();
+    let pad = " ".repeat(number.len());
+    let bar = style::dim("|");
+    let snippet = format!(
+        "{}{} {}:{}:{}\n{} {}\n{} {} {}\n{} {} {}{}",
+        pad,
+        style::dim("-->"),
+        path.display(),
+        line_number,
+        column,
+        pad,
+        bar,
+        style::dim(&number),
+        bar,
+        line,
+        pad,
+        bar,
+        " ".repeat(column - 1),
+        style::error(&"^".repeat(width)).bold()
+    );
+    (snippet, line_number)
+}
+
+/// Suggest a fix for the mistakes people make most often in sop.toml
+fn hint(message: &str) -> Option<String> {
+    if let Some(field) = message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.split('`').next())
+    {
+        return Some(format!("add a `{}` key to this table", field));
+    }
+    let hint = if message.contains("expected a string") {
+        "strings go in double quotes, as in key = \"value\""
+    } else if message.contains("expected a boolean") {
+        "use true or false, without quotes"
+    } else if message.starts_with("duplicate key") {
+        "a key can only be set once per table; 'sop fix' removes duplicates, keeping the last"
+    } else if message.starts_with("invalid table header") || message.contains("redefinition") {
+        "each [table] can only appear once; merge the two into one"
+    } else if message.contains("invalid basic string") || message.contains("invalid literal string")
+    {
+        "a string is missing its closing quote"
+    } else if message.starts_with("expected `.`, `=`") || message.contains("key with no value") {
+        "every key needs a value, as in key = \"value\""
+    } else if message.starts_with("invalid array") || message.contains("expected `]`") {
+        "arrays are written [\"a\", \"b\"] and close with ]"
+    } else {
+        return None;
+    };
+    Some(hint.to_string())
+}
+
+fn floor_char_boundary(content: &str, mut index: usize) -> usize {
+    while !content.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
@@ -0,0 +1,74 @@
+use std::error::Error;
+use std::fmt;
+
+/// Process exit codes that tell automation what kind of failure happened
+///
+/// | Code | Meaning                                          |
+/// |------|--------------------------------------------------|
+/// | 0    | Success                                          |
+/// | 1    | Any other error                                  |
+/// | 2    | Invalid command-line usage                       |
+/// | 3    | Dependencies could not be resolved               |
+/// | 4    | `sop outdated` found dependencies to update      |
+/// | 5    | `sop audit` found vulnerable packages            |
+/// | 6    | `sop check` found problems in the project        |
+/// | 7    | A package failed a checksum or signature check   |
+/// | 8    | `sop licenses` found a forbidden license         |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Failure = 1,
+    Usage = 2,
+    Resolution = 3,
+    Outdated = 4,
+    Audit = 5,
+    Check = 6,
+    Integrity = 7,
+    License = 8,
+}
+
+/// Help text listing the exit codes, shown at the end of `sop --help`
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  Success
+  1  Any other error
+  2  Invalid command-line usage
+  3  Dependencies could not be resolved
+  4  Outdated dependencies were found (sop outdated)
+  5  Vulnerable packages were found (sop audit)
+  6  Problems were found in the project (sop check)
+  7  A package failed a checksum or signature check (sop setup, sop verify)
+  8  A dependency uses a forbidden license (sop licenses)";
+
+/// An error that should end the process with a specific exit code
+///
+/// It displays exactly like the error it wraps.
+#[derive(Debug)]
+pub struct CodedError {
+    code: ExitCode,
+    error: anyhow::Error,
+}
+
+impl fmt::Display for CodedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{:#}", self.error)
+        } else {
+            write!(f, "{}", self.error)
+        }
+    }
+}
+
+impl Error for CodedError {}
+
+/// Attach an exit code to an error
+pub fn with_exit_code(code: ExitCode, error: anyhow::Error) -> anyhow::Error {
+    CodedError { code, error }.into()
+}
+
+/// Get the exit code for an error, falling back to a general failure
+pub fn exit_code_of(error: &anyhow::Error) -> ExitCode {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<CodedError>())
+        .map_or(ExitCode::Failure, |coded| coded.code)
+}
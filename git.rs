@@ -0,0 +1,187 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::debug;
+
+use crate::config::read_config;
+use crate::retry::with_retry;
+
+/// Check whether a package argument looks like a git repository URL
+pub fn is_git_url(source: &str) -> bool {
+    source.starts_with("https://")
+        || source.starts_with("http://")
+        || source.starts_with("ssh://")
+        || source.starts_with("git://")
+        || source.starts_with("file://")
+        || source.starts_with("git@")
+        || source.ends_with(".git")
+}
+
+/// Derive a package name from a repository URL (`https://github.com/user/pkg.git` → `pkg`)
+pub fn name_from_url(url: &str) -> String {
+    url.trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or(url)
+        .trim_end_matches(".git")
+        .to_string()
+}
+
+/// Clone a repository into `dest`, check out `reference` if given, and return the commit hash
+///
+/// The `.git` directory is removed afterwards so the checkout can be copied like any other
+/// package.
+pub fn clone_repository(url: &str, reference: Option<&str>, dest: &Path) -> Result<String> {
+    let action = format!("clone {}", url);
+    with_retry(&action, || {
+        // A clone that died halfway leaves a directory git won't clone into again
+        if dest.exists() {
+            fs::remove_dir_all(dest)?;
+        }
+        run_git(
+            git_command().args(["clone", "--quiet", url]).arg(dest),
+            &action,
+        )
+    })?;
+
+    if let Some(reference) = reference {
+        run_git(
+            git_command()
+                .arg("-C")
+                .arg(dest)
+                .args(["checkout", "--quiet", reference]),
+            &format!("check out '{}' in {}", reference, url),
+        )?;
+    }
+
+    let commit = run_git(
+        git_command()
+            .arg("-C")
+            .arg(dest)
+            .args(["rev-parse", "HEAD"]),
+        &format!("read the checked out commit of {}", url),
+    )?;
+
+    fs::remove_dir_all(dest.join(".git"))?;
+
+    Ok(commit)
+}
+
+/// Check whether a tag exists in the repository containing `dir`
+pub fn tag_exists(dir: &Path, tag: &str) -> Result<bool> {
+    let refname = format!("refs/tags/{}", tag);
+    Ok(git_command()
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--quiet", "--verify", &refname])
+        .output()
+        .map_err(|e| anyhow!("Failed to run git: {}", e))?
+        .status
+        .success())
+}
+
+/// Commit the current contents of some files, leaving anything else staged or changed alone
+pub fn commit_files(dir: &Path, files: &[PathBuf], message: &str) -> Result<()> {
+    run_git(
+        git_command()
+            .arg("-C")
+            .arg(dir)
+            .args(["commit", "--quiet", "--message", message, "--"])
+            .args(files),
+        &format!("commit {}", message),
+    )?;
+    Ok(())
+}
+
+/// Check whether the repository containing `dir` has no uncommitted changes
+pub fn is_clean(dir: &Path) -> Result<bool> {
+    let status = run_git(
+        git_command()
+            .arg("-C")
+            .arg(dir)
+            .args(["status", "--porcelain"]),
+        "read the repository status",
+    )?;
+    Ok(status.is_empty())
+}
+
+/// Create an annotated tag on the current commit
+pub fn create_tag(dir: &Path, tag: &str, message: &str) -> Result<()> {
+    run_git(
+        git_command()
+            .arg("-C")
+            .arg(dir)
+            .args(["tag", "--annotate", "--cleanup=whitespace", tag])
+            .args(["--message", message]),
+        &format!("create tag {}", tag),
+    )?;
+    Ok(())
+}
+
+/// Find the most recent tag reachable from the current commit, if there is one
+pub fn latest_tag(dir: &Path) -> Result<Option<String>> {
+    let output = git_command()
+        .arg("-C")
+        .arg(dir)
+        .args(["describe", "--tags", "--abbrev=0"])
+        .output()
+        .map_err(|e| anyhow!("Failed to run git: {}", e))?;
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+/// Read the full messages of the commits after `since`, or of every commit, newest first
+///
+/// Merge commits are left out.
+pub fn commit_messages(dir: &Path, since: Option<&str>) -> Result<Vec<String>> {
+    let mut command = git_command();
+    command
+        .arg("-C")
+        .arg(dir)
+        .args(["log", "--no-merges", "--format=%B%x00"]);
+    if let Some(since) = since {
+        command.arg(format!("{}..HEAD", since));
+    }
+    let log = run_git(&mut command, "read the commit history")?;
+    Ok(log
+        .split('\0')
+        .map(str::trim)
+        .filter(|message| !message.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Create a git command that goes through the proxy from ~/.sop/config.toml, if one is set
+fn git_command() -> Command {
+    let mut command = Command::new("git");
+    if let Some(proxy) = read_config().ok().and_then(|config| config.proxy) {
+        command.arg("-c").arg(format!("http.proxy={}", proxy));
+    }
+    command
+}
+
+/// Run a git command, returning its trimmed stdout
+fn run_git(command: &mut Command, action: &str) -> Result<String> {
+    debug!("Running {:?}", command);
+    let output = command.output().map_err(|e| {
+        if e.kind() == ErrorKind::NotFound {
+            anyhow!("git not found. Install git to use git dependencies.")
+        } else {
+            e.into()
+        }
+    })?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to {}: {}",
+            action,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,192 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::lockfile::{read_sop_lock_or_default, SopLock};
+use crate::toml_parser::{read_sop_toml, DependencyKind, SopToml};
+use crate::utils::{get_sop_lock_path, get_sop_toml_path};
+
+/// The resolved dependency graph of a project, built from sop.toml and sop.lock
+#[derive(Debug)]
+pub struct DependencyGraph {
+    /// Name of the project at the root of the graph
+    pub root_name: String,
+    /// Version of the project at the root of the graph
+    pub root_version: String,
+    /// Direct dependencies of the project, sorted by name
+    pub roots: Vec<String>,
+    /// Every resolved package, keyed by name
+    pub nodes: BTreeMap<String, GraphNode>,
+}
+
+/// A single resolved package in the dependency graph
+#[derive(Debug)]
+pub struct GraphNode {
+    pub version: String,
+    pub dependencies: Vec<String>,
+}
+
+impl DependencyGraph {
+    /// Build the graph from a manifest and its lockfile
+    pub fn from_manifest_and_lock(config: &SopToml, lock: &SopLock) -> Self {
+        let nodes = lock
+            .packages
+            .iter()
+            .map(|package| {
+                let mut dependencies = package.dependencies.clone();
+                dependencies.sort();
+                (
+                    package.name.clone(),
+                    GraphNode {
+                        version: package.version.clone(),
+                        dependencies,
+                    },
+                )
+            })
+            .collect();
+
+        let mut roots: Vec<String> = config
+            .all_dependencies()
+            .into_iter()
+            .map(|(name, _, _)| name.clone())
+            .collect();
+        roots.sort();
+
+        DependencyGraph {
+            root_name: config.project.name.clone(),
+            root_version: config.project.version.clone(),
+            roots,
+            nodes,
+        }
+    }
+
+    /// Get the resolved version of a package, if it is in the lockfile
+    pub fn version_of(&self, package: &str) -> Option<&str> {
+        self.nodes.get(package).map(|node| node.version.as_str())
+    }
+
+    /// Find the shortest chain of dependencies from the project to a package
+    ///
+    /// The chain starts with a direct dependency and ends with `target`.
+    pub fn path_to(&self, target: &str) -> Option<Vec<String>> {
+        let mut visited = BTreeSet::new();
+        let mut queue: VecDeque<Vec<String>> =
+            self.roots.iter().map(|root| vec![root.clone()]).collect();
+
+        while let Some(path) = queue.pop_front() {
+            let current = path.last()?.clone();
+            if current == target {
+                return Some(path);
+            }
+            if !visited.insert(current.clone()) {
+                continue;
+            }
+
+            for dependency in self.dependencies_of(&current) {
+                let mut next = path.clone();
+                next.push(dependency.clone());
+                queue.push_back(next);
+            }
+        }
+
+        None
+    }
+
+    /// Get the dependencies of a package (empty if it isn't resolved)
+    pub fn dependencies_of(&self, package: &str) -> &[String] {
+        self.nodes
+            .get(package)
+            .map(|node| node.dependencies.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+/// Get the locked packages reachable from `[dependencies]`, leaving out those only
+/// dev-dependencies need
+///
+/// `[target.<platform>.dependencies]` count when they apply to the current platform.
+pub fn runtime_packages(config: &SopToml, lock: &SopLock) -> BTreeSet<String> {
+    let roots = config
+        .all_dependencies()
+        .into_iter()
+        .filter(|(_, _, kind)| *kind == DependencyKind::Normal)
+        .map(|(name, _, _)| name.clone())
+        .collect();
+    reachable_packages(roots, lock)
+}
+
+/// Get the locked packages any dependency in sop.toml needs, directly or transitively
+///
+/// This includes what other platforms need through their `[target.<platform>]` tables, since
+/// sop.lock keeps those locked too.
+pub fn required_packages(config: &SopToml, lock: &SopLock) -> BTreeSet<String> {
+    let roots = config
+        .all_dependencies()
+        .into_iter()
+        .map(|(name, _, _)| name.clone())
+        .chain(
+            config
+                .platform_dependencies()
+                .into_iter()
+                .map(|(_, name, _, _)| name.clone()),
+        )
+        .collect();
+    reachable_packages(roots, lock)
+}
+
+/// Work out which platforms need each locked package, as recorded in sop.lock
+///
+/// Packages that a dependency outside `[target.<platform>]` tables needs map to no qualifiers,
+/// meaning every platform; the rest map to the qualifiers of the tables that lead to them.
+pub fn platform_qualifiers(config: &SopToml, lock: &SopLock) -> BTreeMap<String, Vec<String>> {
+    let unconditional = config
+        .dependencies
+        .iter()
+        .chain(&config.dev_dependencies)
+        .flatten()
+        .map(|(name, _)| name.clone())
+        .collect();
+    let everywhere = reachable_packages(unconditional, lock);
+
+    let mut qualifiers: BTreeMap<String, Vec<String>> = everywhere
+        .into_iter()
+        .map(|package| (package, Vec::new()))
+        .collect();
+    let mut conditional: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (qualifier, name, _, _) in config.platform_dependencies() {
+        for package in reachable_packages(vec![name.clone()], lock) {
+            if !qualifiers.contains_key(&package) {
+                conditional
+                    .entry(package)
+                    .or_default()
+                    .insert(qualifier.to_string());
+            }
+        }
+    }
+    qualifiers.extend(
+        conditional
+            .into_iter()
+            .map(|(package, platforms)| (package, platforms.into_iter().collect())),
+    );
+    qualifiers
+}
+
+/// Follow the locked dependencies of `roots`, collecting every package reached
+fn reachable_packages(mut pending: Vec<String>, lock: &SopLock) -> BTreeSet<String> {
+    let mut included = BTreeSet::new();
+    while let Some(package) = pending.pop() {
+        let Some(entry) = lock.get(&package) else {
+            continue;
+        };
+        if included.insert(package) {
+            pending.extend(entry.dependencies.iter().cloned());
+        }
+    }
+    included
+}
+
+/// Load the dependency graph of the project in the current directory
+pub fn load_project_graph() -> Result<DependencyGraph> {
+    let config = read_sop_toml(&get_sop_toml_path())?;
+    let lock = read_sop_lock_or_default(&get_sop_lock_path())?;
+    Ok(DependencyGraph::from_manifest_and_lock(&config, &lock))
+}
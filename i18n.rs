@@ -0,0 +1,848 @@
+use clap::builder::{EnumValueParser, PossibleValue, TypedValueParser};
+use clap::{Arg, Command, ValueEnum};
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::sync::OnceLock;
+
+use crate::config::read_config;
+
+/// Languages sop's messages can be shown in, chosen with `language` in ~/.sop/config.toml or
+/// `$SOP_LANG`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "so")]
+    Somali,
+}
+
+impl Language {
+    /// Parse a language code such as `so`, or a locale such as `so_SO.UTF-8`
+    pub fn parse(code: &str) -> Option<Self> {
+        let language = code.split(['_', '-', '.']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Language::English),
+            "so" => Some(Language::Somali),
+            _ => None,
+        }
+    }
+
+    /// Translations from English, keyed by the English text
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => &[],
+            Language::Somali => SOMALI,
+        }
+    }
+
+    /// Look up the translation of an English message into this language
+    pub fn translate(self, message: &str) -> Option<&'static str> {
+        self.catalog()
+            .iter()
+            .find(|(english, _)| *english == message)
+            .map(|(_, translation)| *translation)
+    }
+}
+
+/// Get the active language, chosen by `$SOP_LANG` or the user config on first use
+pub fn language() -> Language {
+    static LANGUAGE: OnceLock<Language> = OnceLock::new();
+    *LANGUAGE.get_or_init(|| {
+        let from_env = std::env::var("SOP_LANG")
+            .ok()
+            .and_then(|code| Language::parse(&code));
+        from_env
+            .or_else(|| read_config().ok().and_then(|config| config.language))
+            .unwrap_or_default()
+    })
+}
+
+/// Look up the translation of an English message into the active language
+///
+/// Returns `None` when the catalog doesn't have one, in which case the English is shown.
+pub fn lookup(message: &str) -> Option<&'static str> {
+    language().translate(message)
+}
+
+/// Translate a message into the active language, falling back to the English
+pub fn tr(message: &'static str) -> &'static str {
+    lookup(message).unwrap_or(message)
+}
+
+/// Translate a message with `{}` placeholders, then fill them in with `args` in order
+pub fn tr_args(message: &'static str, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut translated = String::new();
+    let mut parts = tr(message).split("{}").peekable();
+    while let Some(part) = parts.next() {
+        translated.push_str(part);
+        if parts.peek().is_some() {
+            if let Some(arg) = args.next() {
+                translated.push_str(&arg.to_string());
+            }
+        }
+    }
+    translated
+}
+
+/// Translate the help of a command and its subcommands into the active language
+///
+/// Descriptions the catalog has no translation for stay in English, as do the labels and
+/// usage errors clap writes itself, such as "Possible values".
+pub fn localize_command(mut command: Command) -> Command {
+    if language() == Language::English {
+        return command;
+    }
+    // Building adds the help flags and subcommand, so they get translated too
+    command.build();
+    localize(command)
+}
+
+fn localize(mut command: Command) -> Command {
+    let about = command
+        .get_about()
+        .and_then(|about| lookup(&about.to_string()));
+    if let Some(about) = about {
+        command = command.about(about).long_about(None);
+    }
+
+    command
+        .help_template(format!(
+            "{{before-help}}{{about-with-newline}}\n{}: {{usage}}\n\n{{all-args}}{{after-help}}",
+            tr("Usage")
+        ))
+        .subcommand_help_heading(tr("Commands"))
+        .mut_args(localize_arg)
+        .mut_subcommands(localize)
+}
+
+/// Parses a [`ValueEnum`] like clap's own parser, with the help of each value translated
+///
+/// `localize_command` can't reach the values' help, since it belongs to the parser, so
+/// arguments taking a documented enum use this as their `value_parser`.
+#[derive(Clone)]
+pub struct LocalizedEnumParser<E>(PhantomData<E>);
+
+/// Parser for an enum argument whose values have help to translate
+pub fn localized_enum<E>() -> LocalizedEnumParser<E> {
+    LocalizedEnumParser(PhantomData)
+}
+
+impl<E: ValueEnum + Clone + Send + Sync + 'static> TypedValueParser for LocalizedEnumParser<E> {
+    type Value = E;
+
+    fn parse_ref(
+        &self,
+        command: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<E, clap::Error> {
+        EnumValueParser::<E>::new().parse_ref(command, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let values = E::value_variants()
+            .iter()
+            .filter_map(ValueEnum::to_possible_value)
+            .map(
+                |value| match value.get_help().and_then(|help| lookup(&help.to_string())) {
+                    Some(help) => value.help(help),
+                    None => value,
+                },
+            );
+        Some(Box::new(values))
+    }
+}
+
+fn localize_arg(mut arg: Arg) -> Arg {
+    let help = arg.get_help().and_then(|help| lookup(&help.to_string()));
+    if let Some(help) = help {
+        arg = arg.help(help).long_help(None);
+    }
+    if arg.get_help_heading().is_none() {
+        let heading = if arg.is_positional() {
+            "Arguments"
+        } else {
+            "Options"
+        };
+        arg = arg.help_heading(tr(heading));
+    }
+    arg
+}
+
+const SOMALI: &[(&str, &str)] = &[
+    // Help
+    ("Usage", "Isticmaalka"),
+    ("Commands", "Amarrada"),
+    ("Options", "Doorashooyinka"),
+    ("Arguments", "Doodaha"),
+    ("Error", "Khalad"),
+    ("Print help", "Daabac caawimaad"),
+    (
+        "Print help (see a summary with '-h')",
+        "Daabac caawimaad (soo koobid ku arag '-h')",
+    ),
+    (
+        "Print help (see more with '--help')",
+        "Daabac caawimaad (wax badan ku arag '--help')",
+    ),
+    ("Print version", "Daabac nooca"),
+    (
+        "Print this message or the help of the given subcommand(s)",
+        "Daabac fariintan ama caawimaadda amarrada la sheegay",
+    ),
+    (
+        "Official package manager for Soplang",
+        "Maamulaha xirmooyinka rasmiga ah ee Soplang",
+    ),
+    (
+        "Print a timing and download report after the command finishes",
+        "Daabac warbixin waqti iyo soo dejin ah marka amarku dhammaado",
+    ),
+    (
+        "Show more detail about network, cache and resolver activity (-vv for everything)",
+        "Muuji faahfaahin dheeraad ah oo ku saabsan shabakadda, kaydka iyo xalliyaha (-vv wax walba)",
+    ),
+    (
+        "Only print warnings and errors, even with -v",
+        "Daabac digniinaha iyo khaladaadka oo keliya, xitaa -v",
+    ),
+    (
+        "When to color output (overrides $NO_COLOR and the `color` setting)",
+        "Goorta wax-soo-saarka la midabeeyo (waxay ka sarraysaa $NO_COLOR iyo dejinta `color`)",
+    ),
+    (
+        "Work on the project containing DIR instead of the current directory",
+        "Ka shaqee mashruuca ku jira DIR halkii galka hadda",
+    ),
+    // Commands
+    (
+        "Initialize a new Soplang project",
+        "Bilow mashruuc cusub oo Soplang ah",
+    ),
+    (
+        "Install dependencies from sop.toml",
+        "Rakib ku-tiirsanaanta ku qoran sop.toml",
+    ),
+    (
+        "Download every package in sop.lock into the cache without installing",
+        "Kaydka ku soo dejiso xirmo kasta oo ku jira sop.lock adigoon rakibin",
+    ),
+    ("Add a package to the project", "Ku dar xirmo mashruuca"),
+    (
+        "Install a package globally so its binaries can be used outside any project",
+        "Si guud u rakib xirmo si barnaamijyadeeda looga isticmaalo meel kasta",
+    ),
+    (
+        "Uninstall a globally installed package",
+        "Ka saar xirmo si guud loo rakibay",
+    ),
+    ("Remove a package from the project", "Ka saar xirmo mashruuca"),
+    ("Run a Soplang script", "Orodsii qoraal Soplang ah"),
+    (
+        "Bundle the project and its installed packages into dist/",
+        "Mashruuca iyo xirmooyinkiisa la rakibay ku ururi dist/",
+    ),
+    (
+        "Run the project's tests (the .so files under tests/)",
+        "Orodsii imtixaannada mashruuca (faylasha .so ee tests/)",
+    ),
+    (
+        "Run a sop subcommand or shell command in every workspace member",
+        "Ku orodsii amar sop ama amar shell xubin kasta oo ka mid ah goobta shaqada",
+    ),
+    (
+        "Update project dependencies",
+        "Cusboonaysii ku-tiirsanaanta mashruuca",
+    ),
+    (
+        "Show dependencies with newer versions available",
+        "Muuji ku-tiirsanaanta leh noocyo ka cusub",
+    ),
+    ("List installed packages", "Tax xirmooyinka la rakibay"),
+    (
+        "Show the resolved dependency tree",
+        "Muuji geedka ku-tiirsanaanta",
+    ),
+    (
+        "Show every dependency path that leads to a package",
+        "Muuji waddo kasta oo ku-tiirsanaan ah oo gaadha xirmo",
+    ),
+    (
+        "Search project and installed dependency sources for a pattern",
+        "Qaab ka raadi koodhka mashruuca iyo ku-tiirsanaanta la rakibay",
+    ),
+    (
+        "Show information about a package",
+        "Muuji macluumaad ku saabsan xirmo",
+    ),
+    (
+        "Log in to the package registry with an API token",
+        "Ku gal diiwaanka xirmooyinka adigoo isticmaalaya furaha API",
+    ),
+    (
+        "Inspect and clean the global package cache",
+        "Baadh oo nadiifi kaydka guud ee xirmooyinka",
+    ),
+    (
+        "Read and change settings in ~/.sop/config.toml",
+        "Akhri oo beddel dejinta ~/.sop/config.toml",
+    ),
+    (
+        "Check locked packages against the security advisory database",
+        "Xirmooyinka la xidhay kala hubi xogta digniinaha amniga",
+    ),
+    (
+        "Delete installed packages that no dependency in sop.toml needs any more",
+        "Tirtir xirmooyinka la rakibay ee aanay sop.toml mar dambe u baahnayn",
+    ),
+    (
+        "Check that sop_modules holds exactly the packages, versions and checksums in sop.lock",
+        "Hubi in sop_modules ay si sax ah u hayso xirmooyinka, noocyada iyo hubinta ku jira sop.lock",
+    ),
+    (
+        "Show the project's version, or bump it in sop.toml",
+        "Muuji nooca mashruuca, ama kor ugu qaad sop.toml",
+    ),
+    (
+        "Publish the project to the registry",
+        "Ku daabac mashruuca diiwaanka",
+    ),
+    (
+        "Diagnose problems with the interpreter, registries, cache, config and project",
+        "Baadh dhibaatooyinka turjumaha, diiwaannada, kaydka, dejinta iyo mashruuca",
+    ),
+    (
+        "Show the directories, registry, interpreter and config files sop is using",
+        "Muuji galalka, diiwaanka, turjumaha iyo faylasha dejinta ee sop isticmaalayo",
+    ),
+    (
+        "Clean project by removing sop_modules directory",
+        "Nadiifi mashruuca adigoo tirtiraya galka sop_modules",
+    ),
+    (
+        "Repair sop.toml problems that have a safe fix, showing a diff of the changes",
+        "Hagaaji dhibaatooyinka sop.toml ee leh hagaajin ammaan ah, adigoo muujinaya isbeddellada",
+    ),
+    (
+        "Validate sop.toml, with rules turned on or off in its [check] table",
+        "Hubi sop.toml, iyadoo xeerarka lagu shido ama lagu damiyo qaybteeda [check]",
+    ),
+    // Arguments
+    (
+        "Print machine-readable JSON instead of text (list, info, outdated, tree, why, audit, licenses, config, cache stats, env and check)",
+        "Daabac JSON mashiinku akhriyi karo halkii qoraal (list, info, outdated, tree, why, audit, licenses, config, cache stats, env iyo check)",
+    ),
+    (
+        "How to install packages from the cache (overrides the `install-strategy` setting)",
+        "Sida xirmooyinka looga rakibo kaydka (waxay ka sarraysaa dejinta `install-strategy`)",
+    ),
+    (
+        "Don't run package scripts, such as postinstall hooks, during installs",
+        "Ha orodsiin qoraallada xirmooyinka, sida postinstall, inta rakibiddu socoto",
+    ),
+    (
+        "Fail instead of waiting when another sop process is changing the project's packages",
+        "Fashil halkii aad sugi lahayd marka hawl kale oo sop ah ay beddelayso xirmooyinka mashruuca",
+    ),
+    (
+        "Work on the project with this sop.toml instead of the current directory's",
+        "Ka shaqee mashruuca leh sop.toml-kan halkii kan galka hadda",
+    ),
+    ("Color output when it goes to a terminal", "Midabee wax-soo-saarka marka uu aado terminal"),
+    (
+        "Always color output, even when piped",
+        "Had iyo jeer midabee wax-soo-saarka, xitaa marka la dhuumeeyo",
+    ),
+    ("Never color output", "Weligaa ha midabayn wax-soo-saarka"),
+    ("Copy every file of the package", "Koobiyee fayl kasta oo xirmada ah"),
+    (
+        "Hard-link every file from the cache, which is faster and takes no extra disk space",
+        "Fayl kasta si adag ugu xidh kaydka, taas oo ka dhakhso badan oo aan qaadanayn meel dheeraad ah",
+    ),
+    (
+        "Skip interactive prompts and use default values",
+        "Ka bood su'aalaha oo isticmaal qiyamka caadiga ah",
+    ),
+    (
+        "Write a sop.toml for the existing sources in the current directory",
+        "U qor sop.toml koodhka horey ugu jiray galka hadda",
+    ),
+    (
+        "Install only from the local cache, without network access",
+        "Ka rakib kaydka gudaha oo keliya, adigoon shabakadda isticmaalin",
+    ),
+    (
+        "How many packages to download at once (defaults to the `jobs` setting or CPU count)",
+        "Inta xirmo ee hal mar la soo dejinayo (caadi ahaan dejinta `jobs` ama tirada CPU-yada)",
+    ),
+    (
+        "Refuse registry packages without a valid signature from a trusted key",
+        "Diid xirmooyinka diiwaanka ee aan lahayn saxiix sax ah oo ka yimid fure la aamino",
+    ),
+    (
+        "Install only [dependencies], leaving out [dev-dependencies] (also SOP_ENV=production)",
+        "Rakib [dependencies] oo keliya, adigoo ka tagaya [dev-dependencies] (sidoo kale SOP_ENV=production)",
+    ),
+    (
+        "Fail instead of changing sop.lock, e.g. when it is out of date with sop.toml",
+        "Fashil halkii aad beddeli lahayd sop.lock, tusaale marka uusan la socon sop.toml",
+    ),
+    (
+        "Like --locked, and also fail instead of using the network or changing sop.toml",
+        "Sida --locked, oo sidoo kale fashil halkii aad isticmaali lahayd shabakadda ama beddeli lahayd sop.toml",
+    ),
+    (
+        "Package name to add, or a git repository URL (omit to pick one interactively)",
+        "Magaca xirmada lagu darayo, ama URL kayd git ah (ka tag si aad mid u doorato)",
+    ),
+    ("Specific version to install", "Nooca gaarka ah ee la rakibayo"),
+    (
+        "Add to [dev-dependencies] instead of [dependencies]",
+        "Ku dar [dev-dependencies] halkii [dependencies]",
+    ),
+    ("Mark the dependency as optional", "U calaamadee ku-tiirsanaanta inay ikhtiyaari tahay"),
+    (
+        "Git branch to track when adding a git URL",
+        "Laanta git ee la raacayo marka URL git ah lagu darayo",
+    ),
+    (
+        "Git tag to check out when adding a git URL",
+        "Calaamadda git ee la soo saarayo marka URL git ah lagu darayo",
+    ),
+    (
+        "Git commit to check out when adding a git URL",
+        "Commit-ka git ee la soo saarayo marka URL git ah lagu darayo",
+    ),
+    (
+        "Add the package in a local directory, linking it into sop_modules",
+        "Ku dar xirmada galka gudaha ah, adigoo ku xidhaya sop_modules",
+    ),
+    (
+        "Add the package from a registry configured under [registries] in ~/.sop/config.toml",
+        "Ku dar xirmada diiwaan lagu dejiyay [registries] ee ~/.sop/config.toml",
+    ),
+    (
+        "Save exactly the resolved version (=1.2.3) instead of the save-prefix setting",
+        "Kaydi nooca la xalliyay si sax ah (=1.2.3) halkii dejinta save-prefix",
+    ),
+    (
+        "Save a tilde requirement (~1.2.3), allowing patch updates only",
+        "Kaydi shuruud tilde ah (~1.2.3), oo oggol cusboonaysiinta patch oo keliya",
+    ),
+    (
+        "Consider pre-releases, such as 1.2.0-beta.1, when picking the newest version",
+        "Tixgeli noocyada hordhaca ah, sida 1.2.0-beta.1, marka la dooranayo nooca ugu cusub",
+    ),
+    ("Package name to install", "Magaca xirmada la rakibayo"),
+    (
+        "Install into ~/.sop/packages and link binaries into ~/.sop/bin",
+        "Ku rakib ~/.sop/packages oo barnaamijyada ku xidh ~/.sop/bin",
+    ),
+    ("Package name to uninstall", "Magaca xirmada laga saarayo"),
+    ("Uninstall from ~/.sop/packages", "Ka saar ~/.sop/packages"),
+    ("Names of the packages to remove", "Magacyada xirmooyinka laga saarayo"),
+    (
+        "Path to the script (defaults to entry in sop.toml)",
+        "Waddada qoraalka (caadi ahaan entry ee sop.toml)",
+    ),
+    (
+        "Run the named [[bin]] target from sop.toml",
+        "Orodsii bartilmaameedka [[bin]] ee sop.toml ee magacaas leh",
+    ),
+    ("Show how the interpreter is invoked", "Muuji sida turjumaha loogu yeerayo"),
+    (
+        "Write a crash report file if the interpreter fails",
+        "Qor fayl warbixin burbur ah haddii turjumuhu fashilmo",
+    ),
+    (
+        "Leave out packages only needed by dev-dependencies",
+        "Ka tag xirmooyinka ay dev-dependencies oo keliya u baahan yihiin",
+    ),
+    (
+        "Write a JSON manifest describing the bundle next to it",
+        "Ag dhig faylka JSON ah oo sharraxaya xidhmada",
+    ),
+    (
+        "Bundle the named [[bin]] target from sop.toml",
+        "Ku xidh bartilmaameedka [[bin]] ee sop.toml ee magacaas leh",
+    ),
+    (
+        "Only run test files whose path contains this text",
+        "Orodsii faylasha imtixaanka ee waddadoodu ay ku jirto qoraalkan oo keliya",
+    ),
+    (
+        "Re-run affected tests whenever sources change",
+        "Dib u orodsii imtixaannada ay khusayso mar kasta oo koodhku isbeddelo",
+    ),
+    ("Run a binary provided by a dependency", "Orodsii barnaamij ay bixiso ku-tiirsanaan"),
+    ("Name of the binary to run", "Magaca barnaamijka la orodsiinayo"),
+    ("Arguments passed through to the binary", "Doodaha loo gudbinayo barnaamijka"),
+    (
+        "Run each member after the workspace members it depends on",
+        "Xubin kasta orodsii kadib xubnaha goobta shaqada ee ay ku tiirsan tahay",
+    ),
+    (
+        "Run members at the same time (with --topological, one dependency level at a time)",
+        "Xubnaha isku mar orodsii (--topological, hal heer oo ku-tiirsanaan ah markiiba)",
+    ),
+    (
+        "Run the command through the shell instead of as a sop subcommand",
+        "Amarka ku orodsii shell-ka halkii amar-hoosaad sop ah",
+    ),
+    ("Stop starting members once one fails", "Jooji bilaabidda xubnaha marka mid fashilmo"),
+    (
+        "Only run in members whose name matches; may be a glob like `core-*` and repeated",
+        "Ku orodsii xubnaha magacoodu u dhigmo oo keliya; wuxuu noqon karaa glob sida `core-*` oo waa lagu celin karaa",
+    ),
+    (
+        "Command to run, e.g. `sop foreach test` or `sop foreach --shell -- ls src`",
+        "Amarka la orodsiinayo, tusaale `sop foreach test` ama `sop foreach --shell -- ls src`",
+    ),
+    (
+        "Specific package to update (updates all if not specified)",
+        "Xirmada gaarka ah ee la cusboonaysiinayo (dhammaan ayaa la cusboonaysiiyaa haddii aan la sheegin)",
+    ),
+    (
+        "Show which packages would change without modifying anything",
+        "Muuji xirmooyinka isbeddeli lahaa adigoon waxba beddelin",
+    ),
+    (
+        "Update to pre-releases, such as 1.2.0-beta.1, too",
+        "U cusboonaysii noocyada hordhaca ah, sida 1.2.0-beta.1, sidoo kale",
+    ),
+    (
+        "Include the update command and changelog link for each dependency",
+        "Ku dar amarka cusboonaysiinta iyo xiriirka isbeddellada ku-tiirsanaan kasta",
+    ),
+    ("List globally installed packages instead", "Tax xirmooyinka si guud loo rakibay halkii"),
+    ("Output format", "Qaabka wax-soo-saarka"),
+    (
+        "Indented text tree for the terminal",
+        "Geed qoraal ah oo la gudbiyay oo loogu talagalay terminal-ka",
+    ),
+    (
+        "Nested Markdown list with links to registry pages",
+        "Liis Markdown ah oo is-dhex-gala oo leh xiriirro boggaga diiwaanka",
+    ),
+    (
+        "Nested HTML list with links to registry pages",
+        "Liis HTML ah oo is-dhex-gala oo leh xiriirro boggaga diiwaanka",
+    ),
+    ("Maximum depth of the tree", "Qoto-dheerida ugu badan ee geedka"),
+    (
+        "Show the packages that depend on this package instead",
+        "Muuji xirmooyinka ku tiirsan xirmadan halkii",
+    ),
+    (
+        "Expand each package only the first time it appears; later ones are marked (*)",
+        "Xirmo kasta fidi marka ugu horreysa ee ay muuqato oo keliya; kuwa dambe waxaa lagu calaamadeeyaa (*)",
+    ),
+    (
+        "Export the resolved dependency graph for visualization tools",
+        "U dhoofi garaafka ku-tiirsanaanta la xalliyay qalabka muuqaalka",
+    ),
+    ("Graphviz DOT", "Graphviz DOT"),
+    ("Mermaid flowchart", "Jaantus socod Mermaid ah"),
+    ("Nodes and edges as JSON", "Noodhadhka iyo geesaha oo JSON ah"),
+    (
+        "Find unused dependencies, and imports that sop.toml doesn't declare",
+        "Hel ku-tiirsanaanta aan la isticmaalin, iyo soo-dejinta aanay sop.toml sheegin",
+    ),
+    (
+        "Remove the unused dependencies from sop.toml and sop_modules",
+        "Ka saar ku-tiirsanaanta aan la isticmaalin sop.toml iyo sop_modules",
+    ),
+    (
+        "Add the missing dependencies at their latest version",
+        "Ku dar ku-tiirsanaanta maqan noocooda ugu dambeeya",
+    ),
+    ("Name of the package to explain", "Magaca xirmada la sharraxayo"),
+    (
+        "List packages resolved at more than one version across the workspace, with what requires each version",
+        "Tax xirmooyinka loo xalliyay in ka badan hal nooc goobta shaqada oo dhan, iyo waxa u baahan nooc kasta",
+    ),
+    ("Regular expression to search for", "Tibaax caadi ah oo la raadinayo"),
+    ("Match case-insensitively", "U dhigmi adigoon kala saarin xarfaha waaweyn iyo kuwa yaryar"),
+    ("Treat the pattern as a literal string", "U qaado qaabka qoraal toos ah"),
+    ("Only search the project's own sources", "Raadi koodhka mashruuca oo keliya"),
+    (
+        "Package name, optionally scoped (`@org/package`)",
+        "Magaca xirmada, oo ikhtiyaari ahaan baaxad leh (`@org/package`)",
+    ),
+    (
+        "API token (prompted for if not given)",
+        "Furaha API (waa la weydiinayaa haddii aan la bixin)",
+    ),
+    (
+        "Replace the stored token with a new one and revoke the old token",
+        "Ku beddel furaha la kaydiyay mid cusub oo buri furaha hore",
+    ),
+    ("Print where the cache is", "Daabac meesha kaydku ku yaal"),
+    (
+        "Show the cache's size and contents, and hit/miss counts and bytes downloaded per registry",
+        "Muuji cabbirka iyo waxa ku jira kaydka, iyo tirada helitaanka/seegitaanka iyo bytes-ka laga soo dejiyay diiwaan kasta",
+    ),
+    (
+        "Delete cached packages, all of them unless limited by age or size",
+        "Tirtir xirmooyinka kaydsan, dhammaantood haddii aan da' ama cabbir lagu xaddidin",
+    ),
+    (
+        "Only delete packages that haven't been downloaded or used for this long, e.g. 30d",
+        "Tirtir oo keliya xirmooyinka aan la soo dejin ama la isticmaalin muddadan, tusaale 30d",
+    ),
+    (
+        "Delete the least recently used packages until the cache fits in this size, e.g. 2GB",
+        "Tirtir xirmooyinka ugu dheer ee aan la isticmaalin ilaa kaydku ku eg yahay cabbirkan, tusaale 2GB",
+    ),
+    (
+        "List what would be deleted without deleting it",
+        "Tax waxa la tirtiri lahaa adigoon tirtirin",
+    ),
+    (
+        "Delete cached packages that no longer appear in the sop.lock of any project sop knows",
+        "Tirtir xirmooyinka kaydsan ee aan mar dambe ku jirin sop.lock mashruuc kasta oo sop garanayo",
+    ),
+    ("Print the effective value of a setting", "Daabac qiimaha dhaqan galka ah ee dejin"),
+    ("Dotted key, e.g. `registry.url`", "Fure dhibcaysan, tusaale `registry.url`"),
+    ("Only look at the project's .sop/config.toml", "Eeg .sop/config.toml ee mashruuca oo keliya"),
+    ("Change a setting", "Beddel dejin"),
+    ("Dotted key, e.g. `init.license`", "Fure dhibcaysan, tusaale `init.license`"),
+    ("New value; lists are comma-separated", "Qiimaha cusub; liisaska waxaa kala saara hakad"),
+    (
+        "Change the project's .sop/config.toml instead of ~/.sop/config.toml",
+        "Beddel .sop/config.toml ee mashruuca halkii ~/.sop/config.toml",
+    ),
+    (
+        "Remove a setting so its default applies again",
+        "Ka saar dejin si qiimaheeda caadiga ahi dib u dhaqan galo",
+    ),
+    ("Dotted key", "Fure dhibcaysan"),
+    ("List every effective setting", "Tax dejin kasta oo dhaqan gal ah"),
+    ("Only list the project's .sop/config.toml", "Tax .sop/config.toml ee mashruuca oo keliya"),
+    ("Lowest severity that makes the command fail", "Darnaanta ugu hoosaysa ee amarka fashilisa"),
+    (
+        "Advisory IDs to leave out of the report (can be repeated)",
+        "Aqoonsiyada digniinaha laga saarayo warbixinta (waa lagu celin karaa)",
+    ),
+    (
+        "List the license of every dependency and check them against a policy",
+        "Tax shatiga ku-tiirsanaan kasta oo kala hubi siyaasad",
+    ),
+    (
+        "Policy file with `allow` and `deny` lists (defaults to license-policy.toml)",
+        "Faylka siyaasadda leh liisaska `allow` iyo `deny` (caadi ahaan license-policy.toml)",
+    ),
+    (
+        "Open a package's homepage, repository or documentation in the browser",
+        "Ku fur bogga hore, kaydka koodhka ama dukumeentiyada xirmada biraawsarka",
+    ),
+    ("Name of the package", "Magaca xirmada"),
+    ("Open the source repository", "Fur kaydka koodhka"),
+    ("Open the homepage", "Fur bogga hore"),
+    (
+        "Open the documentation, or the registry's documentation pages when it lists none",
+        "Fur dukumeentiyada, ama boggaga dukumeentiyada diiwaanka marka aanay midna sheegin",
+    ),
+    (
+        "List the files and sop.toml of a package archive or registry package without installing it",
+        "Tax faylasha iyo sop.toml ee kayd xirmo ama xirmo diiwaan adigoon rakibin",
+    ),
+    (
+        "Path to a .tar.gz package archive, or name@version from the registry",
+        "Waddada kayd xirmo .tar.gz ah, ama magac@nooc diiwaanka ka yimid",
+    ),
+    (
+        "Resolve merge conflicts in sop.lock by merging both sides and re-resolving it",
+        "Xalli iska-horimaadka isku-darka ee sop.lock adigoo isku daraya labada dhinac oo dib u xallinaya",
+    ),
+    (
+        "Merge the sides of a conflicted sop.lock and lock it against the merged sop.toml",
+        "Isku dar dhinacyada sop.lock iska horimaaday oo ku xidh sop.toml la isku daray",
+    ),
+    (
+        "Run as a git merge driver: merge.<name>.driver = \"sop lock --merge-driver %O %A %B\"",
+        "U orod sidii darawal isku-dar git: merge.<name>.driver = \"sop lock --merge-driver %O %A %B\"",
+    ),
+    (
+        "Re-resolve only from the local cache, without network access",
+        "Dib uga xalli kaydka gudaha oo keliya, adigoon shabakadda isticmaalin",
+    ),
+    (
+        "Print the project's resolved sop.toml, or with --json every setting with its default",
+        "Daabac sop.toml ee mashruuca ee la xalliyay, ama --json dejin kasta iyo qiimaheeda caadiga ah",
+    ),
+    ("Include the packages from sop.lock (with --json)", "Ku dar xirmooyinka sop.lock (--json)"),
+    (
+        "Show how much disk space each installed dependency takes up, largest first",
+        "Muuji inta meel ee disk-ga ah ee ku-tiirsanaan kasta oo la rakibay qaadato, kan ugu weyn marka hore",
+    ),
+    (
+        "Browse the dependency tree, outdated packages and sizes, and add, remove or update packages, in an interactive terminal UI",
+        "Ku dhex mar geedka ku-tiirsanaanta, xirmooyinka duugoobay iyo cabbirrada, oo ku dar, ka saar ama cusboonaysii xirmooyin, shaashad terminal oo wada-hadal ah",
+    ),
+    (
+        "Serve a local web page showing the dependency graph, outdated packages and audit findings, which refreshes when sop.toml or sop.lock changes",
+        "Bixi bog shabakad gudaha ah oo muujinaya garaafka ku-tiirsanaanta, xirmooyinka duugoobay iyo natiijooyinka baadhista, kaas oo cusboonaada marka sop.toml ama sop.lock isbeddelo",
+    ),
+    ("Port to serve the page on, on 127.0.0.1", "Dekedda bogga laga bixinayo, ee 127.0.0.1"),
+    (
+        "Render the doc comments in the project's and its dependencies' .so files into target/doc",
+        "U beddel faallooyinka dukumeentiga ee faylasha .so ee mashruuca iyo ku-tiirsanaantiisa target/doc",
+    ),
+    (
+        "A page per package, browsable offline",
+        "Bog xirmo kasta, oo laga dhex mari karo shabakad la'aan",
+    ),
+    (
+        "A Markdown file per package, for wikis and repositories",
+        "Fayl Markdown ah xirmo kasta, wiki-yada iyo kaydadka koodhka",
+    ),
+    ("Open the generated index in a browser", "Ku fur tusmada la sameeyay biraawsar"),
+    (
+        "Only document the project, not its dependencies",
+        "Dukumeenti mashruuca oo keliya, ee ha dukumeentiyeyn ku-tiirsanaantiisa",
+    ),
+    (
+        "Write a software bill of materials for the project and its dependencies",
+        "Qor liiska qaybaha software-ka (SBOM) ee mashruuca iyo ku-tiirsanaantiisa",
+    ),
+    ("SBOM standard to emit", "Heerka SBOM ee la soo saarayo"),
+    ("SPDX 2.3 JSON", "SPDX 2.3 JSON"),
+    ("CycloneDX 1.5 JSON", "CycloneDX 1.5 JSON"),
+    ("Write the SBOM to a file instead of stdout", "SBOM-ka ku qor fayl halkii stdout"),
+    (
+        "Include packages only dev-dependencies need",
+        "Ku dar xirmooyinka ay dev-dependencies oo keliya u baahan yihiin",
+    ),
+    (
+        "Copy every locked package into the project so setup works offline",
+        "Koobiyee xirmo kasta oo la xidhay mashruuca si setup ugu shaqeeyo shabakad la'aan",
+    ),
+    (
+        "Directory to vendor into (defaults to the one in sop.toml, or vendor/)",
+        "Galka lagu koobiyeynayo (caadi ahaan kan sop.toml, ama vendor/)",
+    ),
+    (
+        "Serve resolve, list, add, remove and outdated to editors over JSON-RPC on stdin/stdout",
+        "U bixi tifaftirayaasha resolve, list, add, remove iyo outdated JSON-RPC ahaan stdin/stdout",
+    ),
+    ("List what would be removed without removing it", "Tax waxa la saari lahaa adigoon saarin"),
+    (
+        "Also check timestamps, permissions and sop.lock's formatting, and print a checksum of the whole installed tree to compare across machines",
+        "Sidoo kale hubi waqtiyada, oggolaanshaha iyo qaabka sop.lock, oo daabac hubinta geedka la rakibay oo dhan si looga barbar dhigo mashiinnada",
+    ),
+    (
+        "major, minor, patch, or an explicit version such as 1.4.0",
+        "major, minor, patch, ama nooc cad sida 1.4.0",
+    ),
+    ("Commit sop.toml as v<version>", "U commit garee sop.toml sidii v<version>"),
+    (
+        "Commit sop.toml and tag the commit v<version>",
+        "Commit garee sop.toml oo commit-ka u calaamadee v<version>",
+    ),
+    (
+        "Add the features, fixes and breaking changes since the last tag to CHANGELOG.md",
+        "Ku dar CHANGELOG.md astaamaha, hagaajinta iyo isbeddellada jebiya tan iyo calaamaddii ugu dambeysay",
+    ),
+    (
+        "List the entries that would be added without changing CHANGELOG.md",
+        "Tax qoraallada lagu dari lahaa adigoon beddelin CHANGELOG.md",
+    ),
+    (
+        "Publish every workspace member, each after the members it depends on",
+        "Daabac xubin kasta oo goobta shaqada ah, mid kasta kadib xubnaha uu ku tiirsan yahay",
+    ),
+    (
+        "Show what would be published without uploading anything",
+        "Muuji waxa la daabici lahaa adigoon waxba soo gelin",
+    ),
+    (
+        "Verify, test, bump the version, update the changelog, build, commit and tag a release",
+        "Hubi, imtixaan, kor u qaad nooca, cusboonaysii isbeddellada, dhis, commit garee oo calaamadee sii-deyn",
+    ),
+    (
+        "Run the checks and show what would change without changing anything",
+        "Orodsii hubinta oo muuji waxa isbeddeli lahaa adigoon waxba beddelin",
+    ),
+    ("Show the changes without writing them", "Muuji isbeddellada adigoon qorin"),
+    (
+        "Treat warnings, such as a missing license or 'latest' dependencies, as errors",
+        "U qaado digniinaha, sida shati la'aan ama ku-tiirsanaan 'latest' ah, khaladaad",
+    ),
+    // Prompts
+    (
+        "Initializing a new Soplang project.",
+        "Waxaa la bilaabayaa mashruuc cusub oo Soplang ah.",
+    ),
+    (
+        "Project name? (Enter a name, or '.' for current directory)",
+        "Magaca mashruuca? (Geli magac, ama '.' galka hadda)",
+    ),
+    (
+        "Directory '{}' already exists. Do you want to overwrite it?",
+        "Galka '{}' horey ayuu u jiray. Ma rabtaa inaad dul qorto?",
+    ),
+    (
+        "A sop.toml file already exists. Overwrite?",
+        "Fayl sop.toml ah ayaa horey u jiray. Ma la dul qoraa?",
+    ),
+    (
+        "Press Enter to skip optional fields and use defaults:",
+        "Riix Enter si aad uga boodo meelaha ikhtiyaariga ah oo aad u isticmaasho qiyamka caadiga ah:",
+    ),
+    ("Version (1.0.0)", "Nooca (1.0.0)"),
+    ("Description", "Sharaxaad"),
+    ("License", "Shatiga"),
+    ("Author", "Qoraaga"),
+    ("Repository", "Kaydka koodhka"),
+    ("Homepage", "Bogga hore"),
+    ("Search packages", "Raadi xirmooyin"),
+    ("Version of {}", "Nooca {}"),
+    ("API token for {}", "Furaha API ee {}"),
+    (
+        "{} wants to run a {} script: {}\nRun it?",
+        "{} waxay rabtaa inay orodsiiso qoraal {} ah: {}\nMa la orodsiiyaa?",
+    ),
+    ("Remove {} from sop.toml?", "Ma laga saaraa {} sop.toml?"),
+    // Errors
+    (
+        "Not in a Soplang project. Run 'sop init' to create a new project.",
+        "Kuma jirtid mashruuc Soplang ah. Orodsii 'sop init' si aad u abuurto mashruuc cusub.",
+    ),
+    (
+        "Current directory is not empty. Cannot initialize with -y flag. Use interactive mode or empty the directory.",
+        "Galka hadda ma madhna, sidaa darteed -y lama isticmaali karo. Isticmaal qaabka wada-hadalka ama madhi galka.",
+    ),
+    (
+        "Current directory is not empty. Please enter a project name or empty the directory.",
+        "Galka hadda ma madhna. Fadlan geli magac mashruuc ama madhi galka.",
+    ),
+    ("Initialization aborted.", "Bilowga waa la joojiyay."),
+    ("No package selected.", "Xirmo lama dooran."),
+    ("No version selected.", "Nooc lama dooran."),
+    (
+        "sop.toml not found. Are you in a Soplang project directory? Run 'sop init' to create a new project.",
+        "sop.toml lama helin. Ma ku jirtaa gal mashruuc Soplang ah? Orodsii 'sop init' si aad u abuurto mashruuc cusub.",
+    ),
+    (
+        "sop init creates the project in the current directory. Run it there instead of using -C or --manifest-path.",
+        "sop init wuxuu mashruuca ka abuuraa galka hadda. Halkaas ka orodsii halkii aad isticmaali lahayd -C ama --manifest-path.",
+    ),
+    ("--json is not supported by this command.", "Amarkan ma taageero --json."),
+    ("Directory not found: {}", "Galka lama helin: {}"),
+    (
+        "--manifest-path must point to a {} file, not {}",
+        "--manifest-path waa inuu tilmaamaa fayl {} ah, ee ma aha {}",
+    ),
+    ("Manifest not found: {}", "Manifest-ka lama helin: {}"),
+    ("No command specified.", "Amar lama sheegin."),
+    (
+        "Unknown command '{}'. Run 'sop --help' for the built-in commands, or put an executable named 'sop-{}' on PATH to add it.",
+        "Amar aan la aqoon '{}'. Orodsii 'sop --help' si aad u aragto amarrada ku dhex jira, ama PATH geli barnaamij la yidhaahdo 'sop-{}' si aad ugu darto.",
+    ),
+    ("Failed to run {}: {}", "Lama orodsiin karo {}: {}"),
+];
@@ -0,0 +1,144 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::toml_parser::SopToml;
+use crate::utils::{
+    file_exists, find_files_with_extension, get_src_path, get_tests_path, project_path,
+};
+
+/// An import statement found in a Soplang source file
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Import {
+    /// A relative import of another file in the project (`import "./utils"`)
+    File(PathBuf),
+    /// An import of an installed package (`import "json"` or `import json`)
+    Package(String),
+}
+
+fn import_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r#"^\s*import\s+["']?([A-Za-z0-9_@./-]+)["']?"#).expect("valid import regex")
+    })
+}
+
+/// Extract the raw targets of every import statement in a source file
+pub fn parse_imports(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .filter_map(|line| import_regex().captures(line))
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+/// Classify an import target relative to the file that contains it
+pub fn resolve_import(from_file: &Path, target: &str) -> Import {
+    if target.starts_with("./") || target.starts_with("../") {
+        let base = from_file.parent().unwrap_or_else(|| Path::new(""));
+        let mut path = normalize_path(&base.join(target));
+        if path.extension().is_none() {
+            path.set_extension("so");
+        }
+        Import::File(path)
+    } else {
+        Import::Package(package_name(target))
+    }
+}
+
+/// Lexically remove `.` and `..` components so equal paths compare equal
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Get the package an import refers to (`json/parser` → `json`, `@org/pkg/x` → `@org/pkg`)
+pub fn package_name(target: &str) -> String {
+    let mut parts = target.split('/');
+    match parts.next() {
+        Some(scope) if scope.starts_with('@') => match parts.next() {
+            Some(name) => format!("{}/{}", scope, name),
+            None => scope.to_string(),
+        },
+        Some(name) => name.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Read a file and resolve its imports
+pub fn file_imports(file: &Path) -> Vec<Import> {
+    let Ok(source) = fs::read_to_string(file) else {
+        return Vec::new();
+    };
+    parse_imports(&source)
+        .iter()
+        .map(|target| resolve_import(file, target))
+        .collect()
+}
+
+/// Collect every project file and package a file depends on, following relative imports
+pub fn import_closure(file: &Path) -> (BTreeSet<PathBuf>, BTreeSet<String>) {
+    let mut files = BTreeSet::new();
+    let mut packages = BTreeSet::new();
+    let mut pending = vec![file.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        if !files.insert(current.clone()) {
+            continue;
+        }
+        for import in file_imports(&current) {
+            match import {
+                Import::File(path) => pending.push(path),
+                Import::Package(name) => {
+                    packages.insert(name);
+                }
+            }
+        }
+    }
+
+    (files, packages)
+}
+
+/// Collect every source file of the project: `src/`, `tests/`, the entry point, and `[[bin]]`s
+pub fn project_sources(config: &SopToml) -> Result<Vec<PathBuf>> {
+    let mut files = BTreeSet::new();
+    files.extend(find_files_with_extension(&get_src_path(), "so")?);
+    files.extend(find_files_with_extension(&get_tests_path(), "so")?);
+
+    let entry_points = std::iter::once(config.project.entry.as_str())
+        .chain(config.bins.iter().map(|bin| bin.path.as_str()));
+    for entry in entry_points {
+        let path = project_path(entry);
+        if file_exists(&path) {
+            files.insert(path);
+        }
+    }
+
+    Ok(files.into_iter().collect())
+}
+
+/// Collect the packages imported by any of the given files
+pub fn imported_packages(files: &[PathBuf]) -> BTreeSet<String> {
+    files
+        .iter()
+        .flat_map(|file| file_imports(file))
+        .filter_map(|import| match import {
+            Import::Package(name) => Some(name),
+            Import::File(_) => None,
+        })
+        .collect()
+}
@@ -0,0 +1,918 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use fs_extra::dir::CopyOptions;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+use crate::cache::{self, is_sealed};
+use crate::checksum::hash_dir;
+use crate::config::read_config;
+use crate::conflict::{check_requirement, locked_version};
+use crate::exit_code::{with_exit_code, ExitCode};
+use crate::lockfile::SopLock;
+use crate::progress::DownloadProgress;
+use crate::registry;
+use crate::scripts::{run_package_script, POSTINSTALL};
+use crate::signature;
+use crate::style;
+use crate::toml_parser::{read_sop_toml, Dependency, DependencyDetail};
+use crate::utils::{get_cache_path, get_sop_toml_path, parallel_map, project_path};
+
+/// How packages get from the cache into the modules directory, chosen with `install-strategy`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum InstallStrategy {
+    /// Copy every file of the package
+    #[default]
+    Copy,
+    /// Hard-link every file from the cache, which is faster and takes no extra disk space
+    Link,
+}
+
+/// Set by `--install-strategy` for the rest of the command
+static INSTALL_STRATEGY: OnceLock<InstallStrategy> = OnceLock::new();
+
+/// Install packages this way for the rest of the command, whatever the config says
+pub fn use_install_strategy(strategy: InstallStrategy) {
+    let _ = INSTALL_STRATEGY.set(strategy);
+}
+
+/// Work out how to install packages: `--install-strategy` wins, then `install-strategy` in the
+/// config
+pub fn install_strategy() -> InstallStrategy {
+    INSTALL_STRATEGY
+        .get()
+        .copied()
+        .or_else(|| {
+            read_config()
+                .ok()
+                .and_then(|config| config.install_strategy)
+        })
+        .unwrap_or_default()
+}
+
+/// The `[patch]` table of the project's sop.toml, read the first time it is needed
+static PATCHES: OnceLock<HashMap<String, Dependency>> = OnceLock::new();
+
+/// Get the `[patch]` entry that replaces a package wherever it appears in the dependency graph
+pub fn patch_for(package: &str) -> Option<&'static Dependency> {
+    PATCHES
+        .get_or_init(|| {
+            read_sop_toml(&get_sop_toml_path())
+                .ok()
+                .and_then(|config| config.patch)
+                .unwrap_or_default()
+        })
+        .get(package)
+}
+
+/// Install a dependency declared in sop.toml and everything it depends on, whatever its origin
+///
+/// A `[patch]` entry for the package is installed in its place. The version locked in `lock` is
+/// reused for registry dependencies while it satisfies the requirement, unless a patch pins a
+/// different one.
+pub fn install_dependency(
+    package: &str,
+    dependency: &Dependency,
+    modules_dir: &Path,
+    lock: &SopLock,
+    new_lock: &mut SopLock,
+    offline: bool,
+) -> Result<()> {
+    let patch = patch_for(package);
+    match patch.unwrap_or(dependency) {
+        Dependency::Detailed(detail @ DependencyDetail { git: Some(url), .. }) => {
+            install_git_dependency(
+                package,
+                url,
+                detail.git_reference(),
+                modules_dir,
+                lock,
+                new_lock,
+                offline,
+            )?;
+        }
+        Dependency::Detailed(DependencyDetail {
+            path: Some(path), ..
+        }) => {
+            install_path_dependency(package, path, modules_dir, lock, new_lock, offline)?;
+        }
+        dependency => {
+            let Some(requirement) = dependency.requirement() else {
+                return Ok(());
+            };
+            if patch.is_none() {
+                check_requirement(package, requirement, None, new_lock, modules_dir)?;
+            }
+
+            let locked = locked_version(lock, package, requirement).filter(|_| patch.is_none());
+            let version = match locked {
+                Some(locked) => locked.to_string(),
+                None => registry::resolve_version(package, requirement)?,
+            };
+            let registry = registry::dependency_registry_url(dependency)?;
+            install_with_dependencies(
+                package,
+                &version,
+                registry.as_deref(),
+                modules_dir,
+                lock,
+                new_lock,
+                offline,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Install a single package from the cache into the modules directory
+///
+/// The package is downloaded into the cache first if it isn't there yet, unless `offline` is set.
+/// `registry` is the URL of a named registry, or `None` for the public one. When
+/// `expected_checksum` is given, a package whose contents don't match it is refused, as is one
+/// whose signature doesn't verify. Returns the checksum of the installed package.
+pub fn install_package(
+    package: &str,
+    version: &str,
+    registry: Option<&str>,
+    modules_dir: &Path,
+    offline: bool,
+    expected_checksum: Option<&str>,
+) -> Result<String> {
+    info!("Installing {} v{}", package, version);
+
+    // Create a directory for the package
+    let package_dir = modules_dir.join(package);
+
+    // If package already exists, check if it's the right version and hasn't been modified
+    if installed_version(modules_dir, package).as_deref() == Some(version) {
+        let checksum = hash_dir(&package_dir)?;
+        if expected_checksum.is_none_or(|expected| expected == checksum) {
+            check_signature(package, version, registry, &checksum, offline)?;
+            // Packages installed by an older sop may still have whatever metadata they came with
+            normalize_metadata(&package_dir)?;
+            info!("  {} {} is already installed", style::skip_mark(), package);
+            return Ok(checksum);
+        }
+        warn!(
+            "  {} {} was modified after it was installed, reinstalling",
+            style::warn_mark(),
+            package
+        );
+    }
+
+    let cached_dir = cache::fetch_package(package, version, registry, offline)?;
+    let checksum = hash_dir(&cached_dir)?;
+    if let Some(expected) = expected_checksum {
+        verify_checksum(package, &cached_dir, expected, &checksum)?;
+    }
+    let signed = check_signature(package, version, registry, &checksum, offline)?;
+    install_from_cache(&cached_dir, &package_dir)?;
+    run_package_script(package, &package_dir, POSTINSTALL)?;
+
+    if signed {
+        info!("  {} {} (signature verified)", style::ok_mark(), package);
+    } else {
+        info!("  {} {}", style::ok_mark(), package);
+    }
+
+    Ok(checksum)
+}
+
+/// Verify the published signature of a package, if there is one or signatures are required
+fn check_signature(
+    package: &str,
+    version: &str,
+    registry: Option<&str>,
+    checksum: &str,
+    offline: bool,
+) -> Result<bool> {
+    let signature = cache::fetch_signature(package, version, registry, offline)?;
+    signature::verify_package(package, version, checksum, signature.as_deref())
+}
+
+/// Refuse a package whose contents in `source` don't match the checksum recorded in sop.lock
+fn verify_checksum(package: &str, source: &Path, expected: &str, actual: &str) -> Result<()> {
+    if expected == actual {
+        return Ok(());
+    }
+
+    Err(with_exit_code(
+        ExitCode::Integrity,
+        anyhow!(
+            "checksum mismatch for {}: sop.lock has {}, but {} is {}. Refusing to install it.",
+            package,
+            expected,
+            source.display(),
+            actual
+        ),
+    ))
+}
+
+/// Replace whatever is installed at `package_dir` with a cached package, by the install strategy
+fn install_from_cache(cached_dir: &Path, package_dir: &Path) -> Result<()> {
+    match install_strategy() {
+        InstallStrategy::Copy => copy_into_modules(cached_dir, package_dir)?,
+        InstallStrategy::Link => hard_link_into_modules(cached_dir, package_dir)?,
+    }
+    normalize_metadata(package_dir)
+}
+
+/// Modification time of everything installed (1980-01-01, the earliest a zip archive can
+/// record), so sop_modules is the same whichever machine installed it and when
+pub const INSTALLED_MTIME: u64 = 315_532_800;
+
+/// Give an installed package, and everything in it, the same modification time and permissions
+///
+/// Files become `rw-r--r--`, or `rwxr-xr-x` if anyone could run them, and directories
+/// `rwxr-xr-x`, whatever the cache, the archive or the umask left them with. Files hard-linked
+/// from the cache were sealed when they were cached and are left alone; a linked file that
+/// isn't sealed gets a copy of its own first, so the cache is never changed through it.
+pub fn normalize_metadata(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            normalize_metadata(&entry.path())?;
+        } else if file_type.is_file() {
+            let metadata = entry.metadata()?;
+            if is_shared(&metadata) && !is_sealed(&metadata) {
+                unshare(&entry.path())?;
+                normalize_entry(&entry.path(), &fs::metadata(entry.path())?)?;
+            } else if !is_normalized(&metadata) {
+                normalize_entry(&entry.path(), &metadata)?;
+            }
+        }
+    }
+    // Last, as normalizing what is inside doesn't change it but adding files did
+    normalize_entry(dir, &fs::metadata(dir)?)
+}
+
+/// Check whether a file has other hard links, such as to the cache
+#[cfg(unix)]
+fn is_shared(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn is_shared(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Replace a hard-linked file with a copy of its own
+fn unshare(path: &Path) -> Result<()> {
+    let copy = path.with_extension("sop-copy");
+    let _ = fs::remove_file(&copy);
+    fs::copy(path, &copy)?;
+    fs::rename(&copy, path)?;
+    Ok(())
+}
+
+/// Check whether an installed file or directory has the metadata `normalize_metadata` gives it
+///
+/// Files hard-linked from the cache are read-only, but otherwise the same.
+pub fn is_normalized(metadata: &fs::Metadata) -> bool {
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok());
+    if mtime != Some(Duration::from_secs(INSTALLED_MTIME)) {
+        return false;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode() & 0o7777;
+        let expected = normalized_mode(metadata);
+        let read_only = metadata.is_file() && mode == expected & !0o222;
+        if mode != expected && !read_only {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(unix)]
+fn normalized_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    if metadata.is_dir() || metadata.permissions().mode() & 0o111 != 0 {
+        0o755
+    } else {
+        0o644
+    }
+}
+
+fn normalize_entry(path: &Path, metadata: &fs::Metadata) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(normalized_mode(metadata)))?;
+    }
+
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(INSTALLED_MTIME);
+    if metadata.is_dir() {
+        // Directories can only be opened this way on Unix; elsewhere they keep their time
+        if cfg!(unix) {
+            fs::File::open(path)?.set_modified(mtime)?;
+        }
+    } else {
+        fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(mtime)?;
+    }
+    Ok(())
+}
+
+/// Replace whatever is installed at `package_dir` with hard links to a cached package's files
+///
+/// Installed files share their contents with the cache, so only files sealed read-only when
+/// they were cached are linked. Packages from the vendor directory or the system store, which
+/// belong to someone else, and files that can't be linked, such as when the cache is on
+/// another filesystem, are copied.
+fn hard_link_into_modules(cached_dir: &Path, package_dir: &Path) -> Result<()> {
+    remove_installed(package_dir)?;
+    fs::create_dir_all(package_dir)?;
+    let linkable = cached_dir.starts_with(get_cache_path()?);
+    link_dir(cached_dir, package_dir, linkable)
+}
+
+fn link_dir(source: &Path, target: &Path, linkable: bool) -> Result<()> {
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let source_path = entry.path();
+        let target_path = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            fs::create_dir_all(&target_path)?;
+            link_dir(&source_path, &target_path, linkable)?;
+        } else if !linkable || !is_sealed(&entry.metadata()?) {
+            fs::copy(&source_path, &target_path)?;
+        } else if let Err(e) = fs::hard_link(&source_path, &target_path) {
+            debug!(
+                "Copying {} instead of linking it: {}",
+                source_path.display(),
+                e
+            );
+            fs::copy(&source_path, &target_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace whatever is installed at `package_dir` with a copy of a cached package
+fn copy_into_modules(cached_dir: &Path, package_dir: &Path) -> Result<()> {
+    remove_installed(package_dir)?;
+    fs::create_dir_all(package_dir)?;
+
+    let options = CopyOptions::new().content_only(true);
+    fs_extra::dir::copy(cached_dir, package_dir, &options)?;
+    Ok(())
+}
+
+/// Remove an installed package, whether it is a copied directory or a link to a local path
+///
+/// The `@scope` directory of a scoped package goes too once its last package is removed.
+pub fn remove_installed(package_dir: &Path) -> Result<()> {
+    match fs::symlink_metadata(package_dir) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::remove_file(package_dir)?,
+        Ok(_) => fs::remove_dir_all(package_dir)?,
+        Err(_) => return Ok(()),
+    }
+
+    let scope_dir = package_dir.parent().filter(|dir| {
+        dir.file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('@'))
+    });
+    if let Some(scope_dir) = scope_dir {
+        if fs::read_dir(scope_dir)?.next().is_none() {
+            fs::remove_dir(scope_dir)?;
+        }
+    }
+    Ok(())
+}
+
+/// Link a local directory into the modules directory so edits to it are picked up immediately
+#[cfg(unix)]
+fn link_into_modules(source_dir: &Path, package_dir: &Path) -> Result<()> {
+    remove_installed(package_dir)?;
+    if let Some(parent) = package_dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    std::os::unix::fs::symlink(fs::canonicalize(source_dir)?, package_dir)?;
+    Ok(())
+}
+
+/// Link a local directory into the modules directory
+///
+/// Directory symlinks need extra privileges on Windows, so the package is copied instead.
+#[cfg(not(unix))]
+fn link_into_modules(source_dir: &Path, package_dir: &Path) -> Result<()> {
+    copy_into_modules(source_dir, package_dir)
+}
+
+/// Install a package and, recursively, everything it depends on
+///
+/// Every installed package is recorded in `new_lock`; packages already present there are
+/// skipped. Versions pinned in `locked` are preferred over fresh resolution. Dependencies of a
+/// package from a named `registry` are looked up in that same registry.
+pub fn install_with_dependencies(
+    package: &str,
+    version: &str,
+    registry: Option<&str>,
+    modules_dir: &Path,
+    locked: &SopLock,
+    new_lock: &mut SopLock,
+    offline: bool,
+) -> Result<()> {
+    // A yanked version is only ever installed because sop.lock pins it
+    if !offline && registry::is_yanked(package, version).unwrap_or(false) {
+        warn!(
+            "  {} {} v{} has been yanked from the registry, but sop.lock pins it. Run 'sop update {}' to move off it.",
+            style::warn_mark(),
+            package,
+            version,
+            package
+        );
+    }
+    let source = registry.map(|url| format!("registry+{}", url));
+    let expected_checksum = locked
+        .get(package)
+        .filter(|entry| entry.version == version && entry.source == source)
+        .and_then(|entry| entry.checksum.as_deref());
+    let checksum = install_package(
+        package,
+        version,
+        registry,
+        modules_dir,
+        offline,
+        expected_checksum,
+    )?;
+    new_lock.set(package, version);
+    new_lock.set_source(package, source);
+    new_lock.set_checksum(package, Some(checksum));
+
+    install_dependencies_of(package, registry, modules_dir, locked, new_lock, offline)
+}
+
+/// A registry package version to download ahead of installing it
+pub struct PackageRequest {
+    pub name: String,
+    pub version: String,
+    /// URL of a named registry, or `None` for the default one
+    pub registry: Option<String>,
+}
+
+/// Lock a registry package and, recursively, everything it depends on, without installing it
+///
+/// Used for `[target.<platform>]` dependencies of other platforms: the package is fetched into
+/// the cache to learn its checksum and dependencies, but sop_modules is left alone. Versions
+/// already in `locked` are kept.
+pub fn lock_with_dependencies(
+    package: &str,
+    version: &str,
+    registry: Option<&str>,
+    locked: &SopLock,
+    new_lock: &mut SopLock,
+    offline: bool,
+) -> Result<()> {
+    let cached_dir = cache::fetch_package(package, version, registry, offline)?;
+    let dependencies = package_dependencies(&cached_dir);
+    new_lock.set(package, version);
+    new_lock.set_source(package, registry.map(|url| format!("registry+{}", url)));
+    new_lock.set_checksum(package, Some(hash_dir(&cached_dir)?));
+    new_lock.set_dependencies(
+        package,
+        dependencies.iter().map(|(name, _)| name.clone()).collect(),
+    );
+
+    for (dependency, requirement) in dependencies {
+        if new_lock.get(&dependency).is_some() {
+            continue;
+        }
+        let dependency_version =
+            locked_or_resolved(locked, &dependency, &requirement, registry::resolve_version)?;
+        lock_with_dependencies(
+            &dependency,
+            &dependency_version,
+            registry,
+            locked,
+            new_lock,
+            offline,
+        )?;
+    }
+    Ok(())
+}
+
+/// Download registry packages and, recursively, their dependencies into the cache
+///
+/// Up to `jobs` downloads run at once. Dependency versions are picked the way
+/// [`install_with_dependencies`] picks them, so the install that follows finds everything in
+/// the cache. This only warms the cache: a package that fails to download is skipped and left
+/// for the install to report. Progress is shown as it goes, followed by how many packages were
+/// downloaded.
+pub fn prefetch_packages(roots: Vec<PackageRequest>, locked: &SopLock, jobs: usize) {
+    let progress = DownloadProgress::new();
+    let mut seen = HashSet::new();
+    let mut pending = roots;
+    let mut downloaded = 0;
+    while !pending.is_empty() {
+        let wave: Vec<PackageRequest> = pending
+            .drain(..)
+            .filter(|request| seen.insert(request.name.clone()))
+            .collect();
+        progress.add_packages(wave.len());
+        let results = parallel_map(&wave, jobs, |request| -> Result<(PathBuf, bool)> {
+            let registry = request.registry.as_deref();
+            let package = progress.start(&request.name, &request.version);
+            let fetched = cache::is_cached(&request.name, &request.version, registry).and_then(
+                |was_cached| {
+                    let cached_dir =
+                        cache::fetch_package(&request.name, &request.version, registry, false)?;
+                    Ok((cached_dir, !was_cached))
+                },
+            );
+            match &fetched {
+                Ok((_, fresh)) => progress.finish(package, *fresh),
+                Err(_) => progress.fail(package),
+            }
+            fetched
+        });
+
+        for (request, result) in wave.iter().zip(results) {
+            let Ok((cached_dir, fresh)) = result else {
+                continue;
+            };
+            if fresh {
+                downloaded += 1;
+            }
+
+            for (dependency, requirement) in package_dependencies(&cached_dir) {
+                // Patched packages are installed from their patch, not prefetched
+                if patch_for(&dependency).is_some() {
+                    continue;
+                }
+                let Ok(version) = locked_or_resolved(
+                    locked,
+                    &dependency,
+                    &requirement,
+                    registry::resolve_version,
+                ) else {
+                    continue;
+                };
+                pending.push(PackageRequest {
+                    name: dependency,
+                    version,
+                    registry: request.registry.clone(),
+                });
+            }
+        }
+    }
+
+    progress.done(downloaded, jobs);
+}
+
+/// Install a git dependency and, recursively, everything it depends on
+///
+/// A commit locked in `locked` for the same repository is checked out in preference to the
+/// branch, tag, or rev from sop.toml. Returns the installed version.
+pub fn install_git_dependency(
+    package: &str,
+    url: &str,
+    reference: Option<&str>,
+    modules_dir: &Path,
+    locked: &SopLock,
+    new_lock: &mut SopLock,
+    offline: bool,
+) -> Result<String> {
+    let locked_commit = locked
+        .get(package)
+        .and_then(|entry| entry.git_source())
+        .filter(|(locked_url, _)| *locked_url == url)
+        .map(|(_, commit)| commit.to_string());
+
+    info!("Installing {} from {}", package, url);
+    let (cached_dir, commit) =
+        cache::fetch_git_package(url, locked_commit.as_deref().or(reference), offline)?;
+    let checksum = hash_dir(&cached_dir)?;
+    let expected_checksum = locked
+        .get(package)
+        .filter(|entry| entry.git_source() == Some((url, commit.as_str())))
+        .and_then(|entry| entry.checksum.as_deref());
+    if let Some(expected) = expected_checksum {
+        verify_checksum(package, &cached_dir, expected, &checksum)?;
+    }
+    install_from_cache(&cached_dir, &modules_dir.join(package))?;
+    run_package_script(package, &modules_dir.join(package), POSTINSTALL)?;
+    info!(
+        "  {} {} ({})",
+        style::ok_mark(),
+        package,
+        short_commit(&commit)
+    );
+
+    let version = installed_version(modules_dir, package).unwrap_or_else(|| "0.0.0".to_string());
+    new_lock.set(package, &version);
+    new_lock.set_source(package, Some(format!("git+{}#{}", url, commit)));
+    new_lock.set_checksum(package, Some(checksum));
+
+    install_dependencies_of(package, None, modules_dir, locked, new_lock, offline)?;
+    Ok(version)
+}
+
+/// Install a local path dependency and, recursively, everything it depends on
+///
+/// `path` is relative to the project root, as written in sop.toml. Returns the installed
+/// version.
+pub fn install_path_dependency(
+    package: &str,
+    path: &str,
+    modules_dir: &Path,
+    locked: &SopLock,
+    new_lock: &mut SopLock,
+    offline: bool,
+) -> Result<String> {
+    // Paths in sop.toml are relative to the project root
+    let source_dir = &project_path(path);
+    if !source_dir.join("sop.toml").exists() {
+        return Err(anyhow!(
+            "No sop.toml found in '{}' for path dependency '{}'",
+            path,
+            package
+        ));
+    }
+
+    info!("Linking {} from {}", package, path);
+    link_into_modules(source_dir, &modules_dir.join(package))?;
+    info!("  {} {}", style::ok_mark(), package);
+
+    let version = installed_version(modules_dir, package).unwrap_or_else(|| "0.0.0".to_string());
+    new_lock.set(package, &version);
+    new_lock.set_source(package, Some(format!("path+{}", path)));
+    // Local paths are meant to change, so there is nothing to pin
+    new_lock.set_checksum(package, None);
+
+    install_dependencies_of(package, None, modules_dir, locked, new_lock, offline)?;
+    Ok(version)
+}
+
+/// Abbreviate a commit hash for display
+pub fn short_commit(commit: &str) -> &str {
+    &commit[..commit.len().min(8)]
+}
+
+/// Install the registry dependencies declared by an installed package and record them
+///
+/// The dependencies are looked up in `registry`, the registry the package itself came from.
+fn install_dependencies_of(
+    package: &str,
+    registry: Option<&str>,
+    modules_dir: &Path,
+    locked: &SopLock,
+    new_lock: &mut SopLock,
+    offline: bool,
+) -> Result<()> {
+    let dependencies = package_dependencies(&modules_dir.join(package));
+    new_lock.set_dependencies(
+        package,
+        dependencies.iter().map(|(name, _)| name.clone()).collect(),
+    );
+
+    for (dependency, requirement) in dependencies {
+        if new_lock.get(&dependency).is_some() {
+            // A patch replaces the package whatever its dependents ask for
+            if patch_for(&dependency).is_none() {
+                check_requirement(
+                    &dependency,
+                    &requirement,
+                    Some(package),
+                    new_lock,
+                    modules_dir,
+                )?;
+            }
+            continue;
+        }
+        if let Some(patch) = patch_for(&dependency) {
+            install_dependency(&dependency, patch, modules_dir, locked, new_lock, offline)?;
+            continue;
+        }
+
+        let dependency_version =
+            locked_or_resolved(locked, &dependency, &requirement, registry::resolve_version)?;
+        install_with_dependencies(
+            &dependency,
+            &dependency_version,
+            registry,
+            modules_dir,
+            locked,
+            new_lock,
+            offline,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Pick the version of a package's dependency: the locked one while it still satisfies what the
+/// package asks for, and otherwise what `resolve` finds for the requirement
+fn locked_or_resolved(
+    locked: &SopLock,
+    dependency: &str,
+    requirement: &str,
+    resolve: impl FnOnce(&str, &str) -> Result<String>,
+) -> Result<String> {
+    match locked_version(locked, dependency, requirement) {
+        Some(version) => Ok(version.to_string()),
+        None => resolve(dependency, requirement),
+    }
+}
+
+/// Read the sop.toml of an installed package
+fn read_package_metadata(package_dir: &Path) -> Option<toml::Value> {
+    let content = fs::read_to_string(package_dir.join("sop.toml")).ok()?;
+    toml::from_str(&content).ok()
+}
+
+/// Read a field of the `[package]` section of an installed package's sop.toml
+///
+/// Packages installed from git are plain projects, so `[project]` is consulted as well.
+pub fn package_field(package_dir: &Path, field: &str) -> Option<String> {
+    let metadata = read_package_metadata(package_dir)?;
+    ["package", "project"]
+        .iter()
+        .find_map(|section| metadata.get(section)?.get(field)?.as_str())
+        .map(str::to_string)
+}
+
+/// Read the version of an installed package from its sop.toml in the modules directory
+pub fn installed_version(modules_dir: &Path, package: &str) -> Option<String> {
+    package_field(&modules_dir.join(package), "version")
+}
+
+/// Read the SPDX license expression a package declares in its sop.toml
+pub fn package_license(package_dir: &Path) -> Option<String> {
+    package_field(package_dir, "license").filter(|license| !license.trim().is_empty())
+}
+
+/// Read the name a package declares in its sop.toml
+pub fn declared_name(package_dir: &Path) -> Option<String> {
+    package_field(package_dir, "name")
+}
+
+/// Read the binaries an installed package exposes through its `[bin]` table
+///
+/// Both the `[bin]` table form (`name = "path"`) and the `[[bin]]` array form
+/// (`name`/`path` entries) are supported.
+pub fn package_binaries(package_dir: &Path) -> Vec<(String, PathBuf)> {
+    let Some(bins) =
+        read_package_metadata(package_dir).and_then(|metadata| metadata.get("bin").cloned())
+    else {
+        return Vec::new();
+    };
+
+    let entries: Vec<(String, String)> = match bins {
+        toml::Value::Table(table) => table
+            .into_iter()
+            .filter_map(|(name, path)| path.as_str().map(|path| (name, path.to_string())))
+            .collect(),
+        toml::Value::Array(targets) => targets
+            .iter()
+            .filter_map(|target| {
+                let name = target.get("name")?.as_str()?;
+                let path = target.get("path")?.as_str()?;
+                Some((name.to_string(), path.to_string()))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    entries
+        .into_iter()
+        .map(|(name, path)| (name, package_dir.join(path)))
+        .collect()
+}
+
+/// Read the dependencies an installed package declares in its sop.toml
+pub fn package_dependencies(package_dir: &Path) -> Vec<(String, String)> {
+    let mut dependencies: Vec<(String, String)> = read_package_metadata(package_dir)
+        .and_then(|metadata| metadata.get("dependencies")?.as_table().cloned())
+        .map(|dependencies| {
+            dependencies
+                .iter()
+                .filter_map(|(name, requirement)| {
+                    requirement
+                        .as_str()
+                        .map(|requirement| (name.clone(), requirement.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    dependencies.sort();
+    dependencies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sop-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn transitive_dependency_is_resolved_again_when_its_lock_is_stale() {
+        let package = temp_dir("stale-transitive");
+        fs::write(
+            package.join("sop.toml"),
+            "[project]\nname = \"lib\"\nversion = \"1.0.0\"\n\n[dependencies]\nyaml = \"^2.0\"\n",
+        )
+        .unwrap();
+        let mut locked = SopLock::default();
+        locked.set("yaml", "1.4.0");
+
+        let (dependency, requirement) = package_dependencies(&package).remove(0);
+        let version = locked_or_resolved(&locked, &dependency, &requirement, |name, asked| {
+            assert_eq!((name, asked), ("yaml", "^2.0"));
+            Ok("2.1.0".to_string())
+        })
+        .unwrap();
+        assert_eq!(version, "2.1.0");
+
+        // The same lock is reused, without resolving, while it satisfies the requirement
+        let version = locked_or_resolved(&locked, "yaml", "^1.0", |_, _| {
+            panic!("a satisfying locked version shouldn't be resolved again")
+        })
+        .unwrap();
+        assert_eq!(version, "1.4.0");
+        fs::remove_dir_all(&package).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn normalizing_never_changes_a_linked_cache_file() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let dir = temp_dir("normalize-linked");
+        let cached = dir.join("cache.so");
+        fs::write(&cached, "export fn hello() {}").unwrap();
+        fs::set_permissions(&cached, fs::Permissions::from_mode(0o664)).unwrap();
+        let cached_mtime = fs::metadata(&cached).unwrap().modified().unwrap();
+        let package_dir = dir.join("package");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::hard_link(&cached, package_dir.join("lib.so")).unwrap();
+
+        normalize_metadata(&package_dir).unwrap();
+
+        let cached_metadata = fs::metadata(&cached).unwrap();
+        assert_eq!(cached_metadata.permissions().mode() & 0o777, 0o664);
+        assert_eq!(cached_metadata.modified().unwrap(), cached_mtime);
+        assert_eq!(cached_metadata.nlink(), 1);
+        let installed = fs::metadata(package_dir.join("lib.so")).unwrap();
+        assert!(is_normalized(&installed));
+        assert_eq!(installed.permissions().mode() & 0o777, 0o644);
+        assert_eq!(
+            fs::read_to_string(package_dir.join("lib.so")).unwrap(),
+            "export fn hello() {}"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sealed_links_are_left_linked() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let dir = temp_dir("normalize-sealed");
+        let cached = dir.join("cache.so");
+        fs::write(&cached, "").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&cached)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(INSTALLED_MTIME))
+            .unwrap();
+        fs::set_permissions(&cached, fs::Permissions::from_mode(0o444)).unwrap();
+        let package_dir = dir.join("package");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::hard_link(&cached, package_dir.join("lib.so")).unwrap();
+
+        normalize_metadata(&package_dir).unwrap();
+
+        let installed = fs::metadata(package_dir.join("lib.so")).unwrap();
+        assert_eq!(installed.nlink(), 2);
+        assert_eq!(installed.permissions().mode() & 0o777, 0o444);
+        assert!(is_normalized(&installed));
+        assert!(is_normalized(&fs::metadata(&package_dir).unwrap()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
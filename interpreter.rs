@@ -0,0 +1,165 @@
+use anyhow::{anyhow, Result};
+use semver::{Version, VersionReq};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::warn;
+
+use crate::style;
+use crate::toml_parser::SopToml;
+use crate::utils::{
+    find_on_path, get_sop_modules_path, installed_packages, is_executable, project_dir,
+};
+use crate::version::parse_version;
+
+/// Get the name of the Soplang interpreter executable (`$SOPLANG`, or `soplang` by default)
+pub fn interpreter_program() -> String {
+    env::var("SOPLANG").unwrap_or_else(|_| "soplang".to_string())
+}
+
+/// Find the interpreter executable that scripts are run with, if it exists
+pub fn interpreter_path() -> Option<PathBuf> {
+    let program = interpreter_program();
+    let path = Path::new(&program);
+    // `$SOPLANG` may name the executable by path rather than by name
+    if path.components().count() > 1 {
+        return is_executable(path).then(|| path.to_path_buf());
+    }
+    find_on_path(&program)
+        .or_else(|| find_on_path(&format!("{}{}", program, env::consts::EXE_SUFFIX)))
+}
+
+/// Location of the generated import map, relative to the project root
+const IMPORT_MAP_PATH: &str = ".sop/import-map.json";
+
+/// Maps package names to the directories the interpreter should load them from
+#[derive(Debug, Serialize)]
+struct ImportMap {
+    modules: PathBuf,
+    packages: BTreeMap<String, PathBuf>,
+}
+
+/// Build a command that runs a script with the project environment configured
+pub fn script_command(script: &Path) -> Result<Command> {
+    let project_root = project_dir()?;
+    let modules_path = env::current_dir()?.join(get_sop_modules_path());
+    let import_map = write_import_map(&project_root, &modules_path)?;
+
+    let mut command = Command::new(interpreter_program());
+    command
+        .arg(script)
+        .env("SOP_PROJECT_ROOT", &project_root)
+        .env("SOP_MODULES_PATH", &modules_path)
+        .env("SOP_IMPORT_MAP", import_map);
+    Ok(command)
+}
+
+/// Regenerate the import map for the packages currently installed in `modules_path`
+///
+/// The map is rewritten before every run so it always follows the configured modules
+/// directory, wherever it lives.
+fn write_import_map(project_root: &Path, modules_path: &Path) -> Result<PathBuf> {
+    let packages: BTreeMap<String, PathBuf> =
+        installed_packages(modules_path)?.into_iter().collect();
+
+    let import_map_path = project_root.join(IMPORT_MAP_PATH);
+    if let Some(parent) = import_map_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let import_map = ImportMap {
+        modules: modules_path.to_path_buf(),
+        packages,
+    };
+    fs::write(
+        &import_map_path,
+        format!("{}\n", serde_json::to_string_pretty(&import_map)?),
+    )?;
+    Ok(import_map_path)
+}
+
+/// Turn a failure to spawn the interpreter into a helpful error
+pub fn spawn_error(error: io::Error) -> anyhow::Error {
+    if error.kind() == ErrorKind::NotFound {
+        anyhow!(
+            "Soplang interpreter '{}' not found. Make sure it is installed and on your PATH.",
+            interpreter_program()
+        )
+    } else {
+        error.into()
+    }
+}
+
+/// Ask the interpreter for its version string
+pub fn interpreter_version() -> Option<String> {
+    let output = Command::new(interpreter_program())
+        .arg("--version")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if version.is_empty() {
+        None
+    } else {
+        Some(version)
+    }
+}
+
+/// Get the interpreter's version as a semantic version, read from `--version` output such as
+/// `Soplang 0.4.2`
+pub fn interpreter_semver() -> Option<Version> {
+    interpreter_version()?
+        .lines()
+        .next()?
+        .split_whitespace()
+        .find_map(parse_version)
+}
+
+/// Parse the `soplang` requirement from a project's sop.toml, if it has one
+pub fn soplang_requirement(config: &SopToml) -> Result<Option<VersionReq>> {
+    let Some(requirement) = &config.project.soplang else {
+        return Ok(None);
+    };
+    VersionReq::parse(requirement).map(Some).map_err(|e| {
+        anyhow!(
+            "Invalid soplang requirement '{}' in sop.toml: {}",
+            requirement,
+            e
+        )
+    })
+}
+
+/// Fail if the interpreter doesn't satisfy the project's `soplang` requirement
+///
+/// An interpreter that is missing or doesn't report a version only gets a warning, since
+/// running it reports the real problem.
+pub fn ensure_interpreter_satisfies(config: &SopToml) -> Result<()> {
+    let Some(requirement) = soplang_requirement(config)? else {
+        return Ok(());
+    };
+    let Some(version) = interpreter_semver() else {
+        warn!(
+            "{} Couldn't tell which Soplang version '{}' is, so the project's soplang = \"{}\" requirement wasn't checked",
+            style::warn_mark(),
+            interpreter_program(),
+            requirement
+        );
+        return Ok(());
+    };
+    if requirement.matches(&version) {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "{} needs Soplang {}, but '{}' is Soplang {}. Install a matching Soplang, or set $SOPLANG to one.",
+        config.project.name,
+        requirement,
+        interpreter_program(),
+        version
+    ))
+}
@@ -0,0 +1,47 @@
+//! Package management for Soplang, as used by the `sop` command
+//!
+//! Editors, bots and language servers can link this crate to work with Soplang projects the
+//! same way `sop` does. The main entry points are:
+//!
+//! - [`toml_parser`] and [`lockfile`] read and write sop.toml and sop.lock.
+//! - [`registry`] resolves versions and downloads packages, with [`cache`] keeping them
+//!   locally and [`mirrors`] choosing where to download from.
+//! - [`install`] installs packages and their dependencies into sop_modules and records what it
+//!   resolved.
+//! - [`graph`] and [`workspace`] describe how a project's packages depend on each other.
+//! - [`config`] reads the user and project settings the rest of the crate honors.
+//!
+//! Functions act on the project in the current directory, like the command line does. Regular
+//! output is logged through `tracing` at the info level, so embedders decide whether and where
+//! it appears by installing a subscriber.
+
+pub mod advisory;
+pub mod cache;
+pub mod checksum;
+pub mod config;
+pub mod conflict;
+pub mod credentials;
+pub mod diagnostic;
+pub mod exit_code;
+pub mod git;
+pub mod graph;
+pub mod i18n;
+pub mod imports;
+pub mod install;
+pub mod interpreter;
+pub mod lockfile;
+pub mod mirrors;
+pub mod naming;
+pub mod plugin;
+mod progress;
+pub mod project_lock;
+pub mod registry;
+pub mod retry;
+pub mod scripts;
+pub mod signature;
+pub mod stats;
+pub mod style;
+pub mod toml_parser;
+pub mod utils;
+pub mod version;
+pub mod workspace;
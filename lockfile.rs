@@ -0,0 +1,256 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::debug;
+
+use crate::cache::register_lockfile;
+use crate::toml_parser::platform_matches;
+use crate::utils::write_atomic;
+
+/// Set by `--locked` or `--frozen` for the rest of the command
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--frozen` for the rest of the command
+static FROZEN: AtomicBool = AtomicBool::new(false);
+
+/// Fail instead of changing sop.lock for the rest of the command, as `--locked` does
+pub fn forbid_lock_changes() {
+    LOCKED.store(true, Ordering::Relaxed);
+}
+
+/// Also fail instead of using the network or changing sop.toml, as `--frozen` does
+pub fn freeze() {
+    forbid_lock_changes();
+    FROZEN.store(true, Ordering::Relaxed);
+}
+
+/// Whether `--locked` or `--frozen` was given
+pub fn lock_changes_forbidden() -> bool {
+    LOCKED.load(Ordering::Relaxed)
+}
+
+/// Whether `--frozen` was given
+pub fn is_frozen() -> bool {
+    FROZEN.load(Ordering::Relaxed)
+}
+
+/// The flag that forbids changes, for error messages
+pub fn restricting_flag() -> &'static str {
+    if is_frozen() {
+        "--frozen"
+    } else {
+        "--locked"
+    }
+}
+
+/// Fail before a command that always changes sop.lock does anything, under `--locked`
+pub fn ensure_lock_may_change(action: &str) -> Result<()> {
+    if lock_changes_forbidden() {
+        return Err(anyhow!(
+            "{} changes sop.lock, but {} was given.",
+            action,
+            restricting_flag()
+        ));
+    }
+    Ok(())
+}
+
+/// Structure representing the sop.lock file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SopLock {
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+/// A single resolved package recorded in sop.lock
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// Where the package came from when it isn't the public registry (`registry+<url>`,
+    /// `git+<url>#<commit>`, `path+<dir>`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Digest of the package contents (`sha256:<hex>`), checked whenever it is installed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+    /// The `[target.<platform>]` qualifiers the package is needed for, empty when every
+    /// platform needs it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<String>,
+}
+
+impl LockedPackage {
+    /// Check whether the platform sop runs on installs this package
+    pub fn for_current_platform(&self) -> bool {
+        self.platforms.is_empty()
+            || self
+                .platforms
+                .iter()
+                .any(|qualifier| platform_matches(qualifier))
+    }
+
+    /// Get the repository URL and commit of a package locked from git
+    pub fn git_source(&self) -> Option<(&str, &str)> {
+        self.source
+            .as_deref()?
+            .strip_prefix("git+")?
+            .rsplit_once('#')
+    }
+
+    /// Get the URL of the registry a package was locked from, if not the public one
+    pub fn registry_source(&self) -> Option<&str> {
+        self.source.as_deref()?.strip_prefix("registry+")
+    }
+
+    /// Get the directory of a package locked from a local path
+    pub fn path_source(&self) -> Option<&str> {
+        self.source.as_deref()?.strip_prefix("path+")
+    }
+}
+
+impl SopLock {
+    /// Look up the locked entry for a package
+    pub fn get(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    /// Insert or replace the locked version of a package
+    pub fn set(&mut self, name: &str, version: &str) {
+        match self.packages.iter_mut().find(|p| p.name == name) {
+            Some(entry) => entry.version = version.to_string(),
+            None => self.packages.push(LockedPackage {
+                name: name.to_string(),
+                version: version.to_string(),
+                source: None,
+                checksum: None,
+                dependencies: Vec::new(),
+                platforms: Vec::new(),
+            }),
+        }
+    }
+
+    /// Record where a locked package came from
+    pub fn set_source(&mut self, name: &str, source: Option<String>) {
+        if let Some(entry) = self.packages.iter_mut().find(|p| p.name == name) {
+            entry.source = source;
+        }
+    }
+
+    /// Record the checksum of a locked package
+    pub fn set_checksum(&mut self, name: &str, checksum: Option<String>) {
+        if let Some(entry) = self.packages.iter_mut().find(|p| p.name == name) {
+            entry.checksum = checksum;
+        }
+    }
+
+    /// Record the names of the packages a locked package depends on
+    pub fn set_dependencies(&mut self, name: &str, dependencies: Vec<String>) {
+        if let Some(entry) = self.packages.iter_mut().find(|p| p.name == name) {
+            entry.dependencies = dependencies;
+        }
+    }
+
+    /// Remove a package from the lockfile
+    pub fn remove(&mut self, name: &str) {
+        self.packages.retain(|p| p.name != name);
+    }
+}
+
+/// Read and parse a sop.lock file
+pub fn read_sop_lock(path: &Path) -> Result<SopLock> {
+    if !path.exists() {
+        return Err(anyhow!("sop.lock file not found at {:?}", path));
+    }
+
+    let content = fs::read_to_string(path)?;
+    let lock: SopLock = toml::from_str(&content)?;
+    Ok(lock)
+}
+
+/// Read sop.lock if it exists, otherwise return an empty lockfile
+pub fn read_sop_lock_or_default(path: &Path) -> Result<SopLock> {
+    if path.exists() {
+        read_sop_lock(path)
+    } else {
+        Ok(SopLock::default())
+    }
+}
+
+/// Render a SopLock structure as the contents of a sop.lock file
+pub fn render_sop_lock(lock: &SopLock) -> Result<String> {
+    // Keep the lockfile stable across runs so it diffs cleanly
+    let mut packages = lock.packages.clone();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(format!(
+        "# This file is generated by sop. Do not edit it by hand.\n\n{}",
+        toml::to_string_pretty(&SopLock { packages })?
+    ))
+}
+
+/// Write a SopLock structure to a sop.lock file, replacing it atomically
+pub fn write_sop_lock(path: &Path, lock: &SopLock) -> Result<()> {
+    let content = render_sop_lock(lock)?;
+    if lock_changes_forbidden() {
+        if fs::read_to_string(path).is_ok_and(|existing| existing == content) {
+            return Ok(());
+        }
+        return Err(anyhow!(
+            "sop.lock is out of date with sop.toml and needs to be updated, but {} was given. Run the command without it to update sop.lock.",
+            restricting_flag()
+        ));
+    }
+    write_atomic(path, &content)?;
+
+    // Not knowing the project only means `sop cache gc` may collect its packages
+    if let Err(e) = register_lockfile(path) {
+        debug!(
+            "Failed to register {} with the cache: {}",
+            path.display(),
+            e
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendered_lock_round_trips_sorted_by_name() {
+        let mut lock = SopLock::default();
+        lock.set("yaml", "2.1.0");
+        lock.set("http", "1.0.0");
+        lock.set_source(
+            "http",
+            Some("git+https://example.com/http.git#abc123".to_string()),
+        );
+        lock.set_checksum("yaml", Some("sha256:00ff".to_string()));
+        lock.set_dependencies("http", vec!["yaml".to_string()]);
+
+        let rendered = render_sop_lock(&lock).unwrap();
+        assert!(rendered.starts_with("# This file is generated by sop."));
+        let parsed: SopLock = toml::from_str(&rendered).unwrap();
+
+        let names: Vec<&str> = parsed.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["http", "yaml"]);
+        assert_eq!(parsed.get("http"), lock.get("http"));
+        assert_eq!(parsed.get("yaml"), lock.get("yaml"));
+        assert_eq!(render_sop_lock(&parsed).unwrap(), rendered);
+    }
+
+    #[test]
+    fn set_replaces_the_locked_version() {
+        let mut lock = SopLock::default();
+        lock.set("json", "1.0.0");
+        lock.set("json", "2.1.0");
+        assert_eq!(lock.packages.len(), 1);
+        assert_eq!(lock.get("json").unwrap().version, "2.1.0");
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+use crate::style;
+
+/// Environment variable with `tracing` filter directives, e.g. `sop::registry=trace`
+const LOG_ENV: &str = "SOP_LOG";
+
+/// Set up logging for this run
+///
+/// Regular output is logged at the info level and goes to stdout as before; warnings and
+/// errors go to stderr. `-v` adds debug detail about the network, cache and resolver, `-vv`
+/// adds everything, and `--quiet` keeps only warnings and errors. Directives in `$SOP_LOG` are
+/// applied on top, so they can turn up detail for one module without losing regular output.
+/// With `stderr_only`, regular output goes to stderr too, leaving stdout to the command.
+pub fn init(verbosity: u8, quiet: bool, stderr_only: bool) {
+    let level = match (quiet, verbosity) {
+        (true, _) => "warn",
+        (false, 0) => "info",
+        (false, 1) => "debug",
+        (false, _) => "trace",
+    };
+    let defaults = format!("warn,sop={}", level);
+    let filter = match std::env::var(LOG_ENV) {
+        Ok(directives) => EnvFilter::try_new(format!("{},{}", defaults, directives))
+            .unwrap_or_else(|e| {
+                eprintln!("{} Ignoring invalid {}: {}", style::warn_mark(), LOG_ENV, e);
+                EnvFilter::new(&defaults)
+            }),
+        Err(_) => EnvFilter::new(&defaults),
+    };
+
+    let writer = if stderr_only {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(
+            std::io::stdout
+                .with_filter(|metadata| *metadata.level() == Level::INFO)
+                .or_else(std::io::stderr),
+        )
+    };
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .event_format(Plain)
+        .try_init();
+}
+
+/// Print regular output, warnings and errors exactly as written, and tag debug detail
+/// with its level and module
+struct Plain;
+
+impl<S, N> FormatEvent<S, N> for Plain
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        if *metadata.level() > Level::INFO {
+            let tag = format!(
+                "[{} {}]",
+                metadata.level().as_str().to_lowercase(),
+                metadata.target()
+            );
+            write!(writer, "{} ", style::dim(&tag))?;
+        }
+        ctx.format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
@@ -1,20 +1,29 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches};
 
 mod cli;
 mod commands;
-mod toml_parser;
-mod utils;
+mod logging;
+mod update_check;
+
+// The commands use the library's modules as if they were part of the binary
+use sop_core::{
+    advisory, cache, checksum, config, conflict, credentials, exit_code, git, graph, i18n, imports,
+    install, interpreter, lockfile, mirrors, naming, plugin, project_lock, registry, scripts,
+    signature, stats, style, toml_parser, utils, version, workspace,
+};
 
 use crate::cli::Cli;
+use crate::exit_code::exit_code_of;
 
 fn main() {
-    let cli = Cli::parse();
+    let matches = i18n::localize_command(Cli::command()).get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
     match cli.execute() {
         Ok(_) => (),
         Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+            eprintln!("{}: {}", i18n::tr("Error"), e);
+            std::process::exit(exit_code_of(&e) as i32);
         }
     }
 }
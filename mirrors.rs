@@ -0,0 +1,126 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::trace;
+
+use crate::config::read_config;
+use crate::credentials::now;
+use crate::registry;
+use crate::utils::{ensure_dir_exists, get_cache_path};
+
+/// Consecutive failures after which a registry is tried last
+const UNHEALTHY_AFTER_FAILURES: u32 = 3;
+
+/// How long an unhealthy registry stays at the back of the queue, in seconds
+const COOLDOWN_SECS: u64 = 10 * 60;
+
+/// Recent outcomes of downloads from one registry URL
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MirrorHealth {
+    pub successes: u64,
+    pub failures: u64,
+    /// Failures since the last success
+    pub consecutive_failures: u32,
+    /// Unix time of the most recent failure
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_failure: Option<u64>,
+}
+
+impl MirrorHealth {
+    /// Check whether the registry failed repeatedly in the last few minutes
+    pub fn is_unhealthy(&self) -> bool {
+        self.consecutive_failures >= UNHEALTHY_AFTER_FAILURES
+            && self
+                .last_failure
+                .is_some_and(|failed_at| now().saturating_sub(failed_at) < COOLDOWN_SECS)
+    }
+}
+
+/// Health of every registry URL sop has downloaded from, persisted in the cache directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MirrorHealthFile {
+    #[serde(default)]
+    pub registries: BTreeMap<String, MirrorHealth>,
+}
+
+/// Get the registry URLs to download from, in the order they should be tried
+///
+/// Mirrors from `[registry] mirrors` in ~/.sop/config.toml come first, in the order they are
+/// listed, followed by the default registry. Registries that keep failing are moved to the back
+/// until they have cooled down, so they are only tried as a last resort.
+pub fn registry_urls() -> Vec<String> {
+    let mirrors = read_config()
+        .map(|config| config.registry.mirrors)
+        .unwrap_or_default();
+    let mut urls: Vec<String> = mirrors
+        .iter()
+        .map(|mirror| mirror.trim_end_matches('/').to_string())
+        .chain(std::iter::once(registry::registry_url()))
+        .collect();
+    urls.dedup();
+
+    let health = read_mirror_health().unwrap_or_default();
+    // A stable sort keeps the configured priority within the healthy and unhealthy groups
+    urls.sort_by_key(|url| {
+        health
+            .registries
+            .get(url)
+            .is_some_and(MirrorHealth::is_unhealthy)
+    });
+    trace!("Registry order: {}", urls.join(", "));
+    urls
+}
+
+/// Record that a download from a registry succeeded
+pub fn record_success(url: &str) {
+    update_health(url, |health| {
+        health.successes += 1;
+        health.consecutive_failures = 0;
+    });
+}
+
+/// Record that a download from a registry failed
+pub fn record_failure(url: &str) {
+    update_health(url, |health| {
+        health.failures += 1;
+        health.consecutive_failures += 1;
+        health.last_failure = Some(now());
+    });
+}
+
+/// Health tracking is best effort, so a cache directory that can't be written is ignored
+fn update_health(url: &str, update: impl FnOnce(&mut MirrorHealth)) {
+    let Ok(path) = get_mirror_health_path() else {
+        return;
+    };
+    let mut file = read_health_file(&path).unwrap_or_default();
+    update(file.registries.entry(url.to_string()).or_default());
+
+    if let Some(parent) = path.parent() {
+        let _ = ensure_dir_exists(parent);
+    }
+    if let Ok(content) = toml::to_string_pretty(&file) {
+        let _ = fs::write(&path, content);
+    }
+}
+
+/// Get the path of the persisted mirror health file
+pub fn get_mirror_health_path() -> Result<PathBuf> {
+    Ok(get_cache_path()?.join("mirror-health.toml"))
+}
+
+/// Read the persisted mirror health, or nothing if no download has been attempted yet
+pub fn read_mirror_health() -> Result<MirrorHealthFile> {
+    read_health_file(&get_mirror_health_path()?)
+}
+
+fn read_health_file(path: &Path) -> Result<MirrorHealthFile> {
+    if !path.exists() {
+        return Ok(MirrorHealthFile::default());
+    }
+
+    let content = fs::read_to_string(path)?;
+    Ok(toml::from_str(&content)?)
+}
@@ -0,0 +1,111 @@
+use anyhow::{anyhow, Result};
+
+use crate::registry::split_scope;
+
+/// Longest a package name may be, counting an `@scope/` prefix
+pub const MAX_NAME_LENGTH: usize = 64;
+
+/// Names that would be confused with sop itself or the Soplang standard library
+pub const RESERVED_NAMES: [&str; 5] = ["sop", "soplang", "core", "std", "stdlib"];
+
+/// Check a package name against the naming policy
+///
+/// A name is lowercase letters, digits and single dashes, starts with a letter, doesn't end with
+/// a dash, and is at most `MAX_NAME_LENGTH` characters long, optionally with an `@scope/` in
+/// front following the same rules. Reserved names can't be used unscoped. The error says what
+/// is wrong and suggests a name that would do.
+pub fn validate_package_name(name: &str) -> Result<()> {
+    let Some(problem) = name_problem(name) else {
+        return Ok(());
+    };
+    let suggestion = match suggest_package_name(name) {
+        Some(suggestion) => format!(" Try '{}'.", suggestion),
+        None => String::new(),
+    };
+    Err(anyhow!(
+        "'{}' isn't a valid package name: {}. Package names use lowercase letters, digits and dashes.{}",
+        name,
+        problem,
+        suggestion
+    ))
+}
+
+/// Check whether a package name follows the naming policy
+pub fn is_valid_package_name(name: &str) -> bool {
+    name_problem(name).is_none()
+}
+
+/// Describe the first way a name breaks the naming policy, if it does
+fn name_problem(name: &str) -> Option<String> {
+    if name.len() > MAX_NAME_LENGTH {
+        return Some(format!("it is longer than {} characters", MAX_NAME_LENGTH));
+    }
+    if name.starts_with('@') && !name.contains('/') {
+        return Some("a scope needs a name after it, as in @scope/name".to_string());
+    }
+
+    let (scope, bare) = split_scope(name);
+    for part in scope.into_iter().chain([bare]) {
+        if part.is_empty() {
+            return Some("it is empty".to_string());
+        }
+        if part.chars().any(|c| c.is_ascii_uppercase()) {
+            return Some("it has uppercase letters".to_string());
+        }
+        if let Some(c) = part
+            .chars()
+            .find(|c| !c.is_ascii_lowercase() && !c.is_ascii_digit() && *c != '-')
+        {
+            return Some(format!("it contains '{}'", c));
+        }
+        if !part.starts_with(|c: char| c.is_ascii_lowercase()) {
+            return Some("it doesn't start with a letter".to_string());
+        }
+        if part.ends_with('-') || part.contains("--") {
+            return Some("dashes go between words, one at a time".to_string());
+        }
+    }
+
+    if scope.is_none() && RESERVED_NAMES.contains(&bare) {
+        return Some("it is reserved".to_string());
+    }
+    None
+}
+
+/// Suggest a valid name close to an invalid one, such as `my-tool` for `My_Tool`
+///
+/// Returns `None` when nothing sensible is left of the name.
+pub fn suggest_package_name(name: &str) -> Option<String> {
+    let (scope, bare) = split_scope(name);
+    let clean = |part: &str| {
+        let mut cleaned = String::new();
+        for c in part.to_ascii_lowercase().chars() {
+            if c.is_ascii_lowercase() || c.is_ascii_digit() {
+                cleaned.push(c);
+            } else if !cleaned.is_empty() && !cleaned.ends_with('-') {
+                cleaned.push('-');
+            }
+        }
+        let cleaned = cleaned
+            .trim_start_matches(|c: char| c.is_ascii_digit() || c == '-')
+            .trim_end_matches('-');
+        (!cleaned.is_empty()).then(|| cleaned.to_string())
+    };
+
+    let mut bare = clean(bare.trim_start_matches('@'))?;
+    if scope.is_none() && RESERVED_NAMES.contains(&bare.as_str()) {
+        bare.push_str("-lib");
+    }
+    let suggestion = match scope.and_then(clean) {
+        Some(scope) => format!("@{}/{}", scope, bare),
+        None => bare,
+    };
+    let suggestion = if suggestion.len() > MAX_NAME_LENGTH {
+        suggestion[..MAX_NAME_LENGTH]
+            .trim_end_matches('-')
+            .to_string()
+    } else {
+        suggestion
+    };
+    is_valid_package_name(&suggestion).then_some(suggestion)
+}
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::lockfile::{read_sop_lock, SopLock};
+use crate::toml_parser::{read_sop_toml, SopToml};
+use crate::utils::{
+    get_global_bin_path, get_sop_home, get_sop_lock_path, get_sop_modules_path, get_sop_toml_path,
+    get_src_path, get_tests_path, is_soplang_project, project_dir,
+};
+
+/// Version of the plugin interface, raised whenever a variable or context field changes
+/// incompatibly
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// What a plugin is told about the sop installation and project it runs for
+///
+/// Plugins receive these environment variables:
+///
+/// | Variable          | Value                                                  |
+/// |-------------------|--------------------------------------------------------|
+/// | `SOP_PLUGIN_API`  | [`PLUGIN_API_VERSION`]                                 |
+/// | `SOP_CONTEXT`     | Path of a JSON file holding the [`PluginContext`]      |
+/// | `SOP`             | The sop executable, for running sop commands           |
+/// | `SOP_HOME`        | The sop home directory                                 |
+/// | `SOP_PROJECT_DIR` | The project directory (only inside a project)          |
+/// | `SOP_MANIFEST`    | The project's sop.toml (only inside a project)         |
+/// | `SOP_LOCKFILE`    | The project's sop.lock (only inside a project)         |
+/// | `SOP_MODULES`     | The project's sop_modules (only inside a project)      |
+///
+/// The context file only exists while the plugin runs.
+pub struct PluginEnv {
+    vars: Vec<(&'static str, OsString)>,
+    context_path: PathBuf,
+}
+
+/// The JSON document `$SOP_CONTEXT` points to
+#[derive(Debug, Serialize)]
+pub struct PluginContext {
+    pub api_version: u32,
+    pub sop: SopContext,
+    /// Absent outside a project
+    pub project: Option<ProjectContext>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SopContext {
+    pub version: String,
+    pub executable: PathBuf,
+    pub home: PathBuf,
+    /// Where binaries of globally installed packages live
+    pub bin_dir: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectContext {
+    pub root: PathBuf,
+    pub paths: ProjectPaths,
+    /// sop.toml, parsed
+    pub manifest: SopToml,
+    /// sop.lock, parsed, or `null` before the first `sop setup`
+    pub lockfile: Option<SopLock>,
+}
+
+/// Absolute paths of the files and directories that make up a project
+#[derive(Debug, Serialize)]
+pub struct ProjectPaths {
+    pub manifest: PathBuf,
+    pub lockfile: PathBuf,
+    pub modules: PathBuf,
+    pub src: PathBuf,
+    pub tests: PathBuf,
+}
+
+impl PluginContext {
+    /// Describe the running sop and, if there is one, the project in the current directory
+    pub fn current() -> Result<Self> {
+        let project = if is_soplang_project() {
+            // Project paths are relative only in the project root, so this makes them absolute
+            let current_dir = env::current_dir()?;
+            Some(ProjectContext {
+                paths: ProjectPaths {
+                    manifest: current_dir.join(get_sop_toml_path()),
+                    lockfile: current_dir.join(get_sop_lock_path()),
+                    modules: current_dir.join(get_sop_modules_path()),
+                    src: current_dir.join(get_src_path()),
+                    tests: current_dir.join(get_tests_path()),
+                },
+                manifest: read_sop_toml(&get_sop_toml_path())?,
+                lockfile: read_sop_lock(&get_sop_lock_path()).ok(),
+                root: project_dir()?,
+            })
+        } else {
+            None
+        };
+
+        Ok(PluginContext {
+            api_version: PLUGIN_API_VERSION,
+            sop: SopContext {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                executable: env::current_exe()?,
+                home: get_sop_home()?,
+                bin_dir: get_global_bin_path()?,
+            },
+            project,
+        })
+    }
+}
+
+impl PluginEnv {
+    /// Write the context document and collect the variables pointing at it
+    pub fn prepare() -> Result<Self> {
+        let context = PluginContext::current()?;
+        let context_path = env::temp_dir().join(format!("sop-context-{}.json", std::process::id()));
+        fs::write(&context_path, serde_json::to_string_pretty(&context)?)?;
+
+        let mut vars = vec![
+            (
+                "SOP_PLUGIN_API",
+                OsString::from(PLUGIN_API_VERSION.to_string()),
+            ),
+            ("SOP_CONTEXT", context_path.clone().into_os_string()),
+            ("SOP", context.sop.executable.into_os_string()),
+            ("SOP_HOME", context.sop.home.into_os_string()),
+        ];
+        if let Some(project) = context.project {
+            vars.push(("SOP_PROJECT_DIR", project.root.into_os_string()));
+            vars.push(("SOP_MANIFEST", project.paths.manifest.into_os_string()));
+            vars.push(("SOP_LOCKFILE", project.paths.lockfile.into_os_string()));
+            vars.push(("SOP_MODULES", project.paths.modules.into_os_string()));
+        }
+
+        Ok(PluginEnv { vars, context_path })
+    }
+
+    /// Pass the variables to a plugin about to be run
+    pub fn apply<'a>(&self, command: &'a mut Command) -> &'a mut Command {
+        command.envs(self.vars.iter().map(|(key, value)| (*key, value)))
+    }
+}
+
+impl Drop for PluginEnv {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.context_path);
+    }
+}
@@ -0,0 +1,116 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::{self, IsTerminal};
+use std::time::Duration;
+use tracing::{info, warn, Level};
+
+use crate::style;
+
+/// How often spinners advance
+const TICK: Duration = Duration::from_millis(100);
+
+/// Progress of a batch of package downloads
+///
+/// On a terminal this draws a spinner for each download in flight under an overall bar.
+/// Otherwise, e.g. in CI logs, each finished download is logged on its own line instead, and
+/// with `--quiet` nothing is shown at all.
+pub struct DownloadProgress {
+    /// Present only when drawing to a terminal
+    bars: Option<(MultiProgress, ProgressBar)>,
+}
+
+/// One download in a [`DownloadProgress`]
+pub struct PackageProgress {
+    label: String,
+    spinner: Option<ProgressBar>,
+}
+
+impl DownloadProgress {
+    pub fn new() -> Self {
+        if !io::stderr().is_terminal() || !tracing::enabled!(Level::INFO) {
+            return DownloadProgress { bars: None };
+        }
+
+        let multi = MultiProgress::with_draw_target(ProgressDrawTarget::stderr());
+        let overall = multi.add(ProgressBar::new(0));
+        overall.set_style(
+            ProgressStyle::with_template("  [{bar:30}] {pos}/{len} packages")
+                .expect("valid template")
+                .progress_chars(style::symbols().progress),
+        );
+        DownloadProgress {
+            bars: Some((multi, overall)),
+        }
+    }
+
+    /// Count more packages towards the total, e.g. dependencies found in the last batch
+    pub fn add_packages(&self, count: usize) {
+        if let Some((_, overall)) = &self.bars {
+            overall.inc_length(count as u64);
+        }
+    }
+
+    /// Show that a package has started downloading
+    pub fn start(&self, name: &str, version: &str) -> PackageProgress {
+        let label = format!("{} v{}", name, version);
+        let spinner = self.bars.as_ref().map(|(multi, overall)| {
+            let spinner = multi.insert_before(overall, ProgressBar::new_spinner());
+            spinner.set_style(
+                ProgressStyle::with_template("  {spinner} {msg}")
+                    .expect("valid template")
+                    .tick_chars(style::symbols().spinner),
+            );
+            spinner.set_message(format!("Downloading {}", label));
+            spinner.enable_steady_tick(TICK);
+            spinner
+        });
+        PackageProgress { label, spinner }
+    }
+
+    /// Show that a package finished, whether it was downloaded or already cached
+    pub fn finish(&self, package: PackageProgress, downloaded: bool) {
+        match package.spinner {
+            Some(spinner) => spinner.finish_and_clear(),
+            None if downloaded => {
+                info!("  {} Downloaded {}", style::ok_mark(), package.label)
+            }
+            None => {}
+        }
+        if let Some((_, overall)) = &self.bars {
+            overall.inc(1);
+        }
+    }
+
+    /// Show that a package could not be downloaded
+    ///
+    /// Only plain logging mentions it; the install that follows reports the error properly.
+    pub fn fail(&self, package: PackageProgress) {
+        match package.spinner {
+            Some(spinner) => spinner.finish_and_clear(),
+            None => warn!(
+                "  {} Could not download {}",
+                style::warn_mark(),
+                package.label
+            ),
+        }
+        if let Some((_, overall)) = &self.bars {
+            overall.inc(1);
+        }
+    }
+
+    /// Remove the bars and summarize how many packages were downloaded
+    pub fn done(self, downloaded: usize, jobs: usize) {
+        if let Some((multi, overall)) = self.bars {
+            overall.finish_and_clear();
+            let _ = multi.clear();
+        }
+        if downloaded > 0 {
+            info!(
+                "  {} Downloaded {} package{} ({} at a time)",
+                style::ok_mark(),
+                downloaded,
+                if downloaded == 1 { "" } else { "s" },
+                jobs
+            );
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Result};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::info;
+
+use crate::utils::{ensure_dir_exists, get_sop_modules_path};
+
+/// Name of the lock file inside the modules directory
+pub const LOCK_FILE: &str = ".lock";
+
+/// Set by `--no-wait` for the rest of the command
+static NO_WAIT: AtomicBool = AtomicBool::new(false);
+
+/// Whether this process already holds the lock, so nested operations don't wait on themselves
+static HELD: AtomicBool = AtomicBool::new(false);
+
+/// Fail instead of waiting when another sop process holds the project lock
+pub fn no_wait() {
+    NO_WAIT.store(true, Ordering::Relaxed);
+}
+
+/// Exclusive access to the project's sop_modules and sop.lock, released when dropped
+///
+/// The lock is advisory: it only keeps out other sop processes, which take it before changing
+/// either. It lives in the modules directory, so projects sharing one through `[modules]` in
+/// sop.toml also wait for each other.
+pub struct ProjectLock {
+    /// `None` when an outer operation in this process holds the lock
+    file: Option<File>,
+}
+
+impl ProjectLock {
+    /// Take the lock, waiting for any other sop process to finish with the project first
+    pub fn acquire() -> Result<Self> {
+        if HELD.swap(true, Ordering::SeqCst) {
+            return Ok(ProjectLock { file: None });
+        }
+
+        let locked = lock_modules();
+        if locked.is_err() {
+            HELD.store(false, Ordering::SeqCst);
+        }
+        locked.map(|file| ProjectLock { file: Some(file) })
+    }
+}
+
+impl Drop for ProjectLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock
+        if self.file.take().is_some() {
+            HELD.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Open and lock the lock file, recording this process as the holder
+fn lock_modules() -> Result<File> {
+    let modules_dir = get_sop_modules_path();
+    ensure_dir_exists(&modules_dir)?;
+    let path = modules_dir.join(LOCK_FILE);
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let holder = holder_description(&path);
+            if NO_WAIT.load(Ordering::Relaxed) {
+                return Err(anyhow!(
+                    "{} is in use by {}. Try again once it finishes, or leave out --no-wait to wait for it.",
+                    modules_dir.display(),
+                    holder
+                ));
+            }
+            info!(
+                "Waiting for {} to finish with {}...",
+                holder,
+                modules_dir.display()
+            );
+            file.lock()
+                .map_err(|e| anyhow!("Failed to lock {}: {}", path.display(), e))?;
+        }
+        Err(TryLockError::Error(e)) => {
+            return Err(anyhow!("Failed to lock {}: {}", path.display(), e));
+        }
+    }
+
+    // Tell the next process who it is waiting for
+    file.set_len(0)?;
+    write!(file, "{}", std::process::id())?;
+    Ok(file)
+}
+
+/// Describe the process holding a lock file, by the PID it wrote there if it can be read
+fn holder_description(path: &Path) -> String {
+    match fs::read_to_string(path) {
+        Ok(pid) if !pid.trim().is_empty() => format!("another sop process (pid {})", pid.trim()),
+        _ => "another sop process".to_string(),
+    }
+}
@@ -0,0 +1,640 @@
+use anyhow::{anyhow, Result};
+use flate2::read::GzDecoder;
+use fs_extra::dir::CopyOptions;
+use semver::{Version, VersionReq};
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::config::read_config;
+use crate::credentials::{now, RegistryToken, TokenStorage};
+use crate::exit_code::{exit_code_of, with_exit_code, ExitCode};
+use crate::mirrors;
+use crate::retry::with_retry;
+use crate::style;
+use crate::toml_parser::Dependency;
+use crate::utils::ensure_dir_exists;
+use crate::version::parse_version;
+
+/// Lifetime of tokens issued by the registry
+const TOKEN_LIFETIME_DAYS: u64 = 90;
+
+/// Base URL of the public Soplang package registry, used unless `[registry] url` is set
+pub const REGISTRY_URL: &str = "https://registry.soplang.org";
+
+/// Get the URL of the default registry: `[registry] url` from ~/.sop/config.toml, or the
+/// public registry
+pub fn registry_url() -> String {
+    read_config()
+        .ok()
+        .and_then(|config| config.registry.url)
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| REGISTRY_URL.to_string())
+}
+
+/// Look up the URL of a registry named in `[registries]` of ~/.sop/config.toml
+pub fn named_registry_url(name: &str) -> Result<String> {
+    let config = read_config()?;
+    match config.registries.get(name) {
+        Some(registry) => Ok(registry.url.trim_end_matches('/').to_string()),
+        None => Err(anyhow!(
+            "Registry '{}' is not configured. Add it to ~/.sop/config.toml:\n\n[registries.{}]\nurl = \"https://...\"",
+            name,
+            name
+        )),
+    }
+}
+
+/// Get the URL of the registry a dependency from sop.toml is downloaded from
+///
+/// Returns `None` for the public registry.
+pub fn dependency_registry_url(dependency: &Dependency) -> Result<Option<String>> {
+    dependency.registry().map(named_registry_url).transpose()
+}
+
+/// Get the URL of a package version's page on the registry
+pub fn package_url(package: &str, version: &str) -> String {
+    format!("{}/packages/{}/{}", registry_url(), package, version)
+}
+
+/// Get the URL of a package version's changelog on the registry
+pub fn changelog_url(package: &str, version: &str) -> String {
+    format!("{}/changelog", package_url(package, version))
+}
+
+/// Get the URL of the documentation the registry builds for a package version
+pub fn docs_url(package: &str, version: &str) -> String {
+    format!("{}/docs", package_url(package, version))
+}
+
+/// Set by `--pre` for the rest of the command
+static PRERELEASES: AtomicBool = AtomicBool::new(false);
+
+/// Let `latest` and version ranges resolve to pre-releases for the rest of the command
+pub fn allow_prereleases() {
+    PRERELEASES.store(true, Ordering::Relaxed);
+}
+
+/// Check whether `--pre` was passed
+pub fn prereleases_allowed() -> bool {
+    PRERELEASES.load(Ordering::Relaxed)
+}
+
+/// Resolve a version requirement from sop.toml to a concrete version
+///
+/// A single version, such as `1.2.0` or `^1.2.0-beta.1`, is taken as-is. `latest` and ranges
+/// such as `>=1.2, <2` pick the newest published version they allow, which is only a
+/// pre-release when the range names a pre-release of the same version or `--pre` was passed.
+pub fn resolve_version(package: &str, requirement: &str) -> Result<String> {
+    let single = requirement.trim_start_matches(['^', '~', '=']);
+    let version = if requirement == "latest" {
+        newest_version(package, prereleases_allowed())?
+    } else if parse_version(single).is_some() {
+        if is_yanked(package, single)? {
+            return Err(with_exit_code(
+                ExitCode::Resolution,
+                anyhow!(
+                    "{} v{} has been yanked from the registry; 'sop info {}' lists the versions available",
+                    package,
+                    single,
+                    package
+                ),
+            ));
+        }
+        single.to_string()
+    } else {
+        let range = VersionReq::parse(requirement)
+            .map_err(|e| anyhow!("invalid version requirement '{}': {}", requirement, e))?;
+        newest_matching(package, &range, prereleases_allowed())?.ok_or_else(|| {
+            with_exit_code(
+                ExitCode::Resolution,
+                anyhow!(
+                    "no published version of '{}' matches {}{}",
+                    package,
+                    requirement,
+                    if prereleases_allowed() {
+                        ""
+                    } else {
+                        " (pass --pre to consider pre-releases)"
+                    }
+                ),
+            )
+        })?
+    };
+    debug!("Resolved {} {} to v{}", package, requirement, version);
+    Ok(version)
+}
+
+/// Get the newest version of a package: the latest release, or with `prereleases` whatever
+/// was published last
+pub fn newest_version(package: &str, prereleases: bool) -> Result<String> {
+    newest_matching(package, &VersionReq::STAR, prereleases)?.ok_or_else(|| {
+        with_exit_code(
+            ExitCode::Resolution,
+            anyhow!("every published version of '{}' has been yanked", package),
+        )
+    })
+}
+
+/// Find the newest published version of a package that a range allows
+///
+/// With `prereleases`, a pre-release counts wherever the release it leads up to would. Yanked
+/// versions are passed over, with a warning when one would otherwise have been picked.
+fn newest_matching(package: &str, range: &VersionReq, prereleases: bool) -> Result<Option<String>> {
+    let allows = |version: &Version| {
+        range.matches(version)
+            || (prereleases
+                && !version.pre.is_empty()
+                && range.matches(&Version::new(version.major, version.minor, version.patch)))
+    };
+    let mut candidates: Vec<(Version, String)> = available_versions(package)?
+        .into_iter()
+        .filter_map(|version| Some((parse_version(&version)?, version)))
+        .filter(|(parsed, _)| allows(parsed))
+        .collect();
+    candidates.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let yanked = yanked_versions(package)?;
+    for (_, version) in candidates {
+        if !yanked.contains(&version) {
+            return Ok(Some(version));
+        }
+        warn!(
+            "  {} Skipping {} v{}, which has been yanked from the registry",
+            style::warn_mark(),
+            package,
+            version
+        );
+    }
+    Ok(None)
+}
+
+/// Get the versions of a package its publisher has yanked from the registry
+///
+/// Yanked versions can still be downloaded, so lockfiles that pin them keep working, but new
+/// resolutions never pick them.
+pub fn yanked_versions(package: &str) -> Result<Vec<String>> {
+    // In a real implementation, this would come from the registry index
+    // For now, the release before the latest one is treated as yanked
+    let latest = latest_version(package)?;
+    let releases = ["2.1.0", "1.4.0", "1.0.3", "1.0.0"];
+    Ok(releases
+        .iter()
+        .skip_while(|version| **version != latest)
+        .nth(1)
+        .map(|version| version.to_string())
+        .into_iter()
+        .collect())
+}
+
+/// Check whether a version of a package has been yanked from the registry
+pub fn is_yanked(package: &str, version: &str) -> Result<bool> {
+    Ok(yanked_versions(package)?
+        .iter()
+        .any(|yanked| yanked == version))
+}
+
+/// Registry failures gathered across several packages so they can be reported together
+#[derive(Debug, Default)]
+pub struct RegistryFailures {
+    failures: Vec<(String, anyhow::Error)>,
+}
+
+impl RegistryFailures {
+    /// Record why a package couldn't be resolved
+    pub fn record(&mut self, package: &str, error: anyhow::Error) {
+        self.failures.push((package.to_string(), error));
+    }
+
+    /// Check whether every package resolved
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Turn the gathered failures into a single error listing every package and its cause
+    pub fn into_result(self) -> Result<()> {
+        if self.failures.is_empty() {
+            return Ok(());
+        }
+
+        // A tampered package is more serious than an unresolvable one, so its code wins
+        let tampered = self
+            .failures
+            .iter()
+            .any(|(_, error)| exit_code_of(error) == ExitCode::Integrity);
+        let (code, action) = if tampered {
+            (ExitCode::Integrity, "install")
+        } else {
+            (ExitCode::Resolution, "resolve")
+        };
+
+        let mut report = format!(
+            "Could not {} {} package{} from the registry:",
+            action,
+            self.failures.len(),
+            if self.failures.len() == 1 { "" } else { "s" }
+        );
+        for (package, error) in &self.failures {
+            report.push_str(&format!("\n  {}: {:#}", package, error));
+        }
+        Err(with_exit_code(code, anyhow!(report)))
+    }
+}
+
+/// The error for a package the registry doesn't know about
+fn not_found(package: &str) -> anyhow::Error {
+    with_exit_code(
+        ExitCode::Resolution,
+        anyhow!("package '{}' not found in the registry", package),
+    )
+}
+
+/// Get the latest published version of a package
+pub fn latest_version(package: &str) -> Result<String> {
+    if !package_exists(package)? {
+        return Err(not_found(package));
+    }
+
+    // In a real implementation, this would query the registry index
+    // For now, derive a stable simulated release from the package name
+    let seed: u32 = package.bytes().map(u32::from).sum();
+    let latest = match seed % 4 {
+        0 => "1.0.0",
+        1 => "1.0.3",
+        2 => "1.4.0",
+        _ => "2.1.0",
+    };
+    Ok(latest.to_string())
+}
+
+/// A package listed in the registry index
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    pub name: String,
+    pub description: String,
+}
+
+/// Get every package listed in the registry index
+pub fn search_index() -> Result<Vec<IndexEntry>> {
+    // In a real implementation, this would download and cache the registry index
+    // For now, list a fixed set of well-known packages
+    let packages = [
+        ("cli-args", "Command-line argument parsing"),
+        ("colors", "Terminal colors and styles"),
+        ("csv", "Read and write CSV files"),
+        ("datetime", "Dates, times, and time zones"),
+        ("http", "HTTP client and server"),
+        ("json", "JSON parsing and serialization"),
+        ("logger", "Structured logging"),
+        ("math", "Extra math functions and constants"),
+        ("regex", "Regular expressions"),
+        ("testkit", "Assertions and helpers for tests"),
+        ("uuid", "Generate and parse UUIDs"),
+        ("yaml", "YAML parsing and serialization"),
+    ];
+    Ok(packages
+        .iter()
+        .map(|(name, description)| IndexEntry {
+            name: name.to_string(),
+            description: description.to_string(),
+        })
+        .collect())
+}
+
+/// Get the published versions of a package, newest first
+pub fn available_versions(package: &str) -> Result<Vec<String>> {
+    // In a real implementation, this would query the registry index
+    // For now, every simulated release up to the latest one is available, along with a
+    // pre-release of the next minor version
+    let latest = latest_version(package)?;
+    let releases = ["2.1.0", "1.4.0", "1.0.3", "1.0.0"];
+    let next = parse_version(&latest)
+        .map(|latest| format!("{}.{}.0-beta.1", latest.major, latest.minor + 1));
+    Ok(next
+        .into_iter()
+        .chain(
+            releases
+                .iter()
+                .skip_while(|version| **version != latest)
+                .map(|version| version.to_string()),
+        )
+        .collect())
+}
+
+/// Split a scoped package name (`@org/package`) into its scope and the name within it
+///
+/// Unscoped names have no scope. The scope is returned without its `@`.
+pub fn split_scope(package: &str) -> (Option<&str>, &str) {
+    match package
+        .strip_prefix('@')
+        .and_then(|scoped| scoped.split_once('/'))
+    {
+        Some((scope, name)) => (Some(scope), name),
+        None => (None, package),
+    }
+}
+
+/// Check whether a package has been published to the registry
+pub fn package_exists(package: &str) -> Result<bool> {
+    // In a real implementation, this would query the registry index
+    // For now, every well-formed package name is treated as published
+    let well_formed = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    };
+    let (scope, name) = split_scope(package);
+    Ok(scope.is_none_or(well_formed) && well_formed(name))
+}
+
+/// Download a package from the registry and extract it into `dest`
+///
+/// `registry` is the URL of a named registry; without one, configured mirrors are tried in
+/// priority order before the public registry, and the first one that serves the package wins.
+/// Transient failures are retried on the same registry before moving on to the next.
+/// Returns the URL of the registry the package came from.
+pub fn download_package(
+    package: &str,
+    version: &str,
+    registry: Option<&str>,
+    dest: &Path,
+) -> Result<String> {
+    if !package_exists(package)? {
+        return Err(not_found(package));
+    }
+
+    let urls = match registry {
+        Some(url) => vec![url.to_string()],
+        None => mirrors::registry_urls(),
+    };
+    let mut failures = Vec::new();
+    for url in urls {
+        debug!("Downloading {} v{} from {}", package, version, url);
+        let action = format!("download {} v{} from {}", package, version, url);
+        let result = with_retry(&action, || {
+            download_from(&url, package, version, dest).inspect_err(|_| {
+                let _ = fs::remove_dir_all(dest);
+            })
+        });
+        match result {
+            Ok(()) => {
+                mirrors::record_success(&url);
+                return Ok(url);
+            }
+            Err(e) => {
+                debug!("Giving up on {}: {:#}", url, e);
+                mirrors::record_failure(&url);
+                failures.push(format!("  {}: {}", url, e));
+            }
+        }
+    }
+
+    Err(anyhow!(
+        "Could not download {} v{} from any registry:\n{}",
+        package,
+        version,
+        failures.join("\n")
+    ))
+}
+
+/// Download a package from one registry or mirror
+fn download_from(url: &str, package: &str, version: &str, dest: &Path) -> Result<()> {
+    if let Some(root) = url.strip_prefix("file://") {
+        // A local mirror stores extracted packages as `<name>/<version>/`
+        let source = Path::new(root).join(package).join(version);
+        if !source.is_dir() {
+            return Err(anyhow!("{} v{} is not on this mirror", package, version));
+        }
+        ensure_dir_exists(dest)?;
+        let options = CopyOptions::new().content_only(true);
+        fs_extra::dir::copy(&source, dest, &options)?;
+        return Ok(());
+    }
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(anyhow!("unsupported registry URL"));
+    }
+    ensure_dir_exists(dest)?;
+
+    // For now, we'll just create placeholder files
+    // In a real implementation, this would download the package archive from a registry
+    let licenses = ["MIT", "Apache-2.0", "MIT OR Apache-2.0"];
+    let seed: usize = package.bytes().map(usize::from).sum();
+    let metadata_file = dest.join("sop.toml");
+    let metadata_content = format!(
+        r#"[package]
+name = "{}"
+version = "{}"
+description = "A Soplang package"
+license = "{}"
+"#,
+        package,
+        version,
+        licenses[seed % licenses.len()]
+    );
+
+    fs::write(metadata_file, metadata_content)?;
+
+    // Create a simple placeholder .so file
+    let lib_file = dest.join("lib.so");
+    let lib_content = format!(
+        r#"// This is a placeholder for the {} library
+
+export fn hello() {{
+    println("Hello from {}!");
+}}
+"#,
+        package, package
+    );
+
+    fs::write(lib_file, lib_content)?;
+
+    Ok(())
+}
+
+/// Download the detached minisign signature published alongside a package version, if any
+pub fn download_signature(package: &str, version: &str) -> Result<Option<String>> {
+    if !available_versions(package)?.iter().any(|v| v == version) {
+        return Ok(None);
+    }
+
+    // In a real implementation, this would fetch `<package_url>/signature.minisig`
+    // For now, no package in the simulated registry is signed
+    Ok(None)
+}
+
+/// Check whether a version of a package is on the registry sop publishes to
+pub fn is_published(package: &str, version: &str) -> Result<bool> {
+    let url = registry_url();
+    if let Some(root) = url.strip_prefix("file://") {
+        return Ok(Path::new(root).join(package).join(version).is_dir());
+    }
+
+    // In a real implementation, this would query the registry index
+    // For now, versions uploaded during this run count along with the simulated releases
+    let uploaded = UPLOADED
+        .lock()
+        .is_ok_and(|uploaded| uploaded.iter().any(|(p, v)| p == package && v == version));
+    Ok(uploaded || available_versions(package)?.iter().any(|v| v == version))
+}
+
+/// Versions uploaded to an http(s) registry during this run
+static UPLOADED: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Upload a package archive built by `sop publish` to the registry sop publishes to
+///
+/// A `file://` registry stores it extracted as `<name>/<version>/`, the layout local mirrors
+/// are read from.
+pub fn upload_package(package: &str, version: &str, archive: &Path) -> Result<()> {
+    if is_published(package, version)? {
+        return Err(anyhow!(
+            "{} v{} is already published. Bump the version with 'sop version patch'.",
+            package,
+            version
+        ));
+    }
+
+    let url = registry_url();
+    if let Some(root) = url.strip_prefix("file://") {
+        // Archives hold a single `<name>-<version>/` directory, whose contents are the package
+        let unpacked = std::env::temp_dir().join(format!(
+            "sop-upload-{}-{}",
+            package.trim_start_matches('@').replace('/', "-"),
+            version
+        ));
+        if unpacked.exists() {
+            fs::remove_dir_all(&unpacked)?;
+        }
+        tar::Archive::new(GzDecoder::new(fs::File::open(archive)?)).unpack(&unpacked)?;
+        let top = fs::read_dir(&unpacked)?
+            .next()
+            .ok_or_else(|| anyhow!("The package archive is empty"))??
+            .path();
+        let dest = Path::new(root).join(package).join(version);
+        ensure_dir_exists(&dest)?;
+        let options = CopyOptions::new().content_only(true);
+        let copied = fs_extra::dir::copy(&top, &dest, &options);
+        let _ = fs::remove_dir_all(&unpacked);
+        copied?;
+        return Ok(());
+    }
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(anyhow!("unsupported registry URL"));
+    }
+
+    // In a real implementation, this would PUT the archive to `package_url`
+    debug!("Uploading {} v{} to {}", package, version, url);
+    if let Ok(mut uploaded) = UPLOADED.lock() {
+        uploaded.push((package.to_string(), version.to_string()));
+    }
+    Ok(())
+}
+
+/// Download the security advisory database as JSON
+pub fn download_advisory_db() -> Result<String> {
+    // In a real implementation, this would fetch `<REGISTRY_URL>/advisories/db.json`
+    // For now, return a small fixed set of advisories
+    Ok(r#"{
+  "advisories": [
+    {
+      "id": "SOP-2024-0003",
+      "package": "yaml",
+      "title": "Arbitrary code execution when loading untrusted documents",
+      "severity": "high",
+      "vulnerable": "<2.0.0",
+      "patched": ">=2.0.0",
+      "url": "https://registry.soplang.org/advisories/SOP-2024-0003"
+    },
+    {
+      "id": "SOP-2024-0011",
+      "package": "http",
+      "title": "Request smuggling through malformed Transfer-Encoding headers",
+      "severity": "medium",
+      "vulnerable": ">=1.0.0, <1.0.3",
+      "patched": ">=1.0.3",
+      "url": "https://registry.soplang.org/advisories/SOP-2024-0011"
+    },
+    {
+      "id": "SOP-2025-0002",
+      "package": "json",
+      "title": "Stack overflow on deeply nested arrays",
+      "severity": "low",
+      "vulnerable": "<1.0.3",
+      "patched": ">=1.0.3",
+      "url": "https://registry.soplang.org/advisories/SOP-2025-0002"
+    },
+    {
+      "id": "SOP-2025-0009",
+      "package": "logger",
+      "title": "Log injection through unescaped newlines",
+      "severity": "critical",
+      "vulnerable": "<1.4.0",
+      "patched": ">=1.4.0",
+      "url": "https://registry.soplang.org/advisories/SOP-2025-0009"
+    }
+  ]
+}"#
+    .to_string())
+}
+
+/// Check that a registry answers requests
+pub fn ping(url: &str) -> Result<()> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(anyhow!("'{}' is not an http(s) URL", url));
+    }
+
+    // In a real implementation, this would request `<url>/index/config.json`
+    // For now, every http(s) registry answers
+    debug!("Pinged {}", url);
+    Ok(())
+}
+
+/// Get the released versions of sop itself, newest first
+pub fn sop_releases() -> Result<Vec<String>> {
+    // In a real implementation, this would fetch `<REGISTRY_URL>/releases/stable.json`
+    // For now, the running version is the only release
+    Ok(vec![env!("CARGO_PKG_VERSION").to_string()])
+}
+
+/// Validate a token with the registry and fetch its metadata
+pub fn token_metadata(token: &str) -> Result<RegistryToken> {
+    if token.trim().is_empty() {
+        return Err(anyhow!("The registry rejected an empty token."));
+    }
+
+    // In a real implementation, this would call the registry's token endpoint
+    // For now, tokens are treated as freshly issued with the default lifetime
+    let created_at = now();
+    Ok(RegistryToken {
+        token: token.to_string(),
+        created_at,
+        expires_at: Some(created_at + TOKEN_LIFETIME_DAYS * 24 * 60 * 60),
+        storage: TokenStorage::default(),
+    })
+}
+
+/// Ask the registry for a new token, authenticating with the current one
+pub fn create_token(current: &RegistryToken) -> Result<RegistryToken> {
+    if current.is_expired() {
+        return Err(anyhow!(
+            "The current token has expired and cannot be used to issue a new one. Run 'sop login' with a fresh token."
+        ));
+    }
+
+    // In a real implementation, this would POST to the registry's token endpoint
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write(current.token.as_bytes());
+    hasher.write_u64(now());
+    token_metadata(&format!("sop_{:016x}", hasher.finish()))
+}
+
+/// Revoke a token so it can no longer be used
+pub fn revoke_token(token: &RegistryToken) -> Result<()> {
+    // In a real implementation, this would DELETE the token via the registry API
+    let _ = token;
+    Ok(())
+}
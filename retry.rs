@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Result};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::ErrorKind;
+use std::thread;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::config;
+
+/// Delay before the first retry; each later retry waits twice as long as the one before
+const BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest a single retry waits, however many attempts came before it
+const MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// Phrases in error messages, e.g. from git's stderr, that mean the network had a hiccup
+const TRANSIENT_MESSAGES: &[&str] = &[
+    "timed out",
+    "timeout",
+    "connection reset",
+    "connection refused",
+    "connection aborted",
+    "broken pipe",
+    "temporary failure",
+    "could not resolve host",
+    "early eof",
+    "returned error: 5",
+    "returned error: 429",
+];
+
+/// Run a network operation, retrying it with jittered exponential backoff while it fails
+/// transiently
+///
+/// Failures that retrying can't fix, like a missing package, are returned straight away. Once
+/// every attempt is used up, the error lists what went wrong on each one.
+pub fn with_retry<T>(action: &str, mut operation: impl FnMut() -> Result<T>) -> Result<T> {
+    let attempts = config::retries() + 1;
+    let mut failures = Vec::new();
+    for attempt in 1..=attempts {
+        match operation() {
+            Ok(value) => return Ok(value),
+            Err(e) if !is_transient(&e) => return Err(e),
+            Err(e) => {
+                failures.push(format!("  attempt {}: {}", attempt, e));
+                if attempt < attempts {
+                    let delay = backoff(attempt);
+                    debug!(
+                        "Failed to {} (attempt {} of {}), retrying in {:.1}s: {}",
+                        action,
+                        attempt,
+                        attempts,
+                        delay.as_secs_f64(),
+                        e
+                    );
+                    thread::sleep(delay);
+                }
+            }
+        }
+    }
+
+    Err(anyhow!(
+        "Failed to {} after {} attempt{}:\n{}",
+        action,
+        attempts,
+        if attempts == 1 { "" } else { "s" },
+        failures.join("\n")
+    ))
+}
+
+/// Check whether an error is worth retrying: a timeout, a dropped connection or a server error
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    let io_transient = error.chain().any(|cause| {
+        cause.downcast_ref::<std::io::Error>().is_some_and(|e| {
+            matches!(
+                e.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::Interrupted
+                    | ErrorKind::UnexpectedEof
+            )
+        })
+    });
+    if io_transient {
+        return true;
+    }
+
+    let message = format!("{:#}", error).to_lowercase();
+    TRANSIENT_MESSAGES
+        .iter()
+        .any(|phrase| message.contains(phrase))
+}
+
+/// Get how long to wait before retrying after `attempt` failed attempts
+///
+/// The delay is picked at random between half and all of the exponential backoff, so many sop
+/// processes retrying at once don't hit the registry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let exponential = BASE_DELAY
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(MAX_DELAY);
+    let jitter = (RandomState::new().build_hasher().finish() % 1000) as f64 / 1000.0;
+    exponential.mul_f64(0.5 + jitter / 2.0)
+}
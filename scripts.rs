@@ -0,0 +1,203 @@
+use anyhow::{anyhow, Result};
+use dialoguer::Confirm;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, info, warn};
+
+use crate::config::read_config;
+use crate::i18n::tr_args;
+use crate::plugin::PluginEnv;
+use crate::style;
+use crate::utils::{get_sop_home, write_atomic};
+
+/// Hook run in a package's directory after it is installed from a registry or git
+pub const POSTINSTALL: &str = "postinstall";
+
+/// Whether package scripts may run, chosen with `scripts` in the config
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScriptPolicy {
+    /// Ask before running a package's script for the first time
+    #[default]
+    Ask,
+    /// Run package scripts without asking
+    Allow,
+    /// Never run package scripts
+    Deny,
+}
+
+/// Set by `--ignore-scripts` for the rest of the command
+static IGNORE_SCRIPTS: AtomicBool = AtomicBool::new(false);
+
+/// Run no package scripts for the rest of the command, whatever the policy says
+pub fn ignore_scripts() {
+    IGNORE_SCRIPTS.store(true, Ordering::Relaxed);
+}
+
+/// The scripts the user agreed to run, by package, as digests of the hook and its command
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ApprovedScripts {
+    #[serde(default)]
+    approved: BTreeMap<String, BTreeSet<String>>,
+}
+
+fn approved_scripts_path() -> Result<PathBuf> {
+    Ok(get_sop_home()?.join("approved-scripts.toml"))
+}
+
+fn read_approved_scripts(path: &Path) -> Result<ApprovedScripts> {
+    if !path.exists() {
+        return Ok(ApprovedScripts::default());
+    }
+    Ok(toml::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Identify a script, so a package that changes what it runs is asked about again
+fn script_digest(hook: &str, script: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(hook.as_bytes());
+    hasher.update([0]);
+    hasher.update(script.as_bytes());
+    format!("sha256:{:x}", hasher.finalize())
+}
+
+/// Read the command a package runs for a hook from the `[scripts]` table of its sop.toml
+pub fn package_script(package_dir: &Path, hook: &str) -> Option<String> {
+    let content = fs::read_to_string(package_dir.join("sop.toml")).ok()?;
+    let metadata: toml::Value = toml::from_str(&content).ok()?;
+    metadata
+        .get("scripts")?
+        .get(hook)?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Run a package's script for a lifecycle hook, if it has one and the policy allows it
+///
+/// Scripts run through the shell in the package's directory, with the environment plugins get
+/// (see [`PluginEnv`]). Under the default `ask` policy the user confirms each script the first
+/// time it would run, and again whenever it changes; without a terminal to ask on, the script
+/// is skipped with a warning. A script that fails fails the install. Files a script writes into
+/// the package directory count as changes to the package, which `sop verify` reports.
+pub fn run_package_script(package: &str, package_dir: &Path, hook: &str) -> Result<()> {
+    let Some(script) = package_script(package_dir, hook) else {
+        return Ok(());
+    };
+    if IGNORE_SCRIPTS.load(Ordering::Relaxed) {
+        debug!(
+            "Not running the {} script of {} (--ignore-scripts)",
+            hook, package
+        );
+        return Ok(());
+    }
+
+    match read_config()?.scripts.unwrap_or_default() {
+        ScriptPolicy::Allow => {}
+        ScriptPolicy::Deny => {
+            warn!(
+                "  {} Not running the {} script of {} (scripts = \"deny\")",
+                style::warn_mark(),
+                hook,
+                package
+            );
+            return Ok(());
+        }
+        ScriptPolicy::Ask => {
+            if !approve(package, hook, &script)? {
+                return Ok(());
+            }
+        }
+    }
+
+    info!("  Running the {} script of {}: {}", hook, package, script);
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    // Script output is progress, like the rest of an install's, and stdout may be spoken for
+    command
+        .arg(&script)
+        .current_dir(package_dir)
+        .stdout(io::stderr());
+
+    let plugin_env = PluginEnv::prepare()?;
+    let status = plugin_env
+        .apply(&mut command)
+        .status()
+        .map_err(|e| anyhow!("Failed to run the {} script of {}: {}", hook, package, e))?;
+    if !status.success() {
+        return Err(anyhow!(
+            "The {} script of {} failed ({}). Rerun with --ignore-scripts to install without it.",
+            hook,
+            package,
+            status
+        ));
+    }
+    Ok(())
+}
+
+/// Check whether the user approved a script before, asking them if they haven't
+fn approve(package: &str, hook: &str, script: &str) -> Result<bool> {
+    let path = approved_scripts_path()?;
+    let mut approved = read_approved_scripts(&path)?;
+    let digest = script_digest(hook, script);
+    if approved
+        .approved
+        .get(package)
+        .is_some_and(|digests| digests.contains(&digest))
+    {
+        return Ok(true);
+    }
+
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        warn!(
+            "  {} Not running the {} script of {}, which hasn't been approved: {}",
+            style::warn_mark(),
+            hook,
+            package,
+            script
+        );
+        warn!(
+            "    Approve it in an interactive 'sop setup', or set scripts = \"allow\" to run package scripts without asking."
+        );
+        return Ok(false);
+    }
+
+    let confirmed = Confirm::new()
+        .with_prompt(tr_args(
+            "{} wants to run a {} script: {}\nRun it?",
+            &[&package, &hook, &script],
+        ))
+        .default(false)
+        .interact()?;
+    if confirmed {
+        approved
+            .approved
+            .entry(package.to_string())
+            .or_default()
+            .insert(digest);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_atomic(&path, &toml::to_string_pretty(&approved)?)?;
+    } else {
+        warn!(
+            "  {} Skipped the {} script of {}",
+            style::warn_mark(),
+            hook,
+            package
+        );
+    }
+    Ok(confirmed)
+}
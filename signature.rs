@@ -0,0 +1,175 @@
+use anyhow::{anyhow, Result};
+use minisign_verify::{PublicKey, Signature};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::config::{get_user_config_path, read_config};
+use crate::exit_code::{with_exit_code, ExitCode};
+
+/// Set by `--require-signatures` for the rest of the command
+static REQUIRED: AtomicBool = AtomicBool::new(false);
+
+/// Refuse registry packages without a valid signature for the rest of the command
+pub fn require_signatures() {
+    REQUIRED.store(true, Ordering::Relaxed);
+}
+
+/// Check the detached signature of a registry package against the trusted keys
+///
+/// Packages are signed over their checksum as recorded in sop.lock (`sha256:<hex>`), so one
+/// signature covers every file of the package. Unsigned packages, and packages signed while no
+/// keys are trusted, are accepted unless signatures are required. Returns whether a signature
+/// was verified.
+pub fn verify_package(
+    package: &str,
+    version: &str,
+    checksum: &str,
+    signature: Option<&str>,
+) -> Result<bool> {
+    let config = read_config()?.signatures;
+    let required = REQUIRED.load(Ordering::Relaxed) || config.require;
+    check_signature(
+        package,
+        version,
+        checksum,
+        signature,
+        &config.trusted_keys,
+        required,
+    )
+}
+
+/// Check a package's signature against `trusted_keys`, as [`verify_package`] does with the
+/// keys and requirement from the user config
+fn check_signature(
+    package: &str,
+    version: &str,
+    checksum: &str,
+    signature: Option<&str>,
+    trusted_keys: &[String],
+    required: bool,
+) -> Result<bool> {
+    let Some(signature) = signature else {
+        if required {
+            return Err(rejected(anyhow!(
+                "{} v{} is not signed, and signatures are required",
+                package,
+                version
+            )));
+        }
+        return Ok(false);
+    };
+
+    let keys = trusted_keys
+        .iter()
+        .map(|key| {
+            PublicKey::from_base64(key.trim())
+                .map_err(|e| anyhow!("Invalid trusted key '{}' in the user config: {}", key, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if keys.is_empty() {
+        if required {
+            return Err(rejected(anyhow!(
+                "signatures are required, but no trusted keys are listed under [signatures] in {}",
+                get_user_config_path()?.display()
+            )));
+        }
+        return Ok(false);
+    }
+
+    let signature = Signature::decode(signature).map_err(|e| {
+        rejected(anyhow!(
+            "the signature of {} v{} is malformed: {}",
+            package,
+            version,
+            e
+        ))
+    })?;
+
+    // Accept the checksum with or without the newline `echo` leaves when it is signed by hand
+    let messages = [checksum.to_string(), format!("{}\n", checksum)];
+    let trusted = keys.iter().any(|key| {
+        messages
+            .iter()
+            .any(|message| key.verify(message.as_bytes(), &signature, false).is_ok())
+    });
+    if !trusted {
+        return Err(rejected(anyhow!(
+            "the signature of {} v{} doesn't match any trusted key. Refusing to install it.",
+            package,
+            version
+        )));
+    }
+
+    Ok(true)
+}
+
+fn rejected(error: anyhow::Error) -> anyhow::Error {
+    with_exit_code(ExitCode::Integrity, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECKSUM: &str =
+        "sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+    const SIGNING_KEY: &str = "RWRJGQsPpJh2eq3yUt0qG9POvhzBxGBcZWe6aa0cvQDogCcQjCZrbapz";
+    const OTHER_KEY: &str = "RWT/9jO8/9Ol9NtF9s6Tg70g6PXxUx7owaKwqAO1Ry01aszLvDM405eG";
+    /// Signature of `CHECKSUM` by the secret half of `SIGNING_KEY`
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RURJGQsPpJh2emAzjaDMshUMgrCBWM2vGictGBtgvQUszZ22tjyjEbIc3zTiYS0zICnnOeZCjAl9LIwCX1v+ZxFSUYcYtga9kQ8=
+trusted comment: timestamp:1700000000
+ftV8N6SRp/+FJGuNSIF2ZySOlJ+ZsKDrOkRI94Ox8zv/dVIjTiVgZWlZKENyQpZKpOsf0zemc/h+07fG7BajDw==
+";
+
+    fn check(signature: Option<&str>, keys: &[&str], required: bool) -> Result<bool> {
+        let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        check_signature("json", "1.0.0", CHECKSUM, signature, &keys, required)
+    }
+
+    #[test]
+    fn valid_signature_is_verified() {
+        assert!(check(Some(SIGNATURE), &[SIGNING_KEY], true).unwrap());
+        assert!(check(Some(SIGNATURE), &[OTHER_KEY, SIGNING_KEY], false).unwrap());
+    }
+
+    #[test]
+    fn signature_from_an_untrusted_key_is_rejected() {
+        let error = check(Some(SIGNATURE), &[OTHER_KEY], false).unwrap_err();
+        assert!(error.to_string().contains("doesn't match any trusted key"));
+    }
+
+    #[test]
+    fn signature_over_another_checksum_is_rejected() {
+        let keys = vec![SIGNING_KEY.to_string()];
+        let tampered = CHECKSUM.replace("9f86", "0000");
+        assert!(
+            check_signature("json", "1.0.0", &tampered, Some(SIGNATURE), &keys, false).is_err()
+        );
+    }
+
+    #[test]
+    fn malformed_signature_is_rejected() {
+        let error = check(Some("not a signature"), &[SIGNING_KEY], false).unwrap_err();
+        assert!(error.to_string().contains("malformed"));
+    }
+
+    #[test]
+    fn missing_signature_is_rejected_only_when_required() {
+        assert!(!check(None, &[SIGNING_KEY], false).unwrap());
+        let error = check(None, &[SIGNING_KEY], true).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("is not signed, and signatures are required"));
+    }
+
+    #[test]
+    fn required_signatures_need_trusted_keys() {
+        assert!(!check(Some(SIGNATURE), &[], false).unwrap());
+        assert!(check(Some(SIGNATURE), &[], true).is_err());
+    }
+
+    #[test]
+    fn invalid_trusted_key_is_an_error() {
+        assert!(check(Some(SIGNATURE), &["not a key"], false).is_err());
+    }
+}
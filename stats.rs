@@ -0,0 +1,111 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::utils::{ensure_dir_exists, get_cache_path};
+
+/// Download and cache counters for a single registry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistryStats {
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub bytes_downloaded: u64,
+}
+
+impl RegistryStats {
+    /// Fraction of package lookups served from the cache, if there were any
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.cache_hits + self.cache_misses;
+        if total == 0 {
+            None
+        } else {
+            Some(self.cache_hits as f64 / total as f64)
+        }
+    }
+
+    fn merge(&mut self, other: &RegistryStats) {
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+        self.bytes_downloaded += other.bytes_downloaded;
+    }
+}
+
+/// Cumulative statistics persisted in the cache directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Counters keyed by registry URL
+    #[serde(default)]
+    pub registries: BTreeMap<String, RegistryStats>,
+}
+
+/// Counters recorded during the current command
+static SESSION: Mutex<BTreeMap<String, RegistryStats>> = Mutex::new(BTreeMap::new());
+
+/// Record that a package was served from the cache
+pub fn record_cache_hit(registry: &str) {
+    update_session(registry, |stats| stats.cache_hits += 1);
+}
+
+/// Record that a package had to be downloaded
+pub fn record_download(registry: &str, bytes: u64) {
+    update_session(registry, |stats| {
+        stats.cache_misses += 1;
+        stats.bytes_downloaded += bytes;
+    });
+}
+
+fn update_session(registry: &str, update: impl FnOnce(&mut RegistryStats)) {
+    if let Ok(mut session) = SESSION.lock() {
+        update(session.entry(registry.to_string()).or_default());
+    }
+}
+
+/// Get the counters recorded during the current command
+pub fn session_stats() -> BTreeMap<String, RegistryStats> {
+    SESSION
+        .lock()
+        .map(|session| session.clone())
+        .unwrap_or_default()
+}
+
+/// Get the path of the persisted statistics file
+pub fn get_stats_path() -> Result<PathBuf> {
+    Ok(get_cache_path()?.join("stats.toml"))
+}
+
+/// Read the persisted statistics, returning empty statistics if there are none yet
+pub fn read_cache_stats(path: &Path) -> Result<CacheStats> {
+    if !path.exists() {
+        return Ok(CacheStats::default());
+    }
+
+    let content = fs::read_to_string(path)?;
+    Ok(toml::from_str(&content)?)
+}
+
+/// Add the current command's counters to the persisted statistics
+pub fn persist_session() -> Result<()> {
+    let session = session_stats();
+    if session.is_empty() {
+        return Ok(());
+    }
+
+    let path = get_stats_path()?;
+    let mut stats = read_cache_stats(&path)?;
+    for (registry, counters) in &session {
+        stats
+            .registries
+            .entry(registry.clone())
+            .or_default()
+            .merge(counters);
+    }
+
+    if let Some(parent) = path.parent() {
+        ensure_dir_exists(parent)?;
+    }
+    fs::write(&path, toml::to_string_pretty(&stats)?)?;
+    Ok(())
+}
@@ -0,0 +1,280 @@
+use clap::ValueEnum;
+use colored::{Color, ColoredString, Colorize};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+use crate::config::read_config;
+
+/// Output themes that can be chosen with `theme` in ~/.sop/config.toml or `$SOP_THEME`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    /// Green, yellow and red with Unicode symbols
+    #[default]
+    Default,
+    /// Blue and magenta instead of green and red, for red-green color blindness
+    Colorblind,
+    /// Bright, bold colors and no dimmed text
+    HighContrast,
+    /// Plain ASCII words and lines instead of symbols
+    Ascii,
+}
+
+impl ThemeName {
+    /// Parse a theme name as written in config or the environment
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(ThemeName::Default),
+            "colorblind" => Some(ThemeName::Colorblind),
+            "high-contrast" => Some(ThemeName::HighContrast),
+            "ascii" => Some(ThemeName::Ascii),
+            _ => None,
+        }
+    }
+}
+
+/// When to color output, chosen with `color` in ~/.sop/config.toml
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorChoice {
+    /// Color output when it goes to a terminal
+    #[default]
+    Auto,
+    /// Always color output, even when piped
+    Always,
+    /// Never color output
+    Never,
+}
+
+/// Work out when to color output
+///
+/// `--color` wins, then a non-empty `$NO_COLOR` (see <https://no-color.org>), then `color` in
+/// the config.
+pub fn resolve_color_choice(flag: Option<ColorChoice>) -> ColorChoice {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    flag.or(no_color.then_some(ColorChoice::Never))
+        .or_else(|| read_config().ok().and_then(|config| config.color))
+        .unwrap_or_default()
+}
+
+/// Apply a color choice to everything printed from now on, including prompts and progress bars
+pub fn set_color_choice(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Auto => {
+            colored::control::unset_override();
+            return;
+        }
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    colored::control::set_override(enabled);
+    console::set_colors_enabled(enabled);
+    console::set_colors_enabled_stderr(enabled);
+}
+
+/// Colors and symbols used for every kind of output
+struct Theme {
+    success: Color,
+    warning: Color,
+    error: Color,
+    info: Color,
+    accent: Color,
+    /// Make every colored piece of text bold
+    bold: bool,
+    /// Allow de-emphasized text; high contrast renders it normally instead
+    dim: bool,
+    symbols: Symbols,
+}
+
+/// Symbols drawn around messages and trees
+pub struct Symbols {
+    pub ok: &'static str,
+    pub warning: &'static str,
+    pub error: &'static str,
+    pub arrow: &'static str,
+    pub rerun: &'static str,
+    pub gutter: &'static str,
+    pub tree_branch: &'static str,
+    pub tree_last: &'static str,
+    pub tree_indent: &'static str,
+    /// Frames of the spinner shown while something is in progress, ending with a blank frame
+    pub spinner: &'static str,
+    /// Filled, partly filled and empty parts of a progress bar
+    pub progress: &'static str,
+}
+
+const UNICODE_SYMBOLS: Symbols = Symbols {
+    ok: "✓",
+    warning: "!",
+    error: "✗",
+    arrow: "→",
+    rerun: "↻",
+    gutter: "|",
+    tree_branch: "├── ",
+    tree_last: "└── ",
+    tree_indent: "│   ",
+    spinner: "⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ ",
+    progress: "█▌ ",
+};
+
+const ASCII_SYMBOLS: Symbols = Symbols {
+    ok: "[ok]",
+    warning: "[warn]",
+    error: "[error]",
+    arrow: "->",
+    rerun: "[rerun]",
+    gutter: "|",
+    tree_branch: "|-- ",
+    tree_last: "`-- ",
+    tree_indent: "|   ",
+    spinner: "|/-\\ ",
+    progress: "=> ",
+};
+
+impl Theme {
+    fn new(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Default => Theme {
+                success: Color::Green,
+                warning: Color::Yellow,
+                error: Color::Red,
+                info: Color::Blue,
+                accent: Color::Magenta,
+                bold: false,
+                dim: true,
+                symbols: UNICODE_SYMBOLS,
+            },
+            ThemeName::Colorblind => Theme {
+                success: Color::Blue,
+                warning: Color::Yellow,
+                error: Color::Magenta,
+                info: Color::Cyan,
+                accent: Color::Cyan,
+                bold: false,
+                dim: true,
+                symbols: UNICODE_SYMBOLS,
+            },
+            ThemeName::HighContrast => Theme {
+                success: Color::BrightGreen,
+                warning: Color::BrightYellow,
+                error: Color::BrightRed,
+                info: Color::BrightCyan,
+                accent: Color::BrightMagenta,
+                bold: true,
+                dim: false,
+                symbols: UNICODE_SYMBOLS,
+            },
+            ThemeName::Ascii => Theme {
+                symbols: ASCII_SYMBOLS,
+                ..Theme::new(ThemeName::Default)
+            },
+        }
+    }
+
+    fn paint(&self, text: &str, color: Color) -> ColoredString {
+        let painted = text.color(color);
+        if self.bold {
+            painted.bold()
+        } else {
+            painted
+        }
+    }
+}
+
+/// Get the active theme, chosen by `$SOP_THEME` or the user config on first use
+fn theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        let from_env = std::env::var("SOP_THEME")
+            .ok()
+            .and_then(|name| ThemeName::parse(&name));
+        let name = from_env
+            .or_else(|| read_config().ok().and_then(|config| config.theme))
+            .unwrap_or_default();
+        Theme::new(name)
+    })
+}
+
+/// Get the symbols of the active theme, for output that draws its own lines and arrows
+pub fn symbols() -> &'static Symbols {
+    &theme().symbols
+}
+
+/// Mark a step that succeeded
+pub fn ok_mark() -> ColoredString {
+    success(theme().symbols.ok)
+}
+
+/// Mark a step that had nothing to do (e.g. already installed)
+pub fn skip_mark() -> ColoredString {
+    warning(theme().symbols.ok)
+}
+
+/// Mark a warning
+pub fn warn_mark() -> ColoredString {
+    warning(theme().symbols.warning)
+}
+
+/// Mark a failure
+pub fn error_mark() -> ColoredString {
+    error(theme().symbols.error)
+}
+
+/// An arrow pointing at what happens next
+pub fn arrow() -> ColoredString {
+    info(theme().symbols.arrow)
+}
+
+/// Mark something being run again
+pub fn rerun_mark() -> ColoredString {
+    info(theme().symbols.rerun)
+}
+
+/// The gutter in front of quoted output lines
+pub fn gutter() -> ColoredString {
+    dim(theme().symbols.gutter)
+}
+
+/// Text reporting success
+pub fn success(text: &str) -> ColoredString {
+    theme().paint(text, theme().success)
+}
+
+/// Text that needs attention but isn't a failure
+pub fn warning(text: &str) -> ColoredString {
+    theme().paint(text, theme().warning)
+}
+
+/// Text reporting a failure
+pub fn error(text: &str) -> ColoredString {
+    theme().paint(text, theme().error)
+}
+
+/// Headings and progress messages
+pub fn info(text: &str) -> ColoredString {
+    theme().paint(text, theme().info)
+}
+
+/// File names and other locations
+pub fn accent(text: &str) -> ColoredString {
+    theme().paint(text, theme().accent)
+}
+
+/// De-emphasized text
+pub fn dim(text: &str) -> ColoredString {
+    if theme().dim {
+        text.dimmed()
+    } else {
+        text.normal()
+    }
+}
+
+/// Something new in a diff, like the version a package moves to
+pub fn added(text: &str) -> ColoredString {
+    success(text)
+}
+
+/// Something going away in a diff
+pub fn removed(text: &str) -> ColoredString {
+    error(text)
+}
@@ -1,21 +1,269 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::env::consts;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::diagnostic::toml_error;
+use crate::lockfile::is_frozen;
+use crate::utils::write_atomic;
 
 /// Structure representing the sop.toml file
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SopToml {
     pub project: ProjectConfig,
-    pub dependencies: Option<HashMap<String, String>>,
+    #[serde(serialize_with = "sorted")]
+    pub dependencies: Option<HashMap<String, Dependency>>,
+    #[serde(
+        default,
+        rename = "dev-dependencies",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sorted"
+    )]
+    pub dev_dependencies: Option<HashMap<String, Dependency>>,
+    /// `[target.<platform>]` sections, keyed by platform qualifier
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub target: BTreeMap<String, TargetConfig>,
+    /// `[patch]` overrides, replacing a package wherever it appears in the dependency graph
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sorted"
+    )]
+    pub patch: Option<HashMap<String, Dependency>>,
+    #[serde(default, rename = "bin", skip_serializing_if = "Vec::is_empty")]
+    pub bins: Vec<BinTarget>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<ManifestConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modules: Option<ModulesConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<WorkspaceConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<VendorConfig>,
+    /// `[check]` rules of `sop check` turned on or off by name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check: Option<BTreeMap<String, bool>>,
+}
+
+/// A `[target.<platform>]` section, holding dependencies only installed on some platforms
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TargetConfig {
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sorted"
+    )]
+    pub dependencies: Option<HashMap<String, Dependency>>,
+    #[serde(
+        default,
+        rename = "dev-dependencies",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sorted"
+    )]
+    pub dev_dependencies: Option<HashMap<String, Dependency>>,
+}
+
+/// Serialize a dependency table sorted by name, so sop.toml is written the same way every time
+fn sorted<S: serde::Serializer>(
+    dependencies: &Option<HashMap<String, Dependency>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    dependencies
+        .as_ref()
+        .map(|dependencies| dependencies.iter().collect::<BTreeMap<_, _>>())
+        .serialize(serializer)
+}
+
+/// Check whether a `[target.<platform>]` qualifier applies to the platform sop runs on
+///
+/// A qualifier names an operating system (`windows`, `linux`, `macos`), an OS family (`unix`),
+/// an architecture (`x86_64`, `aarch64`), or an operating system and architecture together
+/// (`linux-x86_64`).
+pub fn platform_matches(qualifier: &str) -> bool {
+    qualifier == consts::OS
+        || qualifier == consts::FAMILY
+        || qualifier == consts::ARCH
+        || qualifier == format!("{}-{}", consts::OS, consts::ARCH)
+}
+
+/// A dependency entry in sop.toml
+///
+/// Registry dependencies can be written as a plain version string (`json = "1.2.0"`);
+/// anything else uses a table (`mylib = { path = "../mylib" }`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Dependency {
+    Simple(String),
+    Detailed(DependencyDetail),
+}
+
+/// The table form of a dependency entry
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DependencyDetail {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Name of a registry from `[registries]` in ~/.sop/config.toml, instead of the public one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub optional: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Which dependency table an entry lives in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyKind {
+    /// `[dependencies]`, needed at runtime
+    Normal,
+    /// `[dev-dependencies]`, only needed while developing the project
+    Dev,
+}
+
+/// Where a dependency comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DependencyOrigin {
+    Registry,
+    Git,
+    Path,
+}
+
+impl DependencyDetail {
+    /// Get the git reference to check out, preferring `rev` over `tag` over `branch`
+    pub fn git_reference(&self) -> Option<&str> {
+        self.rev
+            .as_deref()
+            .or(self.tag.as_deref())
+            .or(self.branch.as_deref())
+    }
+}
+
+impl Dependency {
+    /// Get the version requirement of a registry dependency ("latest" if none was given)
+    pub fn requirement(&self) -> Option<&str> {
+        match self {
+            Dependency::Simple(version) => Some(version),
+            Dependency::Detailed(detail) if self.origin() == DependencyOrigin::Registry => {
+                Some(detail.version.as_deref().unwrap_or("latest"))
+            }
+            Dependency::Detailed(_) => None,
+        }
+    }
+
+    /// Replace the version requirement, keeping any other settings of the entry
+    pub fn set_requirement(&mut self, version: &str) {
+        match self {
+            Dependency::Simple(current) => *current = version.to_string(),
+            Dependency::Detailed(detail) => detail.version = Some(version.to_string()),
+        }
+    }
+
+    /// Get the name of the registry a registry dependency comes from, if not the public one
+    pub fn registry(&self) -> Option<&str> {
+        match self {
+            Dependency::Detailed(detail) if self.origin() == DependencyOrigin::Registry => {
+                detail.registry.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Check whether the dependency is marked `optional = true`
+    pub fn is_optional(&self) -> bool {
+        matches!(self, Dependency::Detailed(detail) if detail.optional)
+    }
+
+    /// Classify where the dependency comes from
+    pub fn origin(&self) -> DependencyOrigin {
+        match self {
+            Dependency::Detailed(detail) if detail.git.is_some() => DependencyOrigin::Git,
+            Dependency::Detailed(detail) if detail.path.is_some() => DependencyOrigin::Path,
+            _ => DependencyOrigin::Registry,
+        }
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dependency::Simple(version) => write!(f, "{}", version),
+            Dependency::Detailed(detail) => match (&detail.git, &detail.path) {
+                (Some(git), _) => write!(f, "git+{}", git),
+                (None, Some(path)) => write!(f, "path:{}", path),
+                (None, None) => write!(f, "{}", detail.version.as_deref().unwrap_or("latest")),
+            },
+        }
+    }
+}
+
+/// The `[manifest]` section, controlling how sop writes sop.toml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManifestConfig {
+    #[serde(default)]
+    pub style: ManifestStyle,
+}
+
+/// The `[modules]` section, controlling where packages are installed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModulesConfig {
+    /// Directory to install packages into, relative to the project root (`sop_modules` by default)
+    pub path: Option<String>,
+}
+
+/// The `[vendor]` section, written by `sop vendor`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VendorConfig {
+    /// Directory holding vendored packages, relative to the project root
+    pub path: String,
+}
+
+/// The `[workspace]` section, listing the member projects of a monorepo
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceConfig {
+    /// Member directories relative to the workspace root; `*` globs are allowed (`packages/*`)
+    #[serde(default)]
+    pub members: Vec<String>,
+    /// Version shared by the members that declare `version.workspace = true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Layout used when writing the dependencies of sop.toml
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestStyle {
+    /// A single `[dependencies]` table
+    #[default]
+    Plain,
+    /// Dependencies sorted and grouped under commented sections by origin
+    Grouped,
 }
 
 /// Project configuration section of sop.toml
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectConfig {
     pub name: String,
+    /// The project's version; with `version.workspace = true`, the workspace's version
+    #[serde(deserialize_with = "version_or_workspace")]
     pub version: String,
+    /// Whether sop.toml says `version.workspace = true` instead of giving a version
+    #[serde(skip)]
+    pub version_from_workspace: bool,
     pub status: String,
     #[serde(default)]
     pub description: String,
@@ -27,11 +275,124 @@ pub struct ProjectConfig {
     pub repository: String,
     #[serde(default)]
     pub homepage: String,
+    /// Where the package's documentation is published, if not on the registry
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub documentation: String,
     pub entry: String,
     #[serde(default)]
     pub keywords: Vec<String>,
     #[serde(default)]
     pub categories: Vec<String>,
+    /// Soplang versions the project runs on, as a requirement like `">=0.4"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soplang: Option<String>,
+    /// Globs of the files to publish; by default `src/`, the entry point, and the readme,
+    /// license and changelog
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Globs of files never to publish, on top of the lines of `.sopignore`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+/// A named binary entry point declared with `[[bin]]` in sop.toml
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinTarget {
+    pub name: String,
+    pub path: String,
+}
+
+impl SopToml {
+    /// Iterate over the runtime and dev dependencies the current platform needs
+    ///
+    /// This includes the `[target.<platform>]` tables whose qualifier matches the platform sop
+    /// runs on.
+    pub fn all_dependencies(&self) -> Vec<(&String, &Dependency, DependencyKind)> {
+        let normal = self
+            .dependencies
+            .iter()
+            .flatten()
+            .map(|(name, dependency)| (name, dependency, DependencyKind::Normal));
+        let dev = self
+            .dev_dependencies
+            .iter()
+            .flatten()
+            .map(|(name, dependency)| (name, dependency, DependencyKind::Dev));
+        let target = self
+            .platform_dependencies()
+            .into_iter()
+            .filter(|(qualifier, _, _, _)| platform_matches(qualifier))
+            .map(|(_, name, dependency, kind)| (name, dependency, kind));
+        normal.chain(dev).chain(target).collect()
+    }
+
+    /// Iterate over the dependencies of every `[target.<platform>]` table, with its qualifier
+    pub fn platform_dependencies(&self) -> Vec<(&str, &String, &Dependency, DependencyKind)> {
+        self.target
+            .iter()
+            .flat_map(|(qualifier, target)| {
+                let normal = target
+                    .dependencies
+                    .iter()
+                    .flatten()
+                    .map(|(name, dependency)| (name, dependency, DependencyKind::Normal));
+                let dev = target
+                    .dev_dependencies
+                    .iter()
+                    .flatten()
+                    .map(|(name, dependency)| (name, dependency, DependencyKind::Dev));
+                normal.chain(dev).map(move |(name, dependency, kind)| {
+                    (qualifier.as_str(), name, dependency, kind)
+                })
+            })
+            .collect()
+    }
+
+    /// Check whether a package is declared in any dependency table
+    pub fn has_dependency(&self, name: &str) -> bool {
+        self.find_dependency(name).is_some()
+    }
+
+    /// Look up a dependency in any dependency table
+    pub fn find_dependency(&self, name: &str) -> Option<&Dependency> {
+        let targets = self
+            .target
+            .values()
+            .flat_map(|target| [&target.dependencies, &target.dev_dependencies]);
+        [&self.dependencies, &self.dev_dependencies]
+            .into_iter()
+            .chain(targets)
+            .find_map(|dependencies| dependencies.as_ref()?.get(name))
+    }
+
+    /// Look up a dependency in any dependency table for modification
+    pub fn find_dependency_mut(&mut self, name: &str) -> Option<&mut Dependency> {
+        let targets = self
+            .target
+            .values_mut()
+            .flat_map(|target| [&mut target.dependencies, &mut target.dev_dependencies]);
+        [&mut self.dependencies, &mut self.dev_dependencies]
+            .into_iter()
+            .chain(targets)
+            .find_map(|dependencies| dependencies.as_mut()?.get_mut(name))
+    }
+
+    /// Remove a dependency from whichever table declares it
+    pub fn remove_dependency(&mut self, name: &str) -> Option<Dependency> {
+        let targets = self
+            .target
+            .values_mut()
+            .flat_map(|target| [&mut target.dependencies, &mut target.dev_dependencies]);
+        [&mut self.dependencies, &mut self.dev_dependencies]
+            .into_iter()
+            .chain(targets)
+            .find_map(|dependencies| dependencies.as_mut()?.remove(name))
+    }
+
+    /// Look up a `[[bin]]` target by name
+    pub fn find_bin(&self, name: &str) -> Option<&BinTarget> {
+        self.bins.iter().find(|bin| bin.name == name)
+    }
 }
 
 /// Read and parse a sop.toml file
@@ -41,18 +402,271 @@ pub fn read_sop_toml(path: &Path) -> Result<SopToml> {
     }
 
     let content = fs::read_to_string(path)?;
-    let config: SopToml = toml::from_str(&content)?;
+    parse_sop_toml(&content, path)
+}
+
+/// Parse the contents of the sop.toml at `path`, which a version inherited from the workspace
+/// is looked up relative to
+///
+/// Errors point at the line and column of the problem in `content`.
+pub fn parse_sop_toml(content: &str, path: &Path) -> Result<SopToml> {
+    let mut config: SopToml = toml::from_str(content).map_err(|e| toml_error(path, content, &e))?;
+    if config.project.version.is_empty() && inherits_version(content) {
+        config.project.version = workspace_version(path)?.ok_or_else(|| {
+            anyhow!(
+                "{} takes its version from the workspace, but no sop.toml above it sets [workspace] version",
+                path.display()
+            )
+        })?;
+        config.project.version_from_workspace = true;
+    }
     Ok(config)
 }
 
+/// Whether sop.toml says `version.workspace = true` instead of giving a version
+fn inherits_version(content: &str) -> bool {
+    toml::from_str::<toml::Table>(content)
+        .ok()
+        .and_then(|document| {
+            document
+                .get("project")?
+                .get("version")?
+                .get("workspace")?
+                .as_bool()
+        })
+        .unwrap_or(false)
+}
+
+/// Read `[project] version`, which is either a version or `{ workspace = true }`
+///
+/// The workspace's version is left empty here, for `parse_sop_toml` to look up.
+fn version_or_workspace<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct VersionVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for VersionVisitor {
+        type Value = String;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a string, or { workspace = true } to use the workspace's version")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, version: &str) -> std::result::Result<String, E> {
+            Ok(version.to_string())
+        }
+
+        fn visit_map<A>(self, mut map: A) -> std::result::Result<String, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+        {
+            let mut inherits = false;
+            while let Some(key) = map.next_key::<String>()? {
+                if key != "workspace" {
+                    return Err(serde::de::Error::unknown_field(&key, &["workspace"]));
+                }
+                inherits = map.next_value()?;
+            }
+            if !inherits {
+                return Err(serde::de::Error::custom(
+                    "expected { workspace = true } to use the workspace's version",
+                ));
+            }
+            Ok(String::new())
+        }
+    }
+
+    deserializer.deserialize_any(VersionVisitor)
+}
+
+/// Find the `[workspace] version` of the nearest sop.toml at or above `path` that sets one
+fn workspace_version(path: &Path) -> Result<Option<String>> {
+    let dir = path
+        .canonicalize()?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    for dir in dir.ancestors() {
+        let manifest = dir.join("sop.toml");
+        let Ok(content) = fs::read_to_string(&manifest) else {
+            continue;
+        };
+        let document: toml::Table =
+            toml::from_str(&content).map_err(|e| toml_error(&manifest, &content, &e))?;
+        let version = document
+            .get("workspace")
+            .and_then(|workspace| workspace.get("version"))
+            .and_then(toml::Value::as_str);
+        if let Some(version) = version {
+            return Ok(Some(version.to_string()));
+        }
+    }
+    Ok(None)
+}
+
 /// Write a SopToml structure to a sop.toml file
+///
+/// The file is replaced atomically, and the version it replaces is kept as `sop.toml.bak`.
 pub fn write_sop_toml(path: &Path, config: &SopToml) -> Result<()> {
-    let content = toml::to_string_pretty(config)?;
-    fs::write(path, content)?;
+    let content = render_sop_toml(config)?;
+
+    if is_frozen() {
+        if fs::read_to_string(path).is_ok_and(|existing| existing == content) {
+            return Ok(());
+        }
+        return Err(anyhow!(
+            "sop.toml needs to be changed, but --frozen was given."
+        ));
+    }
+
+    // Keep the previous manifest around in case the change needs undoing by hand
+    if path.is_file() {
+        fs::copy(path, backup_path(path))?;
+    }
+    write_atomic(path, &content)
+}
+
+/// Render a SopToml structure as the contents of sop.toml, in the manifest style it asks for
+pub fn render_sop_toml(config: &SopToml) -> Result<String> {
+    let style = config
+        .manifest
+        .as_ref()
+        .map(|manifest| manifest.style)
+        .unwrap_or_default();
+
+    let content = match style {
+        ManifestStyle::Plain => toml::to_string_pretty(config)?,
+        ManifestStyle::Grouped => to_grouped_toml(config)?,
+    };
+    if config.project.version_from_workspace {
+        return Ok(inherit_workspace_version(&content));
+    }
+    Ok(content)
+}
+
+/// Write `version.workspace = true` back in place of the version read from the workspace
+///
+/// `[project]` is always the first table written, so its version is the first `version` key.
+fn inherit_workspace_version(content: &str) -> String {
+    let mut replaced = false;
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            if !replaced && line.starts_with("version = ") {
+                replaced = true;
+                "version.workspace = true\n"
+            } else {
+                line
+            }
+        })
+        .collect()
+}
+
+/// Get the path the previous version of a manifest is kept at (`sop.toml.bak`)
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Serialize sop.toml with dependencies grouped under a commented section per origin
+fn to_grouped_toml(config: &SopToml) -> Result<String> {
+    let without_dependencies = SopToml {
+        project: config.project.clone(),
+        dependencies: None,
+        dev_dependencies: None,
+        target: BTreeMap::new(),
+        patch: None,
+        bins: config.bins.clone(),
+        manifest: config.manifest.clone(),
+        modules: config.modules.clone(),
+        workspace: config.workspace.clone(),
+        vendor: config.vendor.clone(),
+        check: config.check.clone(),
+    };
+    let mut content = toml::to_string_pretty(&without_dependencies)?;
+
+    if let Some(dependencies) = &config.dependencies {
+        push_grouped_table(&mut content, "dependencies", dependencies)?;
+    }
+    if let Some(dev_dependencies) = &config.dev_dependencies {
+        push_grouped_table(&mut content, "dev-dependencies", dev_dependencies)?;
+    }
+    for (qualifier, target) in &config.target {
+        let prefix = format!("target.{}", toml_key(qualifier));
+        if let Some(dependencies) = &target.dependencies {
+            push_grouped_table(
+                &mut content,
+                &format!("{}.dependencies", prefix),
+                dependencies,
+            )?;
+        }
+        if let Some(dev_dependencies) = &target.dev_dependencies {
+            push_grouped_table(
+                &mut content,
+                &format!("{}.dev-dependencies", prefix),
+                dev_dependencies,
+            )?;
+        }
+    }
+    if let Some(patch) = &config.patch {
+        push_grouped_table(&mut content, "patch", patch)?;
+    }
+
+    Ok(content)
+}
+
+/// Append a dependency table with its entries sorted and grouped by origin
+fn push_grouped_table(
+    content: &mut String,
+    table: &str,
+    dependencies: &HashMap<String, Dependency>,
+) -> Result<()> {
+    let mut dependencies: Vec<(&String, &Dependency)> = dependencies.iter().collect();
+    dependencies.sort_by(|a, b| (a.1.origin(), a.0).cmp(&(b.1.origin(), b.0)));
+
+    content.push_str(&format!("\n[{}]\n", table));
+    let mut current_origin = None;
+    for (name, dependency) in dependencies {
+        let origin = dependency.origin();
+        if current_origin != Some(origin) {
+            if current_origin.is_some() {
+                content.push('\n');
+            }
+            content.push_str(match origin {
+                DependencyOrigin::Registry => "# Registry dependencies\n",
+                DependencyOrigin::Git => "# Git dependencies\n",
+                DependencyOrigin::Path => "# Path dependencies\n",
+            });
+            current_origin = Some(origin);
+        }
+
+        content.push_str(&format!(
+            "{} = {}\n",
+            toml_key(name),
+            toml::Value::try_from(dependency)?
+        ));
+    }
+
     Ok(())
 }
 
+/// Format a string as a TOML key, quoting it when it isn't a valid bare key
+fn toml_key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if is_bare {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
+
 /// Create a default SopToml configuration
+#[allow(dead_code)]
 pub fn create_default_config(name: &str) -> SopToml {
     SopToml {
         project: ProjectConfig {
@@ -64,10 +678,24 @@ pub fn create_default_config(name: &str) -> SopToml {
             author: String::new(),
             repository: String::new(),
             homepage: String::new(),
+            documentation: String::new(),
             entry: "src/main.so".to_string(),
             keywords: Vec::new(),
             categories: Vec::new(),
+            soplang: None,
+            version_from_workspace: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
         },
         dependencies: Some(HashMap::new()),
+        dev_dependencies: None,
+        target: BTreeMap::new(),
+        patch: None,
+        bins: Vec::new(),
+        manifest: None,
+        modules: None,
+        workspace: None,
+        vendor: None,
+        check: None,
     }
 }
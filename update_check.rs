@@ -0,0 +1,127 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::config::read_config;
+use crate::credentials::now;
+use crate::registry;
+use crate::style;
+use crate::utils::{ensure_dir_exists, get_cache_path};
+use crate::version::parse_version;
+
+/// How long the result of a release check is reused before checking again, in seconds
+const CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+/// Longest a finished command waits for a background check to complete
+const MAX_WAIT: Duration = Duration::from_millis(250);
+
+/// The newest release seen by the last check, persisted in the cache directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ReleaseCheck {
+    /// Unix time of the check
+    checked_at: u64,
+    latest: Option<String>,
+}
+
+/// A check for a newer sop that runs alongside the command
+pub struct UpdateCheck {
+    /// The newest release from the last check, which may be stale
+    cached: Option<String>,
+    /// Set while a fresh check is running in the background
+    pending: Option<JoinHandle<Option<String>>>,
+}
+
+/// Start checking for a newer sop, unless that is switched off or sop is offline
+///
+/// The registry is asked at most once a day, on a background thread so the command doesn't
+/// wait for it; in between, the result of the last check is used.
+pub fn start(offline: bool) -> Option<UpdateCheck> {
+    let config = read_config().ok()?;
+    if offline || config.offline || config.update_check == Some(false) {
+        return None;
+    }
+
+    let path = get_release_check_path().ok()?;
+    let check = read_release_check(&path).unwrap_or_default();
+    let pending = (now().saturating_sub(check.checked_at) >= CHECK_INTERVAL_SECS).then(|| {
+        thread::spawn(move || {
+            let latest = registry::sop_releases().ok()?.into_iter().next();
+            let _ = write_release_check(
+                &path,
+                &ReleaseCheck {
+                    checked_at: now(),
+                    latest: latest.clone(),
+                },
+            );
+            latest
+        })
+    });
+
+    Some(UpdateCheck {
+        cached: check.latest,
+        pending,
+    })
+}
+
+impl UpdateCheck {
+    /// Print a one-line upgrade hint if a newer sop is known
+    ///
+    /// A background check gets a moment to complete; one that takes longer is abandoned rather
+    /// than holding up the command, and the next command checks again.
+    pub fn finish(self) {
+        let latest = match self.pending {
+            Some(handle) if wait_for(&handle) => handle.join().ok().flatten(),
+            _ => self.cached,
+        };
+        let Some(latest) = latest else {
+            return;
+        };
+
+        let current = env!("CARGO_PKG_VERSION");
+        if parse_version(&latest) > parse_version(current) {
+            eprintln!(
+                "\n{} sop v{} is available (you have v{}). Upgrade it the way you installed sop.",
+                style::info("note:"),
+                latest,
+                current
+            );
+        }
+    }
+}
+
+/// Wait up to [`MAX_WAIT`] for a thread to finish, returning whether it did
+fn wait_for<T>(handle: &JoinHandle<T>) -> bool {
+    let started = Instant::now();
+    while !handle.is_finished() {
+        if started.elapsed() >= MAX_WAIT {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    true
+}
+
+/// Get the path of the cached release check
+fn get_release_check_path() -> Result<PathBuf> {
+    Ok(get_cache_path()?.join("release-check.toml"))
+}
+
+fn read_release_check(path: &Path) -> Result<ReleaseCheck> {
+    if !path.exists() {
+        return Ok(ReleaseCheck::default());
+    }
+
+    let content = fs::read_to_string(path)?;
+    Ok(toml::from_str(&content)?)
+}
+
+fn write_release_check(path: &Path, check: &ReleaseCheck) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        ensure_dir_exists(parent)?;
+    }
+    fs::write(path, toml::to_string_pretty(check)?)?;
+    Ok(())
+}
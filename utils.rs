@@ -1,6 +1,15 @@
 use anyhow::{anyhow, Result};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::i18n::{tr, tr_args};
+use crate::toml_parser::read_sop_toml;
 
 /// Check if a file exists at the specified path
 pub fn file_exists(path: &Path) -> bool {
@@ -20,12 +29,208 @@ pub fn ensure_dir_exists(path: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Get the path to the sop.toml file in the current directory
+/// Replace the contents of a file atomically
+///
+/// The content goes to a temporary file in the same directory, is flushed to disk, and is then
+/// renamed over `path`, so an interrupted write leaves either the old or the new file, never a
+/// truncated one.
+pub fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    write_atomic_as(path, content, false)
+}
+
+/// Replace the contents of a file atomically, as [`write_atomic`] does, leaving it readable
+/// only by the current user
+///
+/// The file is created with those permissions rather than changed to them afterwards, so its
+/// contents are never readable by anyone else, not even for a moment.
+pub fn write_private(path: &Path, content: &str) -> Result<()> {
+    write_atomic_as(path, content, true)
+}
+
+fn write_atomic_as(path: &Path, content: &str, private: bool) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("'{}' is not a file path.", path.display()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id()
+    ));
+
+    let written = (|| -> Result<()> {
+        // A temporary file left by an earlier crash may have other permissions
+        let _ = fs::remove_file(&temp_path);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        if private {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        #[cfg(not(unix))]
+        let _ = private;
+        let mut file = options.open(&temp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written.map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+}
+
+/// File name of a project manifest
+pub const SOP_TOML: &str = "sop.toml";
+
+/// Where to find the project instead of the current directory, set by `-C` or `--manifest-path`
+static PROJECT_LOCATION: OnceLock<ProjectLocation> = OnceLock::new();
+
+enum ProjectLocation {
+    /// Look for sop.toml from this directory upwards
+    SearchFrom(PathBuf),
+    /// The project is in exactly this directory
+    Root(PathBuf),
+}
+
+/// Look for the project from `dir` instead of the current directory, as `sop -C <dir>` does
+pub fn search_project_from(dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        return Err(anyhow!(tr_args(
+            "Directory not found: {}",
+            &[&dir.display()]
+        )));
+    }
+    let dir = normalize(&std::env::current_dir()?.join(dir));
+    let _ = PROJECT_LOCATION.set(ProjectLocation::SearchFrom(dir));
+    Ok(())
+}
+
+/// Use the project whose sop.toml is at `manifest`, as `sop --manifest-path <manifest>` does
+pub fn use_manifest_path(manifest: &Path) -> Result<()> {
+    if manifest.file_name().is_none_or(|name| name != SOP_TOML) {
+        return Err(anyhow!(tr_args(
+            "--manifest-path must point to a {} file, not {}",
+            &[&SOP_TOML, &manifest.display()]
+        )));
+    }
+    if !manifest.is_file() {
+        return Err(anyhow!(tr_args(
+            "Manifest not found: {}",
+            &[&manifest.display()]
+        )));
+    }
+    let manifest = normalize(&std::env::current_dir()?.join(manifest));
+    let root = manifest.parent().map(Path::to_path_buf).unwrap_or_default();
+    let _ = PROJECT_LOCATION.set(ProjectLocation::Root(root));
+    Ok(())
+}
+
+/// Find the directory of the project sop is working on
+///
+/// Like cargo and git, sop looks for sop.toml in the current directory and then in each of its
+/// parents, so commands work from anywhere inside a project. `-C` starts the search elsewhere,
+/// and `--manifest-path` names the project outright.
+pub fn find_project_dir() -> Option<PathBuf> {
+    let start = match PROJECT_LOCATION.get() {
+        Some(ProjectLocation::Root(root)) => return Some(root.clone()),
+        Some(ProjectLocation::SearchFrom(dir)) => dir.clone(),
+        None => std::env::current_dir().ok()?,
+    };
+    start
+        .ancestors()
+        .find(|dir| dir.join(SOP_TOML).is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Get the root of the current project, which project paths are built on
+///
+/// This is an empty path when sop runs in the project root, keeping paths in messages as short
+/// as they always were, and the absolute project directory otherwise. Outside a project it is
+/// the directory given with `-C`, or the current directory.
+pub fn project_root() -> PathBuf {
+    let current_dir = std::env::current_dir().ok();
+    let dir = match (find_project_dir(), PROJECT_LOCATION.get()) {
+        (Some(dir), _) => dir,
+        (None, Some(ProjectLocation::SearchFrom(dir))) => dir.clone(),
+        (None, _) => return PathBuf::new(),
+    };
+    if Some(&dir) == current_dir.as_ref() {
+        PathBuf::new()
+    } else {
+        dir
+    }
+}
+
+/// Get the absolute path of the project root
+pub fn project_dir() -> Result<PathBuf> {
+    Ok(std::env::current_dir()?.join(project_root()))
+}
+
+/// Resolve a path written in sop.toml, which is relative to the project root
+pub fn project_path(path: impl AsRef<Path>) -> PathBuf {
+    project_root().join(path)
+}
+
+/// Express a path given on the command line relative to the project root, for sop.toml
+///
+/// Relative paths on the command line are relative to the current directory, which may be a
+/// subdirectory of the project or, with `-C`, somewhere else entirely.
+pub fn relative_to_project(path: &str) -> Result<String> {
+    if Path::new(path).is_absolute() {
+        return Ok(path.to_string());
+    }
+
+    let current_dir = std::env::current_dir()?;
+    let target = normalize(&current_dir.join(path));
+    let root = normalize(&project_dir()?);
+
+    // `../lib` from `src/` is recorded as `lib`, and `../lib` from the root stays as it is
+    let common = root
+        .components()
+        .zip(target.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return Ok(target.to_string_lossy().to_string());
+    }
+    let parts: Vec<String> =
+        std::iter::repeat_n("..".to_string(), root.components().count() - common)
+            .chain(
+                target
+                    .components()
+                    .skip(common)
+                    .map(|component| component.as_os_str().to_string_lossy().to_string()),
+            )
+            .collect();
+    if parts.is_empty() {
+        return Ok(".".to_string());
+    }
+    Ok(parts.join("/"))
+}
+
+/// Resolve `.` and `..` in a path without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Get the path to the sop.toml file of the current project
 pub fn get_sop_toml_path() -> PathBuf {
-    PathBuf::from("sop.toml")
+    project_path(SOP_TOML)
 }
 
-/// Check if the current directory is a Soplang project by checking for sop.toml
+/// Check if the current directory is inside a Soplang project by looking for sop.toml
 pub fn is_soplang_project() -> bool {
     file_exists(&get_sop_toml_path())
 }
@@ -33,34 +238,473 @@ pub fn is_soplang_project() -> bool {
 /// Ensure we're in a Soplang project directory
 pub fn ensure_in_project() -> Result<()> {
     if !is_soplang_project() {
-        return Err(anyhow!(
+        return Err(anyhow!(tr(
             "Not in a Soplang project. Run 'sop init' to create a new project."
-        ));
+        )));
     }
     Ok(())
 }
 
+/// Name of the directory packages are installed into unless sop.toml says otherwise
+pub const DEFAULT_MODULES_DIR: &str = "sop_modules";
+
 /// Get the path to the sop_modules directory
+///
+/// The location can be changed with `path` in the `[modules]` section of sop.toml, e.g. to
+/// share one directory between sibling projects or to keep it under `target/`.
 pub fn get_sop_modules_path() -> PathBuf {
-    PathBuf::from("sop_modules")
+    read_sop_toml(&get_sop_toml_path())
+        .ok()
+        .and_then(|config| config.modules?.path)
+        .map(project_path)
+        .unwrap_or_else(|| project_path(DEFAULT_MODULES_DIR))
+}
+
+/// Get the vendor directory set up by `sop vendor`, if the project has one
+pub fn get_vendor_path() -> Option<PathBuf> {
+    read_sop_toml(&get_sop_toml_path())
+        .ok()
+        .and_then(|config| config.vendor)
+        .map(|vendor| project_path(vendor.path))
+}
+
+/// Get the path to the sop.lock file of the current project
+pub fn get_sop_lock_path() -> PathBuf {
+    project_path("sop.lock")
+}
+
+/// Get the path to the sop home directory (`$SOP_HOME`, or `~/.sop` by default)
+pub fn get_sop_home() -> Result<PathBuf> {
+    if let Ok(home) = std::env::var("SOP_HOME") {
+        return Ok(PathBuf::from(home));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".sop"))
+        .ok_or_else(|| anyhow!("Unable to determine the home directory"))
+}
+
+/// Get the path to the global package cache
+pub fn get_cache_path() -> Result<PathBuf> {
+    Ok(get_sop_home()?.join("cache"))
+}
+
+/// Get the path to the read-only system-wide package store
+///
+/// `$SOP_SYSTEM_STORE` overrides the platform default, so images can pre-seed packages anywhere.
+pub fn get_system_store_path() -> PathBuf {
+    if let Ok(store) = std::env::var("SOP_SYSTEM_STORE") {
+        return PathBuf::from(store);
+    }
+    if cfg!(windows) {
+        let program_data =
+            std::env::var("PROGRAMDATA").unwrap_or_else(|_| r"C:\ProgramData".to_string());
+        PathBuf::from(program_data).join("sop").join("store")
+    } else {
+        PathBuf::from("/usr/share/sop/store")
+    }
+}
+
+/// Get the path where globally installed packages live
+pub fn get_global_packages_path() -> Result<PathBuf> {
+    Ok(get_sop_home()?.join("packages"))
+}
+
+/// Get the path where launcher shims for global packages are created
+pub fn get_global_bin_path() -> Result<PathBuf> {
+    Ok(get_sop_home()?.join("bin"))
 }
 
 /// Get the path to the src directory
 pub fn get_src_path() -> PathBuf {
-    PathBuf::from("src")
+    project_path("src")
+}
+
+/// Get the path to the tests directory
+pub fn get_tests_path() -> PathBuf {
+    project_path("tests")
+}
+
+/// List the packages installed in a modules directory, sorted by name
+///
+/// Scoped packages live one level deeper, in `@org/package`, and are listed under their full
+/// scoped name.
+pub fn installed_packages(modules_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut packages = Vec::new();
+    if !dir_exists(modules_dir) {
+        return Ok(packages);
+    }
+
+    for entry in fs::read_dir(modules_dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+
+        if name.starts_with('@') {
+            for scoped in fs::read_dir(&path)? {
+                let scoped = scoped?.path();
+                if scoped.is_dir() {
+                    let package = scoped.file_name().unwrap_or_default().to_string_lossy();
+                    packages.push((format!("{}/{}", name, package), scoped.clone()));
+                }
+            }
+        } else {
+            packages.push((name, path));
+        }
+    }
+
+    packages.sort();
+    Ok(packages)
+}
+
+/// Find an executable by file name in the directories on PATH
+pub fn find_on_path(file_name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(file_name))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Check whether a path is a file the current user may run
+#[cfg(unix)]
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Check whether a path is a file the current user may run
+#[cfg(not(unix))]
+pub fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Escape text for inclusion in HTML
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Open a web page in the user's browser, `$BROWSER` if it is set
+///
+/// Only `http` and `https` URLs are opened, since they may come from a package's sop.toml.
+pub fn open_in_browser(url: &str) -> Result<()> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(anyhow!(
+            "Refusing to open '{}', which isn't a web address.",
+            url
+        ));
+    }
+    launch_browser(url)
+}
+
+/// Open a file sop wrote, such as generated documentation, in the user's browser
+pub fn open_file_in_browser(path: &Path) -> Result<()> {
+    launch_browser(&path.to_string_lossy())
+}
+
+fn launch_browser(target: &str) -> Result<()> {
+    let mut command = match std::env::var("BROWSER") {
+        Ok(browser) if !browser.is_empty() => process::Command::new(browser),
+        _ if cfg!(target_os = "macos") => process::Command::new("open"),
+        _ if cfg!(windows) => {
+            let mut command = process::Command::new("cmd");
+            command.args(["/C", "start", ""]);
+            command
+        }
+        _ => process::Command::new("xdg-open"),
+    };
+    let status = command
+        .arg(target)
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status()
+        .map_err(|e| anyhow!("Couldn't open a browser ({}). The address is {}", e, target))?;
+    if !status.success() {
+        return Err(anyhow!(
+            "The browser exited with {}. The address is {}",
+            status,
+            target
+        ));
+    }
+    Ok(())
+}
+
+/// Apply `f` to every item on up to `jobs` threads, returning the results in item order
+pub fn parallel_map<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<Option<R>>>());
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .map(|result| result.expect("every item is processed"))
+        .collect()
+}
+
+/// Recursively collect files with the given extension under a directory, sorted by path
+pub fn find_files_with_extension(dir: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !dir_exists(dir) {
+        return Ok(files);
+    }
+
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().and_then(|ext| ext.to_str()) == Some(extension) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Get the total size in bytes of all files under a directory
+pub fn dir_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    if !dir_exists(dir) {
+        return Ok(size);
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+
+    Ok(size)
+}
+
+/// Format a byte count for humans (e.g. "1.5 MB")
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Parse a size for humans (e.g. "500MB" or "1.5 GB"), counting in the same 1024s as [`format_bytes`]
+pub fn parse_size(size: &str) -> Result<u64> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number: f64 = number.parse().map_err(|_| {
+        anyhow!(
+            "Invalid size '{}'. Use a number and unit, e.g. 500MB.",
+            size
+        )
+    })?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        "T" | "TB" => 1 << 40,
+        _ => {
+            return Err(anyhow!(
+                "Invalid size unit '{}'. Use B, KB, MB, GB or TB.",
+                unit.trim()
+            ))
+        }
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Parse a duration for humans (e.g. "30d", "12h" or "2w")
+pub fn parse_duration(duration: &str) -> Result<Duration> {
+    let duration = duration.trim();
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let (number, unit) = duration.split_at(split);
+    let number: u64 = number.parse().map_err(|_| {
+        anyhow!(
+            "Invalid duration '{}'. Use a number and unit, e.g. 30d.",
+            duration
+        )
+    })?;
+    let seconds = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(anyhow!(
+                "Invalid duration unit '{}'. Use s, m, h, d or w.",
+                unit.trim()
+            ))
+        }
+    };
+    let seconds = number.checked_mul(seconds).ok_or_else(|| {
+        anyhow!(
+            "Invalid duration '{}'. It is too long to be represented.",
+            duration
+        )
+    })?;
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Format seconds since the Unix epoch as an RFC 3339 UTC timestamp (`2024-05-01T12:00:00Z`)
+pub fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    // Convert days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
 }
 
 /// Create a pretty printed error message
+#[allow(dead_code)]
 pub fn format_error(msg: &str) -> String {
     format!("Error: {}", msg)
 }
 
 /// Create a pretty printed success message
+#[allow(dead_code)]
 pub fn format_success(msg: &str) -> String {
     format!("Success: {}", msg)
 }
 
 /// Create a pretty printed info message
+#[allow(dead_code)]
 pub fn format_info(msg: &str) -> String {
     format!("Info: {}", msg)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sop-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_files_are_never_readable_by_others() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("write-private");
+        let path = dir.join("credentials.toml");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, "token = \"secret\"").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "token = \"secret\"");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn durations_are_parsed_in_every_unit() {
+        assert_eq!(parse_duration("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(
+            parse_duration(" 12h ").unwrap(),
+            Duration::from_secs(43_200)
+        );
+        assert_eq!(
+            parse_duration("30d").unwrap(),
+            Duration::from_secs(2_592_000)
+        );
+        assert_eq!(
+            parse_duration("2w").unwrap(),
+            Duration::from_secs(1_209_600)
+        );
+    }
+
+    #[test]
+    fn invalid_durations_are_rejected() {
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("30y").is_err());
+        assert!(parse_duration("-3d").is_err());
+    }
+
+    #[test]
+    fn overflowing_durations_are_rejected() {
+        let error = parse_duration("99999999999999999w").unwrap_err();
+        assert!(error.to_string().starts_with("Invalid duration"));
+        assert!(parse_duration("99999999999999999999999s").is_err());
+    }
+
+    #[test]
+    fn atomic_writes_replace_the_whole_file() {
+        let dir = temp_dir("write-atomic");
+        let path = dir.join("sop.lock");
+        write_atomic(&path, "a much longer first version").unwrap();
+        write_atomic(&path, "short").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "short");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,71 @@
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+/// Parse a version string, tolerating a leading `v` and a missing minor or patch component
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim().trim_start_matches('v');
+    if let Ok(parsed) = Version::parse(version) {
+        return Some(parsed);
+    }
+
+    // Accept shorthand like "1" or "1.2" by padding the missing components
+    let parts: Vec<&str> = version.split('.').collect();
+    match parts.len() {
+        1 => Version::parse(&format!("{}.0.0", version)).ok(),
+        2 => Version::parse(&format!("{}.0", version)).ok(),
+        _ => None,
+    }
+}
+
+/// Split a requirement like `^1.2.0` into its operator (`^`, `~`, `=`, or empty) and version
+pub fn split_requirement(requirement: &str) -> (&str, &str) {
+    let version = requirement.trim_start_matches(['^', '~', '=']);
+    (&requirement[..requirement.len() - version.len()], version)
+}
+
+/// The operator `sop add` writes before a version in sop.toml, chosen with `save-prefix`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SavePrefix {
+    /// `^1.2.3`: compatible updates
+    #[default]
+    Caret,
+    /// `~1.2.3`: patch updates only
+    Tilde,
+    /// `=1.2.3`: exactly this version
+    Exact,
+}
+
+impl SavePrefix {
+    /// Write a requirement for `version` with this prefix
+    pub fn requirement(self, version: &str) -> String {
+        let operator = match self {
+            SavePrefix::Caret => "^",
+            SavePrefix::Tilde => "~",
+            SavePrefix::Exact => "=",
+        };
+        format!("{}{}", operator, version)
+    }
+}
+
+/// How big a change moving from one version to another is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateKind {
+    Major,
+    Minor,
+    Patch,
+}
+
+/// Classify the update from `current` to `latest`, or `None` if `latest` is not newer
+pub fn update_kind(current: &Version, latest: &Version) -> Option<UpdateKind> {
+    if latest <= current {
+        None
+    } else if latest.major != current.major {
+        Some(UpdateKind::Major)
+    } else if latest.minor != current.minor {
+        Some(UpdateKind::Minor)
+    } else {
+        Some(UpdateKind::Patch)
+    }
+}
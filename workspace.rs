@@ -0,0 +1,108 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::toml_parser::{read_sop_toml, SopToml};
+
+/// A member project of a workspace
+#[derive(Debug)]
+pub struct WorkspaceMember {
+    /// Project name from the member's sop.toml
+    pub name: String,
+    /// Directory of the member, as listed under `[workspace]`
+    pub dir: PathBuf,
+    pub config: SopToml,
+}
+
+/// Load the members listed in the `[workspace]` section of a project, sorted by directory
+pub fn load_members(root: &Path, config: &SopToml) -> Result<Vec<WorkspaceMember>> {
+    let patterns = match &config.workspace {
+        Some(workspace) if !workspace.members.is_empty() => &workspace.members,
+        _ => {
+            return Err(anyhow!(
+                "No workspace members found. List them under [workspace] members in sop.toml."
+            ))
+        }
+    };
+
+    let mut dirs = BTreeSet::new();
+    for pattern in patterns {
+        let full_pattern = root.join(pattern);
+        let matches = glob::glob(&full_pattern.to_string_lossy())
+            .map_err(|e| anyhow!("Invalid workspace member pattern '{}': {}", pattern, e))?;
+
+        let mut found = false;
+        for path in matches {
+            let path = path?;
+            // Globs like `packages/*` may also match stray files and non-project folders
+            if path.join("sop.toml").is_file() {
+                dirs.insert(path);
+                found = true;
+            }
+        }
+        if !found {
+            return Err(anyhow!(
+                "Workspace member '{}' does not match any directory with a sop.toml",
+                pattern
+            ));
+        }
+    }
+
+    dirs.into_iter()
+        .map(|dir| {
+            let config = read_sop_toml(&dir.join("sop.toml"))
+                .map_err(|e| anyhow!("Failed to read {}: {}", dir.join("sop.toml").display(), e))?;
+            Ok(WorkspaceMember {
+                name: config.project.name.clone(),
+                dir,
+                config,
+            })
+        })
+        .collect()
+}
+
+/// Group members into waves so that every member comes after the members it depends on
+///
+/// Members within a wave don't depend on each other and can run at the same time. The
+/// waves hold indices into `members`.
+pub fn dependency_waves(members: &[WorkspaceMember]) -> Result<Vec<Vec<usize>>> {
+    let depends_on: Vec<Vec<usize>> = members
+        .iter()
+        .map(|member| {
+            members
+                .iter()
+                .enumerate()
+                .filter(|(_, other)| {
+                    other.name != member.name && member.config.has_dependency(&other.name)
+                })
+                .map(|(index, _)| index)
+                .collect()
+        })
+        .collect();
+
+    let mut done = vec![false; members.len()];
+    let mut waves = Vec::new();
+    while done.iter().any(|finished| !finished) {
+        let wave: Vec<usize> = (0..members.len())
+            .filter(|&index| !done[index] && depends_on[index].iter().all(|&dep| done[dep]))
+            .collect();
+
+        if wave.is_empty() {
+            let cycle: Vec<&str> = (0..members.len())
+                .filter(|&index| !done[index])
+                .map(|index| members[index].name.as_str())
+                .collect();
+            return Err(anyhow!(
+                "Workspace members depend on each other in a cycle: {}",
+                cycle.join(", ")
+            ));
+        }
+
+        for &index in &wave {
+            done[index] = true;
+        }
+        waves.push(wave);
+    }
+
+    Ok(waves)
+}